@@ -4,13 +4,136 @@ use std::sync::RwLock;
 use async_trait::async_trait;
 use chrono::Utc;
 
-use crate::cascade::{CascadeInput, CascadeTier};
+use crate::cascade::{CascadeInput, CascadeTier, TierMetrics};
 use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
-use crate::error::Result;
+use crate::error::{HookwiseError, Result};
 
-/// Tier 1: Exact cache lookup.
+/// Prefix marking a cache key whose input is a glob pattern rather than a
+/// literal sanitized input, e.g. `pattern:npm install *`.
+pub const PATTERN_PREFIX: &str = "pattern:";
+
+/// Whether `record` is a pattern rule. Only the exact cache matches these;
+/// the similarity tiers would compare the glob as if it were a command.
+pub(crate) fn is_pattern_rule(record: &DecisionRecord) -> bool {
+    record.key.sanitized_input.starts_with(PATTERN_PREFIX)
+}
+
+/// The text a pattern rule is matched against: the command for Bash, the
+/// target path for file tools, otherwise the whole sanitized input.
+pub fn pattern_subject(tool_name: &str, sanitized_input: &str) -> String {
+    let field = match tool_name {
+        "Bash" => "command",
+        "Write" | "Edit" | "Read" => "file_path",
+        "NotebookEdit" => "notebook_path",
+        _ => return sanitized_input.to_string(),
+    };
+    serde_json::from_str::<serde_json::Value>(sanitized_input)
+        .ok()
+        .and_then(|v| v.get(field).and_then(|f| f.as_str()).map(String::from))
+        .unwrap_or_else(|| sanitized_input.to_string())
+}
+
+/// What a wildcard may match: anything but the shell metacharacters that
+/// chain, substitute, or redirect commands.
+const WILDCARD_CLASS: &str = r"[^;&|$`<>()\n]";
+
+/// A compiled pattern rule glob.
+#[derive(Debug, Clone)]
+pub struct PatternMatcher {
+    regex: regex::Regex,
+}
+
+impl PatternMatcher {
+    /// Whether the whole of `subject` matches the glob.
+    pub fn is_match(&self, subject: &str) -> bool {
+        self.regex.is_match(subject)
+    }
+}
+
+/// Compile a pattern rule's glob. `*` matches any run of characters,
+/// including spaces and `/`, but not shell metacharacters (`;`, `&`, `|`,
+/// `$`, backticks, `<`, `>`, parentheses, newlines), so `npm install *`
+/// cannot match `npm install x && curl ... | sh`. Metacharacters written
+/// literally in the pattern still match themselves. `?`, `[...]`, `{a,b}`,
+/// and `\` escapes work as in shell globs.
+pub fn compile_pattern(pattern: &str) -> Result<PatternMatcher> {
+    let invalid = |reason: &str| HookwiseError::GlobPattern {
+        pattern: pattern.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let mut re = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    let mut alternations = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                while chars.peek() == Some(&'*') {
+                    chars.next();
+                }
+                re.push_str(WILDCARD_CLASS);
+                re.push('*');
+            }
+            '?' => re.push_str(WILDCARD_CLASS),
+            '[' => {
+                re.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    re.push('^');
+                }
+                // A `]` right after the opening bracket is literal
+                let mut first = true;
+                loop {
+                    match chars.next() {
+                        Some(']') if !first => break,
+                        Some(c @ ('\\' | '[' | ']' | '^' | '&' | '~')) => {
+                            re.push('\\');
+                            re.push(c);
+                        }
+                        Some(c) => re.push(c),
+                        None => return Err(invalid("unclosed character class")),
+                    }
+                    first = false;
+                }
+                re.push(']');
+            }
+            '{' => {
+                alternations += 1;
+                re.push_str("(?:");
+            }
+            ',' if alternations > 0 => re.push('|'),
+            '}' if alternations > 0 => {
+                alternations -= 1;
+                re.push(')');
+            }
+            '\\' => match chars.next() {
+                Some(c) => re.push_str(&regex::escape(&c.to_string())),
+                None => return Err(invalid("dangling escape")),
+            },
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if alternations > 0 {
+        return Err(invalid("unclosed alternation"));
+    }
+    re.push('$');
+
+    regex::Regex::new(&re)
+        .map(|regex| PatternMatcher { regex })
+        .map_err(|e| invalid(&e.to_string()))
+}
+
+/// A pattern rule with its compiled matcher.
+struct PatternRule {
+    matcher: PatternMatcher,
+    record: DecisionRecord,
+}
+
+/// Tier 1: Exact cache lookup, plus generalized pattern rules.
 pub struct ExactCache {
     entries: RwLock<HashMap<CacheKey, DecisionRecord>>,
+    /// Rules keyed by `pattern:<glob>`, checked when no exact entry matches.
+    patterns: RwLock<Vec<PatternRule>>,
     hits: std::sync::atomic::AtomicU64,
     misses: std::sync::atomic::AtomicU64,
 }
@@ -25,6 +148,7 @@ impl ExactCache {
     pub fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            patterns: RwLock::new(Vec::new()),
             hits: std::sync::atomic::AtomicU64::new(0),
             misses: std::sync::atomic::AtomicU64::new(0),
         }
@@ -32,15 +156,34 @@ impl ExactCache {
 
     /// Load cache from stored decisions.
     pub fn load_from(&self, records: Vec<DecisionRecord>) {
-        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         for record in records {
-            entries.insert(record.key.clone(), record);
+            self.insert(record);
         }
     }
 
-    /// Insert or update a cache entry.
+    /// Insert or update a cache entry. An immutable entry is only replaced by
+    /// another immutable entry.
     pub fn insert(&self, record: DecisionRecord) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if !record.immutable && entries.get(&record.key).is_some_and(|e| e.immutable) {
+            return;
+        }
+        if let Some(pattern) = record.key.sanitized_input.strip_prefix(PATTERN_PREFIX) {
+            match compile_pattern(pattern) {
+                Ok(matcher) => {
+                    let mut patterns = self.patterns.write().unwrap_or_else(|e| e.into_inner());
+                    patterns.retain(|p| p.record.key != record.key);
+                    patterns.push(PatternRule {
+                        matcher,
+                        record: record.clone(),
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("skipping invalid pattern rule: {}", e);
+                    return;
+                }
+            }
+        }
         entries.insert(record.key.clone(), record);
     }
 
@@ -48,12 +191,60 @@ impl ExactCache {
     pub fn invalidate_role(&self, role: &str) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         entries.retain(|k, _| k.role != role);
+        let mut patterns = self.patterns.write().unwrap_or_else(|e| e.into_inner());
+        patterns.retain(|p| p.record.key.role != role);
     }
 
     /// Remove all entries.
     pub fn invalidate_all(&self) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         entries.clear();
+        let mut patterns = self.patterns.write().unwrap_or_else(|e| e.into_inner());
+        patterns.clear();
+    }
+
+    /// Find the most authoritative pattern rule matching this input for the
+    /// given role or the wildcard role: immutable rules first, then
+    /// DENY > ASK > ALLOW.
+    fn match_pattern(&self, input: &CascadeInput, role_name: &str) -> Option<DecisionRecord> {
+        let patterns = self.patterns.read().unwrap_or_else(|e| e.into_inner());
+        if patterns.is_empty() {
+            return None;
+        }
+        let subject = pattern_subject(&input.tool_name, &input.sanitized_input);
+        patterns
+            .iter()
+            .filter(|p| p.record.key.tool == input.tool_name)
+            .filter(|p| p.record.key.role == role_name || p.record.key.role == "*")
+            .filter(|p| p.matcher.is_match(&subject))
+            .max_by_key(|p| (p.record.immutable, p.record.decision.precedence()))
+            .map(|p| p.record.clone())
+    }
+
+    /// The entry stored under `key`, if any.
+    pub fn get(&self, key: &CacheKey) -> Option<DecisionRecord> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries.get(key).cloned()
+    }
+
+    /// Count a hit at `at` on the entry (exact or pattern) stored under `key`.
+    fn touch(&self, key: &CacheKey, at: chrono::DateTime<Utc>) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(record) = entries.get_mut(key) {
+            record.record_hits(1, at);
+        }
+        let mut patterns = self.patterns.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(rule) = patterns.iter_mut().find(|p| &p.record.key == key) {
+            rule.record.record_hits(1, at);
+        }
+    }
+
+    /// Hits and misses as [`TierMetrics`].
+    pub fn tier_metrics(&self) -> TierMetrics {
+        TierMetrics {
+            matches: self.hits.load(std::sync::atomic::Ordering::Relaxed),
+            fall_throughs: self.misses.load(std::sync::atomic::Ordering::Relaxed),
+        }
     }
 
     /// Get cache statistics.
@@ -94,19 +285,38 @@ impl CascadeTier for ExactCache {
 
         let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
 
-        // Try exact role match first, then wildcard
-        let record = entries.get(&key).or_else(|| {
-            let wildcard_key = CacheKey {
-                sanitized_input: input.sanitized_input.clone(),
-                tool: input.tool_name.clone(),
-                role: "*".to_string(),
-            };
-            entries.get(&wildcard_key)
-        });
+        // Try exact role match first, then wildcard; an immutable entry for
+        // either wins over a mutable one.
+        let wildcard_key = CacheKey {
+            role: "*".to_string(),
+            ..key.clone()
+        };
+        let exact = entries.get(&key);
+        let wildcard = entries.get(&wildcard_key);
+        let direct = [exact, wildcard]
+            .into_iter()
+            .flatten()
+            .find(|r| r.immutable)
+            .or(exact)
+            .or(wildcard)
+            .cloned();
+        drop(entries);
+
+        // Pattern rules apply when nothing matches directly, or when an
+        // immutable pattern pins what a direct entry would otherwise decide.
+        let record = match direct {
+            Some(r) if r.immutable => Some(r),
+            direct => match self.match_pattern(input, &role_name) {
+                Some(p) if p.immutable => Some(p),
+                pattern => direct.or(pattern),
+            },
+        };
 
         match record {
             Some(cached) => {
                 self.hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let now = Utc::now();
+                self.touch(&cached.key, now);
 
                 // Return a new record with ExactCache tier metadata
                 Ok(Some(DecisionRecord {
@@ -115,17 +325,28 @@ impl CascadeTier for ExactCache {
                     metadata: DecisionMetadata {
                         tier: DecisionTier::ExactCache,
                         confidence: 1.0,
-                        reason: format!(
-                            "exact cache hit: {} (originally from {:?})",
-                            cached.decision, cached.metadata.tier
-                        ),
+                        reason: match cached.key.sanitized_input.strip_prefix(PATTERN_PREFIX) {
+                            Some(pattern) => format!(
+                                "pattern rule hit: {} for '{}' (originally from {:?})",
+                                cached.decision, pattern, cached.metadata.tier
+                            ),
+                            None => format!(
+                                "exact cache hit: {} (originally from {:?})",
+                                cached.decision, cached.metadata.tier
+                            ),
+                        },
                         matched_key: Some(cached.key.clone()),
                         similarity_score: None,
                     },
-                    timestamp: Utc::now(),
+                    timestamp: now,
                     scope: cached.scope,
                     file_path: cached.file_path.clone(),
                     session_id: String::new(), // Filled by CascadeRunner
+                    tags: HashMap::new(),
+                    immutable: false,
+                    id: String::new(),
+                    last_used: None,
+                    hit_count: 0,
                 }))
             }
             None => {
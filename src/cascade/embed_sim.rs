@@ -1,19 +1,30 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, RwLock};
 
 use async_trait::async_trait;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 
-use crate::cascade::{CascadeInput, CascadeTier};
+use crate::cascade::cache::is_pattern_rule;
+use crate::cascade::{CascadeInput, CascadeTier, TierCounters, TierMetrics};
 use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
 use crate::error::{HookwiseError, Result};
+use crate::storage::index::HnswIndexStore;
 
 /// An entry in the HNSW index.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingEntry {
     pub embedding: Vec<f32>,
     pub record: DecisionRecord,
 }
 
+/// First line of a saved index, naming the model that made its embeddings.
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexHeader {
+    model: String,
+}
+
 /// A point in the embedding space (wrapper for instant-distance).
 #[derive(Clone)]
 pub struct Point(pub Vec<f32>);
@@ -32,27 +43,92 @@ impl instant_distance::Point for Point {
     }
 }
 
+/// Whether an embedding is unusable for cosine similarity: empty, or all
+/// zeros (no direction). Such vectors are never indexed.
+fn is_degenerate(embedding: &[f32]) -> bool {
+    embedding.iter().all(|v| *v == 0.0)
+}
+
 /// Wrapper around instant-distance HNSW index.
 pub struct HnswIndex {
     hnsw: instant_distance::HnswMap<Point, usize>,
+    /// Dimension of every vector in the index. Queries of any other
+    /// dimension (e.g. from a different model) are not comparable.
+    dim: usize,
+}
+
+impl HnswIndex {
+    /// Build an index over the entries sharing the dimension of the most
+    /// recent entry. Values are positions in `entries`; entries of any other
+    /// dimension are left out. Returns None if there is nothing to index.
+    fn build(entries: &[EmbeddingEntry]) -> Option<Self> {
+        let dim = entries.last()?.embedding.len();
+        let (points, values): (Vec<Point>, Vec<usize>) = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.embedding.len() == dim)
+            .map(|(i, e)| (Point(e.embedding.clone()), i))
+            .unzip();
+        if points.len() < entries.len() {
+            tracing::warn!(
+                "embedding index: skipped {} entries with dimension other than {}",
+                entries.len() - points.len(),
+                dim
+            );
+        }
+        let hnsw = instant_distance::Builder::default().build(points, values);
+        Some(Self { hnsw, dim })
+    }
 }
 
-/// Maximum pending entries before an automatic rebuild.
-const PENDING_REBUILD_THRESHOLD: usize = 50;
+/// Snapshot of the embedding index state, for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexHealth {
+    /// Whether an embedding model is loaded. When false the tier is a no-op.
+    pub model_available: bool,
+    /// Entries covered by the last index build.
+    pub entries: usize,
+    /// Entries waiting for the next rebuild (linear-scanned on search).
+    pub pending: usize,
+    /// Dimension of the built HNSW index, or None if no index is built.
+    pub index_dim: Option<usize>,
+}
+
+impl IndexHealth {
+    /// Whether the HNSW index has been built.
+    pub fn is_built(&self) -> bool {
+        self.index_dim.is_some()
+    }
+}
+
+/// Default number of pending entries that triggers an automatic rebuild.
+pub const DEFAULT_PENDING_REBUILD_THRESHOLD: usize = 50;
 
 /// Tier 2b: Embedding-based HNSW similarity search.
 pub struct EmbeddingSimilarity {
     index: RwLock<Option<HnswIndex>>,
     model: Option<Mutex<fastembed::TextEmbedding>>,
+    /// Name of the configured model, saved with the index so an index
+    /// built by another model is never reused.
+    model_name: String,
     threshold: f64,
     entries: RwLock<Vec<EmbeddingEntry>>,
     /// Buffer for entries not yet in the HNSW index (linear-scanned on search).
     pending_entries: RwLock<Vec<EmbeddingEntry>>,
+    /// Pending entries that trigger a rebuild.
+    pending_threshold: usize,
+    /// Deny when the best match is a cached deny, instead of falling through.
+    deny_on_similarity: bool,
+    /// Dimension every entry must have, set by the first embedding added.
+    /// Reset by a full rebuild.
+    dim: RwLock<Option<usize>>,
+    metrics: TierCounters,
 }
 
 impl EmbeddingSimilarity {
-    /// Create a new embedding similarity engine.
-    pub fn new(_model_name: &str, threshold: f64) -> Result<Self> {
+    /// Create a new embedding similarity engine that rebuilds its index once
+    /// `pending_threshold` entries are pending.
+    pub fn new(model_name: &str, threshold: f64, pending_threshold: usize) -> Result<Self> {
         let model = fastembed::TextEmbedding::try_new(Default::default()).map_err(|e| {
             HookwiseError::Embedding {
                 reason: e.to_string(),
@@ -61,31 +137,74 @@ impl EmbeddingSimilarity {
         Ok(Self {
             index: RwLock::new(None),
             model: Some(Mutex::new(model)),
+            model_name: model_name.to_string(),
             threshold,
             entries: RwLock::new(Vec::new()),
             pending_entries: RwLock::new(Vec::new()),
+            pending_threshold,
+            deny_on_similarity: false,
+            dim: RwLock::new(None),
+            metrics: TierCounters::default(),
         })
     }
 
     /// Create a no-op embedding tier that always returns None.
     /// Used when the embedding model is unavailable.
-    pub fn new_noop() -> Self {
+    pub fn new_noop(pending_threshold: usize) -> Self {
         Self {
             index: RwLock::new(None),
             model: None,
+            model_name: String::new(),
             threshold: f64::MAX,
             entries: RwLock::new(Vec::new()),
             pending_entries: RwLock::new(Vec::new()),
+            pending_threshold,
+            deny_on_similarity: false,
+            dim: RwLock::new(None),
+            metrics: TierCounters::default(),
+        }
+    }
+
+    /// Create an engine with no embedding model. Entries must be added with
+    /// precomputed embeddings via `insert_embedding`, and queries passed to
+    /// `search` directly.
+    pub fn without_model(threshold: f64) -> Self {
+        Self {
+            threshold,
+            ..Self::new_noop(DEFAULT_PENDING_REBUILD_THRESHOLD)
         }
     }
 
-    /// Build/rebuild the HNSW index from a set of decision records.
+    /// Name the model whose embeddings this engine holds, for engines
+    /// created [`without_model`](Self::without_model).
+    pub fn with_model_name(mut self, model_name: &str) -> Self {
+        self.model_name = model_name.to_string();
+        self
+    }
+
+    /// Deny a call whose nearest match is a cached deny (off by default).
+    pub fn with_deny_on_similarity(mut self, deny_on_similarity: bool) -> Self {
+        self.deny_on_similarity = deny_on_similarity;
+        self
+    }
+
+    /// Where a project's index is saved: the gitignored `.index/` of its
+    /// configuration directory `config_dir`.
+    pub fn index_path(config_dir: &Path) -> PathBuf {
+        config_dir.join(".index").join("embeddings.jsonl")
+    }
+
+    /// Build/rebuild the HNSW index from a set of decision records. Pattern
+    /// rules are left out, as their key is a glob rather than an input.
     pub fn build_index(&self, records: &[DecisionRecord]) -> Result<()> {
+        let records: Vec<&DecisionRecord> =
+            records.iter().filter(|r| !is_pattern_rule(r)).collect();
         if records.is_empty() {
             let mut index = self.index.write().unwrap_or_else(|e| e.into_inner());
             *index = None;
             let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
             entries.clear();
+            *self.dim.write().unwrap_or_else(|e| e.into_inner()) = None;
             return Ok(());
         }
 
@@ -109,38 +228,94 @@ impl EmbeddingSimilarity {
                 })?
         };
 
-        // Build entries
+        // Build entries, leaving out degenerate embeddings
         let mut new_entries = Vec::with_capacity(records.len());
-        for (i, (record, embedding)) in records.iter().zip(embeddings.iter()).enumerate() {
+        for (record, embedding) in records.iter().zip(embeddings) {
+            if is_degenerate(&embedding) {
+                continue;
+            }
             new_entries.push(EmbeddingEntry {
-                embedding: embedding.clone(),
-                record: record.clone(),
+                embedding,
+                record: (*record).clone(),
             });
-            let _ = i; // index position matches
+        }
+        if new_entries.len() < records.len() {
+            tracing::warn!(
+                "embedding index: skipped {} records with empty or all-zero embeddings",
+                records.len() - new_entries.len()
+            );
         }
 
-        // Build HNSW index
-        let points: Vec<Point> = embeddings.iter().map(|e| Point(e.clone())).collect();
-        let values: Vec<usize> = (0..points.len()).collect();
-        let hnsw = instant_distance::Builder::default().build(points, values);
+        self.replace_entries(new_entries);
+        Ok(())
+    }
 
+    /// Replace all entries (dropping pending ones), rebuild the HNSW index
+    /// over them, and take the index dimension from them.
+    fn replace_entries(&self, new_entries: Vec<EmbeddingEntry>) {
         {
             let mut idx = self.index.write().unwrap_or_else(|e| e.into_inner());
-            *idx = Some(HnswIndex { hnsw });
+            *idx = HnswIndex::build(&new_entries);
         }
         {
-            let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
-            *entries = new_entries;
+            let mut dim = self.dim.write().unwrap_or_else(|e| e.into_inner());
+            *dim = new_entries.last().map(|e| e.embedding.len());
+        }
+        {
+            let mut pending = self
+                .pending_entries
+                .write()
+                .unwrap_or_else(|e| e.into_inner());
+            pending.clear();
         }
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        *entries = new_entries;
+    }
 
-        Ok(())
+    /// Check an embedding's length against the index dimension, adopting it
+    /// as the dimension if none is set yet.
+    fn check_dim(&self, len: usize) -> Result<()> {
+        let mut dim = self.dim.write().unwrap_or_else(|e| e.into_inner());
+        match *dim {
+            Some(expected) if expected != len => Err(HookwiseError::Embedding {
+                reason: format!(
+                    "embedding has dimension {len}, but the index has dimension {expected}"
+                ),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                *dim = Some(len);
+                Ok(())
+            }
+        }
     }
 
     /// Add a single entry to the pending buffer. Does NOT rebuild the HNSW index.
     /// Pending entries are searched via linear scan until `rebuild()` is called
-    /// or the pending buffer exceeds the threshold.
+    /// or the pending buffer exceeds the threshold. Pattern rules are skipped.
     pub fn insert(&self, record: &DecisionRecord) -> Result<()> {
+        if is_pattern_rule(record) {
+            return Ok(());
+        }
         let embedding = self.embed(&record.key.sanitized_input)?;
+        self.insert_embedding(record, embedding)
+    }
+
+    /// Add an entry with a precomputed embedding to the pending buffer.
+    /// Empty or all-zero embeddings are skipped with a warning; an embedding
+    /// whose dimension differs from the index's is an error.
+    pub fn insert_embedding(&self, record: &DecisionRecord, embedding: Vec<f32>) -> Result<()> {
+        if is_pattern_rule(record) {
+            return Ok(());
+        }
+        if is_degenerate(&embedding) {
+            tracing::warn!(
+                "embedding index: skipped {} with an empty or all-zero embedding",
+                record.key.sanitized_input
+            );
+            return Ok(());
+        }
+        self.check_dim(embedding.len())?;
 
         let should_rebuild = {
             let mut pending = self
@@ -151,7 +326,7 @@ impl EmbeddingSimilarity {
                 embedding,
                 record: record.clone(),
             });
-            pending.len() >= PENDING_REBUILD_THRESHOLD
+            pending.len() >= self.pending_threshold
         };
 
         if should_rebuild {
@@ -183,13 +358,9 @@ impl EmbeddingSimilarity {
 
         // Rebuild HNSW from all entries
         let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
-        let points: Vec<Point> = entries.iter().map(|e| Point(e.embedding.clone())).collect();
-        let values: Vec<usize> = (0..points.len()).collect();
-
-        if !points.is_empty() {
-            let hnsw = instant_distance::Builder::default().build(points, values);
+        if let Some(rebuilt) = HnswIndex::build(&entries) {
             let mut idx = self.index.write().unwrap_or_else(|e| e.into_inner());
-            *idx = Some(HnswIndex { hnsw });
+            *idx = Some(rebuilt);
         }
 
         Ok(())
@@ -219,82 +390,386 @@ impl EmbeddingSimilarity {
     }
 
     /// Search the index for the nearest neighbor.
-    /// Checks both the HNSW index and the pending entries buffer.
     /// Returns the best match above the threshold, or None.
     pub fn search(&self, query_embedding: &[f32]) -> Option<(f64, EmbeddingEntry)> {
-        let mut best: Option<(f64, EmbeddingEntry)> = None;
+        self.search_k(query_embedding, 1).into_iter().next()
+    }
+
+    /// Search for up to `k` entries whose similarity to the query meets the
+    /// threshold, best first. Checks both the HNSW index and the pending
+    /// entries buffer; on equal similarity indexed entries come first.
+    pub fn search_k(&self, query_embedding: &[f32], k: usize) -> Vec<(f64, EmbeddingEntry)> {
+        self.search_k_above(query_embedding, k, self.threshold)
+    }
+
+    /// Like [`EmbeddingSimilarity::search_k`], with `threshold` in place of
+    /// the engine's own.
+    fn search_k_above(
+        &self,
+        query_embedding: &[f32],
+        k: usize,
+        threshold: f64,
+    ) -> Vec<(f64, EmbeddingEntry)> {
+        if is_degenerate(query_embedding) || k == 0 {
+            return Vec::new();
+        }
+        let query_point = Point(query_embedding.to_vec());
+        let mut matches: Vec<(f64, EmbeddingEntry)> = Vec::new();
 
-        // 1. Search the HNSW index
+        // 1. Search the HNSW index (only comparable if dimensions agree)
         {
             let index_guard = self.index.read().unwrap_or_else(|e| e.into_inner());
-            if let Some(hnsw_index) = index_guard.as_ref() {
-                let query_point = Point(query_embedding.to_vec());
+            if let Some(hnsw_index) = index_guard
+                .as_ref()
+                .filter(|idx| idx.dim == query_embedding.len())
+            {
                 let mut search_buf = instant_distance::Search::default();
-                // Extract the first result's data before search_buf is dropped
-                let first_result = {
-                    let results = hnsw_index.hnsw.search(&query_point, &mut search_buf);
-                    results.into_iter().next().map(|r| (*r.value, r.distance))
-                };
-
-                if let Some((idx, distance)) = first_result {
+                // Extract the results' data before search_buf is dropped
+                let nearest: Vec<(usize, f32)> = hnsw_index
+                    .hnsw
+                    .search(&query_point, &mut search_buf)
+                    .take(k)
+                    .map(|r| (*r.value, r.distance))
+                    .collect();
+
+                let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+                for (idx, distance) in nearest {
                     let similarity = (1.0 - distance) as f64;
-
-                    if similarity >= self.threshold {
-                        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
-                        if let Some(entry) = entries.get(idx) {
-                            best = Some((similarity, entry.clone()));
-                        }
+                    if similarity < threshold {
+                        continue;
+                    }
+                    if let Some(entry) = entries.get(idx) {
+                        matches.push((similarity, entry.clone()));
                     }
                 }
             }
         }
 
-        // 2. Linear-scan pending entries for potentially closer matches
+        // 2. Linear-scan pending entries
         {
             let pending = self
                 .pending_entries
                 .read()
                 .unwrap_or_else(|e| e.into_inner());
-            for entry in pending.iter() {
-                let query_point = Point(query_embedding.to_vec());
+            for entry in pending
+                .iter()
+                .filter(|e| e.embedding.len() == query_embedding.len())
+            {
                 let entry_point = Point(entry.embedding.clone());
                 let distance =
                     <Point as instant_distance::Point>::distance(&query_point, &entry_point);
                 let similarity = (1.0 - distance) as f64;
 
-                if similarity >= self.threshold
-                    && best
-                        .as_ref()
-                        .is_none_or(|(best_sim, _)| similarity > *best_sim)
-                {
-                    best = Some((similarity, entry.clone()));
+                if similarity >= threshold {
+                    matches.push((similarity, entry.clone()));
                 }
             }
         }
 
-        best
+        // Stable, so indexed entries stay ahead of pending ones on ties
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+        matches.truncate(k);
+        matches
+    }
+
+    /// Name of the embedding model this engine uses.
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Dimension of the built HNSW index, if any.
+    pub fn index_dim(&self) -> Option<usize> {
+        let index = self.index.read().unwrap_or_else(|e| e.into_inner());
+        index.as_ref().map(|idx| idx.dim)
+    }
+
+    /// Dimension every entry, indexed or pending, must have. None until the
+    /// first embedding is added.
+    pub fn dim(&self) -> Option<usize> {
+        *self.dim.read().unwrap_or_else(|e| e.into_inner())
     }
 
-    /// Save the HNSW index to disk.
-    pub fn save_index(&self, _path: &std::path::Path) -> Result<()> {
-        // instant-distance doesn't have built-in serialization (serde feature is broken).
-        // Store the entries as JSONL and rebuild on load.
-        // This is a known limitation documented in the ADR.
+    /// Report entry counts, index state, and model availability.
+    pub fn health(&self) -> IndexHealth {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner()).len();
+        let pending = self
+            .pending_entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .len();
+        IndexHealth {
+            model_available: self.model.is_some(),
+            entries,
+            pending,
+            index_dim: self.index_dim(),
+        }
+    }
+
+    /// Re-embed every known record with the current model and rebuild the
+    /// index. Used when the index was built by a model with a different
+    /// output dimension.
+    fn reembed_all(&self) -> Result<()> {
+        let records: Vec<DecisionRecord> = {
+            let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+            let mut pending = self
+                .pending_entries
+                .write()
+                .unwrap_or_else(|e| e.into_inner());
+            entries
+                .iter()
+                .chain(pending.drain(..).collect::<Vec<_>>().iter())
+                .map(|e| e.record.clone())
+                .collect()
+        };
+        self.build_index(&records)
+    }
+
+    /// Save the index entries, pending ones included, to `path` as JSONL,
+    /// after a header line with the model name. instant-distance cannot
+    /// serialize the graph itself, so the embeddings are stored and the graph
+    /// is rebuilt from them on load.
+    pub fn save_index(&self, path: &std::path::Path) -> Result<()> {
+        let (store, name) = index_store(path)?;
+        let mut data = Vec::new();
+        serde_json::to_writer(
+            &mut data,
+            &IndexHeader {
+                model: self.model_name.clone(),
+            },
+        )?;
+        data.push(b'\n');
+        {
+            let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+            let pending = self
+                .pending_entries
+                .read()
+                .unwrap_or_else(|e| e.into_inner());
+            for entry in entries.iter().chain(pending.iter()) {
+                serde_json::to_writer(&mut data, entry)?;
+                data.push(b'\n');
+            }
+        }
+        store.save(&name, &data)
+    }
+
+    /// Replace the index with the entries saved at `path`, rebuilding the
+    /// graph from the stored embeddings without calling the model. A missing
+    /// file leaves the index as it is.
+    ///
+    /// If the index was saved by another model (or by a version that did not
+    /// record one), or the stored embeddings differ in dimension from each
+    /// other or from the live model's output, every record is re-embedded
+    /// instead; without a model that is an error.
+    pub fn load_index(&self, path: &std::path::Path) -> Result<()> {
+        let Some((saved_model, loaded)) = read_saved_index(path)? else {
+            return Ok(());
+        };
+
+        let Some(first) = loaded.first() else {
+            self.replace_entries(loaded);
+            return Ok(());
+        };
+        // A no-op tier has no name and takes whatever was saved
+        if !self.model_name.is_empty() && saved_model.as_deref() != Some(&self.model_name) {
+            let saved = saved_model.as_deref().unwrap_or("an unnamed model");
+            if self.model.is_none() {
+                return Err(HookwiseError::Embedding {
+                    reason: format!(
+                        "saved index {} was built by {}, not '{}', and no model is available to re-embed it",
+                        path.display(),
+                        saved,
+                        self.model_name
+                    ),
+                });
+            }
+            tracing::warn!(
+                "saved index {} was built by {}, not '{}'; re-embedding",
+                path.display(),
+                saved,
+                self.model_name
+            );
+            let records: Vec<DecisionRecord> = loaded.into_iter().map(|e| e.record).collect();
+            return self.build_index(&records);
+        }
+        let expected = match self.model {
+            Some(_) => self.embed(&first.record.key.sanitized_input)?.len(),
+            None => first.embedding.len(),
+        };
+        if loaded.iter().any(|e| e.embedding.len() != expected) {
+            if self.model.is_none() {
+                return Err(HookwiseError::Embedding {
+                    reason: format!(
+                        "saved index {} mixes embedding dimensions and no model is available to re-embed it",
+                        path.display()
+                    ),
+                });
+            }
+            tracing::warn!(
+                "saved index {} does not match model '{}' output ({}); re-embedding",
+                path.display(),
+                self.model_name,
+                expected
+            );
+            let records: Vec<DecisionRecord> = loaded.into_iter().map(|e| e.record).collect();
+            return self.build_index(&records);
+        }
+
+        self.replace_entries(loaded);
         Ok(())
     }
 
-    /// Load the HNSW index from disk.
-    pub fn load_index(&self, _path: &std::path::Path) -> Result<()> {
-        // See save_index comment -- rebuild from JSONL entries instead.
+    /// Load the index saved at `path` if it holds exactly `records`, and
+    /// otherwise build it from them and save it for the next process. A
+    /// saved index that can't be loaded is rebuilt over.
+    pub fn load_or_build_index(&self, path: &Path, records: &[DecisionRecord]) -> Result<()> {
+        match self.load_index(path) {
+            Ok(()) if self.holds_exactly(records) => return Ok(()),
+            Ok(()) => {}
+            Err(e) => tracing::warn!("rebuilding embedding index {}: {}", path.display(), e),
+        }
+        self.build_index(records)?;
+        if let Err(e) = self.save_index(path) {
+            tracing::warn!("could not save embedding index {}: {}", path.display(), e);
+        }
         Ok(())
     }
 
-    /// Remove all entries for a specific role and rebuild.
+    /// Whether the entries, pending ones included, are those of `records`
+    /// that get indexed.
+    fn holds_exactly(&self, records: &[DecisionRecord]) -> bool {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        let pending = self
+            .pending_entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        let held: HashSet<&str> = entries
+            .iter()
+            .chain(pending.iter())
+            .map(|e| e.record.id.as_str())
+            .collect();
+        held == records
+            .iter()
+            .filter(|r| !is_pattern_rule(r))
+            .map(|r| r.id.as_str())
+            .collect()
+    }
+
+    /// Health of the index saved at `path`, read without loading a model.
+    /// A missing file reads as an index that was never built.
+    pub fn saved_health(path: &Path) -> Result<IndexHealth> {
+        let entries = read_saved_index(path)?
+            .map(|(_, entries)| entries)
+            .unwrap_or_default();
+        Ok(IndexHealth {
+            model_available: true,
+            entries: entries.len(),
+            pending: 0,
+            index_dim: entries.last().map(|e| e.embedding.len()),
+        })
+    }
+
+    /// Decide a call from its precomputed query embedding: the nearest match
+    /// applies if it is for the same tool and role (or the wildcard role).
+    pub fn evaluate_embedding(
+        &self,
+        input: &CascadeInput,
+        query_embedding: &[f32],
+    ) -> Result<Option<DecisionRecord>> {
+        let result = self.match_embedding(input, query_embedding);
+        self.metrics.record(&result);
+        result
+    }
+
+    /// Matches and fall-throughs since the tier was created.
+    pub fn tier_metrics(&self) -> TierMetrics {
+        self.metrics.snapshot()
+    }
+
+    fn match_embedding(
+        &self,
+        input: &CascadeInput,
+        query_embedding: &[f32],
+    ) -> Result<Option<DecisionRecord>> {
+        let threshold = input
+            .session
+            .role
+            .as_ref()
+            .and_then(|r| r.similarity.as_ref())
+            .and_then(|s| s.embedding_threshold)
+            .unwrap_or(self.threshold);
+        let Some((similarity, entry)) = self
+            .search_k_above(query_embedding, 1, threshold)
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let role_name = input
+            .session
+            .role
+            .as_ref()
+            .map(|r| r.name.as_str())
+            .unwrap_or("*");
+
+        // Only match same role or wildcard
+        if entry.record.key.role != role_name && entry.record.key.role != "*" {
+            return Ok(None);
+        }
+        // Only match same tool
+        if entry.record.key.tool != input.tool_name {
+            return Ok(None);
+        }
+
+        // Similarity behavior: allow auto-approves, ask escalates, deny falls
+        // through unless deny_on_similarity is set
+        let decision = entry.record.decision;
+        if decision == Decision::Deny && !self.deny_on_similarity {
+            return Ok(None);
+        }
+        let mut reason = format!(
+            "embedding cosine similarity {:.3} >= {:.3} with cached {}",
+            similarity, threshold, decision
+        );
+        if decision == Decision::Deny {
+            reason.push_str(" (similarity.deny_on_match is set)");
+        }
+        Ok(Some(DecisionRecord {
+            key: CacheKey {
+                sanitized_input: input.sanitized_input.clone(),
+                tool: input.tool_name.clone(),
+                role: role_name.to_string(),
+            },
+            decision,
+            metadata: DecisionMetadata {
+                tier: DecisionTier::EmbeddingSimilarity,
+                confidence: similarity,
+                reason,
+                matched_key: Some(entry.record.key.clone()),
+                similarity_score: Some(similarity),
+            },
+            timestamp: Utc::now(),
+            scope: entry.record.scope,
+            file_path: input.file_path.clone(),
+            session_id: String::new(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
+        }))
+    }
+
+    /// Remove all entries for a specific role (indexed or pending) and rebuild.
     pub fn invalidate_role(&self, role: &str) -> Result<()> {
         let remaining: Vec<DecisionRecord> = {
             let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+            let mut pending = self
+                .pending_entries
+                .write()
+                .unwrap_or_else(|e| e.into_inner());
             entries
                 .iter()
+                .chain(pending.drain(..).collect::<Vec<_>>().iter())
                 .filter(|e| e.record.key.role != role)
                 .map(|e| e.record.clone())
                 .collect()
@@ -313,9 +788,49 @@ impl EmbeddingSimilarity {
             .write()
             .unwrap_or_else(|e| e.into_inner());
         pending.clear();
+        *self.dim.write().unwrap_or_else(|e| e.into_inner()) = None;
     }
 }
 
+/// The model name and usable entries of the index saved at `path`, or
+/// `None` if there is no saved index.
+fn read_saved_index(path: &Path) -> Result<Option<(Option<String>, Vec<EmbeddingEntry>)>> {
+    let (store, name) = index_store(path)?;
+    let Some(data) = store.load(&name)? else {
+        return Ok(None);
+    };
+    let mut saved_model = None;
+    let mut loaded = Vec::new();
+    for line in String::from_utf8_lossy(&data).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if saved_model.is_none() && loaded.is_empty() {
+            if let Ok(header) = serde_json::from_str::<IndexHeader>(line) {
+                saved_model = Some(header.model);
+                continue;
+            }
+        }
+        let entry: EmbeddingEntry = serde_json::from_str(line)?;
+        if !is_degenerate(&entry.embedding) {
+            loaded.push(entry);
+        }
+    }
+    Ok(Some((saved_model, loaded)))
+}
+
+/// Split an index file path into a store over its directory and the file name.
+fn index_store(path: &std::path::Path) -> Result<(HnswIndexStore, String)> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| HookwiseError::Storage {
+            reason: format!("invalid index path {}", path.display()),
+        })?;
+    let dir = path.parent().unwrap_or(std::path::Path::new("."));
+    Ok((HnswIndexStore::new(dir.to_path_buf()), name.to_string()))
+}
+
 #[async_trait]
 impl CascadeTier for EmbeddingSimilarity {
     async fn evaluate(&self, input: &CascadeInput) -> Result<Option<DecisionRecord>> {
@@ -327,60 +842,26 @@ impl CascadeTier for EmbeddingSimilarity {
                 .read()
                 .unwrap_or_else(|e| e.into_inner());
             if index.is_none() && pending.is_empty() {
-                return Ok(None);
+                let result = Ok(None);
+                self.metrics.record(&result);
+                return result;
             }
         }
 
         let query_embedding = self.embed(&input.sanitized_input)?;
-        let result = self.search(&query_embedding);
-
-        match result {
-            Some((similarity, entry)) => {
-                let role_name = input
-                    .session
-                    .role
-                    .as_ref()
-                    .map(|r| r.name.as_str())
-                    .unwrap_or("*");
-
-                // Only match same role or wildcard
-                if entry.record.key.role != role_name && entry.record.key.role != "*" {
-                    return Ok(None);
-                }
-                // Only match same tool
-                if entry.record.key.tool != input.tool_name {
-                    return Ok(None);
-                }
 
-                // Similarity behavior: allow auto-approves, deny falls through, ask escalates
-                match entry.record.decision {
-                    Decision::Deny => Ok(None),
-                    Decision::Allow | Decision::Ask => Ok(Some(DecisionRecord {
-                        key: CacheKey {
-                            sanitized_input: input.sanitized_input.clone(),
-                            tool: input.tool_name.clone(),
-                            role: role_name.to_string(),
-                        },
-                        decision: entry.record.decision,
-                        metadata: DecisionMetadata {
-                            tier: DecisionTier::EmbeddingSimilarity,
-                            confidence: similarity,
-                            reason: format!(
-                                "embedding cosine similarity {:.3} >= {:.3} with cached {}",
-                                similarity, self.threshold, entry.record.decision
-                            ),
-                            matched_key: Some(entry.record.key.clone()),
-                            similarity_score: Some(similarity),
-                        },
-                        timestamp: Utc::now(),
-                        scope: entry.record.scope,
-                        file_path: input.file_path.clone(),
-                        session_id: String::new(),
-                    })),
-                }
-            }
-            None => Ok(None),
+        // An index built by a different model cannot be searched with this
+        // query; rebuild it rather than returning meaningless distances.
+        if self.dim().is_some_and(|dim| dim != query_embedding.len()) {
+            tracing::warn!(
+                "embedding index dimension differs from model '{}' output ({}); rebuilding",
+                self.model_name,
+                query_embedding.len()
+            );
+            self.reembed_all()?;
         }
+
+        self.evaluate_embedding(input, &query_embedding)
     }
 
     fn tier(&self) -> DecisionTier {
@@ -0,0 +1,117 @@
+use crate::decision::{Decision, DecisionRecord, DecisionTier};
+use crate::error::Result;
+use crate::session::SessionContext;
+
+use super::{CascadeInput, CascadeRunner, CascadeTier};
+
+/// What one tier did with a traced call.
+#[derive(Debug, Clone)]
+pub enum TierOutcome {
+    /// The tier resolved the call; the trace stops here.
+    Resolved(DecisionRecord),
+    /// A similarity match that missed the confidence floor of its scope, so
+    /// the call fell through anyway.
+    BelowFloor { record: DecisionRecord, floor: f64 },
+    /// The tier had no opinion.
+    FellThrough,
+}
+
+/// One step of a [`CascadeTrace`].
+#[derive(Debug, Clone)]
+pub struct TierStep {
+    pub name: String,
+    pub tier: DecisionTier,
+    pub outcome: TierOutcome,
+}
+
+/// Every tier a call passed through, in cascade order.
+#[derive(Debug, Clone)]
+pub struct CascadeTrace {
+    /// The tool input after sanitization, as the tiers matched on it.
+    pub sanitized_input: String,
+    /// Set when the input was decided before reaching any tier (empty or
+    /// bulk-redacted input), with the reason.
+    pub short_circuit: Option<(Decision, &'static str)>,
+    pub steps: Vec<TierStep>,
+}
+
+impl CascadeTrace {
+    /// The record of the tier that resolved the call, if a local tier did.
+    pub fn resolved(&self) -> Option<&DecisionRecord> {
+        self.steps.iter().find_map(|step| match &step.outcome {
+            TierOutcome::Resolved(record) => Some(record),
+            _ => None,
+        })
+    }
+
+    /// The tier that resolved the call, if a local tier did.
+    pub fn decider(&self) -> Option<DecisionTier> {
+        self.resolved().map(|record| record.metadata.tier)
+    }
+}
+
+impl CascadeRunner {
+    /// Run a tool call through the local tiers and record what each one
+    /// did, stopping at the first that resolves it.
+    ///
+    /// Like [`CascadeRunner::replay`], this is read-only: the supervisor and
+    /// human tiers are never contacted, and nothing is persisted or inserted
+    /// into the caches.
+    pub async fn trace(
+        &self,
+        session: &SessionContext,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        cwd: Option<&str>,
+    ) -> Result<CascadeTrace> {
+        let raw_input = serde_json::to_string(tool_input).unwrap_or_default();
+        let sanitized_input = self.sanitizer.sanitize_tool_input(tool_name, &raw_input);
+        let mut trace = CascadeTrace {
+            short_circuit: self.unmatchable(tool_name, tool_input, &sanitized_input),
+            sanitized_input,
+            steps: Vec::new(),
+        };
+        if trace.short_circuit.is_some() {
+            return Ok(trace);
+        }
+
+        let input = CascadeInput {
+            session: session.clone(),
+            tool_name: tool_name.to_string(),
+            tool_input: tool_input.clone(),
+            sanitized_input: trace.sanitized_input.clone(),
+            file_path: Self::extract_file_path(tool_name, tool_input),
+            cwd: cwd.map(String::from),
+        };
+
+        let tiers: Vec<&dyn CascadeTier> = vec![
+            self.path_policy.as_ref(),
+            self.exact_cache.as_ref(),
+            self.token_jaccard.as_ref(),
+            self.embedding_similarity.as_ref(),
+        ];
+        for tier in tiers {
+            let outcome = match tier.evaluate(&input).await? {
+                Some(record) => match self.missed_confidence_floor(&input, &record) {
+                    Some(floor) => TierOutcome::BelowFloor { record, floor },
+                    None => {
+                        let mut record = record;
+                        self.normalize_record(&mut record);
+                        TierOutcome::Resolved(record)
+                    }
+                },
+                None => TierOutcome::FellThrough,
+            };
+            let resolved = matches!(outcome, TierOutcome::Resolved(_));
+            trace.steps.push(TierStep {
+                name: tier.name().to_string(),
+                tier: tier.tier(),
+                outcome,
+            });
+            if resolved {
+                break;
+            }
+        }
+        Ok(trace)
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
 use async_trait::async_trait;
@@ -10,7 +11,9 @@ use crate::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
 };
 use crate::error::{HookwiseError, Result};
+use crate::ipc::pending_queue::{deserialize_pending, serialize_pending};
 use crate::scope::ScopeLevel as ScopeLevelType;
+use crate::session::set_file_permissions_0600;
 
 /// A pending decision waiting for human response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +45,9 @@ pub struct HumanResponse {
     pub always_ask: bool,
     pub add_rule: bool,
     pub rule_scope: Option<ScopeLevelType>,
+    /// Allow this one call only: nothing is cached or written to storage.
+    #[serde(default)]
+    pub once: bool,
 }
 
 /// File-backed queue state persisted to disk so separate CLI processes can interact.
@@ -55,10 +61,20 @@ pub struct QueueFileState {
 /// Includes CLAUDE_TEAM_ID in the filename to isolate per-team state
 /// and prevent cross-process interference when multiple teams run concurrently.
 pub fn pending_queue_path() -> PathBuf {
+    runtime_queue_file("hookwise-pending")
+}
+
+/// Returns the path the supervisor saves its pending decisions to on
+/// shutdown, per team like `pending_queue_path`.
+pub fn persisted_queue_path() -> PathBuf {
+    runtime_queue_file("hookwise-pending-saved")
+}
+
+fn runtime_queue_file(stem: &str) -> PathBuf {
     let team_suffix = std::env::var("CLAUDE_TEAM_ID")
         .map(|id| format!("-{}", id))
         .unwrap_or_default();
-    let filename = format!("hookwise-pending{}.json", team_suffix);
+    let filename = format!("{}{}.json", stem, team_suffix);
 
     if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
         PathBuf::from(runtime_dir).join(filename)
@@ -190,6 +206,52 @@ impl DecisionQueue {
         let mut completed = self.completed.write().unwrap_or_else(|e| e.into_inner());
         completed.remove(id)
     }
+
+    /// Save the decisions this process is still waiting on to `path`,
+    /// oldest first, readable by the owner only.
+    pub fn persist(&self, path: &Path) -> Result<()> {
+        let mut pending: Vec<PendingDecision> = self
+            .pending
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        pending.sort_by_key(|decision| decision.queued_at);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Created owner-only so the queue is never readable by others, even
+        // briefly; an existing file is tightened after the write
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        file.write_all(serialize_pending(&pending)?.as_bytes())?;
+        set_file_permissions_0600(path);
+        Ok(())
+    }
+
+    /// A queue holding the decisions saved at `path` by `persist`. They are
+    /// enqueued again, so the `queue` command lists them. A missing file
+    /// gives an empty queue.
+    pub fn load(path: &Path) -> Result<Self> {
+        let queue = Self::new();
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(queue),
+            Err(e) => return Err(e.into()),
+        };
+        for decision in deserialize_pending(&data)? {
+            queue.enqueue(decision);
+        }
+        Ok(queue)
+    }
 }
 
 /// Tier 4: Human-in-the-loop.
@@ -252,6 +314,17 @@ impl crate::cascade::CascadeTier for HumanTier {
         } else {
             response.decision
         };
+        let (tier, reason) = if response.once {
+            (
+                DecisionTier::HumanOnce,
+                format!("human decision: {} (once)", response.decision),
+            )
+        } else {
+            (
+                DecisionTier::Human,
+                format!("human decision: {}", response.decision),
+            )
+        };
 
         Ok(Some(DecisionRecord {
             key: CacheKey {
@@ -261,9 +334,9 @@ impl crate::cascade::CascadeTier for HumanTier {
             },
             decision: effective_decision,
             metadata: DecisionMetadata {
-                tier: DecisionTier::Human,
+                tier,
                 confidence: 1.0,
-                reason: format!("human decision: {}", response.decision),
+                reason,
                 matched_key: None,
                 similarity_score: None,
             },
@@ -271,6 +344,11 @@ impl crate::cascade::CascadeTier for HumanTier {
             scope: response.rule_scope.unwrap_or(ScopeLevel::Project),
             file_path: input.file_path.clone(),
             session_id: String::new(), // Filled by CascadeRunner
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
         }))
     }
 
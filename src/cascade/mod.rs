@@ -1,20 +1,27 @@
 pub mod cache;
 pub mod embed_sim;
+pub mod explain;
 pub mod human;
 pub mod path_policy;
+pub mod replay;
 pub mod supervisor;
 pub mod token_sim;
 
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
 };
-use crate::error::Result;
+use crate::error::{HookwiseError, Result};
 use crate::session::SessionContext;
+use crate::storage::RuleUsage;
 
 /// Input to each cascade tier.
 #[derive(Debug, Clone)]
@@ -42,6 +49,129 @@ pub trait CascadeTier: Send + Sync {
     fn name(&self) -> &str;
 }
 
+/// How often a tier resolved a call and how often it let one fall through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierMetrics {
+    pub matches: u64,
+    pub fall_throughs: u64,
+}
+
+impl TierMetrics {
+    fn add(&mut self, other: &TierMetrics) {
+        self.matches += other.matches;
+        self.fall_throughs += other.fall_throughs;
+    }
+}
+
+/// Running counts behind a tier's [`TierMetrics`].
+#[derive(Debug, Default)]
+pub struct TierCounters {
+    matches: AtomicU64,
+    fall_throughs: AtomicU64,
+}
+
+impl TierCounters {
+    /// Count one evaluation by its outcome. Errors count as neither.
+    pub fn record<T>(&self, outcome: &Result<Option<T>>) {
+        match outcome {
+            Ok(Some(_)) => self.matches.fetch_add(1, Ordering::Relaxed),
+            Ok(None) => self.fall_throughs.fetch_add(1, Ordering::Relaxed),
+            Err(_) => return,
+        };
+    }
+
+    pub fn snapshot(&self) -> TierMetrics {
+        TierMetrics {
+            matches: self.matches.load(Ordering::Relaxed),
+            fall_throughs: self.fall_throughs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per-tier counts for the cache and similarity tiers of one runner, since
+/// it was built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CascadeMetrics {
+    pub exact_cache: TierMetrics,
+    pub token_jaccard: TierMetrics,
+    pub embedding_similarity: TierMetrics,
+}
+
+impl CascadeMetrics {
+    /// Where a project's running totals are kept: the gitignored `.index/`
+    /// of its configuration directory `config_dir`.
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(".index").join("metrics.json")
+    }
+
+    /// The totals saved at `path`, or `None` if none have been saved.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Add these counts to the totals saved at `path`, so each short-lived
+    /// `check` process adds to one running total. The file is rewritten
+    /// under a lock and replaced by rename.
+    pub fn add_to_file(&self, path: &Path) -> Result<()> {
+        if *self == Self::default() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let _lock = crate::session::registration::FileLock::acquire(path)?;
+
+        let mut totals = Self::load(path)?.unwrap_or_default();
+        totals.exact_cache.add(&self.exact_cache);
+        totals.token_jaccard.add(&self.token_jaccard);
+        totals.embedding_similarity.add(&self.embedding_similarity);
+
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&totals)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// How many rules may have unsaved hits before they are written back to
+/// storage.
+pub const USAGE_FLUSH_BATCH: usize = 32;
+
+/// Hits on stored rules that have not been written back yet.
+#[derive(Debug, Default)]
+pub struct UsageLog {
+    pending: Mutex<HashMap<(CacheKey, ScopeLevel), RuleUsage>>,
+}
+
+impl UsageLog {
+    /// Count a hit at `at` on the rule `key` stored at `scope`. Returns how
+    /// many rules now have unsaved hits.
+    pub fn record(&self, key: &CacheKey, scope: ScopeLevel, at: DateTime<Utc>) -> usize {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = pending
+            .entry((key.clone(), scope))
+            .or_insert_with(|| RuleUsage {
+                key: key.clone(),
+                scope,
+                hits: 0,
+                last_used: at,
+            });
+        usage.hits += 1;
+        usage.last_used = usage.last_used.max(at);
+        pending.len()
+    }
+
+    /// Remove and return every unsaved hit.
+    pub fn take(&self) -> Vec<RuleUsage> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.drain().map(|(_, usage)| usage).collect()
+    }
+}
+
 /// The complete cascade runner. Evaluates tiers in order until one resolves.
 pub struct CascadeRunner {
     pub sanitizer: crate::sanitize::SanitizePipeline,
@@ -55,9 +185,28 @@ pub struct CascadeRunner {
     pub policy: crate::config::PolicyConfig,
     /// Normalizes file paths to `category:relative` form for portable storage.
     pub normalizer: Option<crate::config::roles::PathNormalizer>,
+    /// Compute decisions without acting on them: nothing is persisted or
+    /// cached, and calls that would go to a human resolve to Ask instead of
+    /// being queued.
+    pub dry_run: bool,
+    /// Rule hits waiting to be written back to `storage`.
+    pub usage: UsageLog,
+    /// Cross-scope resolver held next to the caches by a long-running
+    /// process, refreshed with them on `invalidate_role`.
+    pub scopes: Option<Arc<crate::scope::ScopeResolver>>,
 }
 
 impl CascadeRunner {
+    /// Snapshot of how often each cache and similarity tier has resolved
+    /// calls.
+    pub fn metrics(&self) -> CascadeMetrics {
+        CascadeMetrics {
+            exact_cache: self.exact_cache.tier_metrics(),
+            token_jaccard: self.token_jaccard.tier_metrics(),
+            embedding_similarity: self.embedding_similarity.tier_metrics(),
+        }
+    }
+
     /// Run the full cascade for a tool call.
     pub async fn evaluate(
         &self,
@@ -79,11 +228,45 @@ impl CascadeRunner {
     ) -> Result<DecisionRecord> {
         // Sanitize the tool input
         let raw_input = serde_json::to_string(tool_input).unwrap_or_default();
-        let sanitized_input = self.sanitizer.sanitize(&raw_input);
+        let sanitized_input = self.sanitizer.sanitize_tool_input(tool_name, &raw_input);
 
         // Extract file path from tool input
         let file_path = Self::extract_file_path(tool_name, tool_input);
 
+        if let Some((decision, reason)) = self.unmatchable(tool_name, tool_input, &sanitized_input)
+        {
+            let mut record = DecisionRecord {
+                key: CacheKey {
+                    sanitized_input,
+                    tool: tool_name.to_string(),
+                    role: session
+                        .role
+                        .as_ref()
+                        .map(|r| r.name.clone())
+                        .unwrap_or_else(|| "*".to_string()),
+                },
+                decision,
+                metadata: DecisionMetadata {
+                    tier: DecisionTier::Default,
+                    confidence: 1.0,
+                    reason: reason.to_string(),
+                    matched_key: None,
+                    similarity_score: None,
+                },
+                timestamp: Utc::now(),
+                scope: ScopeLevel::Project,
+                file_path,
+                session_id: format!("{}/{}/{}", session.org, session.project, session.user),
+                tags: session.tags.clone(),
+                immutable: false,
+                id: String::new(),
+                last_used: None,
+                hit_count: 0,
+            };
+            self.normalize_record(&mut record);
+            return Ok(record);
+        }
+
         let input = CascadeInput {
             session: session.clone(),
             tool_name: tool_name.to_string(),
@@ -95,17 +278,30 @@ impl CascadeRunner {
 
         // Run tiers in order: path_policy -> exact_cache -> token_jaccard ->
         // embedding_similarity -> supervisor -> human
-        let tiers: Vec<&dyn CascadeTier> = vec![
+        let mut tiers: Vec<&dyn CascadeTier> = vec![
             self.path_policy.as_ref(),
             self.exact_cache.as_ref(),
             self.token_jaccard.as_ref(),
             self.embedding_similarity.as_ref(),
             self.supervisor.as_ref(),
-            self.human.as_ref(),
         ];
+        if !self.dry_run {
+            tiers.push(self.human.as_ref());
+        }
 
         for tier in &tiers {
             if let Some(mut record) = tier.evaluate(&input).await? {
+                if let Some(floor) = self.missed_confidence_floor(&input, &record) {
+                    tracing::debug!(
+                        "{}: confidence {:.3} below {} floor {:.3}, falling through",
+                        tier.name(),
+                        record.metadata.confidence,
+                        record.scope,
+                        floor
+                    );
+                    continue;
+                }
+
                 // Fill in session_id on all records
                 if record.session_id.is_empty() {
                     // Use a session identifier from the context
@@ -114,15 +310,36 @@ impl CascadeRunner {
                         input.session.org, input.session.project, input.session.user
                     );
                 }
+                if record.tags.is_empty() {
+                    record.tags = input.session.tags.clone();
+                }
 
                 // Normalize file_path to category:relative form for portable storage
                 self.normalize_record(&mut record);
 
+                if self.dry_run {
+                    return Ok(record);
+                }
+
+                if let Some(key) = &record.metadata.matched_key {
+                    if matches!(
+                        record.metadata.tier,
+                        DecisionTier::ExactCache
+                            | DecisionTier::TokenJaccard
+                            | DecisionTier::EmbeddingSimilarity
+                    ) {
+                        self.note_rule_use(key, record.scope);
+                    }
+                }
+
                 // Persist decisions from tiers that produce new decisions
                 match record.metadata.tier {
                     DecisionTier::ExactCache => {
                         // Already in exact cache -- no need to persist again
                     }
+                    DecisionTier::HumanOnce => {
+                        // One-shot grant: applies to this call only
+                    }
                     DecisionTier::TokenJaccard | DecisionTier::EmbeddingSimilarity => {
                         // Similarity tiers: insert into exact cache to prevent
                         // "ask drift" where repeated similar commands might match
@@ -146,17 +363,31 @@ impl CascadeRunner {
             .map(|r| r.name.clone())
             .unwrap_or_else(|| "*".to_string());
 
+        // A dry run stops short of the human, who would have been asked
+        let (decision, tier, reason) = if self.dry_run {
+            (
+                Decision::Ask,
+                DecisionTier::Human,
+                "dry run: would be queued for a human",
+            )
+        } else {
+            (
+                Decision::Deny,
+                DecisionTier::Default,
+                "no cascade tier resolved; default deny",
+            )
+        };
         let mut record = DecisionRecord {
             key: CacheKey {
                 sanitized_input: input.sanitized_input,
                 tool: tool_name.to_string(),
                 role: role_name,
             },
-            decision: Decision::Deny,
+            decision,
             metadata: DecisionMetadata {
-                tier: DecisionTier::Default,
+                tier,
                 confidence: 1.0,
-                reason: "no cascade tier resolved; default deny".to_string(),
+                reason: reason.to_string(),
                 matched_key: None,
                 similarity_score: None,
             },
@@ -164,13 +395,59 @@ impl CascadeRunner {
             scope: ScopeLevel::Project,
             file_path: input.file_path,
             session_id: format!("{}/{}/{}", session.org, session.project, session.user),
+            tags: session.tags.clone(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
         };
 
         self.normalize_record(&mut record);
-        self.persist_decision(&record).await?;
+        if !self.dry_run {
+            self.persist_decision(&record).await?;
+        }
         Ok(record)
     }
 
+    /// The decision for input that must not reach the cache or similarity
+    /// tiers, and why. Empty input carries nothing to match on, and
+    /// bulk-redacted input has lost it.
+    fn unmatchable(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        sanitized_input: &str,
+    ) -> Option<(Decision, &'static str)> {
+        if Self::is_empty_input(tool_name, tool_input) {
+            Some((self.policy.empty_input_decision, "empty tool input"))
+        } else if sanitized_input == crate::sanitize::BULK_REDACTION_MARKER {
+            Some((Decision::Ask, "input redacted in bulk"))
+        } else {
+            None
+        }
+    }
+
+    /// The floor a similarity match fell short of, if any. A match must clear
+    /// the confidence floor of the scope its cached record came from.
+    fn missed_confidence_floor(
+        &self,
+        input: &CascadeInput,
+        record: &DecisionRecord,
+    ) -> Option<f64> {
+        if !matches!(
+            record.metadata.tier,
+            DecisionTier::TokenJaccard | DecisionTier::EmbeddingSimilarity
+        ) {
+            return None;
+        }
+        let floor = self
+            .policy
+            .confidence
+            .for_role(input.session.role.as_ref())
+            .floor(record.scope);
+        (record.metadata.confidence < floor).then_some(floor)
+    }
+
     /// Extract file path from tool input for file-related tools.
     fn extract_file_path(tool_name: &str, tool_input: &serde_json::Value) -> Option<String> {
         match tool_name {
@@ -196,17 +473,73 @@ impl CascadeRunner {
         }
     }
 
-    /// Normalize a decision record's file_path using the category normalizer.
+    /// Whether a tool call's input is empty: the tool's primary field (e.g.
+    /// `command` for Bash) is missing or whitespace-only, or, for tools
+    /// without a known primary field, the whole input is null or empty.
+    fn is_empty_input(tool_name: &str, tool_input: &serde_json::Value) -> bool {
+        let primary = match tool_name {
+            "Bash" => Some("command"),
+            "Write" | "Edit" | "Read" => Some("file_path"),
+            "Glob" | "Grep" => Some("pattern"),
+            "NotebookEdit" => Some("notebook_path"),
+            _ => None,
+        };
+        let is_blank = |v: &serde_json::Value| match v {
+            serde_json::Value::Null => true,
+            serde_json::Value::String(s) => s.trim().is_empty(),
+            serde_json::Value::Object(map) => map.is_empty(),
+            _ => false,
+        };
+
+        match primary {
+            Some(field) => tool_input.get(field).is_none_or(is_blank),
+            None => is_blank(tool_input),
+        }
+    }
+
+    /// Assign a decision record's id and normalize its file_path using the
+    /// category normalizer.
     fn normalize_record(&self, record: &mut DecisionRecord) {
+        record.ensure_id();
         if let (Some(normalizer), Some(ref path)) = (&self.normalizer, &record.file_path) {
             record.file_path = Some(normalizer.normalize(path));
         }
     }
 
+    /// Count a hit on the stored rule a call was resolved from, writing
+    /// hits back once [`USAGE_FLUSH_BATCH`] rules have some pending. A failed
+    /// write-back is logged; the hits are dropped rather than fail the call.
+    fn note_rule_use(&self, key: &CacheKey, scope: ScopeLevel) {
+        if self.usage.record(key, scope, Utc::now()) >= USAGE_FLUSH_BATCH {
+            if let Err(e) = self.flush_usage() {
+                tracing::warn!("could not record rule usage: {}", e);
+            }
+        }
+    }
+
+    /// Write every pending rule hit back to storage, so `last_used` and
+    /// `hit_count` survive the process. Short-lived callers should call this
+    /// before exiting.
+    pub fn flush_usage(&self) -> Result<()> {
+        let usage = self.usage.take();
+        if usage.is_empty() {
+            return Ok(());
+        }
+        self.storage.record_usage(&usage)
+    }
+
     /// Persist a decision to storage and update in-memory caches.
     async fn persist_decision(&self, record: &DecisionRecord) -> Result<()> {
-        // 1. Save to JSONL storage (file_path already normalized by caller)
-        self.storage.save_decision(record)?;
+        // 1. Save to JSONL storage (file_path already normalized by caller).
+        // A decision shadowed by a pinned rule is returned but not learned.
+        match self.storage.save_decision(record) {
+            Ok(()) => {}
+            Err(e @ HookwiseError::ImmutableDecision { .. }) => {
+                tracing::warn!("not persisting decision: {}", e);
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
 
         // 2. Update exact cache
         self.exact_cache.insert(record.clone());
@@ -222,4 +555,41 @@ impl CascadeRunner {
 
         Ok(())
     }
+
+    /// Drop every in-memory entry for `role` and reload that role's
+    /// decisions from storage at every scope, since the tiers don't track
+    /// which scope an entry came from. Lets a long-running process pick up
+    /// `hookwise invalidate --role` without a restart.
+    pub fn invalidate_role(&self, role: &str) -> Result<()> {
+        self.exact_cache.invalidate_role(role);
+        self.token_jaccard.invalidate_role(role);
+        if let Err(e) = self.embedding_similarity.invalidate_role(role) {
+            eprintln!("hookwise: embedding index invalidation failed: {}", e);
+        }
+        if let Some(scopes) = &self.scopes {
+            scopes.invalidate_role(role)?;
+        }
+
+        // Scopes sharing a directory (JSONL's Role and Project) load the same
+        // records, so keep each id once
+        let mut seen = HashSet::new();
+        let mut records = Vec::new();
+        for &scope in &crate::scope::SCOPES {
+            for record in self.storage.load_decisions(scope)? {
+                if record.key.role == role && seen.insert(record.id.clone()) {
+                    records.push(record);
+                }
+            }
+        }
+        for record in &records {
+            self.exact_cache.insert(record.clone());
+            self.token_jaccard.insert(record);
+            if let Err(e) = self.embedding_similarity.insert(record) {
+                eprintln!("hookwise: embedding index update failed: {}", e);
+                break;
+            }
+        }
+
+        Ok(())
+    }
 }
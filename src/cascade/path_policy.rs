@@ -1,9 +1,11 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use async_trait::async_trait;
 use chrono::Utc;
 
 use crate::cascade::{CascadeInput, CascadeTier};
+use crate::config::{normalize_lexically, CompiledPathPolicy, PathVerdict};
 use crate::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
 };
@@ -82,31 +84,37 @@ impl PathPolicyEngine {
         paths
     }
 
-    /// Make an absolute path relative to the cwd, for glob matching.
-    /// If the path is already relative, or cwd is None, returns the path as-is.
+    /// Make an absolute path relative to the cwd, for glob matching, with
+    /// `.` and `..` resolved first so `src/../.env` can't slip past `.env*`.
+    /// If the path is already relative, or cwd is None, only the resolving
+    /// is done.
     fn relativize(path: &str, cwd: Option<&str>) -> String {
+        let p = normalize_lexically(Path::new(path));
         match cwd {
             Some(cwd) => {
-                let p = Path::new(path);
-                let c = Path::new(cwd);
-                p.strip_prefix(c)
-                    .map(|rel| rel.to_string_lossy().to_string())
-                    .unwrap_or_else(|_| path.to_string())
+                let c = normalize_lexically(Path::new(cwd));
+                match p.strip_prefix(&c) {
+                    Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+                    Ok(rel) => rel.to_string_lossy().to_string(),
+                    Err(_) => p.to_string_lossy().to_string(),
+                }
             }
-            None => path.to_string(),
+            None => p.to_string_lossy().to_string(),
         }
     }
 
-    /// Extract file paths from tool input depending on tool type.
+    /// Extract file paths from tool input depending on tool type. A `Glob`
+    /// or `Grep` without a path searches the working directory.
     fn extract_paths(&self, tool_name: &str, input: &CascadeInput) -> Vec<String> {
         match tool_name {
-            "Write" | "Edit" | "Read" | "Glob" | "Grep" => {
+            "Write" | "Edit" | "Read" => {
                 if let Some(fp) = &input.file_path {
                     vec![fp.clone()]
                 } else {
                     Vec::new()
                 }
             }
+            "Glob" | "Grep" => vec![input.file_path.clone().unwrap_or_else(|| ".".into())],
             "Bash" => {
                 let command = input
                     .tool_input
@@ -118,6 +126,20 @@ impl PathPolicyEngine {
             _ => Vec::new(),
         }
     }
+
+    /// Whether `allow_read` covers `path`. A directory being searched is
+    /// covered only if its entries are, so `src/**` allows searching `src`
+    /// but not the whole working directory.
+    fn readable(policy: &CompiledPathPolicy, path: &str, is_dir: bool) -> bool {
+        if !is_dir {
+            return policy.allow_read.is_match(path);
+        }
+        let entry = match path.trim_end_matches('/') {
+            "" | "." => "_".to_string(),
+            dir => format!("{}/_", dir),
+        };
+        policy.allow_read.is_match(path) || policy.allow_read.is_match(entry)
+    }
 }
 
 #[async_trait]
@@ -141,6 +163,7 @@ impl CascadeTier for PathPolicyEngine {
 
         let is_read_only =
             input.tool_name == "Read" || input.tool_name == "Glob" || input.tool_name == "Grep";
+        let is_search = input.tool_name == "Glob" || input.tool_name == "Grep";
 
         // Evaluate each path against the policy. Most restrictive wins.
         let mut worst_decision: Option<Decision> = None;
@@ -149,27 +172,21 @@ impl CascadeTier for PathPolicyEngine {
 
         for path in &paths {
             let decision = if is_read_only {
-                // For read operations, check sensitive paths first, then allow_read
-                if policy.sensitive_ask_write.is_match(path) {
+                // For read operations, check sensitive paths first, then
+                // allow_read. An empty allow_read leaves reads unrestricted.
+                if policy.is_sensitive(path, input.cwd.as_deref()) {
                     Some(Decision::Ask) // Sensitive path read requires human approval
-                } else if policy.allow_read.is_match(path) {
+                } else if policy.allow_read.is_empty() || Self::readable(policy, path, is_search) {
                     None // Allowed, no policy action needed
                 } else {
                     Some(Decision::Deny)
                 }
             } else {
-                // For write operations, check in order:
-                // 1. sensitive_ask_write -> Ask
-                // 2. deny_write -> Deny
-                // 3. allow_write -> Allow
-                if policy.sensitive_ask_write.is_match(path) {
-                    Some(Decision::Ask)
-                } else if policy.deny_write.is_match(path) {
-                    Some(Decision::Deny)
-                } else if policy.allow_write.is_match(path) {
-                    Some(Decision::Allow)
-                } else {
-                    None // No match = fall through
+                match policy.classify_in(path, input.cwd.as_deref()) {
+                    PathVerdict::Deny => Some(Decision::Deny),
+                    PathVerdict::Ask => Some(Decision::Ask),
+                    PathVerdict::Allow => Some(Decision::Allow),
+                    PathVerdict::NoOpinion => None, // No match = fall through
                 }
             };
 
@@ -182,6 +199,9 @@ impl CascadeTier for PathPolicyEngine {
                     worst_decision = Some(d);
                     worst_path = path.clone();
                     worst_reason = match d {
+                        Decision::Deny if is_read_only => {
+                            format!("path '{}' is outside the role's allow_read", path)
+                        }
                         Decision::Deny => format!("path '{}' denied by role path policy", path),
                         Decision::Ask => format!("path '{}' matches sensitive path pattern", path),
                         Decision::Allow => format!("path '{}' allowed by role path policy", path),
@@ -217,6 +237,11 @@ impl CascadeTier for PathPolicyEngine {
                     scope: ScopeLevel::Role,
                     file_path: Some(worst_path),
                     session_id: String::new(), // Filled by CascadeRunner
+                    tags: HashMap::new(),
+                    immutable: false,
+                    id: String::new(),
+                    last_used: None,
+                    hit_count: 0,
                 }))
             }
             None => Ok(None), // No path policy match = fall through
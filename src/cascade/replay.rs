@@ -0,0 +1,91 @@
+use crate::decision::{Decision, DecisionRecord, DecisionTier};
+use crate::error::Result;
+use crate::session::SessionContext;
+
+use super::{CascadeInput, CascadeRunner, CascadeTier};
+
+/// The result of replaying a past decision against the current rules.
+#[derive(Debug, Clone)]
+pub struct ReplayOutcome {
+    /// The decision as originally recorded.
+    pub original: DecisionRecord,
+    /// What the local tiers decide today. `None` means no local tier
+    /// resolves the call and it would escalate to the supervisor/human.
+    pub current: Option<DecisionRecord>,
+}
+
+impl ReplayOutcome {
+    /// The decision the cascade would reach today, if a local tier resolves it.
+    pub fn current_decision(&self) -> Option<Decision> {
+        self.current.as_ref().map(|r| r.decision)
+    }
+
+    /// The tier that would resolve the call today, if any.
+    pub fn current_tier(&self) -> Option<DecisionTier> {
+        self.current.as_ref().map(|r| r.metadata.tier)
+    }
+
+    /// Whether the outcome differs from the original decision.
+    pub fn decision_changed(&self) -> bool {
+        self.current_decision() != Some(self.original.decision)
+    }
+
+    /// Whether a different tier would resolve the call today. An exact cache
+    /// hit means the stored decision still answers the call, so it does not
+    /// count as a change.
+    pub fn tier_changed(&self) -> bool {
+        match self.current_tier() {
+            Some(DecisionTier::ExactCache) => false,
+            tier => tier != Some(self.original.metadata.tier),
+        }
+    }
+}
+
+impl CascadeRunner {
+    /// Re-run a recorded tool call through the cascade without side effects.
+    ///
+    /// Only the local tiers (path policy, exact cache, similarity) are
+    /// consulted. The supervisor and human tiers are never contacted, and
+    /// nothing is persisted or inserted into the caches.
+    pub async fn replay(
+        &self,
+        session: &SessionContext,
+        record: &DecisionRecord,
+    ) -> Result<ReplayOutcome> {
+        // Sanitized inputs are stored as serialized tool input JSON; fall back
+        // to a plain string for records that were written by hand.
+        let tool_input = serde_json::from_str(&record.key.sanitized_input)
+            .unwrap_or_else(|_| serde_json::Value::String(record.key.sanitized_input.clone()));
+        let file_path = Self::extract_file_path(&record.key.tool, &tool_input);
+
+        let input = CascadeInput {
+            session: session.clone(),
+            tool_name: record.key.tool.clone(),
+            tool_input,
+            sanitized_input: record.key.sanitized_input.clone(),
+            file_path,
+            cwd: None,
+        };
+
+        let tiers: Vec<&dyn CascadeTier> = vec![
+            self.path_policy.as_ref(),
+            self.exact_cache.as_ref(),
+            self.token_jaccard.as_ref(),
+            self.embedding_similarity.as_ref(),
+        ];
+
+        let mut current = None;
+        for tier in &tiers {
+            if let Some(mut result) = tier.evaluate(&input).await? {
+                self.normalize_record(&mut result);
+                current = Some(result);
+                break;
+            }
+        }
+
+        Ok(ReplayOutcome {
+            original: record.clone(),
+            current,
+        })
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -7,6 +11,8 @@ use crate::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
 };
 use crate::error::{HookwiseError, Result};
+use crate::session::registration::FileLock;
+use crate::session::set_file_permissions_0600;
 
 /// Request sent to the supervisor for evaluation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,10 +153,18 @@ impl SupervisorBackend for UnixSocketSupervisor {
             scope: ScopeLevel::Project,
             file_path: request.file_path.clone(),
             session_id: request.session_id.clone(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
         })
     }
 }
 
+/// Default time allowed for a supervisor API request, in seconds.
+pub const DEFAULT_API_TIMEOUT_SECS: u64 = 30;
+
 /// API supervisor -- calls the Anthropic API directly.
 pub struct ApiSupervisor {
     client: reqwest::Client,
@@ -158,6 +172,7 @@ pub struct ApiSupervisor {
     api_key: String,
     model: String,
     max_tokens: u32,
+    timeout_secs: u64,
 }
 
 impl ApiSupervisor {
@@ -168,9 +183,17 @@ impl ApiSupervisor {
             api_key,
             model,
             max_tokens,
+            timeout_secs: DEFAULT_API_TIMEOUT_SECS,
         }
     }
 
+    /// Give up on a request after `timeout_secs`, failing with
+    /// `SupervisorTimeout`.
+    pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
+        self.timeout_secs = timeout_secs;
+        self
+    }
+
     fn build_system_prompt(&self, policy: &PolicyConfig) -> String {
         format!(
             "You are a permission supervisor for hookwise. \
@@ -246,11 +269,20 @@ impl SupervisorBackend for ApiSupervisor {
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
             .json(&body)
             .send()
             .await
-            .map_err(|e| HookwiseError::Supervisor {
-                reason: format!("API request failed: {}", e),
+            .map_err(|e| {
+                if e.is_timeout() {
+                    HookwiseError::SupervisorTimeout {
+                        timeout_secs: self.timeout_secs,
+                    }
+                } else {
+                    HookwiseError::Supervisor {
+                        reason: format!("API request failed: {}", e),
+                    }
+                }
             })?;
 
         if !resp.status().is_success() {
@@ -296,10 +328,176 @@ impl SupervisorBackend for ApiSupervisor {
             scope: ScopeLevel::Project,
             file_path: request.file_path.clone(),
             session_id: request.session_id.clone(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
         })
     }
 }
 
+/// Path of the supervisor's Unix socket: the configured path, otherwise
+/// [`crate::session::supervisor_socket_path`] for the team.
+pub fn supervisor_socket_path(configured: Option<&Path>, team_id: Option<&str>) -> PathBuf {
+    configured
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| crate::session::supervisor_socket_path(team_id))
+}
+
+/// Path of the shared supervisor rate-limit state file.
+/// Uses `$XDG_RUNTIME_DIR` if set, otherwise the user's global config
+/// directory, never a predictable name in the shared `/tmp`.
+pub fn supervisor_budget_path() -> PathBuf {
+    let team_suffix = std::env::var("CLAUDE_TEAM_ID")
+        .map(|id| format!("-{}", id))
+        .unwrap_or_default();
+    let filename = format!("hookwise-supervisor-budget{}.json", team_suffix);
+
+    match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(runtime_dir) if !runtime_dir.is_empty() => PathBuf::from(runtime_dir).join(filename),
+        _ => crate::config::dirs_global().join(filename),
+    }
+}
+
+/// Persisted token bucket state.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct BucketState {
+    tokens: f64,
+    /// Unix time (seconds) of the last refill.
+    updated_at: f64,
+}
+
+/// Token bucket limiting how often the supervisor backend is called.
+///
+/// Each `hookwise check` is a short-lived process, so the bucket can keep its
+/// state in a file to share one budget across processes. Each update holds a
+/// lock on the file and replaces it by rename, so concurrent checks can't
+/// overspend the budget.
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<BucketState>,
+    state_path: Option<PathBuf>,
+}
+
+impl TokenBucket {
+    /// An in-memory bucket allowing `calls_per_minute` calls, with bursts up
+    /// to the same amount.
+    pub fn per_minute(calls_per_minute: u32) -> Self {
+        let capacity = f64::from(calls_per_minute);
+        Self {
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                updated_at: 0.0,
+            }),
+            state_path: None,
+        }
+    }
+
+    /// A bucket whose state is shared through the file at `path`.
+    pub fn persisted(calls_per_minute: u32, path: PathBuf) -> Self {
+        Self {
+            state_path: Some(path),
+            ..Self::per_minute(calls_per_minute)
+        }
+    }
+
+    /// Take a token if one is available.
+    pub fn try_acquire(&self) -> bool {
+        let now = Utc::now().timestamp_millis() as f64 / 1000.0;
+        self.try_acquire_at(now)
+    }
+
+    /// Take a token if one is available at time `now` (Unix seconds).
+    pub fn try_acquire_at(&self, now: f64) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        // Held until the updated state is written back
+        let _file_lock = self.state_path.as_deref().and_then(lock_bucket_state);
+        if let Some(on_disk) = self.state_path.as_deref().and_then(read_bucket_state) {
+            *state = on_disk;
+        }
+
+        let elapsed = (now - state.updated_at).max(0.0);
+        state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        state.updated_at = now;
+
+        let granted = state.tokens >= 1.0;
+        if granted {
+            state.tokens -= 1.0;
+        }
+
+        if let Some(path) = &self.state_path {
+            if let Err(e) = write_bucket_state(path, &state) {
+                tracing::warn!("could not save supervisor budget {}: {}", path.display(), e);
+            }
+        }
+
+        granted
+    }
+}
+
+/// Lock the state file for a read-modify-write. Without the lock the bucket
+/// still works, only without protection from concurrent checks.
+fn lock_bucket_state(path: &Path) -> Option<FileLock> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match FileLock::acquire(path) {
+        Ok(lock) => Some(lock),
+        Err(e) => {
+            tracing::warn!("could not lock supervisor budget {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Replace the state file with `state`, readable only by its owner.
+fn write_bucket_state(path: &Path, state: &BucketState) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_string(state)?)?;
+    set_file_permissions_0600(&tmp_path);
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn read_bucket_state(path: &Path) -> Option<BucketState> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Supervisor backend decorator that enforces a call budget. Calls over
+/// budget fail without reaching the inner backend, so the supervisor tier
+/// falls through exactly as it does when the supervisor is unavailable.
+pub struct RateLimitedSupervisor {
+    inner: Box<dyn SupervisorBackend>,
+    bucket: TokenBucket,
+}
+
+impl RateLimitedSupervisor {
+    pub fn new(inner: Box<dyn SupervisorBackend>, bucket: TokenBucket) -> Self {
+        Self { inner, bucket }
+    }
+}
+
+#[async_trait]
+impl SupervisorBackend for RateLimitedSupervisor {
+    async fn evaluate(
+        &self,
+        request: &SupervisorRequest,
+        policy: &PolicyConfig,
+    ) -> Result<DecisionRecord> {
+        if !self.bucket.try_acquire() {
+            return Err(HookwiseError::Supervisor {
+                reason: "rate limit exceeded".into(),
+            });
+        }
+        self.inner.evaluate(request, policy).await
+    }
+}
+
 /// Wraps a SupervisorBackend as a CascadeTier.
 pub struct SupervisorTier {
     backend: Box<dyn SupervisorBackend>,
@@ -360,7 +558,8 @@ impl crate::cascade::CascadeTier for SupervisorTier {
         };
 
         // If supervisor has low confidence, return None to escalate to human
-        if record.metadata.confidence < self.policy.confidence.project {
+        let confidence = self.policy.confidence.for_role(input.session.role.as_ref());
+        if record.metadata.confidence < confidence.project {
             return Ok(None);
         }
 
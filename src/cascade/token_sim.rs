@@ -1,9 +1,12 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::RwLock;
 
 use async_trait::async_trait;
 use chrono::Utc;
 
-use crate::cascade::{CascadeInput, CascadeTier};
+use crate::cascade::cache::is_pattern_rule;
+use crate::cascade::{CascadeInput, CascadeTier, TierCounters, TierMetrics};
 use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
 use crate::error::Result;
 
@@ -15,38 +18,98 @@ pub struct TokenEntry {
     pub record: DecisionRecord,
 }
 
+/// Default minimum number of tokens a query must share with a cached entry.
+pub const DEFAULT_MIN_OVERLAP: usize = 2;
+
 /// Tier 2a: Token-level Jaccard similarity.
 pub struct TokenJaccard {
     entries: RwLock<Vec<TokenEntry>>,
     threshold: f64,
     min_tokens: usize,
+    /// Absolute floor on shared tokens, so short commands cannot match on
+    /// ratio alone (two 2-token commands can score 1.0).
+    min_overlap: usize,
+    /// Length of the contiguous word sequences compared. 1 compares single
+    /// words, ignoring their order.
+    ngram: usize,
+    /// Score with `weighted_jaccard` instead of the plain set coefficient.
+    idf_weighting: bool,
+    /// Number of entries each token appears in.
+    doc_freq: RwLock<HashMap<String, usize>>,
+    /// Deny when the best match is a cached deny, instead of falling through.
+    deny_on_similarity: bool,
+    metrics: TierCounters,
 }
 
 impl TokenJaccard {
-    pub fn new(threshold: f64, min_tokens: usize) -> Self {
+    /// Create the tier. With `ngram` above 1, inputs are compared as sets of
+    /// `ngram`-word sequences in their original order, so reordered
+    /// arguments (`chmod 777 file` vs `chmod file 777`) no longer match.
+    pub fn new(threshold: f64, min_tokens: usize, ngram: usize) -> Self {
         Self {
             entries: RwLock::new(Vec::new()),
             threshold,
             min_tokens,
+            min_overlap: DEFAULT_MIN_OVERLAP,
+            ngram: ngram.max(1),
+            idf_weighting: false,
+            doc_freq: RwLock::new(HashMap::new()),
+            deny_on_similarity: false,
+            metrics: TierCounters::default(),
         }
     }
 
-    /// Load entries from cached decisions.
+    /// Require at least `min_overlap` shared tokens for a match, in addition
+    /// to the ratio threshold.
+    pub fn with_min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    /// Weight tokens by inverse document frequency when scoring, so tokens
+    /// common to most entries (`cargo`, `--release`) count for less than
+    /// rare ones.
+    pub fn with_idf_weighting(mut self, idf_weighting: bool) -> Self {
+        self.idf_weighting = idf_weighting;
+        self
+    }
+
+    /// Deny a call whose best match is a cached deny (off by default).
+    pub fn with_deny_on_similarity(mut self, deny_on_similarity: bool) -> Self {
+        self.deny_on_similarity = deny_on_similarity;
+        self
+    }
+
+    /// Load entries from cached decisions. Pattern rules are skipped: their
+    /// key is a glob, not a command, and the exact cache matches them.
     pub fn load_from(&self, records: &[DecisionRecord]) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         for record in records {
-            let tokens = Self::tokenize(&record.key.sanitized_input);
+            if is_pattern_rule(record) {
+                continue;
+            }
+            let tokens = self.tokens(&record.key.sanitized_input);
             entries.push(TokenEntry {
                 tokens,
                 cache_key: record.key.clone(),
                 record: record.clone(),
             });
         }
+        self.recount(&entries);
     }
 
-    /// Add a single entry.
+    /// Add a single entry, unless it is a pattern rule.
     pub fn insert(&self, record: &DecisionRecord) {
-        let tokens = Self::tokenize(&record.key.sanitized_input);
+        if is_pattern_rule(record) {
+            return;
+        }
+        let tokens = self.tokens(&record.key.sanitized_input);
+        {
+            let mut doc_freq = self.doc_freq.write().unwrap_or_else(|e| e.into_inner());
+            for token in &tokens {
+                *doc_freq.entry(token.clone()).or_default() += 1;
+            }
+        }
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         entries.push(TokenEntry {
             tokens,
@@ -55,19 +118,105 @@ impl TokenJaccard {
         });
     }
 
+    /// Recompute document frequencies from `entries`.
+    fn recount(&self, entries: &[TokenEntry]) {
+        let mut doc_freq = self.doc_freq.write().unwrap_or_else(|e| e.into_inner());
+        doc_freq.clear();
+        for entry in entries {
+            for token in &entry.tokens {
+                *doc_freq.entry(token.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Smoothed inverse document frequency of a token. Always positive, and
+    /// highest for tokens no entry contains.
+    fn idf(doc_freq: &HashMap<String, usize>, entries: usize, token: &str) -> f64 {
+        let df = doc_freq.get(token).copied().unwrap_or(0);
+        ((entries + 1) as f64 / (df + 1) as f64).ln() + 1.0
+    }
+
+    /// Jaccard coefficient over IDF-weighted token presence: the summed
+    /// weight of shared tokens over the summed weight of all tokens in
+    /// either slice (both sorted), against the loaded entries.
+    pub fn weighted_jaccard(&self, a: &[String], b: &[String]) -> f64 {
+        let entries = self.len();
+        let doc_freq = self.doc_freq.read().unwrap_or_else(|e| e.into_inner());
+        Self::weighted_coefficient(&doc_freq, entries, a, b)
+    }
+
+    fn weighted_coefficient(
+        doc_freq: &HashMap<String, usize>,
+        entries: usize,
+        a: &[String],
+        b: &[String],
+    ) -> f64 {
+        if a.is_empty() && b.is_empty() {
+            return 1.0;
+        }
+        let weight = |token: &String| Self::idf(doc_freq, entries, token);
+        let shared: f64 = a
+            .iter()
+            .filter(|token| b.binary_search(token).is_ok())
+            .map(weight)
+            .sum();
+        let union = a.iter().map(weight).sum::<f64>() + b.iter().map(weight).sum::<f64>() - shared;
+        if union == 0.0 {
+            return 0.0;
+        }
+        shared / union
+    }
+
+    /// Matches and fall-throughs since the tier was created.
+    pub fn tier_metrics(&self) -> TierMetrics {
+        self.metrics.snapshot()
+    }
+
+    /// Number of loaded entries.
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Whether no entries are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Tokenize an input string: split on whitespace + punctuation, lowercase,
     /// deduplicate, sort.
     pub fn tokenize(input: &str) -> Vec<String> {
-        let mut tokens: Vec<String> = input
+        Self::tokenize_ngrams(input, 1)
+    }
+
+    /// Tokenize into contiguous `n`-word sequences of the lowercased words,
+    /// taken in input order, then deduplicate and sort them. An input with
+    /// fewer than `n` words is a single token.
+    pub fn tokenize_ngrams(input: &str, n: usize) -> Vec<String> {
+        let words: Vec<String> = input
             .split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
             .filter(|s| !s.is_empty())
             .map(|s| s.to_lowercase())
             .collect();
+        let mut tokens: Vec<String> = if n <= 1 {
+            words
+        } else if words.len() <= n {
+            vec![words.join(" ")]
+        } else {
+            words.windows(n).map(|w| w.join(" ")).collect()
+        };
+        tokens.retain(|t| !t.is_empty());
         tokens.sort();
         tokens.dedup();
         tokens
     }
 
+    /// Tokenize an input with this tier's n-gram length. A JSON tool input
+    /// contributes only its values, so field names like `command`, which
+    /// every input of a tool shares, never count towards the overlap.
+    pub fn tokens(&self, input: &str) -> Vec<String> {
+        Self::tokenize_ngrams(&value_text(input), self.ngram)
+    }
+
     /// Compute Jaccard coefficient between two sorted token slices.
     pub fn jaccard_coefficient(a: &[String], b: &[String]) -> f64 {
         if a.is_empty() && b.is_empty() {
@@ -99,81 +248,153 @@ impl TokenJaccard {
         count
     }
 
+    /// Up to `k` entries for `tool` and `role` (or the wildcard role) whose
+    /// similarity to `query` meets the threshold, best first. Empty if the
+    /// query has fewer than `min_tokens` distinct words.
+    pub fn top_k(&self, query: &str, tool: &str, role: &str, k: usize) -> Vec<(f64, TokenEntry)> {
+        self.top_k_above(query, tool, role, k, self.threshold)
+    }
+
+    /// Like [`TokenJaccard::top_k`], with `threshold` in place of the
+    /// tier's own.
+    fn top_k_above(
+        &self,
+        query: &str,
+        tool: &str,
+        role: &str,
+        k: usize,
+        threshold: f64,
+    ) -> Vec<(f64, TokenEntry)> {
+        // `min_tokens` counts words, whatever the n-gram length
+        if Self::tokenize(&value_text(query)).len() < self.min_tokens {
+            return Vec::new();
+        }
+        let query_tokens = self.tokens(query);
+
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        let doc_freq = self.doc_freq.read().unwrap_or_else(|e| e.into_inner());
+        let score = |tokens: &[String]| {
+            if self.idf_weighting {
+                Self::weighted_coefficient(&doc_freq, entries.len(), &query_tokens, tokens)
+            } else {
+                Self::jaccard_coefficient(&query_tokens, tokens)
+            }
+        };
+        let mut matches: Vec<(f64, &TokenEntry)> = entries
+            .iter()
+            .filter(|entry| entry.cache_key.role == role || entry.cache_key.role == "*")
+            .filter(|entry| entry.cache_key.tool == tool)
+            .filter(|entry| {
+                Self::sorted_intersection_count(&query_tokens, &entry.tokens) >= self.min_overlap
+            })
+            .filter_map(|entry| {
+                let score = score(&entry.tokens);
+                (score >= threshold).then_some((score, entry))
+            })
+            .collect();
+
+        // Stable, so equal scores keep load order
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+        matches
+            .into_iter()
+            .take(k)
+            .map(|(score, entry)| (score, entry.clone()))
+            .collect()
+    }
+
     /// Remove all entries for a specific role.
     pub fn invalidate_role(&self, role: &str) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         entries.retain(|e| e.cache_key.role != role);
+        self.recount(&entries);
     }
 
     /// Remove all entries.
     pub fn invalidate_all(&self) {
         let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
         entries.clear();
+        self.recount(&entries);
+    }
+}
+
+/// The text of a tool input to tokenize: the values of a JSON object,
+/// ordered by key, or the input itself if it is not one.
+fn value_text(input: &str) -> Cow<'_, str> {
+    fn collect(value: &serde_json::Value, out: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => map.values().for_each(|v| collect(v, out)),
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect(v, out)),
+            serde_json::Value::String(s) => out.push(s.clone()),
+            serde_json::Value::Null => {}
+            other => out.push(other.to_string()),
+        }
+    }
+    match serde_json::from_str::<serde_json::Value>(input) {
+        Ok(value @ serde_json::Value::Object(_)) => {
+            let mut values = Vec::new();
+            collect(&value, &mut values);
+            Cow::Owned(values.join(" "))
+        }
+        _ => Cow::Borrowed(input),
     }
 }
 
 #[async_trait]
 impl CascadeTier for TokenJaccard {
     async fn evaluate(&self, input: &CascadeInput) -> Result<Option<DecisionRecord>> {
-        let query_tokens = Self::tokenize(&input.sanitized_input);
-
-        // Skip if too few tokens
-        if query_tokens.len() < self.min_tokens {
-            return Ok(None);
-        }
-
         let role_name = input
             .session
             .role
             .as_ref()
             .map(|r| r.name.as_str())
             .unwrap_or("*");
+        // A role may demand closer matches than the project does
+        let threshold = input
+            .session
+            .role
+            .as_ref()
+            .and_then(|r| r.similarity.as_ref())
+            .and_then(|s| s.jaccard_threshold)
+            .unwrap_or(self.threshold);
 
-        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
-
-        let mut best_match: Option<(f64, &TokenEntry)> = None;
-
-        for entry in entries.iter() {
-            // Only match same role or wildcard entries
-            if entry.cache_key.role != role_name && entry.cache_key.role != "*" {
-                continue;
-            }
-            // Only match same tool
-            if entry.cache_key.tool != input.tool_name {
-                continue;
-            }
-
-            let score = Self::jaccard_coefficient(&query_tokens, &entry.tokens);
-
-            if score >= self.threshold && best_match.as_ref().is_none_or(|(best, _)| score > *best)
-            {
-                best_match = Some((score, entry));
-            }
-        }
+        let best_match = self
+            .top_k_above(
+                &input.sanitized_input,
+                &input.tool_name,
+                role_name,
+                1,
+                threshold,
+            )
+            .into_iter()
+            .next();
 
-        match best_match {
+        let result = match best_match {
             Some((score, entry)) => {
                 // Similarity behavior:
                 // - allow -> auto-approve
-                // - deny -> fall through (similarity never auto-denies)
+                // - deny -> fall through, unless deny_on_similarity is set
                 // - ask -> return ask (escalate)
                 match entry.record.decision {
-                    Decision::Deny => Ok(None), // Never auto-deny from similarity
-                    Decision::Allow | Decision::Ask => {
+                    Decision::Deny if !self.deny_on_similarity => Ok(None),
+                    decision => {
+                        let mut reason = format!(
+                            "token Jaccard similarity {:.3} >= {:.3} with cached {}",
+                            score, threshold, decision
+                        );
+                        if decision == Decision::Deny {
+                            reason.push_str(" (similarity.deny_on_match is set)");
+                        }
                         Ok(Some(DecisionRecord {
                             key: CacheKey {
                                 sanitized_input: input.sanitized_input.clone(),
                                 tool: input.tool_name.clone(),
                                 role: role_name.to_string(),
                             },
-                            decision: entry.record.decision,
+                            decision,
                             metadata: DecisionMetadata {
                                 tier: DecisionTier::TokenJaccard,
                                 confidence: score,
-                                reason: format!(
-                                    "token Jaccard similarity {:.3} >= {:.3} with cached {}",
-                                    score, self.threshold, entry.record.decision
-                                ),
+                                reason,
                                 matched_key: Some(entry.cache_key.clone()),
                                 similarity_score: Some(score),
                             },
@@ -181,12 +402,19 @@ impl CascadeTier for TokenJaccard {
                             scope: entry.record.scope,
                             file_path: input.file_path.clone(),
                             session_id: String::new(), // Filled by CascadeRunner
+                            tags: HashMap::new(),
+                            immutable: false,
+                            id: String::new(),
+                            last_used: None,
+                            hit_count: 0,
                         }))
                     }
                 }
             }
             None => Ok(None), // No match above threshold
-        }
+        };
+        self.metrics.record(&result);
+        result
     }
 
     fn tier(&self) -> DecisionTier {
@@ -1,21 +1,23 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::cascade::embed_sim::EmbeddingSimilarity;
+use crate::cascade::supervisor::supervisor_socket_path;
 use crate::cascade::token_sim::TokenJaccard;
-use crate::config::PolicyConfig;
+use crate::config::{PolicyConfig, SupervisorConfig};
 use crate::error::Result;
+use crate::ipc::socket_client::IpcClient;
+use crate::ipc::IpcControl;
 use crate::scope::ScopeLevel;
-use crate::storage::jsonl::JsonlStorage;
-use crate::storage::StorageBackend;
 
 /// Rebuild vector indexes from rules.
 pub async fn run_build() -> Result<()> {
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_root = cwd.join(".hookwise");
+    let cwd = crate::config::project_root();
+    let project_root = crate::config::project_config_dir(&cwd);
     let global_root = dirs_global();
     let policy = PolicyConfig::load_project(&cwd)?;
 
-    let storage = JsonlStorage::new(project_root, global_root, None);
+    let storage =
+        crate::storage::open_backend(&policy.storage, project_root.clone(), global_root, None)?;
     let decisions = storage.load_decisions(ScopeLevel::Project)?;
 
     eprintln!(
@@ -27,14 +29,20 @@ pub async fn run_build() -> Result<()> {
     let token_jaccard = TokenJaccard::new(
         policy.similarity.jaccard_threshold,
         policy.similarity.jaccard_min_tokens,
+        policy.similarity.jaccard_ngram,
     );
     token_jaccard.load_from(&decisions);
     eprintln!("  Token Jaccard: loaded {} entries", decisions.len());
 
     // Rebuild embedding similarity index
-    match EmbeddingSimilarity::new("default", policy.similarity.embedding_threshold) {
+    match EmbeddingSimilarity::new(
+        "default",
+        policy.similarity.embedding_threshold,
+        policy.similarity.embedding_pending_threshold,
+    ) {
         Ok(es) => {
             es.build_index(&decisions)?;
+            es.save_index(&EmbeddingSimilarity::index_path(&project_root))?;
             eprintln!(
                 "  Embedding HNSW: built index with {} entries",
                 decisions.len()
@@ -51,11 +59,12 @@ pub async fn run_build() -> Result<()> {
 
 /// Clear cached decisions.
 pub async fn run_invalidate(role: Option<&str>, scope: Option<&str>, all: bool) -> Result<()> {
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_root = cwd.join(".hookwise");
+    let cwd = crate::config::project_root();
+    let project_root = crate::config::project_config_dir(&cwd);
     let global_root = dirs_global();
 
-    let storage = JsonlStorage::new(project_root, global_root, None);
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage = crate::storage::open_backend(&policy.storage, project_root, global_root, None)?;
 
     let scope_level = scope
         .map(|s| {
@@ -77,6 +86,14 @@ pub async fn run_invalidate(role: Option<&str>, scope: Option<&str>, all: bool)
             "hookwise: cleared decisions for role '{}' at scope '{}'",
             role, scope_level
         );
+        notify_supervisor(
+            &cwd,
+            IpcControl::InvalidateRole {
+                scope: scope_level,
+                role: role.to_string(),
+            },
+        )
+        .await?;
     } else {
         eprintln!("hookwise: specify --role <role> or --all");
         std::process::exit(1);
@@ -85,6 +102,107 @@ pub async fn run_invalidate(role: Option<&str>, scope: Option<&str>, all: bool)
     Ok(())
 }
 
+/// Collapse duplicate decisions at one scope, or at every scope.
+pub async fn run_compact(scope: Option<&str>) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(&cwd),
+        dirs_global(),
+        None,
+    )?;
+
+    let mut dropped = 0;
+    for scope_level in scopes_to_maintain(scope)? {
+        dropped += storage.compact(scope_level)?;
+    }
+    eprintln!("hookwise: removed {} duplicate decision(s)", dropped);
+    Ok(())
+}
+
+/// Delete rules unused for `unused_for` at one scope, or at every scope.
+/// Pinned rules are kept.
+pub async fn run_prune_rules(unused_for: chrono::Duration, scope: Option<&str>) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(&cwd),
+        dirs_global(),
+        None,
+    )?;
+
+    let cutoff = chrono::Utc::now() - unused_for;
+    let mut pruned = 0;
+    for scope_level in scopes_to_maintain(scope)? {
+        pruned += storage.prune_unused(scope_level, cutoff)?;
+    }
+    eprintln!(
+        "hookwise: removed {} rule(s) unused since {}",
+        pruned,
+        cutoff.format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+/// Parse an age such as `90d`: a positive whole number followed by `s`,
+/// `m`, `h`, `d` or `w`. A zero or negative age would prune every rule.
+pub fn parse_age(s: &str) -> std::result::Result<chrono::Duration, String> {
+    let s = s.trim();
+    let unit_at = s.len() - s.chars().last().map_or(0, char::len_utf8);
+    let (amount, unit) = s.split_at(unit_at);
+    let amount: i64 = amount
+        .parse()
+        .ok()
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| format!("expected a positive age like 90d, got '{s}'"))?;
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => return Err(format!("unknown unit in '{s}' (use s, m, h, d or w)")),
+    };
+    age.ok_or_else(|| format!("age '{s}' is too large"))
+}
+
+/// The `--scope` given, or every stored scope.
+fn scopes_to_maintain(scope: Option<&str>) -> Result<Vec<ScopeLevel>> {
+    Ok(match scope {
+        Some(s) => vec![s
+            .parse::<ScopeLevel>()
+            .map_err(|e| crate::error::HookwiseError::InvalidPolicy { reason: e })?],
+        None => vec![
+            ScopeLevel::Project,
+            ScopeLevel::User,
+            ScopeLevel::Org,
+            ScopeLevel::Global,
+        ],
+    })
+}
+
+/// Tell a live socket supervisor about a change to storage, if one is
+/// listening. A missing socket means there is nothing to notify.
+async fn notify_supervisor(cwd: &Path, control: IpcControl) -> Result<()> {
+    let policy = PolicyConfig::load_project(cwd)?;
+    let SupervisorConfig::Socket { socket_path, .. } = &policy.supervisor else {
+        return Ok(());
+    };
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let socket_path = supervisor_socket_path(socket_path.as_deref(), team_id.as_deref());
+    if !socket_path.exists() {
+        return Ok(());
+    }
+
+    match IpcClient::new(socket_path, 5).send_control(&control).await {
+        Ok(()) => eprintln!("hookwise: live supervisor reloaded"),
+        Err(e) => eprintln!("hookwise: could not notify live supervisor ({})", e),
+    }
+    Ok(())
+}
+
 fn dirs_global() -> PathBuf {
     crate::config::dirs_global()
 }
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::PolicyConfig;
+use crate::decision::{CacheKey, DecisionRecord};
+use crate::error::{HookwiseError, Result};
+use crate::sanitize::SanitizePipeline;
+use crate::scope::ScopeLevel;
+use crate::storage::{SecretFinding, Severity};
+
+/// Schema version written by `export`. `import` refuses any other.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A portable set of decisions from one scope, as written by `export`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleBundle {
+    pub version: u32,
+    /// The scope the decisions were exported from.
+    pub scope: ScopeLevel,
+    pub exported_at: DateTime<Utc>,
+    pub decisions: Vec<DecisionRecord>,
+}
+
+impl RuleBundle {
+    /// Read a bundle, rejecting one written with a different schema version.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let bundle: Self =
+            serde_json::from_str(&contents).map_err(|e| HookwiseError::ConfigParse {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+        if bundle.version != BUNDLE_VERSION {
+            return Err(HookwiseError::ConfigParse {
+                path: path.to_path_buf(),
+                reason: format!(
+                    "bundle version {} is not supported (expected {})",
+                    bundle.version, BUNDLE_VERSION
+                ),
+            });
+        }
+        Ok(bundle)
+    }
+
+    /// High-severity secrets in the bundle's decisions, one finding per
+    /// decision, numbered from 1.
+    pub fn secrets(&self, pipeline: &SanitizePipeline, path: &Path) -> Result<Vec<SecretFinding>> {
+        let mut findings = Vec::new();
+        for (i, record) in self.decisions.iter().enumerate() {
+            let line = serde_json::to_string(record)?;
+            let (sanitized, events) = pipeline.sanitize_with_report(&line);
+            if sanitized == line {
+                continue;
+            }
+            let finding = SecretFinding::from_report(
+                path.to_path_buf(),
+                i + 1,
+                format!("potential secret in decision for {}", record.key.tool),
+                &line,
+                &sanitized,
+                &events,
+            );
+            if finding.severity == Severity::High {
+                findings.push(finding);
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// Write every decision stored at `scope` to `out` as a single bundle.
+pub async fn run_export(scope: &str, out: &Path) -> Result<()> {
+    let scope = parse_scope(scope)?;
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(&cwd),
+        dirs_global(),
+        None,
+    )?;
+
+    let mut decisions = storage.load_decisions(scope)?;
+    // Project and role rules share a directory
+    if matches!(scope, ScopeLevel::Project | ScopeLevel::Role) {
+        decisions.retain(|r| r.scope == scope);
+    }
+
+    let bundle = RuleBundle {
+        version: BUNDLE_VERSION,
+        scope,
+        exported_at: Utc::now(),
+        decisions,
+    };
+    std::fs::write(out, serde_json::to_string_pretty(&bundle)?)?;
+    eprintln!(
+        "hookwise: exported {} decision(s) at scope '{}' to {}",
+        bundle.decisions.len(),
+        scope,
+        out.display()
+    );
+    Ok(())
+}
+
+/// Save a bundle's decisions at `scope`. The whole bundle is rejected if it
+/// carries a secret. Where a key is already stored, the newer decision
+/// replaces the older; pinned decisions are never replaced. The bundle's own
+/// pins are dropped unless `keep_pins` is set.
+pub async fn run_import(file: &Path, scope: &str, keep_pins: bool) -> Result<()> {
+    let scope = parse_scope(scope)?;
+    let bundle = RuleBundle::load(file)?;
+
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let pipeline = SanitizePipeline::for_role(&policy, None)?;
+    let secrets = bundle.secrets(&pipeline, file)?;
+    if !secrets.is_empty() {
+        for finding in &secrets {
+            eprintln!(
+                "  [{}] {} decision {}: {}",
+                finding.severity,
+                finding.file.display(),
+                finding.line,
+                finding.matched_excerpt
+            );
+        }
+        eprintln!(
+            "hookwise: {} decision(s) in {} carry a potential secret; nothing imported",
+            secrets.len(),
+            file.display()
+        );
+        std::process::exit(1);
+    }
+
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(&cwd),
+        dirs_global(),
+        None,
+    )?;
+    let mut stored = storage.load_decisions(scope)?;
+    // Project and role rules share a directory
+    if matches!(scope, ScopeLevel::Project | ScopeLevel::Role) {
+        stored.retain(|r| r.scope == scope);
+    }
+    let mut existing: HashMap<CacheKey, DecisionRecord> = HashMap::new();
+    for record in stored.iter().cloned() {
+        keep_newest(&mut existing, record);
+    }
+    let pinned: Vec<&CacheKey> = stored
+        .iter()
+        .filter(|r| r.immutable)
+        .map(|r| &r.key)
+        .collect();
+
+    let total = bundle.decisions.len();
+    let mut incoming: HashMap<CacheKey, DecisionRecord> = HashMap::new();
+    for mut record in bundle.decisions {
+        // The id hashes the scope, so a record moved to another scope needs
+        // a fresh one
+        if record.scope != scope {
+            record.scope = scope;
+            record.id = record.derive_id();
+        }
+        record.immutable &= keep_pins;
+        record.ensure_id();
+        keep_newest(&mut incoming, record);
+    }
+
+    let mut new_records: Vec<DecisionRecord> = incoming
+        .into_values()
+        .filter(|record| match existing.get(&record.key) {
+            Some(current) => !pinned.contains(&&record.key) && record.timestamp > current.timestamp,
+            None => true,
+        })
+        .collect();
+    new_records.sort_by_key(|record| record.timestamp);
+    storage.save_decisions(&new_records)?;
+
+    // The older decisions for replaced keys go only once the new ones are
+    // saved, so a failure leaves both rather than neither
+    let new_ids: Vec<&str> = new_records.iter().map(|r| r.id.as_str()).collect();
+    let replaced: Vec<String> = stored
+        .into_iter()
+        .filter(|r| incoming_key(&new_records, &r.key) && !new_ids.contains(&r.id.as_str()))
+        .map(|r| r.id)
+        .collect();
+    storage.delete_decisions(scope, &replaced)?;
+
+    eprintln!(
+        "hookwise: imported {} of {} decision(s) from {} at scope '{}'",
+        new_records.len(),
+        total,
+        file.display(),
+        scope
+    );
+    Ok(())
+}
+
+/// Whether one of `records` is for `key`.
+fn incoming_key(records: &[DecisionRecord], key: &CacheKey) -> bool {
+    records.iter().any(|r| &r.key == key)
+}
+
+/// Insert `record` unless a decision for its key at least as new is present.
+fn keep_newest(records: &mut HashMap<CacheKey, DecisionRecord>, record: DecisionRecord) {
+    match records.get(&record.key) {
+        Some(current) if current.timestamp >= record.timestamp => {}
+        _ => {
+            records.insert(record.key.clone(), record);
+        }
+    }
+}
+
+fn parse_scope(scope: &str) -> Result<ScopeLevel> {
+    scope
+        .parse::<ScopeLevel>()
+        .map_err(|e| HookwiseError::InvalidPolicy { reason: e })
+}
+
+fn dirs_global() -> PathBuf {
+    crate::config::dirs_global()
+}
@@ -1,30 +1,57 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::cascade::cache::ExactCache;
 use crate::cascade::embed_sim::EmbeddingSimilarity;
 use crate::cascade::human::{DecisionQueue, HumanTier};
 use crate::cascade::path_policy::PathPolicyEngine;
-use crate::cascade::supervisor::{SupervisorTier, UnixSocketSupervisor};
+use crate::cascade::supervisor::{
+    supervisor_budget_path, supervisor_socket_path, RateLimitedSupervisor, SupervisorBackend,
+    SupervisorTier, TokenBucket, UnixSocketSupervisor,
+};
 use crate::cascade::token_sim::TokenJaccard;
-use crate::cascade::CascadeRunner;
-use crate::config::{PolicyConfig, SupervisorConfig};
-use crate::decision::Decision;
+use crate::cascade::{CascadeMetrics, CascadeRunner};
+use crate::config::roles::PathNormalizer;
+use crate::config::{PolicyConfig, RoleDefinition, SupervisorConfig};
+use crate::decision::{Decision, DecisionRecord, DecisionTier};
 use crate::error::Result;
-use crate::hook_io::{self, HookFormat};
+use crate::hook_io::{self, HookErrorCode, HookFormat, HookInput};
 use crate::sanitize::SanitizePipeline;
-use crate::session::SessionManager;
-use crate::storage::jsonl::JsonlStorage;
-use crate::storage::StorageBackend;
+use crate::scope::ScopeResolver;
+use crate::session::{RateLimiter, SessionContext, SessionManager};
+use crate::storage::audit::{AuditEntry, AuditLog};
 
 /// Run the `check` subcommand (hook mode).
-/// Reads JSON from stdin, runs the cascade, writes JSON to stdout.
-pub async fn run(format: HookFormat) -> Result<()> {
-    // 1. Read hook input from stdin
-    let input = hook_io::read_hook_input()?;
+/// Reads JSON from stdin, runs the cascade, writes JSON to stdout. With
+/// `explain`, the stored rules that matched are listed on stderr. With
+/// `dry_run`, the decision is only reported, on stderr and optionally to
+/// `audit_log`, and the call is allowed.
+pub async fn run(
+    format: HookFormat,
+    explain: bool,
+    dry_run: bool,
+    audit_log: Option<&Path>,
+) -> Result<()> {
+    // 1. Read hook input from stdin. A malformed payload still gets a
+    // structured answer, so the caller can tell it apart from a deny.
+    let input = match hook_io::read_hook_input() {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("hookwise: invalid hook payload: {}", e);
+            hook_io::write_hook_error(
+                Decision::Ask,
+                HookErrorCode::InvalidInput,
+                "invalid hook payload",
+                format,
+            )?;
+            std::process::exit(hook_io::ERROR_EXIT_CODE);
+        }
+    };
 
-    let cwd = &input.cwd;
-    let cwd_path = PathBuf::from(cwd);
+    // Config, storage, and path globs are all relative to the project root,
+    // which may be an ancestor of the agent's cwd.
+    let cwd_path = crate::config::project_root_from(Path::new(&input.cwd));
+    let cwd = cwd_path.to_string_lossy().into_owned();
 
     // 2. Load config
     let policy = PolicyConfig::load_project(&cwd_path)?;
@@ -33,11 +60,17 @@ pub async fn run(format: HookFormat) -> Result<()> {
     let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
 
     // 3. Get session context
-    let session_mgr = SessionManager::new(team_id.as_deref());
+    crate::session::SESSIONS.set_capacity(policy.session_cache_capacity);
+    let session_mgr =
+        SessionManager::new(team_id.as_deref()).with_session_ttl(policy.session_ttl_secs);
 
     // Check if session is disabled
     if session_mgr.is_disabled(&input.session_id) {
         // Disabled sessions always allow
+        let entry = forced_entry(&input, Decision::Allow, DecisionTier::Default);
+        if let Err(e) = audit(&cwd_path, &policy, None, &entry) {
+            eprintln!("hookwise: could not write audit log ({})", e);
+        }
         hook_io::write_hook_output(Decision::Allow, format)?;
         return Ok(());
     }
@@ -50,28 +83,264 @@ pub async fn run(format: HookFormat) -> Result<()> {
         {
             // Registration timeout — write deny JSON so callers always get valid output
             eprintln!("hookwise: {}", e);
+            let entry = forced_entry(&input, Decision::Deny, DecisionTier::Default);
+            if let Err(e) = audit(&cwd_path, &policy, None, &entry) {
+                eprintln!("hookwise: could not write audit log ({})", e);
+            }
+            if dry_run {
+                return allow_dry_run(Decision::Deny, "session not registered", format);
+            }
             hook_io::write_hook_output(Decision::Deny, format)?;
             std::process::exit(hook_io::deny_exit_code(format));
         }
     }
 
-    let session = session_mgr.get_or_populate(&input.session_id, cwd)?;
+    let session = session_mgr.get_or_populate(&input.session_id, &cwd)?;
 
     // If session has no role, deny (unregistered)
     if session.role.is_none() && !session.disabled {
+        let entry = forced_entry(&input, Decision::Deny, DecisionTier::Default);
+        if let Err(e) = audit(&cwd_path, &policy, None, &entry) {
+            eprintln!("hookwise: could not write audit log ({})", e);
+        }
+        if dry_run {
+            return allow_dry_run(Decision::Deny, "session has no role", format);
+        }
+        hook_io::write_hook_output(Decision::Deny, format)?;
+        return Ok(());
+    }
+
+    // Tools the role may not call are denied before anything is sanitized
+    if session
+        .role
+        .as_ref()
+        .is_some_and(|role| role.tool_blocked(&input.tool_name))
+    {
+        eprintln!(
+            "hookwise: tool {} is not permitted for this role",
+            input.tool_name
+        );
+        let entry = forced_entry(&input, Decision::Deny, DecisionTier::Override);
+        if let Err(e) = audit(&cwd_path, &policy, session.role.as_ref(), &entry) {
+            eprintln!("hookwise: could not write audit log ({})", e);
+        }
+        if dry_run {
+            return allow_dry_run(Decision::Deny, "tool not permitted for role", format);
+        }
         hook_io::write_hook_output(Decision::Deny, format)?;
+        std::process::exit(hook_io::deny_exit_code(format));
+    }
+
+    // A session over its call rate skips the cascade entirely
+    if let Some(max) = policy.rate_limit.max_per_minute {
+        let limiter =
+            RateLimiter::persisted(max, crate::session::rate_limit_path(team_id.as_deref()));
+        if !limiter.try_acquire(&input.session_id) {
+            let decision = policy.rate_limit.decision;
+            eprintln!(
+                "hookwise: session exceeded {} calls per minute, forcing {}",
+                max, decision
+            );
+            let entry = forced_entry(&input, decision, DecisionTier::Override);
+            if let Err(e) = audit(&cwd_path, &policy, session.role.as_ref(), &entry) {
+                eprintln!("hookwise: could not write audit log ({})", e);
+            }
+            if dry_run {
+                return allow_dry_run(decision, "rate limit exceeded", format);
+            }
+            hook_io::write_hook_output(decision, format)?;
+            if decision == Decision::Deny {
+                std::process::exit(hook_io::deny_exit_code(format));
+            }
+            return Ok(());
+        }
+    }
+
+    // 4. Build cascade runner, sanitizing with the role's profile
+    let mut runner = build_runner(
+        &cwd_path,
+        &policy,
+        normalizer,
+        &session.org,
+        team_id.as_deref(),
+    )?;
+    runner.sanitizer = SanitizePipeline::for_role(&policy, session.role.as_ref())?;
+    runner.dry_run = dry_run;
+
+    // 5. Run cascade
+    let record = match runner
+        .evaluate_with_cwd(&session, &input.tool_name, &input.tool_input, Some(&cwd))
+        .await
+    {
+        Ok(record) => record,
+        Err(e) => {
+            // On cascade error (e.g. human timeout), default to deny
+            // but still write output so callers can parse it.
+            eprintln!("hookwise: cascade error, defaulting to deny ({})", e);
+            let entry = forced_entry(&input, Decision::Deny, DecisionTier::Default);
+            if let Err(e) = audit(&cwd_path, &policy, session.role.as_ref(), &entry) {
+                eprintln!("hookwise: could not write audit log ({})", e);
+            }
+            if dry_run {
+                return allow_dry_run(Decision::Deny, "cascade error", format);
+            }
+            hook_io::write_hook_output(Decision::Deny, format)?;
+            std::process::exit(hook_io::deny_exit_code(format));
+        }
+    };
+
+    if let Err(e) = runner.flush_usage() {
+        eprintln!("hookwise: could not record rule usage ({})", e);
+    }
+    if !dry_run {
+        let metrics_path = CascadeMetrics::path(&crate::config::project_config_dir(&cwd_path));
+        if let Err(e) = runner.metrics().add_to_file(&metrics_path) {
+            eprintln!("hookwise: could not record tier metrics ({})", e);
+        }
+    }
+    let entry = AuditEntry::from_record(&input.session_id, &record);
+    if let Err(e) = audit(&cwd_path, &policy, session.role.as_ref(), &entry) {
+        eprintln!("hookwise: could not write audit log ({})", e);
+    }
+
+    if explain {
+        explain_decision(&cwd_path, &policy, &session, &record)?;
+    }
+
+    if dry_run {
+        if let Some(path) = audit_log {
+            append_audit_record(path, &record)?;
+        }
+        let reason = format!("{:?}: {}", record.metadata.tier, record.metadata.reason);
+        return allow_dry_run(record.decision, &reason, format);
+    }
+
+    // 6. Output result
+    hook_io::write_hook_output(record.decision, format)?;
+
+    // Exit with appropriate code for deny
+    if record.decision == Decision::Deny {
+        std::process::exit(hook_io::deny_exit_code(format));
+    }
+
+    Ok(())
+}
+
+/// Report the decision a dry run would have made, then allow the call.
+fn allow_dry_run(decision: Decision, reason: &str, format: HookFormat) -> Result<()> {
+    eprintln!("hookwise: dry run: would {} ({})", decision, reason);
+    hook_io::write_hook_output(Decision::Allow, format)
+}
+
+/// Append `record` as one JSON line to the dry-run audit log.
+fn append_audit_record(path: &Path, record: &DecisionRecord) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Audit entry for a call decided without running the cascade. The input
+/// is sanitized when it is appended.
+fn forced_entry(input: &HookInput, decision: Decision, tier: DecisionTier) -> AuditEntry {
+    AuditEntry {
+        timestamp: chrono::Utc::now(),
+        session_id: input.session_id.clone(),
+        tool: input.tool_name.clone(),
+        sanitized_input: serde_json::to_string(&input.tool_input).unwrap_or_default(),
+        decision,
+        tier,
+        confidence: 1.0,
+        id: String::new(),
+        role: String::new(),
+        scope: None,
+        origin: String::new(),
+    }
+}
+
+/// Append an outcome to the project's audit log, when `audit.enabled` is
+/// set, sanitizing the input with `role`'s pipeline (the default one for a
+/// session without a role).
+fn audit(
+    cwd_path: &Path,
+    policy: &PolicyConfig,
+    role: Option<&RoleDefinition>,
+    entry: &AuditEntry,
+) -> Result<()> {
+    if !policy.audit.enabled {
         return Ok(());
     }
+    let sanitizer = SanitizePipeline::for_role(policy, role)?;
+    let config_dir = crate::config::project_config_dir(cwd_path);
+    match AuditLog::for_project(&config_dir, &policy.audit, sanitizer) {
+        Some(log) => log.append(entry),
+        None => Ok(()),
+    }
+}
+
+/// Print the decision and every stored rule, across all scopes, that matches
+/// its key.
+fn explain_decision(
+    cwd_path: &Path,
+    policy: &PolicyConfig,
+    session: &SessionContext,
+    record: &DecisionRecord,
+) -> Result<()> {
+    eprintln!(
+        "hookwise: {} by {:?} ({})",
+        record.decision, record.metadata.tier, record.metadata.reason
+    );
+
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(cwd_path),
+        dirs_global(),
+        Some(session.org.clone()),
+    )?;
+    let resolver = ScopeResolver::new(storage);
+    match resolver.resolve_explained(&record.key, session)? {
+        Some(explanation) => {
+            eprintln!(
+                "hookwise: stored rules resolve to {} at {} scope",
+                explanation.winner.decision, explanation.winner.scope
+            );
+            for candidate in &explanation.candidates {
+                eprintln!(
+                    "  {:<8} {:<6} role={} tier={:?}",
+                    candidate.scope.to_string(),
+                    candidate.decision.to_string(),
+                    candidate.record.key.role,
+                    candidate.record.metadata.tier
+                );
+            }
+        }
+        None => eprintln!("hookwise: no stored rule matches at any scope"),
+    }
+    Ok(())
+}
 
-    // 4. Build cascade runner
-    let project_root = cwd_path.join(".hookwise");
+/// Build a cascade runner for the project at `cwd_path`, with caches loaded
+/// from project-scope storage.
+pub(crate) fn build_runner(
+    cwd_path: &Path,
+    policy: &PolicyConfig,
+    normalizer: Option<PathNormalizer>,
+    org: &str,
+    team_id: Option<&str>,
+) -> Result<CascadeRunner> {
+    let project_root = crate::config::project_config_dir(cwd_path);
     let global_root = dirs_global();
 
-    let storage = JsonlStorage::new(
+    let storage = crate::storage::open_backend(
+        &policy.storage,
         project_root.clone(),
         global_root.clone(),
-        Some(session.org.clone()),
-    );
+        Some(org.to_string()),
+    )?;
 
     // Load existing decisions for caches
     let all_decisions = storage.load_decisions(crate::scope::ScopeLevel::Project)?;
@@ -81,32 +350,44 @@ pub async fn run(format: HookFormat) -> Result<()> {
     let exact_cache = Arc::new(ExactCache::new());
     exact_cache.load_from(all_decisions.clone());
 
-    let token_jaccard = Arc::new(TokenJaccard::new(
-        policy.similarity.jaccard_threshold,
-        policy.similarity.jaccard_min_tokens,
-    ));
+    let token_jaccard = Arc::new(
+        TokenJaccard::new(
+            policy.similarity.jaccard_threshold,
+            policy.similarity.jaccard_min_tokens,
+            policy.similarity.jaccard_ngram,
+        )
+        .with_min_overlap(policy.similarity.jaccard_min_overlap)
+        .with_idf_weighting(policy.similarity.jaccard_weighting)
+        .with_deny_on_similarity(policy.similarity.deny_on_match),
+    );
     token_jaccard.load_from(&all_decisions);
 
     // Embedding similarity -- try to create, fall back to no-op if model loading fails
-    let embedding_similarity =
-        match EmbeddingSimilarity::new("default", policy.similarity.embedding_threshold) {
-            Ok(es) => {
-                let _ = es.build_index(&all_decisions);
-                Arc::new(es)
-            }
-            Err(e) => {
-                eprintln!("hookwise: embedding tier unavailable, skipping ({})", e);
-                Arc::new(EmbeddingSimilarity::new_noop())
+    let embedding_similarity = match EmbeddingSimilarity::new(
+        "default",
+        policy.similarity.embedding_threshold,
+        policy.similarity.embedding_pending_threshold,
+    ) {
+        Ok(es) => {
+            let es = es.with_deny_on_similarity(policy.similarity.deny_on_match);
+            let index_path = EmbeddingSimilarity::index_path(&project_root);
+            if let Err(e) = es.load_or_build_index(&index_path, &all_decisions) {
+                tracing::warn!("embedding index unavailable: {}", e);
             }
-        };
+            Arc::new(es)
+        }
+        Err(e) => {
+            eprintln!("hookwise: embedding tier unavailable, skipping ({})", e);
+            Arc::new(EmbeddingSimilarity::new_noop(
+                policy.similarity.embedding_pending_threshold,
+            ))
+        }
+    };
 
     // Supervisor tier
     let supervisor: Box<dyn crate::cascade::CascadeTier> = match &policy.supervisor {
-        SupervisorConfig::Socket { socket_path } => {
-            let sock_path = socket_path.clone().unwrap_or_else(|| {
-                let tid = team_id.as_deref().unwrap_or("solo");
-                PathBuf::from(format!("/tmp/hookwise-{tid}.sock"))
-            });
+        SupervisorConfig::Socket { socket_path, .. } => {
+            let sock_path = supervisor_socket_path(socket_path.as_deref(), team_id);
             let backend = UnixSocketSupervisor::new(sock_path, 30);
             Box::new(SupervisorTier::new(Box::new(backend), policy.clone()))
         }
@@ -114,9 +395,11 @@ pub async fn run(format: HookFormat) -> Result<()> {
             api_base_url,
             model,
             max_tokens,
+            calls_per_minute,
+            timeout_secs,
         } => {
-            let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
-            let backend = crate::cascade::supervisor::ApiSupervisor::new(
+            let api_key = supervisor_api_key();
+            let mut backend = crate::cascade::supervisor::ApiSupervisor::new(
                 api_base_url
                     .clone()
                     .unwrap_or_else(|| "https://api.anthropic.com".into()),
@@ -126,7 +409,17 @@ pub async fn run(format: HookFormat) -> Result<()> {
                     .unwrap_or_else(|| "claude-sonnet-4-5-20250929".into()),
                 max_tokens.unwrap_or(1024),
             );
-            Box::new(SupervisorTier::new(Box::new(backend), policy.clone()))
+            if let Some(timeout_secs) = timeout_secs {
+                backend = backend.with_timeout(*timeout_secs);
+            }
+            let backend: Box<dyn SupervisorBackend> = match calls_per_minute {
+                Some(limit) => Box::new(RateLimitedSupervisor::new(
+                    Box::new(backend),
+                    TokenBucket::persisted(*limit, supervisor_budget_path()),
+                )),
+                None => Box::new(backend),
+            };
+            Box::new(SupervisorTier::new(backend, policy.clone()))
         }
     };
 
@@ -134,43 +427,35 @@ pub async fn run(format: HookFormat) -> Result<()> {
     let decision_queue = Arc::new(DecisionQueue::new());
     let human = HumanTier::new(decision_queue, policy.human_timeout_secs);
 
-    let runner = CascadeRunner {
-        sanitizer: SanitizePipeline::default_pipeline(),
+    Ok(CascadeRunner {
+        sanitizer: SanitizePipeline::for_role(policy, None)?,
         path_policy: Box::new(path_policy),
         exact_cache,
         token_jaccard,
         embedding_similarity,
         supervisor,
         human: Box::new(human),
-        storage: Box::new(storage),
+        storage,
         policy: policy.clone(),
         normalizer,
-    };
-
-    // 5. Run cascade
-    let record = match runner
-        .evaluate_with_cwd(&session, &input.tool_name, &input.tool_input, Some(cwd))
-        .await
-    {
-        Ok(record) => record,
-        Err(e) => {
-            // On cascade error (e.g. human timeout), default to deny
-            // but still write output so callers can parse it.
-            eprintln!("hookwise: cascade error, defaulting to deny ({})", e);
-            hook_io::write_hook_output(Decision::Deny, format)?;
-            std::process::exit(hook_io::deny_exit_code(format));
-        }
-    };
-
-    // 6. Output result
-    hook_io::write_hook_output(record.decision, format)?;
-
-    // Exit with appropriate code for deny
-    if record.decision == Decision::Deny {
-        std::process::exit(hook_io::deny_exit_code(format));
-    }
+        dry_run: false,
+        usage: Default::default(),
+        scopes: None,
+    })
+}
 
-    Ok(())
+/// API key for the supervisor: `ANTHROPIC_API_KEY`, otherwise `api_key` from
+/// the global config.
+fn supervisor_api_key() -> String {
+    std::env::var("ANTHROPIC_API_KEY")
+        .ok()
+        .or_else(|| {
+            crate::config::GlobalConfig::load()
+                .ok()
+                .flatten()
+                .and_then(|config| config.api_key)
+        })
+        .unwrap_or_default()
 }
 
 /// Get the global config directory.
@@ -0,0 +1,24 @@
+use crate::error::Result;
+use crate::storage::jsonl::JsonlStorage;
+
+/// Report rule file lines that could not be parsed and were quarantined.
+pub async fn run() -> Result<()> {
+    let cwd = crate::config::project_root();
+    let storage = JsonlStorage::new(
+        crate::config::project_config_dir(&cwd),
+        crate::config::dirs_global(),
+        None,
+    );
+
+    let lines = storage.quarantined_lines()?;
+    if lines.is_empty() {
+        eprintln!("hookwise: no quarantined lines");
+        return Ok(());
+    }
+
+    eprintln!("hookwise: {} quarantined line(s):", lines.len());
+    for line in &lines {
+        eprintln!("  {}:{}", line.file.display(), line.line);
+    }
+    std::process::exit(1);
+}
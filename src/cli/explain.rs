@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::cascade::explain::{CascadeTrace, TierOutcome};
+use crate::config::{CompiledPathPolicy, PolicyConfig, RolesConfig};
+use crate::decision::DecisionRecord;
+use crate::error::Result;
+use crate::sanitize::SanitizePipeline;
+use crate::session::SessionContext;
+
+/// Trace a tool call through the cascade as `role` would see it, printing
+/// what each tier decided. Read-only: nothing is persisted and the
+/// supervisor/human are never contacted.
+pub async fn run(tool: &str, input: &str, role: &str, file: Option<&str>) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let cwd_str = cwd.to_string_lossy().into_owned();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let roles = RolesConfig::load_project(&cwd)?;
+
+    let Some(role_def) = roles.get_role(role) else {
+        eprintln!("hookwise: unknown role '{}'", role);
+        std::process::exit(1);
+    };
+    let (org, project) = crate::session::extract_git_org_project(&cwd_str);
+    let mut session = SessionContext::new_minimal(crate::session::whoami(), org, project);
+    let compiled = CompiledPathPolicy::compile(&role_def.paths, &policy.sensitive_paths.ask_write)?;
+    session.path_policy = Some(Arc::new(compiled));
+    session.role = Some(role_def.clone());
+
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let mut runner = super::check::build_runner(
+        &cwd,
+        &policy,
+        roles.normalizer().ok(),
+        &session.org,
+        team_id.as_deref(),
+    )?;
+    runner.sanitizer = SanitizePipeline::for_role(&policy, Some(role_def))?;
+
+    let tool_input = tool_input(tool, input, file);
+    let trace = runner
+        .trace(&session, tool, &tool_input, Some(&cwd_str))
+        .await?;
+
+    println!("Explaining {} as role {}", tool, role);
+    println!("  input:     {}", tool_input);
+    println!("  sanitized: {}", trace.sanitized_input);
+    println!();
+    print_trace(&trace);
+    Ok(())
+}
+
+/// Build the tool input JSON. A JSON object is taken as is; anything else
+/// fills the tool's primary field. `file` fills its path field.
+fn tool_input(tool: &str, input: &str, file: Option<&str>) -> Value {
+    let mut value = match serde_json::from_str::<Value>(input) {
+        Ok(value @ Value::Object(_)) => value,
+        _ => {
+            let field = match tool {
+                "Bash" => "command",
+                "Glob" | "Grep" => "pattern",
+                "Write" => "content",
+                "Edit" => "new_string",
+                _ => "input",
+            };
+            json!({ field: input })
+        }
+    };
+    if let (Some(file), Value::Object(map)) = (file, &mut value) {
+        let field = match tool {
+            "Glob" | "Grep" => "path",
+            "NotebookEdit" => "notebook_path",
+            _ => "file_path",
+        };
+        map.insert(field.to_string(), Value::String(file.to_string()));
+    }
+    value
+}
+
+fn print_trace(trace: &CascadeTrace) {
+    if let Some((decision, reason)) = trace.short_circuit {
+        println!("  decided before the cascade: {} ({})", decision, reason);
+        return;
+    }
+
+    for step in &trace.steps {
+        match &step.outcome {
+            TierOutcome::Resolved(record) => {
+                println!("  {:<22} matched", step.name);
+                print_record(record);
+            }
+            TierOutcome::BelowFloor { record, floor } => {
+                println!(
+                    "  {:<22} matched below the {} confidence floor ({:.2}), fell through",
+                    step.name, record.scope, floor
+                );
+                print_record(record);
+            }
+            TierOutcome::FellThrough => println!("  {:<22} no match", step.name),
+        }
+    }
+
+    match trace.resolved() {
+        Some(record) => println!(
+            "\nDecided by {:?}: {}",
+            record.metadata.tier, record.decision
+        ),
+        None => println!("\nNo local tier resolved it; it would escalate to the supervisor/human."),
+    }
+}
+
+fn print_record(record: &DecisionRecord) {
+    println!("      decision:    {}", record.decision);
+    println!("      confidence:  {:.2}", record.metadata.confidence);
+    println!("      reason:      {}", record.metadata.reason);
+    if let Some(key) = &record.metadata.matched_key {
+        println!(
+            "      matched key: {} (tool {}, role {})",
+            key.sanitized_input, key.tool, key.role
+        );
+    }
+}
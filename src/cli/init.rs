@@ -6,11 +6,13 @@ use crate::error::Result;
 /// Initialize .hookwise/ in the current repo.
 pub async fn run() -> Result<()> {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let hook_dir = cwd.join(".hookwise");
+    let hook_dir = crate::config::project_config_dir(&cwd);
+    let dir_name = hook_dir.strip_prefix(&cwd).unwrap_or(&hook_dir).display();
 
     if hook_dir.exists() {
         eprintln!(
-            "hookwise: .hookwise/ already exists in {}",
+            "hookwise: {}/ already exists in {}",
+            dir_name,
             cwd.display()
         );
         return Ok(());
@@ -21,8 +23,8 @@ pub async fn run() -> Result<()> {
     fs::create_dir_all(hook_dir.join(".index"))?;
     fs::create_dir_all(hook_dir.join(".user"))?;
 
-    // Write default policy.yml
-    let policy_content = r#"# hookwise project policy
+    // Write default policy.yml, protecting the directory just created
+    let policy_template = r#"# hookwise project policy
 # See docs for full configuration reference.
 
 sensitive_paths:
@@ -45,6 +47,11 @@ similarity:
   jaccard_threshold: 0.7
   embedding_threshold: 0.85
   jaccard_min_tokens: 3
+  jaccard_min_overlap: 2
+  jaccard_ngram: 1
+  jaccard_weighting: false
+  deny_on_match: false
+  embedding_pending_threshold: 50
 
 human_timeout_secs: 60
 registration_timeout_secs: 5
@@ -52,6 +59,8 @@ registration_timeout_secs: 5
 supervisor:
   backend: socket
 "#;
+    let policy_content =
+        policy_template.replace("\".hookwise/**\"", &format!("\"{}/**\"", dir_name));
     fs::write(hook_dir.join("policy.yml"), policy_content)?;
 
     // Write default roles.yml
@@ -115,8 +124,8 @@ roles:
 "#;
     fs::write(hook_dir.join("roles.yml"), roles_content)?;
 
-    // Write .gitignore for local-only directories
-    let gitignore_content = ".index/\n.user/\n";
+    // Write .gitignore for local-only directories and the audit log
+    let gitignore_content = ".index/\n.user/\naudit.jsonl*\n";
     fs::write(hook_dir.join(".gitignore"), gitignore_content)?;
 
     // Create empty rule files
@@ -124,10 +133,7 @@ roles:
     fs::write(hook_dir.join("rules").join("deny.jsonl"), "")?;
     fs::write(hook_dir.join("rules").join("ask.jsonl"), "")?;
 
-    eprintln!(
-        "hookwise: initialized .hookwise/ in {}",
-        cwd.display()
-    );
+    eprintln!("hookwise: initialized {}/ in {}", dir_name, cwd.display());
     eprintln!("  policy.yml  -- project policy (sensitive paths, thresholds)");
     eprintln!("  roles.yml   -- role definitions with path policies");
     eprintln!("  rules/      -- cached decisions (allow.jsonl, deny.jsonl, ask.jsonl)");
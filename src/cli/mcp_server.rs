@@ -1,4 +1,3 @@
-use std::path::PathBuf;
 use std::sync::Arc;
 
 use rmcp::handler::server::tool::ToolRouter;
@@ -11,12 +10,11 @@ use serde::Deserialize;
 
 use crate::cascade::cache::ExactCache;
 use crate::cascade::human::{load_queue_file, DecisionQueue, HumanResponse};
+use crate::config::PolicyConfig;
 use crate::decision::Decision;
 use crate::error::Result;
 use crate::scope::ScopeLevel;
 use crate::session::SessionManager;
-use crate::storage::jsonl::JsonlStorage;
-use crate::storage::StorageBackend;
 
 #[derive(Clone)]
 pub struct HookwiseMcp {
@@ -59,9 +57,12 @@ pub struct ApproveParams {
     /// Add as a persistent rule
     #[serde(default)]
     pub add_rule: bool,
-    /// Rule scope: project, user, or org
+    /// Rule scope: project, user, org, or global
     #[serde(default = "default_scope")]
     pub scope: String,
+    /// Allow this call only, without caching or persisting a rule
+    #[serde(default)]
+    pub once: bool,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -74,7 +75,7 @@ pub struct DenyParams {
     /// Add as a persistent rule
     #[serde(default)]
     pub add_rule: bool,
-    /// Rule scope: project, user, or org
+    /// Rule scope: project, user, org, or global
     #[serde(default = "default_scope")]
     pub scope: String,
 }
@@ -105,7 +106,7 @@ impl HookwiseMcp {
         let session_mgr = SessionManager::new(team_id.as_deref());
 
         // Validate role
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let cwd = crate::config::project_root();
         let roles = crate::config::RolesConfig::load_project(&cwd).map_err(|e| {
             McpError::internal_error(format!("Failed to load roles config: {}", e), None)
         })?;
@@ -120,7 +121,7 @@ impl HookwiseMcp {
         }
 
         session_mgr
-            .register(&p.session_id, &p.role, p.task.as_deref(), None)
+            .register(&p.session_id, &p.role, p.task.as_deref(), None, None)
             .map_err(|e| McpError::internal_error(format!("Registration failed: {}", e), None))?;
 
         let role_def = roles.get_role(&p.role).unwrap();
@@ -176,11 +177,17 @@ impl HookwiseMcp {
         description = "Show hookwise statistics: cached decisions, hit rates, and decision distribution by tier/role/tool."
     )]
     async fn hookwise_status(&self) -> std::result::Result<CallToolResult, McpError> {
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let project_root = cwd.join(".hookwise");
+        let cwd = crate::config::project_root();
+        let project_root = crate::config::project_config_dir(&cwd);
         let global_root = crate::config::dirs_global();
 
-        let storage = JsonlStorage::new(project_root, global_root, None);
+        let storage = PolicyConfig::load_project(&cwd)
+            .and_then(|policy| {
+                crate::storage::open_backend(&policy.storage, project_root, global_root, None)
+            })
+            .map_err(|e| {
+                McpError::internal_error(format!("Failed to open storage: {}", e), None)
+            })?;
         let decisions = storage.load_decisions(ScopeLevel::Project).map_err(|e| {
             McpError::internal_error(format!("Failed to load decisions: {}", e), None)
         })?;
@@ -258,6 +265,13 @@ impl HookwiseMcp {
         let p = params.0;
         let queue = Arc::new(DecisionQueue::new());
 
+        if p.once && (p.always_ask || p.add_rule) {
+            return Err(McpError::invalid_params(
+                "'once' cannot be combined with 'always_ask' or 'add_rule'".to_string(),
+                None,
+            ));
+        }
+
         let rule_scope = if p.add_rule {
             Some(p.scope.parse::<ScopeLevel>().map_err(|e| {
                 McpError::invalid_params(format!("Invalid scope '{}': {}", p.scope, e), None)
@@ -271,6 +285,7 @@ impl HookwiseMcp {
             always_ask: p.always_ask,
             add_rule: p.add_rule,
             rule_scope,
+            once: p.once,
         };
 
         queue
@@ -278,6 +293,9 @@ impl HookwiseMcp {
             .map_err(|e| McpError::internal_error(format!("Approve failed: {}", e), None))?;
 
         let mut msg = format!("Approved decision {}", p.id);
+        if p.once {
+            msg.push_str(" (this call only -- not cached)");
+        }
         if p.always_ask {
             msg.push_str(" (cached as 'ask' -- will always prompt)");
         }
@@ -312,6 +330,7 @@ impl HookwiseMcp {
             always_ask: p.always_ask,
             add_rule: p.add_rule,
             rule_scope,
+            once: false,
         };
 
         queue
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::{LEGACY_PROJECT_DIR_NAME, PROJECT_DIR_NAME};
+use crate::error::Result;
+
+/// Move the project's configuration directory between the legacy
+/// `.captain-hook/` name and `.hookwise/` (the reverse when `to_legacy`),
+/// and point the repository's `.gitignore` and `policy.yml` at the new name.
+///
+/// The directory is renamed in place, so `rules/`, `.index/`, `.user/` and
+/// file permissions carry over. Running it again once migrated does nothing.
+/// A non-empty destination is only replaced with `force`.
+pub async fn run(to_legacy: bool, force: bool) -> Result<()> {
+    let (from, to) = if to_legacy {
+        (PROJECT_DIR_NAME, LEGACY_PROJECT_DIR_NAME)
+    } else {
+        (LEGACY_PROJECT_DIR_NAME, PROJECT_DIR_NAME)
+    };
+    let root = crate::config::project_root();
+    let src = root.join(from);
+    let dst = root.join(to);
+
+    if !src.is_dir() {
+        if dst.is_dir() {
+            eprintln!("hookwise: {}/ is already in use; nothing to migrate", to);
+        } else {
+            eprintln!("hookwise: no {}/ found in {}", from, root.display());
+        }
+        return Ok(());
+    }
+
+    if dst.exists() {
+        let empty = dst.is_dir() && fs::read_dir(&dst)?.next().is_none();
+        if !empty && !force {
+            eprintln!(
+                "hookwise: {} already exists and is not empty; use --force to replace it",
+                dst.display()
+            );
+            std::process::exit(1);
+        }
+        if dst.is_dir() {
+            fs::remove_dir_all(&dst)?;
+        } else {
+            fs::remove_file(&dst)?;
+        }
+    }
+
+    fs::rename(&src, &dst)?;
+    eprintln!("hookwise: moved {}/ to {}/", from, to);
+
+    for file in [root.join(".gitignore"), dst.join("policy.yml")] {
+        if rewrite_dir_references(&file, from, to)? {
+            eprintln!("hookwise: updated {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Replace `from/` with `to/` in `file`, if it exists. Returns whether it
+/// changed.
+fn rewrite_dir_references(file: &Path, from: &str, to: &str) -> Result<bool> {
+    let Ok(contents) = fs::read_to_string(file) else {
+        return Ok(false);
+    };
+    let updated = contents.replace(&format!("{from}/"), &format!("{to}/"));
+    if updated == contents {
+        return Ok(false);
+    }
+    fs::write(file, updated)?;
+    Ok(true)
+}
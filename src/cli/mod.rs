@@ -1,53 +1,121 @@
 pub mod build;
+pub mod bundle;
 pub mod check;
+pub mod doctor;
+pub mod explain;
 pub mod init;
 pub mod mcp_server;
+pub mod migrate;
 pub mod monitor;
 pub mod override_cmd;
+pub mod ping;
 pub mod queue;
 pub mod register;
+pub mod replay;
+pub mod roles;
 pub mod scan;
+pub mod seed;
 pub mod self_update;
 pub mod session_check;
+pub mod sync;
 
 use std::path::PathBuf;
 
-use crate::config::{GlobalConfig, PolicyConfig};
+use crate::config::{EffectiveConfig, GlobalConfig, PolicyConfig};
 use crate::error::Result;
 
 /// Dispatch a CLI command.
 pub async fn dispatch(command: crate::Commands) -> Result<()> {
     match command {
-        crate::Commands::Check { format } => check::run(format).await,
+        crate::Commands::Check {
+            format,
+            explain,
+            dry_run,
+            audit_log,
+        } => check::run(format, explain, dry_run, audit_log.as_deref()).await,
         crate::Commands::SessionCheck { format } => session_check::run(format).await,
         crate::Commands::Register {
             session_id,
             role,
             task,
             prompt_file,
+            registered_by,
+            tags,
+            wait,
         } => {
-            register::run_register(&session_id, &role, task.as_deref(), prompt_file.as_deref())
-                .await
+            register::run_register(
+                &session_id,
+                &role,
+                task.as_deref(),
+                prompt_file.as_deref(),
+                registered_by.as_deref(),
+                tags.into_iter().collect(),
+                wait,
+            )
+            .await
         }
         crate::Commands::Disable { session_id } => register::run_disable(&session_id).await,
         crate::Commands::Enable { session_id } => register::run_enable(&session_id).await,
-        crate::Commands::Queue => queue::run_queue().await,
+        crate::Commands::Unregister { session_id } => register::run_unregister(&session_id).await,
+        crate::Commands::Sessions { json } => register::run_list_sessions(json).await,
+        crate::Commands::Prune => register::run_prune().await,
+        crate::Commands::Queue {
+            width,
+            full,
+            watch,
+            interval,
+        } => {
+            let width = (!full).then_some(width);
+            if watch {
+                queue::run_queue_watch(width, interval).await
+            } else {
+                queue::run_queue(width).await
+            }
+        }
         crate::Commands::Approve {
             id,
             always_ask,
             add_rule,
             scope,
-        } => queue::run_approve(&id, always_ask, add_rule, &scope).await,
+            generalize,
+            once,
+            all,
+            role,
+            tool,
+            yes,
+        } => {
+            let target = queue::QueueTarget::from_args(id, all, role, tool, yes);
+            queue::run_approve(
+                &target,
+                always_ask,
+                add_rule,
+                &scope,
+                generalize.as_deref(),
+                once,
+            )
+            .await
+        }
         crate::Commands::Deny {
             id,
             always_ask,
             add_rule,
             scope,
-        } => queue::run_deny(&id, always_ask, add_rule, &scope).await,
+            all,
+            role,
+            tool,
+            yes,
+        } => {
+            let target = queue::QueueTarget::from_args(id, all, role, tool, yes);
+            queue::run_deny(&target, always_ask, add_rule, &scope).await
+        }
         crate::Commands::Build => build::run_build().await,
         crate::Commands::Invalidate { role, scope, all } => {
             build::run_invalidate(role.as_deref(), scope.as_deref(), all).await
         }
+        crate::Commands::Compact { scope } => build::run_compact(scope.as_deref()).await,
+        crate::Commands::PruneRules { unused_for, scope } => {
+            build::run_prune_rules(unused_for, scope.as_deref()).await
+        }
         crate::Commands::Override {
             role,
             command,
@@ -57,6 +125,7 @@ pub async fn dispatch(command: crate::Commands) -> Result<()> {
             deny,
             ask,
             scope,
+            immutable,
         } => {
             override_cmd::run(
                 &role,
@@ -67,15 +136,53 @@ pub async fn dispatch(command: crate::Commands) -> Result<()> {
                 deny,
                 ask,
                 &scope,
+                immutable,
             )
             .await
         }
-        crate::Commands::Monitor => monitor::run_monitor().await,
-        crate::Commands::Stats => monitor::run_stats().await,
+        crate::Commands::Seed { file } => seed::run(&file).await,
+        crate::Commands::Monitor { format } => monitor::run_monitor(format).await,
+        crate::Commands::Stats { tags, json, scope } => {
+            monitor::run_stats(&tags, json, scope.as_deref()).await
+        }
+        crate::Commands::Export { scope, out } => bundle::run_export(&scope, &out).await,
+        crate::Commands::Import {
+            file,
+            scope,
+            keep_pins,
+        } => bundle::run_import(&file, &scope, keep_pins).await,
+        crate::Commands::Roles { command } => match command {
+            crate::RolesCommand::List => roles::run_list().await,
+            crate::RolesCommand::Show { name } => roles::run_show(&name).await,
+            crate::RolesCommand::Validate => roles::run_validate().await,
+        },
+        crate::Commands::Replay { id } => replay::run(&id).await,
+        crate::Commands::Explain {
+            tool,
+            input,
+            role,
+            file,
+        } => explain::run(&tool, &input, &role, file.as_deref()).await,
         crate::Commands::Scan { staged, path } => scan::run(staged, path.as_deref()).await,
+        crate::Commands::Doctor => doctor::run().await,
         crate::Commands::Init => init::run().await,
-        crate::Commands::Config => run_config().await,
-        crate::Commands::Sync => run_sync().await,
+        crate::Commands::Ping => ping::run().await,
+        crate::Commands::Migrate { to_legacy, force } => migrate::run(to_legacy, force).await,
+        crate::Commands::Config { effective, json } => {
+            if effective {
+                run_config_effective(json).await
+            } else {
+                run_config().await
+            }
+        }
+        crate::Commands::Sync { pull, push, remote } => {
+            let mode = match (pull, push) {
+                (true, _) => sync::SyncMode::Pull,
+                (_, true) => sync::SyncMode::Push,
+                _ => sync::SyncMode::Both,
+            };
+            sync::run(mode, &remote).await
+        }
         crate::Commands::McpServer => mcp_server::run().await,
         crate::Commands::SelfUpdate { check } => self_update::run(check).await,
     }
@@ -104,8 +211,8 @@ async fn run_config() -> Result<()> {
     }
 
     // Show project config
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_config_path = cwd.join(".hookwise").join("policy.yml");
+    let cwd = crate::config::project_root();
+    let project_config_path = crate::config::project_config_dir(&cwd).join("policy.yml");
 
     println!("\nProject config: {}", project_config_path.display());
     if project_config_path.exists() {
@@ -136,10 +243,21 @@ async fn run_config() -> Result<()> {
     Ok(())
 }
 
-/// Pull latest org-level rules (placeholder).
-async fn run_sync() -> Result<()> {
-    eprintln!("hookwise: sync is not yet implemented.");
-    eprintln!("Org-level rule syncing will be available in a future release.");
+/// Print the effective configuration: built-in defaults overlaid by the
+/// global config, then the project policy, each setting tagged with the
+/// layer it came from.
+async fn run_config_effective(json: bool) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let config = EffectiveConfig::load(&cwd)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(());
+    }
+    let width = config.settings.keys().map(String::len).max().unwrap_or(0);
+    for (key, setting) in &config.settings {
+        println!("{:width$}  {}  ({})", key, setting.value, setting.source);
+    }
     Ok(())
 }
 
@@ -1,138 +1,424 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tokio::sync::mpsc;
 
 use crate::cascade::cache::ExactCache;
-use crate::error::Result;
+use crate::cascade::embed_sim::{EmbeddingSimilarity, IndexHealth};
+use crate::cascade::token_sim::TokenJaccard;
+use crate::cascade::{CascadeMetrics, TierMetrics};
+use crate::config::{PolicyConfig, StorageBackendKind};
+use crate::decision::DecisionRecord;
+use crate::error::{HookwiseError, Result};
 use crate::scope::ScopeLevel;
-use crate::storage::jsonl::JsonlStorage;
-use crate::storage::StorageBackend;
+
+/// How `monitor` prints each decision.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MonitorFormat {
+    /// One readable line with the id, tier, confidence, and reason.
+    #[default]
+    Pretty,
+    /// The raw decision record, one JSON object per line.
+    Json,
+    /// Time, decision, tool, role, and tier only.
+    Compact,
+}
+
+/// Render one decision as a single line in `format`.
+pub fn format_record(record: &DecisionRecord, format: MonitorFormat) -> String {
+    match format {
+        MonitorFormat::Pretty => format!(
+            "[{}] {} {} {} {} (tier: {:?}, confidence: {:.2}) -- {}",
+            record.timestamp.format("%H:%M:%S"),
+            record.id,
+            record.decision,
+            record.key.tool,
+            record.key.role,
+            record.metadata.tier,
+            record.metadata.confidence,
+            record.metadata.reason,
+        ),
+        MonitorFormat::Json => serde_json::to_string(record).unwrap_or_default(),
+        MonitorFormat::Compact => format!(
+            "{} {} {} {} {:?}",
+            record.timestamp.format("%H:%M:%S"),
+            record.decision,
+            record.key.tool,
+            record.key.role,
+            record.metadata.tier,
+        ),
+    }
+}
 
 /// Stream decisions in real time.
-/// Watches the JSONL rule files for changes and prints new decisions.
-pub async fn run_monitor() -> Result<()> {
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_root = cwd.join(".hookwise");
+/// Watches the JSONL rule files for changes and prints each new decision in
+/// `format`. Exits with an error under the SQLite backend, which has no rule
+/// files to follow.
+pub async fn run_monitor(format: MonitorFormat) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    if policy.storage.backend != StorageBackendKind::Jsonl {
+        eprintln!("hookwise: monitor follows JSONL rule files; it does not support the sqlite storage backend");
+        std::process::exit(1);
+    }
+    let project_root = crate::config::project_config_dir(&cwd);
     let rules_dir = project_root.join("rules");
 
-    eprintln!(
-        "hookwise: monitoring decisions in {}",
-        rules_dir.display()
-    );
+    let mut stream = DecisionStream::open(&rules_dir);
+    eprintln!("hookwise: monitoring decisions in {}", rules_dir.display());
+    if !stream.is_watching() {
+        eprintln!("hookwise: file watching unavailable, polling every second");
+    }
     eprintln!("Press Ctrl+C to stop.\n");
 
-    // Track file sizes to detect new entries
-    let mut last_sizes = std::collections::HashMap::new();
-    for file in &["allow.jsonl", "deny.jsonl", "ask.jsonl"] {
-        let path = rules_dir.join(file);
-        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-        last_sizes.insert(file.to_string(), size);
+    loop {
+        for record in stream.next_batch().await {
+            println!("{}", format_record(&record, format));
+        }
     }
+}
 
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-
-        for file in &["allow.jsonl", "deny.jsonl", "ask.jsonl"] {
-            let path = rules_dir.join(file);
-            let current_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-            let last_size = last_sizes.get(*file).copied().unwrap_or(0);
-
-            if current_size > last_size {
-                // New content added -- read and display new lines
-                if let Ok(contents) = std::fs::read_to_string(&path) {
-                    // Find a safe UTF-8 char boundary at or after the byte offset
-                    let byte_offset = last_size as usize;
-                    let safe_offset = if byte_offset >= contents.len() {
-                        contents.len()
-                    } else if contents.is_char_boundary(byte_offset) {
-                        byte_offset
-                    } else {
-                        // Scan forward to the next char boundary
-                        (byte_offset..contents.len())
-                            .find(|&i| contents.is_char_boundary(i))
-                            .unwrap_or(contents.len())
-                    };
-                    let new_content = &contents[safe_offset..];
-                    for line in new_content.lines() {
-                        let trimmed = line.trim();
-                        if trimmed.is_empty() {
-                            continue;
-                        }
-                        if let Ok(record) =
-                            serde_json::from_str::<crate::decision::DecisionRecord>(trimmed)
-                        {
-                            println!(
-                                "[{}] {} {} {} (tier: {:?}, confidence: {:.2}) -- {}",
-                                record.timestamp.format("%H:%M:%S"),
-                                record.decision,
-                                record.key.tool,
-                                record.key.role,
-                                record.metadata.tier,
-                                record.metadata.confidence,
-                                record.metadata.reason,
-                            );
+/// Rule files `monitor` follows.
+const RULE_FILES: [&str; 3] = ["allow.jsonl", "deny.jsonl", "ask.jsonl"];
+
+/// Decisions appended to a project's rule files, as they are written.
+///
+/// Reacts to file system events on the rules directory, and falls back to
+/// polling once a second when the watcher cannot be started (e.g. the
+/// directory does not exist yet, or the platform has no notify backend).
+pub struct DecisionStream {
+    rules_dir: PathBuf,
+    /// Bytes of each rule file already read, up to the end of its last
+    /// complete line.
+    offsets: HashMap<&'static str, u64>,
+    /// Kept alive for as long as the stream; `None` when polling.
+    watcher: Option<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)>,
+}
+
+impl DecisionStream {
+    /// Follow the rule files in `rules_dir` from their current end.
+    pub fn open(rules_dir: &Path) -> Self {
+        let offsets = RULE_FILES
+            .iter()
+            .map(|file| (*file, file_len(&rules_dir.join(file))))
+            .collect();
+        let watcher = match watch_rules(rules_dir) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::warn!("could not watch {}: {}", rules_dir.display(), e);
+                None
+            }
+        };
+        Self {
+            rules_dir: rules_dir.to_path_buf(),
+            offsets,
+            watcher,
+        }
+    }
+
+    /// Whether new decisions arrive through file system events rather than
+    /// polling.
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Wait for the rule files to change, then return the decisions
+    /// appended since the last call. May return none, e.g. when a file was
+    /// touched without growing.
+    pub async fn next_batch(&mut self) -> Vec<DecisionRecord> {
+        match &mut self.watcher {
+            Some((_, events)) => {
+                if events.recv().await.is_some() {
+                    // One read covers every event queued so far
+                    while events.try_recv().is_ok() {}
+                } else {
+                    // The watcher died; carry on by polling
+                    self.watcher = None;
+                }
+            }
+            None => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+        }
+        self.read_appended()
+    }
+
+    /// Read the lines appended to each rule file since it was last read.
+    /// A line still being written is left for the next call, and a file
+    /// that shrank (rewritten by `prune` or `import`) is read from the start.
+    fn read_appended(&mut self) -> Vec<DecisionRecord> {
+        let mut records = Vec::new();
+        for file in RULE_FILES {
+            let path = self.rules_dir.join(file);
+            let current_size = file_len(&path);
+            let mut offset = self.offsets.get(file).copied().unwrap_or(0);
+            if current_size < offset {
+                offset = 0;
+            }
+            if current_size > offset {
+                match read_complete_lines(&path, offset) {
+                    Ok(bytes) => {
+                        for line in String::from_utf8_lossy(&bytes).lines() {
+                            let trimmed = line.trim();
+                            if trimmed.is_empty() {
+                                continue;
+                            }
+                            if let Ok(mut record) = serde_json::from_str::<DecisionRecord>(trimmed)
+                            {
+                                record.ensure_id();
+                                records.push(record);
+                            }
                         }
+                        offset += bytes.len() as u64;
                     }
+                    Err(e) => tracing::warn!("could not read {}: {}", path.display(), e),
                 }
-
-                last_sizes.insert(file.to_string(), current_size);
             }
+            self.offsets.insert(file, offset);
         }
+        records
     }
 }
 
-/// Show cache hit rates and decision distribution.
-pub async fn run_stats() -> Result<()> {
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_root = cwd.join(".hookwise");
+/// The bytes of `path` from `offset` through its last newline.
+fn read_complete_lines(path: &Path, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let end = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    bytes.truncate(end);
+    Ok(bytes)
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Watch `rules_dir` and signal on the channel whenever a rule file is
+/// written or replaced.
+fn watch_rules(rules_dir: &Path) -> Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let handler = move |event: notify::Result<Event>| {
+        let relevant = match event {
+            Ok(event) => {
+                // Lost events may have been appends, so check every file
+                event.need_rescan()
+                    || event.paths.iter().any(|path| {
+                        path.file_name()
+                            .is_some_and(|name| RULE_FILES.iter().any(|f| name == *f))
+                    })
+            }
+            Err(_) => true,
+        };
+        if relevant {
+            let _ = tx.send(());
+        }
+    };
+
+    let watch_error = |e: notify::Error| HookwiseError::Watch {
+        reason: e.to_string(),
+    };
+    let mut watcher = notify::recommended_watcher(handler).map_err(watch_error)?;
+    watcher
+        .watch(rules_dir, RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+    Ok((watcher, rx))
+}
+
+/// Show cache hit rates and decision distribution across every scope, or
+/// only `scope`, optionally restricted to decisions carrying all of the
+/// given session tags. With `json`, only the counts are printed, as a single
+/// JSON object.
+pub async fn run_stats(tags: &[(String, String)], json: bool, scope: Option<&str>) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let project_root = crate::config::project_config_dir(&cwd);
     let global_root = dirs_global();
 
-    let storage = JsonlStorage::new(project_root, global_root, None);
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage =
+        crate::storage::open_backend(&policy.storage, project_root.clone(), global_root, None)?;
 
-    let decisions = storage.load_decisions(ScopeLevel::Project)?;
+    let mut decisions = match scope {
+        Some(s) => {
+            let scope = s
+                .parse::<ScopeLevel>()
+                .map_err(|e| crate::error::HookwiseError::InvalidPolicy { reason: e })?;
+            let mut decisions = storage.load_decisions(scope)?;
+            // Project and role rules share a directory; tell them apart by
+            // the scope each was saved at
+            if matches!(scope, ScopeLevel::Project | ScopeLevel::Role) {
+                decisions.retain(|r| r.scope == scope);
+            }
+            decisions
+        }
+        None => {
+            // Role is left out: loading Project already reads its directory
+            let mut decisions = Vec::new();
+            for scope in [
+                ScopeLevel::Project,
+                ScopeLevel::User,
+                ScopeLevel::Org,
+                ScopeLevel::Global,
+            ] {
+                decisions.extend(storage.load_decisions(scope)?);
+            }
+            let mut seen = std::collections::HashSet::new();
+            decisions.retain(|r| seen.insert(r.id.clone()));
+            decisions
+        }
+    };
+    if !tags.is_empty() {
+        decisions.retain(|r| tags.iter().all(|(k, v)| r.tags.get(k) == Some(v)));
+    }
 
-    // Build an ExactCache to get stats
-    let cache = ExactCache::new();
-    cache.load_from(decisions.clone());
-    let stats = cache.stats();
+    let mut summary = StatsSummary::from_decisions(&decisions);
+    summary.tiers = CascadeMetrics::load(&CascadeMetrics::path(&project_root))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
 
     println!("hookwise statistics");
     println!("=======================");
-    println!("Total cached decisions: {}", stats.total_entries);
-    println!("  Allow: {}", stats.allow_entries);
-    println!("  Deny:  {}", stats.deny_entries);
-    println!("  Ask:   {}", stats.ask_entries);
+    if !tags.is_empty() {
+        let filter: Vec<String> = tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        println!("Tags: {}", filter.join(", "));
+    }
+    println!("Total cached decisions: {}", summary.total);
+    println!("  Allow: {}", summary.allow);
+    println!("  Deny:  {}", summary.deny);
+    println!("  Ask:   {}", summary.ask);
     println!();
 
-    // Count by tier
-    let mut tier_counts = std::collections::HashMap::new();
-    let mut role_counts = std::collections::HashMap::new();
-    let mut tool_counts = std::collections::HashMap::new();
-
-    for record in &decisions {
-        *tier_counts
-            .entry(format!("{:?}", record.metadata.tier))
-            .or_insert(0) += 1;
-        *role_counts.entry(record.key.role.clone()).or_insert(0) += 1;
-        *tool_counts.entry(record.key.tool.clone()).or_insert(0) += 1;
+    println!("By scope:");
+    for (scope, count) in &summary.by_scope {
+        println!("  {}: {}", scope, count);
     }
 
-    println!("By tier:");
-    for (tier, count) in &tier_counts {
+    println!("\nBy tier:");
+    for (tier, count) in &summary.by_tier {
         println!("  {}: {}", tier, count);
     }
 
     println!("\nBy role:");
-    for (role, count) in &role_counts {
+    for (role, count) in &summary.by_role {
         println!("  {}: {}", role, count);
     }
 
     println!("\nBy tool:");
-    for (tool, count) in &tool_counts {
+    for (tool, count) in &summary.by_tool {
         println!("  {}: {}", tool, count);
     }
 
+    // Similarity index health. The embedding index is reported as `check`
+    // and `build` last saved it, without loading the model.
+    let token_jaccard = TokenJaccard::new(
+        policy.similarity.jaccard_threshold,
+        policy.similarity.jaccard_min_tokens,
+        policy.similarity.jaccard_ngram,
+    );
+    token_jaccard.load_from(&decisions);
+    let embedding =
+        EmbeddingSimilarity::saved_health(&EmbeddingSimilarity::index_path(&project_root))?;
+
+    println!("\nIndexes:");
+    println!("  Token Jaccard: {} entries", token_jaccard.len());
+    println!("  Embedding:     {}", format_index_health(&embedding));
+
+    match &summary.tiers {
+        Some(metrics) => {
+            println!("\nTier activity:");
+            for line in format_tier_metrics(metrics) {
+                println!("  {}", line);
+            }
+        }
+        None => println!("\nTier activity: none recorded yet"),
+    }
+
     Ok(())
 }
 
+/// One line per tier: how many calls it resolved and how many fell through.
+pub fn format_tier_metrics(metrics: &CascadeMetrics) -> Vec<String> {
+    let line = |name: &str, tier: &TierMetrics| {
+        format!(
+            "{:<14} {} matched, {} fell through",
+            format!("{}:", name),
+            tier.matches,
+            tier.fall_throughs
+        )
+    };
+    vec![
+        line("Exact cache", &metrics.exact_cache),
+        line("Token Jaccard", &metrics.token_jaccard),
+        line("Embedding", &metrics.embedding_similarity),
+    ]
+}
+
+/// Decision counts reported by `stats`. The totals count distinct cached
+/// entries, as the exact cache would hold them; the breakdowns count every
+/// stored decision.
+#[derive(Debug, Default, Serialize)]
+pub struct StatsSummary {
+    pub total: usize,
+    pub allow: usize,
+    pub deny: usize,
+    pub ask: usize,
+    pub by_scope: BTreeMap<String, usize>,
+    pub by_tier: BTreeMap<String, usize>,
+    pub by_role: BTreeMap<String, usize>,
+    pub by_tool: BTreeMap<String, usize>,
+    /// Per-tier counters added up by `check`, once any have been recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tiers: Option<CascadeMetrics>,
+}
+
+impl StatsSummary {
+    pub fn from_decisions(decisions: &[DecisionRecord]) -> Self {
+        let cache = ExactCache::new();
+        cache.load_from(decisions.to_vec());
+        let stats = cache.stats();
+
+        let mut summary = Self {
+            total: stats.total_entries,
+            allow: stats.allow_entries,
+            deny: stats.deny_entries,
+            ask: stats.ask_entries,
+            ..Self::default()
+        };
+        for record in decisions {
+            *summary
+                .by_scope
+                .entry(record.scope.to_string())
+                .or_insert(0) += 1;
+            *summary
+                .by_tier
+                .entry(format!("{:?}", record.metadata.tier))
+                .or_insert(0) += 1;
+            *summary.by_role.entry(record.key.role.clone()).or_insert(0) += 1;
+            *summary.by_tool.entry(record.key.tool.clone()).or_insert(0) += 1;
+        }
+        summary
+    }
+}
+
+/// One-line summary of the embedding index state for `stats`.
+pub fn format_index_health(health: &IndexHealth) -> String {
+    if !health.model_available {
+        return "noop (model not available; similarity matching disabled)".to_string();
+    }
+    let index = match health.index_dim {
+        Some(dim) => format!("built, dim {}", dim),
+        None => "not built -- run `hookwise build`".to_string(),
+    };
+    format!(
+        "{} entries, {} pending, index {}",
+        health.entries, health.pending, index
+    )
+}
+
 fn dirs_global() -> PathBuf {
     crate::config::dirs_global()
 }
@@ -1,12 +1,12 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use chrono::Utc;
 
+use crate::config::PolicyConfig;
 use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
 use crate::error::Result;
 use crate::scope::ScopeLevel;
-use crate::storage::jsonl::JsonlStorage;
-use crate::storage::StorageBackend;
 
 /// Set an explicit permission override.
 #[allow(clippy::too_many_arguments)]
@@ -19,6 +19,7 @@ pub async fn run(
     deny: bool,
     ask: bool,
     scope: &str,
+    immutable: bool,
 ) -> Result<()> {
     let decision = if allow {
         Decision::Allow
@@ -68,18 +69,29 @@ pub async fn run(
         scope: scope_level,
         file_path: file.map(String::from),
         session_id: "override".to_string(),
+        tags: HashMap::new(),
+        immutable,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     };
 
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let project_root = cwd.join(".hookwise");
+    let cwd = crate::config::project_root();
+    let project_root = crate::config::project_config_dir(&cwd);
     let global_root = dirs_global();
 
-    let storage = JsonlStorage::new(project_root, global_root, None);
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage = crate::storage::open_backend(&policy.storage, project_root, global_root, None)?;
     storage.save_decision(&record)?;
 
+    let kind = if immutable {
+        "immutable override"
+    } else {
+        "override"
+    };
     eprintln!(
-        "hookwise: override set -- {} {} for role '{}' at scope '{}'",
-        decision, tool_name, role, scope
+        "hookwise: {} set -- {} {} for role '{}' at scope '{}'",
+        kind, decision, tool_name, role, scope
     );
 
     Ok(())
@@ -0,0 +1,40 @@
+use crate::config::{PolicyConfig, SupervisorConfig};
+use crate::error::Result;
+use crate::ipc::socket_client::IpcClient;
+
+/// Ask the project's socket supervisor for its health and print the reply.
+/// Exits non-zero if no supervisor answers.
+pub async fn run() -> Result<()> {
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let SupervisorConfig::Socket { socket_path, .. } = &policy.supervisor else {
+        eprintln!("hookwise: the api supervisor backend has no process to ping");
+        std::process::exit(1);
+    };
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let client = IpcClient::for_supervisor(socket_path.as_deref(), team_id.as_deref(), 5);
+
+    match client.health().await {
+        Ok(health) => {
+            println!("Supervisor: {}", client.socket_path().display());
+            println!("  Version: {}", health.version);
+            println!("  Uptime: {}s", health.uptime_secs);
+            println!("  Pending decisions: {}", health.pending_queue_len);
+            println!("  Loaded decisions: {}", health.loaded_decisions);
+            if let Some(metrics) = &health.metrics {
+                for line in super::monitor::format_tier_metrics(metrics) {
+                    println!("  {}", line);
+                }
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!(
+                "hookwise: no supervisor answered on {} ({})",
+                client.socket_path().display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
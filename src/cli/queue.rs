@@ -1,14 +1,23 @@
-use crate::cascade::human::{load_queue_file, DecisionQueue, HumanResponse};
-use crate::decision::Decision;
+use crate::cascade::cache::{compile_pattern, pattern_subject, PATTERN_PREFIX};
+use crate::cascade::human::{
+    load_queue_file, DecisionQueue, HumanResponse, PendingDecision, QueueFileState,
+};
+use crate::config::PolicyConfig;
+use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
 use crate::error::Result;
 use crate::scope::ScopeLevel;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-/// List pending permission decisions.
-pub async fn run_queue() -> Result<()> {
+use chrono::Utc;
+
+/// List pending permission decisions. Inputs are cut to `width` characters;
+/// `None` prints them in full.
+pub async fn run_queue(width: Option<usize>) -> Result<()> {
     let state = load_queue_file();
-    let pending: Vec<_> = state.pending.values().cloned().collect();
+    let mut pending: Vec<_> = state.pending.values().cloned().collect();
+    pending.sort_by_key(|decision| decision.queued_at);
 
     if pending.is_empty() {
         println!("No pending decisions.");
@@ -16,24 +25,215 @@ pub async fn run_queue() -> Result<()> {
     }
 
     for decision in &pending {
-        println!(
-            "ID: {}\n  Role: {}\n  Tool: {}\n  Input: {}\n  File: {}\n  Queued: {}\n",
-            decision.id,
-            decision.role,
-            decision.tool_name,
-            truncate(&decision.sanitized_input, 80),
-            decision.file_path.as_deref().unwrap_or("-"),
-            decision.queued_at,
-        );
+        print_pending(decision, width);
     }
 
     println!("{} pending decision(s)", pending.len());
     Ok(())
 }
 
-/// Approve a pending decision. Writes the response to the file-backed queue
-/// so the blocking `check` process can pick it up.
-pub async fn run_approve(id: &str, always_ask: bool, add_rule: bool, scope: &str) -> Result<()> {
+/// Follow the queue, polling every `interval_secs` and printing decisions
+/// as they are queued and resolved, until Ctrl+C.
+pub async fn run_queue_watch(width: Option<usize>, interval_secs: u64) -> Result<()> {
+    eprintln!("hookwise: watching pending decisions");
+    eprintln!("Press Ctrl+C to stop.\n");
+
+    let interval = std::time::Duration::from_secs(interval_secs.max(1));
+    let mut previous = QueueFileState::default();
+    loop {
+        let current = load_queue_file();
+        let delta = queue_delta(&previous, &current);
+        for decision in &delta.added {
+            print_pending(decision, width);
+        }
+        for id in &delta.resolved {
+            match current.responses.get(id) {
+                Some(response) => println!("Resolved: {} ({})\n", id, response.decision),
+                None => println!("Resolved: {}\n", id),
+            }
+        }
+        previous = current;
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("hookwise: stopped watching");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The change in pending decisions between two polls of the queue file.
+#[derive(Debug, Default)]
+pub struct QueueDelta {
+    /// Decisions pending now but not before, oldest first.
+    pub added: Vec<PendingDecision>,
+    /// Ids pending before but not now, sorted.
+    pub resolved: Vec<String>,
+}
+
+/// Compare two snapshots of the queue file.
+pub fn queue_delta(previous: &QueueFileState, current: &QueueFileState) -> QueueDelta {
+    let mut added: Vec<PendingDecision> = current
+        .pending
+        .values()
+        .filter(|decision| !previous.pending.contains_key(&decision.id))
+        .cloned()
+        .collect();
+    added.sort_by_key(|decision| decision.queued_at);
+
+    let mut resolved: Vec<String> = previous
+        .pending
+        .keys()
+        .filter(|id| !current.pending.contains_key(*id))
+        .cloned()
+        .collect();
+    resolved.sort();
+
+    QueueDelta { added, resolved }
+}
+
+fn print_pending(decision: &PendingDecision, width: Option<usize>) {
+    let input = match width {
+        Some(width) => truncate(&decision.sanitized_input, width),
+        None => decision.sanitized_input.clone(),
+    };
+    println!(
+        "ID: {}\n  Role: {}\n  Tool: {}\n  Input: {}\n  File: {}",
+        decision.id,
+        decision.role,
+        decision.tool_name,
+        input,
+        decision.file_path.as_deref().unwrap_or("-"),
+    );
+    if let Some(reason) = &decision.ask_reason {
+        println!("  Reason: {}", reason);
+    }
+    if decision.is_ask_reprompt {
+        println!("  (re-prompt: a cached rule always asks for this input)");
+    }
+    println!("  Queued: {}\n", decision.queued_at);
+}
+
+/// Which pending decisions `approve` and `deny` resolve.
+#[derive(Debug, Clone)]
+pub enum QueueTarget {
+    /// A single decision by id, whether or not it is still listed.
+    Id(String),
+    /// Every pending decision matching the filters; with neither set, all of
+    /// them. `confirmed` skips the prompt that guards resolving all.
+    Filter {
+        role: Option<String>,
+        tool: Option<String>,
+        confirmed: bool,
+    },
+}
+
+impl QueueTarget {
+    /// Build a target from the command-line selectors. Clap guarantees
+    /// either an id or a selector is present.
+    pub fn from_args(
+        id: Option<String>,
+        all: bool,
+        role: Option<String>,
+        tool: Option<String>,
+        yes: bool,
+    ) -> Self {
+        match id {
+            Some(id) if !all => Self::Id(id),
+            _ => Self::Filter {
+                role,
+                tool,
+                confirmed: yes,
+            },
+        }
+    }
+
+    /// The pending decisions in `state` this target selects, oldest first.
+    pub fn select(&self, state: &QueueFileState) -> Vec<String> {
+        let (role, tool) = match self {
+            Self::Id(id) => return vec![id.clone()],
+            Self::Filter { role, tool, .. } => (role, tool),
+        };
+        let mut matching: Vec<&PendingDecision> = state
+            .pending
+            .values()
+            .filter(|decision| role.as_ref().is_none_or(|role| decision.role == *role))
+            .filter(|decision| tool.as_ref().is_none_or(|tool| decision.tool_name == *tool))
+            .collect();
+        matching.sort_by_key(|decision| decision.queued_at);
+        matching
+            .into_iter()
+            .map(|decision| decision.id.clone())
+            .collect()
+    }
+
+    /// Whether this target resolves the whole queue without confirmation.
+    fn needs_confirmation(&self) -> bool {
+        matches!(
+            self,
+            Self::Filter {
+                role: None,
+                tool: None,
+                confirmed: false,
+            }
+        )
+    }
+}
+
+/// Resolve `target` to ids, asking before resolving the whole queue. Exits
+/// if the user declines; an empty result means nothing matched.
+fn resolve_target(target: &QueueTarget, verb: &str) -> Result<Vec<String>> {
+    let ids = target.select(&load_queue_file());
+    if ids.is_empty() {
+        eprintln!("hookwise: no pending decisions match");
+        return Ok(ids);
+    }
+    if target.needs_confirmation() {
+        eprint!(
+            "hookwise: {} all {} pending decision(s)? [y/N] ",
+            verb,
+            ids.len()
+        );
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            eprintln!("hookwise: cancelled");
+            std::process::exit(1);
+        }
+    }
+    Ok(ids)
+}
+
+/// Report what was resolved: the id alone for one decision, a count for a
+/// filter.
+fn report_resolved(target: &QueueTarget, ids: &[String], done: &str) {
+    match target {
+        QueueTarget::Id(id) => eprintln!("hookwise: {} {}", done, id),
+        QueueTarget::Filter { .. } => {
+            eprintln!("hookwise: {} {} decision(s)", done, ids.len());
+            for id in ids {
+                eprintln!("  {}", id);
+            }
+        }
+    }
+}
+
+/// Approve pending decisions. Writes the responses to the file-backed queue
+/// so the blocking `check` processes can pick them up.
+///
+/// With `generalize`, also writes a pattern override so that sibling inputs
+/// matching the glob (e.g. `npm install *`) are allowed without prompting.
+/// With `once`, only the pending call is allowed; nothing is cached or persisted.
+pub async fn run_approve(
+    target: &QueueTarget,
+    always_ask: bool,
+    add_rule: bool,
+    scope: &str,
+    generalize: Option<&str>,
+    once: bool,
+) -> Result<()> {
     let queue = Arc::new(DecisionQueue::new());
 
     let rule_scope = if add_rule {
@@ -42,29 +242,120 @@ pub async fn run_approve(id: &str, always_ask: bool, add_rule: bool, scope: &str
         None
     };
 
+    let ids = resolve_target(target, "approve")?;
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(pattern) = generalize {
+        let id = &ids[0];
+        let Some(pending) = queue.get_pending(id) else {
+            eprintln!("hookwise: no pending decision with id '{}'", id);
+            std::process::exit(1);
+        };
+        let decision = if always_ask {
+            Decision::Ask
+        } else {
+            Decision::Allow
+        };
+        let record = generalized_rule(&pending, pattern, decision, parse_scope(scope)?)?;
+
+        let cwd = crate::config::project_root();
+        let policy = PolicyConfig::load_project(&cwd)?;
+        let storage = crate::storage::open_backend(
+            &policy.storage,
+            crate::config::project_config_dir(&cwd),
+            crate::config::dirs_global(),
+            None,
+        )?;
+        storage.save_decision(&record)?;
+    }
+
     let response = HumanResponse {
         decision: Decision::Allow,
         always_ask,
         add_rule,
         rule_scope,
+        once,
     };
 
-    queue.respond(id, response)?;
-    eprintln!("hookwise: approved {}", id);
+    for id in &ids {
+        queue.respond(id, response.clone())?;
+    }
+    report_resolved(target, &ids, "approved");
 
+    if once {
+        eprintln!("  (this call only -- not cached)");
+    }
     if always_ask {
         eprintln!("  (cached as 'ask' -- will always prompt)");
     }
     if add_rule {
         eprintln!("  (added as persistent rule at scope '{}')", scope);
     }
+    if let Some(pattern) = generalize {
+        eprintln!(
+            "  (generalized: inputs matching '{}' added at scope '{}')",
+            pattern, scope
+        );
+    }
 
     Ok(())
 }
 
-/// Deny a pending decision. Writes the response to the file-backed queue
-/// so the blocking `check` process can pick it up.
-pub async fn run_deny(id: &str, always_ask: bool, add_rule: bool, scope: &str) -> Result<()> {
+/// Build a pattern override from a pending decision. The pattern must match
+/// the pending input itself, so a typo cannot approve something unrelated.
+fn generalized_rule(
+    pending: &PendingDecision,
+    pattern: &str,
+    decision: Decision,
+    scope: ScopeLevel,
+) -> Result<DecisionRecord> {
+    let matcher = compile_pattern(pattern)?;
+    let subject = pattern_subject(&pending.tool_name, &pending.sanitized_input);
+    if !matcher.is_match(&subject) {
+        eprintln!(
+            "hookwise: pattern '{}' does not match the pending input '{}'",
+            pattern,
+            truncate(&subject, 80)
+        );
+        std::process::exit(1);
+    }
+
+    Ok(DecisionRecord {
+        key: CacheKey {
+            sanitized_input: format!("{PATTERN_PREFIX}{pattern}"),
+            tool: pending.tool_name.clone(),
+            role: pending.role.clone(),
+        },
+        decision,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Override,
+            confidence: 1.0,
+            reason: format!("generalized from approval of {}", pending.id),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope,
+        file_path: None,
+        session_id: "override".to_string(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    })
+}
+
+/// Deny pending decisions. Writes the responses to the file-backed queue
+/// so the blocking `check` processes can pick them up.
+pub async fn run_deny(
+    target: &QueueTarget,
+    always_ask: bool,
+    add_rule: bool,
+    scope: &str,
+) -> Result<()> {
     let queue = Arc::new(DecisionQueue::new());
 
     let rule_scope = if add_rule {
@@ -78,10 +369,16 @@ pub async fn run_deny(id: &str, always_ask: bool, add_rule: bool, scope: &str) -
         always_ask,
         add_rule,
         rule_scope,
+        once: false,
     };
 
-    queue.respond(id, response)?;
-    eprintln!("hookwise: denied {}", id);
+    let ids = resolve_target(target, "deny")?;
+    for id in &ids {
+        queue.respond(id, response.clone())?;
+    }
+    if !ids.is_empty() {
+        report_resolved(target, &ids, "denied");
+    }
 
     if always_ask {
         eprintln!("  (cached as 'ask' -- will always prompt)");
@@ -1,4 +1,11 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cascade::supervisor::supervisor_socket_path;
+use crate::config::{PolicyConfig, SupervisorConfig};
 use crate::error::Result;
+use crate::ipc::socket_client::IpcClient;
+use crate::ipc::IpcControl;
 use crate::session::SessionManager;
 
 /// Register a session with a role.
@@ -7,12 +14,15 @@ pub async fn run_register(
     role: &str,
     task: Option<&str>,
     prompt_file: Option<&str>,
+    registered_by: Option<&str>,
+    tags: HashMap<String, String>,
+    wait: bool,
 ) -> Result<()> {
     let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
     let session_mgr = SessionManager::new(team_id.as_deref());
 
     // Validate the role exists
-    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let cwd = crate::config::project_root();
     let roles = crate::config::RolesConfig::load_project(&cwd)?;
     if roles.get_role(role).is_none() {
         eprintln!("hookwise: unknown role '{}'. Available roles:", role);
@@ -22,11 +32,44 @@ pub async fn run_register(
         std::process::exit(1);
     }
 
-    session_mgr.register(session_id, role, task, prompt_file)?;
-    eprintln!(
-        "hookwise: session {} registered as '{}'",
-        session_id, role
-    );
+    session_mgr.register_with_tags(session_id, role, task, prompt_file, registered_by, tags)?;
+    eprintln!("hookwise: session {} registered as '{}'", session_id, role);
+
+    if wait {
+        wait_for_supervisor(session_id, &cwd, team_id.as_deref()).await?;
+    }
+    Ok(())
+}
+
+/// Tell the socket supervisor about a new session and wait until it has
+/// loaded it, so the session's first check does not race the registration.
+/// Gives up with a warning after `registration_timeout_secs`.
+async fn wait_for_supervisor(session_id: &str, cwd: &Path, team_id: Option<&str>) -> Result<()> {
+    let policy = PolicyConfig::load_project(cwd)?;
+    let SupervisorConfig::Socket { socket_path, .. } = &policy.supervisor else {
+        eprintln!("hookwise: --wait has no effect with the api supervisor backend");
+        return Ok(());
+    };
+    let socket_path = supervisor_socket_path(socket_path.as_deref(), team_id);
+    if !socket_path.exists() {
+        eprintln!(
+            "hookwise: no supervisor listening on {}; not waiting",
+            socket_path.display()
+        );
+        return Ok(());
+    }
+
+    let control = IpcControl::SessionRegistered {
+        session_id: session_id.to_string(),
+        cwd: cwd.to_string_lossy().into_owned(),
+    };
+    match IpcClient::new(socket_path, policy.registration_timeout_secs)
+        .send_control(&control)
+        .await
+    {
+        Ok(()) => eprintln!("hookwise: supervisor acknowledged session {}", session_id),
+        Err(e) => eprintln!("hookwise: supervisor did not acknowledge ({})", e),
+    }
     Ok(())
 }
 
@@ -40,6 +83,86 @@ pub async fn run_disable(session_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Remove a session's registration and exclusion entries.
+pub async fn run_unregister(session_id: &str) -> Result<()> {
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let session_mgr = SessionManager::new(team_id.as_deref());
+
+    session_mgr.unregister(session_id)?;
+    eprintln!("hookwise: session {} unregistered", session_id);
+    Ok(())
+}
+
+/// List registered and disabled sessions as a table, or as JSON with `json`.
+pub async fn run_list_sessions(json: bool) -> Result<()> {
+    let policy = PolicyConfig::load_project(&crate::config::project_root())?;
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let session_mgr =
+        SessionManager::new(team_id.as_deref()).with_session_ttl(policy.session_ttl_secs);
+    let sessions = session_mgr.list()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
+    if sessions.is_empty() {
+        eprintln!("hookwise: no active sessions");
+        return Ok(());
+    }
+
+    let id_width = sessions
+        .iter()
+        .map(|s| s.session_id.len())
+        .chain(["SESSION".len()])
+        .max()
+        .unwrap_or_default();
+    let role_width = sessions
+        .iter()
+        .filter_map(|s| s.role.as_ref().map(String::len))
+        .chain(["ROLE".len()])
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:<id_width$}  {:<role_width$}  {:<16}  {:<8}  TASK",
+        "SESSION", "ROLE", "REGISTERED", "DISABLED"
+    );
+    for session in &sessions {
+        let registered = session
+            .registered_at
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<id_width$}  {:<role_width$}  {:<16}  {:<8}  {}",
+            session.session_id,
+            session.role.as_deref().unwrap_or("-"),
+            registered,
+            if session.disabled { "yes" } else { "no" },
+            session.task.as_deref().unwrap_or("-"),
+        );
+    }
+    println!("\n{} session(s)", sessions.len());
+    Ok(())
+}
+
+/// Remove registrations older than `session_ttl_secs` from the registration
+/// file.
+pub async fn run_prune() -> Result<()> {
+    let policy = PolicyConfig::load_project(&crate::config::project_root())?;
+    let Some(ttl) = policy.session_ttl_secs else {
+        eprintln!("hookwise: no session_ttl_secs set in policy.yml; nothing expires");
+        return Ok(());
+    };
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+    let session_mgr = SessionManager::new(team_id.as_deref()).with_session_ttl(Some(ttl));
+
+    let removed = session_mgr.prune_expired()?;
+    eprintln!(
+        "hookwise: pruned {} registration(s) older than {}s",
+        removed, ttl
+    );
+    Ok(())
+}
+
 /// Re-enable hookwise for a session.
 pub async fn run_enable(session_id: &str) -> Result<()> {
     let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::{CompiledPathPolicy, PolicyConfig, RolesConfig};
+use crate::decision::DecisionRecord;
+use crate::error::Result;
+use crate::sanitize::SanitizePipeline;
+use crate::scope::ScopeLevel;
+use crate::session::SessionContext;
+use crate::storage::audit::{AuditLog, AUDIT_FILE};
+
+/// Re-run a past decision through the current rules and report differences.
+/// Read-only: nothing is persisted and the supervisor/human are never contacted.
+/// A decision whose rule was deleted or pruned is looked up in the audit log.
+pub async fn run(id: &str) -> Result<()> {
+    let id = id.trim();
+    if id.is_empty() {
+        eprintln!("hookwise: replay requires a decision id");
+        std::process::exit(1);
+    }
+
+    let cwd = crate::config::project_root();
+    let project_root = crate::config::project_config_dir(&cwd);
+    let global_root = dirs_global();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let storage =
+        crate::storage::open_backend(&policy.storage, project_root.clone(), global_root, None)?;
+
+    let mut matches: Vec<DecisionRecord> = Vec::new();
+    for scope in [
+        ScopeLevel::Project,
+        ScopeLevel::User,
+        ScopeLevel::Org,
+        ScopeLevel::Global,
+    ] {
+        matches.extend(
+            storage
+                .load_decisions(scope)?
+                .into_iter()
+                .filter(|r| r.id.starts_with(id)),
+        );
+    }
+    if matches.is_empty() {
+        matches = audited_decisions(&project_root, &policy, id)?;
+    }
+
+    let record = match matches.len() {
+        0 => {
+            eprintln!("hookwise: no decision found with id '{}'", id);
+            std::process::exit(1);
+        }
+        1 => matches.remove(0),
+        n => {
+            eprintln!("hookwise: id '{}' is ambiguous ({} matches):", id, n);
+            for r in &matches {
+                eprintln!("  {}  {} {} {}", r.id, r.decision, r.key.tool, r.key.role);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let roles = RolesConfig::load_project(&cwd)?;
+    let session = replay_session(&record, &roles, &policy)?;
+    let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
+
+    let runner = super::check::build_runner(
+        &cwd,
+        &policy,
+        roles.normalizer().ok(),
+        &session.org,
+        team_id.as_deref(),
+    )?;
+    let outcome = runner.replay(&session, &record).await?;
+
+    println!(
+        "Replaying {} ({}, role: {}, scope: {})",
+        record.id, record.key.tool, record.key.role, record.scope
+    );
+    println!("  input:    {}", truncate(&record.key.sanitized_input, 100));
+    if !record.tags.is_empty() {
+        let mut tags: Vec<String> = record
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        tags.sort();
+        println!("  tags:     {}", tags.join(", "));
+    }
+    println!(
+        "  original: {} via {:?} at {} -- {}",
+        record.decision,
+        record.metadata.tier,
+        record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        record.metadata.reason
+    );
+    match &outcome.current {
+        Some(current) => println!(
+            "  current:  {} via {:?} -- {}",
+            current.decision, current.metadata.tier, current.metadata.reason
+        ),
+        None => println!("  current:  unresolved -- would escalate to supervisor/human"),
+    }
+
+    if outcome.decision_changed() {
+        let now = outcome
+            .current_decision()
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "escalate".to_string());
+        println!("\n  CHANGED: decision {} -> {}", record.decision, now);
+    } else if outcome.tier_changed() {
+        println!(
+            "\n  same decision, now resolved by {:?} (was {:?})",
+            outcome.current_tier().unwrap_or(record.metadata.tier),
+            record.metadata.tier
+        );
+    } else {
+        println!("\n  unchanged");
+    }
+
+    Ok(())
+}
+
+/// The decisions in the project's audit log whose id starts with `id`, each
+/// as last logged.
+fn audited_decisions(
+    config_dir: &Path,
+    policy: &PolicyConfig,
+    id: &str,
+) -> Result<Vec<DecisionRecord>> {
+    let log = AuditLog::new(
+        config_dir.join(AUDIT_FILE),
+        policy.audit.max_bytes,
+        policy.audit.keep,
+        SanitizePipeline::default_pipeline(),
+    );
+    let mut records: Vec<DecisionRecord> = Vec::new();
+    for entry in log.read_entries()? {
+        if entry.id.is_empty() || !entry.id.starts_with(id) {
+            continue;
+        }
+        let record = entry.to_record();
+        match records.iter_mut().find(|r| r.id == record.id) {
+            Some(earlier) => *earlier = record,
+            None => records.push(record),
+        }
+    }
+    Ok(records)
+}
+
+/// Rebuild the session a record was decided under from its role and the
+/// `org/project/user` session identifier.
+fn replay_session(
+    record: &DecisionRecord,
+    roles: &RolesConfig,
+    policy: &PolicyConfig,
+) -> Result<SessionContext> {
+    let mut parts = record.session_id.splitn(3, '/');
+    let org = parts.next().unwrap_or_default().to_string();
+    let project = parts.next().unwrap_or_default().to_string();
+    let user = parts.next().unwrap_or_default().to_string();
+
+    let mut session = SessionContext::new_minimal(user, org, project);
+    if let Some(role_def) = roles.get_role(&record.key.role) {
+        let compiled =
+            CompiledPathPolicy::compile(&role_def.paths, &policy.sensitive_paths.ask_write)?;
+        session.path_policy = Some(Arc::new(compiled));
+        session.role = Some(role_def.clone());
+    }
+    Ok(session)
+}
+
+/// Truncate a string for display, appending "..." if it was cut.
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let truncated: String = s.chars().take(max).collect();
+        format!("{}...", truncated)
+    }
+}
+
+fn dirs_global() -> PathBuf {
+    crate::config::dirs_global()
+}
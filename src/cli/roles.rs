@@ -0,0 +1,220 @@
+use crate::config::{CompiledPathPolicy, PathPolicyConfig, PolicyConfig, RolesConfig};
+use crate::error::{HookwiseError, Result};
+use crate::sanitize::regex_san::RegexSanitizer;
+use crate::sanitize::SanitizePipeline;
+
+/// List the project's roles by name, with their descriptions.
+pub async fn run_list() -> Result<()> {
+    let cwd = crate::config::project_root();
+    let roles = RolesConfig::load_project(&cwd)?;
+
+    if roles.roles.is_empty() {
+        println!("No roles configured.");
+        return Ok(());
+    }
+
+    let mut names: Vec<&String> = roles.roles.keys().collect();
+    names.sort();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+    for name in names {
+        let description = roles.roles[name].description.trim();
+        let summary = description.lines().next().unwrap_or_default();
+        println!("{:width$}  {}", name, summary);
+    }
+    Ok(())
+}
+
+/// Print a role's path policy, with categories expanded and inherited globs
+/// merged in, and the sensitive paths it gets from `policy.yml`. Exits
+/// non-zero if the role is unknown or one of its globs doesn't compile.
+pub async fn run_show(name: &str) -> Result<()> {
+    let cwd = crate::config::project_root();
+    let roles = RolesConfig::load_project(&cwd)?;
+    let Some(role) = roles.get_role(name) else {
+        eprintln!("hookwise: unknown role '{}'", name);
+        std::process::exit(1);
+    };
+    let policy = PolicyConfig::load_project(&cwd)?;
+
+    println!("Role: {}", role.name);
+    println!("Description: {}", role.description.trim());
+    if !role.extends.is_empty() {
+        println!("Extends: {}", role.extends.join(", "));
+    }
+    if let Some(profile) = &role.sanitize_profile {
+        println!("Sanitize profile: {}", profile);
+    }
+    print_globs("allow_write", &role.paths.allow_write);
+    print_globs("deny_write", &role.paths.deny_write);
+    print_globs("allow_read", &role.paths.allow_read);
+    print_globs(
+        "sensitive ask_write (policy.yml)",
+        &policy.sensitive_paths.ask_write,
+    );
+
+    let confidence = policy.confidence.for_role(Some(role));
+    let similarity = policy.similarity.for_role(Some(role));
+    println!("\nThresholds:");
+    println!(
+        "  confidence: org={}, project={}, user={}",
+        confidence.org, confidence.project, confidence.user
+    );
+    println!(
+        "  similarity: jaccard={}, embedding={}",
+        similarity.jaccard_threshold, similarity.embedding_threshold
+    );
+
+    match CompiledPathPolicy::compile(&role.paths, &policy.sensitive_paths.ask_write) {
+        Ok(_) => println!("\nAll globs compile."),
+        Err(HookwiseError::GlobPattern { pattern, reason }) => {
+            eprintln!("hookwise: invalid glob '{}': {}", pattern, reason);
+            std::process::exit(1);
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+fn print_globs(label: &str, globs: &[String]) {
+    println!("\n{}:", label);
+    if globs.is_empty() {
+        println!("  (none)");
+    }
+    for glob in globs {
+        println!("  {}", glob);
+    }
+}
+
+/// Check `roles.yml` and `policy.yml` the way a session would load them,
+/// printing every problem found rather than stopping at the first. Exits
+/// non-zero if there are errors; warnings alone pass.
+pub async fn run_validate() -> Result<()> {
+    let cwd = crate::config::project_root();
+    let mut errors: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    if let Err(e) = RegexSanitizer::new(RegexSanitizer::default_patterns()) {
+        errors.push(format!("built-in sanitize patterns: {}", e));
+    }
+
+    let policy = match PolicyConfig::load_project(&cwd) {
+        Ok(policy) => {
+            for pattern in &policy.sensitive_paths.ask_write {
+                if let Err(reason) = check_glob(pattern) {
+                    errors.push(format!(
+                        "policy.yml: sensitive_paths.ask_write: invalid glob '{}': {}",
+                        pattern, reason
+                    ));
+                }
+            }
+            Some(policy)
+        }
+        Err(e) => {
+            errors.push(format!("policy.yml: {}", e));
+            None
+        }
+    };
+
+    let roles_path = crate::config::project_config_dir(&cwd).join("roles.yml");
+    let empty = std::fs::read_to_string(&roles_path).map_or(true, |c| c.trim().is_empty());
+    let roles = if empty {
+        errors.push("roles.yml: missing or empty; define at least one role".to_string());
+        None
+    } else {
+        match RolesConfig::load_from(&roles_path) {
+            Ok(roles) => Some(roles),
+            Err(e) => {
+                errors.push(format!("roles.yml: {}", e));
+                None
+            }
+        }
+    };
+
+    if let Some(roles) = &roles {
+        if roles.roles.is_empty() {
+            errors.push("roles.yml: no roles defined".to_string());
+        }
+        let sensitive = policy
+            .as_ref()
+            .map(|p| p.sensitive_paths.ask_write.clone())
+            .unwrap_or_default();
+        let mut names: Vec<&String> = roles.roles.keys().collect();
+        names.sort();
+        for name in names {
+            let role = &roles.roles[name];
+            let before = errors.len();
+            for (field, globs) in path_fields(&role.paths) {
+                for pattern in globs {
+                    if let Err(reason) = check_glob(pattern) {
+                        errors.push(format!(
+                            "roles.yml: role '{}' {}: invalid glob '{}': {}",
+                            name, field, pattern, reason
+                        ));
+                    }
+                }
+            }
+            if errors.len() == before {
+                if let Err(e) = CompiledPathPolicy::compile(&role.paths, &sensitive) {
+                    errors.push(format!("roles.yml: role '{}': {}", name, e));
+                }
+            }
+            if let Some(policy) = &policy {
+                if let Err(e) = SanitizePipeline::for_role(policy, Some(role)) {
+                    errors.push(format!(
+                        "roles.yml: role '{}' sanitize_profile: {}",
+                        name, e
+                    ));
+                }
+            }
+
+            if role.paths.allow_write.iter().any(|p| p == "**") && role.paths.deny_write.is_empty()
+            {
+                warnings.push(format!(
+                    "roles.yml: role '{}' may write anywhere (allow_write '**' with no deny_write)",
+                    name
+                ));
+            }
+            if role.name != *name {
+                warnings.push(format!(
+                    "roles.yml: role '{}' has name '{}'; sessions register by the key",
+                    name, role.name
+                ));
+            }
+        }
+    }
+
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if !errors.is_empty() {
+        eprintln!(
+            "hookwise: configuration has {} error(s), {} warning(s)",
+            errors.len(),
+            warnings.len()
+        );
+        std::process::exit(1);
+    }
+    eprintln!(
+        "hookwise: configuration is valid ({} role(s), {} warning(s))",
+        roles.map_or(0, |r| r.roles.len()),
+        warnings.len()
+    );
+    Ok(())
+}
+
+fn path_fields(paths: &PathPolicyConfig) -> [(&'static str, &[String]); 3] {
+    [
+        ("allow_write", &paths.allow_write),
+        ("deny_write", &paths.deny_write),
+        ("allow_read", &paths.allow_read),
+    ]
+}
+
+fn check_glob(pattern: &str) -> std::result::Result<(), String> {
+    globset::Glob::new(pattern)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
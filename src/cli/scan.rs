@@ -1,12 +1,15 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::config::{PolicyConfig, StorageBackendKind};
 use crate::error::Result;
-use crate::sanitize::SanitizePipeline;
+use crate::sanitize::{RedactionEvent, SanitizePipeline};
+use crate::storage::{SecretFinding, Severity};
 
 /// Pre-commit secret scan on staged files or a specified path.
 pub async fn run(staged: bool, path: Option<&str>) -> Result<()> {
-    let pipeline = SanitizePipeline::default_pipeline();
-    let mut total_findings = 0;
+    let policy = PolicyConfig::load_project(&crate::config::project_root())?;
+    let pipeline = SanitizePipeline::for_role(&policy, None)?;
+    let mut findings = Vec::new();
 
     if staged {
         // Scan git staged files
@@ -30,74 +33,164 @@ pub async fn run(staged: bool, path: Option<&str>) -> Result<()> {
         eprintln!("hookwise: scanning {} staged file(s)...", files.len());
 
         for file in files {
-            let findings = scan_file(&pipeline, file)?;
-            total_findings += findings;
+            findings.extend(scan_file(&pipeline, file)?);
         }
     } else if let Some(path) = path {
         let path_buf = PathBuf::from(path);
         if path_buf.is_dir() {
             // Scan all files in directory recursively
             eprintln!("hookwise: scanning directory {}...", path);
-            total_findings += scan_dir(&pipeline, &path_buf)?;
+            findings.extend(scan_dir(&pipeline, &path_buf)?);
         } else if path_buf.is_file() {
             eprintln!("hookwise: scanning file {}...", path);
-            total_findings += scan_file(&pipeline, path)?;
+            findings.extend(scan_file(&pipeline, path)?);
         } else {
             eprintln!("hookwise: path not found: {}", path);
             std::process::exit(1);
         }
+    } else if policy.storage.backend == StorageBackendKind::Sqlite {
+        // Scan the project's decision database by default
+        let cwd = crate::config::project_root();
+        let project_root = crate::config::project_config_dir(&cwd);
+        let storage = crate::storage::open_backend(
+            &policy.storage,
+            project_root.clone(),
+            crate::config::dirs_global(),
+            None,
+        )?;
+        eprintln!("hookwise: scanning decision database...");
+        findings.extend(storage.scan_for_secrets(&project_root)?);
     } else {
-        // Scan .hookwise/rules/ by default
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let rules_dir = cwd.join(".hookwise").join("rules");
+        // Scan the project's rules/ by default
+        let cwd = crate::config::project_root();
+        let rules_dir = crate::config::project_config_dir(&cwd).join("rules");
 
         if rules_dir.exists() {
             eprintln!("hookwise: scanning rules directory...");
-            total_findings += scan_dir(&pipeline, &rules_dir)?;
+            findings.extend(scan_dir(&pipeline, &rules_dir)?);
         } else {
             eprintln!(
-                "hookwise: no .hookwise/rules/ found. Use --staged or provide a path."
+                "hookwise: no {} found. Use --staged or provide a path.",
+                rules_dir.display()
             );
             std::process::exit(1);
         }
     }
 
-    if total_findings > 0 {
+    if findings.is_empty() {
+        eprintln!("hookwise: scan clean -- no secrets detected.");
+        return Ok(());
+    }
+
+    // Most severe first; the sort is stable, so files keep their order within
+    // a severity.
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    for finding in &findings {
+        eprintln!(
+            "  [{}] {}:{}: {}",
+            finding.severity,
+            finding.file.display(),
+            finding.line,
+            finding.description
+        );
+        eprintln!("      {}", finding.matched_excerpt);
+    }
+
+    let high = findings
+        .iter()
+        .filter(|f| f.severity == Severity::High)
+        .count();
+    if high > 0 {
         eprintln!(
-            "\nhookwise: {} potential secret(s) found. Aborting.",
-            total_findings
+            "\nhookwise: {} potential secret(s) found ({} high severity). Aborting.",
+            findings.len(),
+            high
         );
         std::process::exit(1);
-    } else {
-        eprintln!("hookwise: scan clean -- no secrets detected.");
     }
+    eprintln!(
+        "\nhookwise: {} lower-severity finding(s); review them, but nothing blocks.",
+        findings.len()
+    );
 
     Ok(())
 }
 
-/// Scan a single file for secrets. Returns the number of findings.
-fn scan_file(pipeline: &SanitizePipeline, path: &str) -> Result<usize> {
-    let contents = match std::fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return Ok(0), // Skip binary/unreadable files
+/// Scan every file under `dir` with the project's sanitize pipeline, most
+/// severe findings first.
+pub(crate) fn scan_for_secrets(policy: &PolicyConfig, dir: &Path) -> Result<Vec<SecretFinding>> {
+    let pipeline = SanitizePipeline::for_role(policy, None)?;
+    let mut findings = scan_dir(&pipeline, &dir.to_path_buf())?;
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    Ok(findings)
+}
+
+/// Scan a single file for secrets.
+fn scan_file(pipeline: &SanitizePipeline, path: &str) -> Result<Vec<SecretFinding>> {
+    let contents = if path.ends_with(".jsonl.gz") {
+        // Compressed rule files are decoded so they get the same scrutiny
+        match read_gzip_to_string(path) {
+            Ok(c) => c,
+            Err(_) => return Ok(Vec::new()),
+        }
+    } else {
+        match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => return Ok(Vec::new()), // Skip binary/unreadable files
+        }
     };
 
-    let mut findings = 0;
+    let mut findings = Vec::new();
+
+    let lines: Vec<&str> = contents.lines().collect();
+    let sanitized = pipeline.sanitize_lines(&lines);
 
-    for (line_num, line) in contents.lines().enumerate() {
-        let sanitized = pipeline.sanitize(line);
+    for (line_num, (line, sanitized)) in lines.iter().zip(&sanitized).enumerate() {
         if sanitized != line {
-            findings += 1;
-            eprintln!("  {}:{}: potential secret detected", path, line_num + 1);
+            // Only the few flagged lines are re-run to attribute the redaction
+            let (_, events) = pipeline.sanitize_with_report(line);
+            findings.push(SecretFinding::from_report(
+                PathBuf::from(path),
+                line_num + 1,
+                format!("potential secret redacted by {}", layer_names(&events)),
+                line,
+                sanitized,
+                &events,
+            ));
         }
     }
 
     Ok(findings)
 }
 
-/// Scan a directory recursively for secrets. Returns the number of findings.
-fn scan_dir(pipeline: &SanitizePipeline, dir: &PathBuf) -> Result<usize> {
-    let mut total = 0;
+/// Describe the layers that made a line's redactions, e.g. "entropy layer".
+fn layer_names(events: &[RedactionEvent]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for event in events {
+        if !names.contains(&event.layer.as_str()) {
+            names.push(&event.layer);
+        }
+    }
+    match names.len() {
+        0 => "the sanitizer".to_string(),
+        1 => format!("{} layer", names[0]),
+        _ => format!("{} layers", names.join(", ")),
+    }
+}
+
+/// Decompress a gzip file (possibly multi-member) into a string.
+fn read_gzip_to_string(path: &str) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(path)?;
+    let mut contents = String::new();
+    flate2::read::MultiGzDecoder::new(file).read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Scan a directory recursively for secrets.
+fn scan_dir(pipeline: &SanitizePipeline, dir: &PathBuf) -> Result<Vec<SecretFinding>> {
+    let mut findings = Vec::new();
 
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
@@ -111,11 +204,11 @@ fn scan_dir(pipeline: &SanitizePipeline, dir: &PathBuf) -> Result<usize> {
             {
                 continue;
             }
-            total += scan_dir(pipeline, &path)?;
+            findings.extend(scan_dir(pipeline, &path)?);
         } else if path.is_file() {
-            total += scan_file(pipeline, &path.to_string_lossy())?;
+            findings.extend(scan_file(pipeline, &path.to_string_lossy())?);
         }
     }
 
-    Ok(total)
+    Ok(findings)
 }
@@ -0,0 +1,164 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::cascade::cache::{compile_pattern, PATTERN_PREFIX};
+use crate::config::{PolicyConfig, RolesConfig};
+use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier};
+use crate::error::{HookwiseError, Result};
+use crate::sanitize::SanitizePipeline;
+use crate::scope::ScopeLevel;
+
+/// A seed file: a list of rules to bootstrap a project's decisions.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedFile {
+    pub rules: Vec<SeedRule>,
+}
+
+/// One seeded rule. Exactly one of `command` (matched literally) or
+/// `pattern` (a glob) must be set; both are matched against the tool's
+/// primary field, e.g. the command for Bash or the file path for Write.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedRule {
+    #[serde(default = "default_role")]
+    pub role: String,
+    pub tool: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub decision: Decision,
+    #[serde(default = "default_scope")]
+    pub scope: ScopeLevel,
+}
+
+fn default_role() -> String {
+    "*".to_string()
+}
+
+fn default_scope() -> ScopeLevel {
+    ScopeLevel::Project
+}
+
+impl SeedRule {
+    /// Validate the rule and build the override record it seeds. The command
+    /// or pattern is sanitized before it is stored.
+    pub fn to_record(&self, sanitizer: &SanitizePipeline) -> Result<DecisionRecord> {
+        let glob = match (&self.command, &self.pattern) {
+            (Some(command), None) => globset::escape(&sanitizer.sanitize(command)),
+            (None, Some(pattern)) => sanitizer.sanitize(pattern),
+            _ => {
+                return Err(HookwiseError::InvalidPolicy {
+                    reason: format!(
+                        "seed rule ({} / {}): set exactly one of command or pattern",
+                        self.role, self.tool
+                    ),
+                })
+            }
+        };
+        compile_pattern(&glob)?;
+
+        Ok(DecisionRecord {
+            key: CacheKey {
+                sanitized_input: format!("{PATTERN_PREFIX}{glob}"),
+                tool: self.tool.clone(),
+                role: self.role.clone(),
+            },
+            decision: self.decision,
+            metadata: DecisionMetadata {
+                tier: DecisionTier::Override,
+                confidence: 1.0,
+                reason: format!(
+                    "seeded: {} for role={}, tool={}",
+                    self.decision, self.role, self.tool
+                ),
+                matched_key: None,
+                similarity_score: None,
+            },
+            timestamp: Utc::now(),
+            scope: self.scope,
+            file_path: None,
+            session_id: "seed".to_string(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
+        })
+    }
+}
+
+/// Load and parse a seed file.
+pub fn load_seed_file(path: &Path) -> Result<SeedFile> {
+    crate::config::read_yaml(path)
+}
+
+/// Seed decisions from a YAML file. Every rule is validated before anything
+/// is written; rules whose key already exists at their scope are skipped, so
+/// re-running the same file is a no-op.
+pub async fn run(file: &str) -> Result<()> {
+    let seed = load_seed_file(Path::new(file))?;
+
+    let cwd = crate::config::project_root();
+    let policy = PolicyConfig::load_project(&cwd)?;
+    let roles = RolesConfig::load_project(&cwd)?;
+    let sanitizer = SanitizePipeline::for_role(&policy, None)?;
+
+    let mut records = Vec::with_capacity(seed.rules.len());
+    for rule in &seed.rules {
+        if rule.role != "*" && roles.get_role(&rule.role).is_none() {
+            eprintln!(
+                "hookwise: seed rule references unknown role '{}'",
+                rule.role
+            );
+            std::process::exit(1);
+        }
+        records.push(rule.to_record(&sanitizer)?);
+    }
+
+    let storage = crate::storage::open_backend(
+        &policy.storage,
+        crate::config::project_config_dir(&cwd),
+        dirs_global(),
+        None,
+    )?;
+
+    let mut existing: HashMap<ScopeLevel, HashSet<CacheKey>> = HashMap::new();
+    let mut new_records = Vec::new();
+    let mut skipped = 0;
+    for record in records {
+        let keys = match existing.entry(record.scope) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(
+                storage
+                    .load_decisions(record.scope)?
+                    .into_iter()
+                    .map(|r| r.key)
+                    .collect(),
+            ),
+        };
+        if keys.insert(record.key.clone()) {
+            new_records.push(record);
+        } else {
+            skipped += 1;
+        }
+    }
+    storage.save_decisions(&new_records)?;
+
+    eprintln!(
+        "hookwise: seeded {} rule(s) from {} ({} already present)",
+        new_records.len(),
+        file,
+        skipped
+    );
+    Ok(())
+}
+
+fn dirs_global() -> PathBuf {
+    crate::config::dirs_global()
+}
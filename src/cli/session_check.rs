@@ -9,8 +9,11 @@ use crate::session::SessionManager;
 pub async fn run(format: HookFormat) -> Result<()> {
     // Read hook input from stdin to get session_id
     let input = crate::hook_io::read_hook_input()?;
+    let cwd = crate::config::project_root_from(std::path::Path::new(&input.cwd));
+    let policy = crate::config::PolicyConfig::load_project(&cwd)?;
     let team_id = std::env::var("CLAUDE_TEAM_ID").ok();
-    let session_mgr = SessionManager::new(team_id.as_deref());
+    let session_mgr =
+        SessionManager::new(team_id.as_deref()).with_session_ttl(policy.session_ttl_secs);
 
     if session_mgr.is_disabled(&input.session_id) {
         // Session is disabled, nothing to do
@@ -23,7 +26,6 @@ pub async fn run(format: HookFormat) -> Result<()> {
     }
 
     // Not registered -- output a registration prompt
-    let cwd = std::path::PathBuf::from(&input.cwd);
     let roles = crate::config::RolesConfig::load_project(&cwd)?;
     let role_names: Vec<&String> = roles.roles.keys().collect();
 
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use crate::config::{PolicyConfig, StorageBackendKind};
+use crate::decision::{CacheKey, DecisionRecord};
+use crate::error::{HookwiseError, Result};
+use crate::scope::merge::decision_priority;
+use crate::storage::{SecretFinding, Severity};
+
+/// Which way `sync` moves rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Fetch and merge the remote's rules only.
+    Pull,
+    /// Push local rules only.
+    Push,
+    /// Fetch, merge, then push.
+    Both,
+}
+
+/// Share the project's rules through its git remote. Local rule changes are
+/// committed first; a merge conflict in a rule file is resolved by taking the
+/// union of both sides' rules. Nothing is committed or pushed while the rules
+/// contain a high-severity secret. Only the JSONL backend's rule files are
+/// shared; a project on the sqlite backend is refused.
+pub async fn run(mode: SyncMode, remote: &str) -> Result<()> {
+    let root = crate::config::project_root();
+    if !git(&root, &["rev-parse", "--is-inside-work-tree"])?
+        .status
+        .success()
+    {
+        eprintln!("hookwise: {} is not in a git repository", root.display());
+        std::process::exit(1);
+    }
+
+    let policy = PolicyConfig::load_project(&root)?;
+    if policy.storage.backend != StorageBackendKind::Jsonl {
+        eprintln!("hookwise: sync shares JSONL rule files; it does not support the sqlite storage backend");
+        std::process::exit(1);
+    }
+    block_secrets(&policy, &root)?;
+
+    if commit_rules(&root)? {
+        eprintln!("hookwise: committed local rule changes");
+    }
+
+    let branch = stdout(&run_git(&root, &["rev-parse", "--abbrev-ref", "HEAD"])?);
+    if mode != SyncMode::Push {
+        pull(&policy, &root, remote, &branch)?;
+    }
+    if mode != SyncMode::Pull {
+        run_git(
+            &root,
+            &["push", remote, &format!("HEAD:refs/heads/{branch}")],
+        )?;
+        eprintln!("hookwise: pushed rules to {}/{}", remote, branch);
+    }
+    Ok(())
+}
+
+/// Exit before anything is committed or pushed if the rules hold a
+/// high-severity secret.
+fn block_secrets(policy: &PolicyConfig, root: &Path) -> Result<()> {
+    let high = high_severity_secrets(policy, root)?;
+    if high.is_empty() {
+        return Ok(());
+    }
+    report_secrets(&high);
+    std::process::exit(1);
+}
+
+/// High-severity findings in the rules as they are on disk.
+fn high_severity_secrets(policy: &PolicyConfig, root: &Path) -> Result<Vec<SecretFinding>> {
+    let findings = super::scan::scan_for_secrets(policy, &root.join(rules_dir(root)))?;
+    Ok(findings
+        .into_iter()
+        .filter(|f| f.severity == Severity::High)
+        .collect())
+}
+
+fn report_secrets(high: &[SecretFinding]) {
+    for finding in high {
+        eprintln!(
+            "  [{}] {}:{}: {}",
+            finding.severity,
+            finding.file.display(),
+            finding.line,
+            finding.description
+        );
+    }
+    eprintln!(
+        "hookwise: {} potential secret(s) in the rules; not syncing",
+        high.len()
+    );
+}
+
+/// Commit changes under the rules directory. Returns whether there were any.
+fn commit_rules(root: &Path) -> Result<bool> {
+    let rules = rules_dir(root);
+    run_git(root, &["add", "--all", "--", &rules])?;
+    let staged = git(root, &["diff", "--cached", "--quiet", "--", &rules])?;
+    if staged.status.success() {
+        return Ok(false);
+    }
+    run_git(
+        root,
+        &[
+            "commit",
+            "--quiet",
+            "-m",
+            "hookwise: sync rules",
+            "--",
+            &rules,
+        ],
+    )?;
+    Ok(true)
+}
+
+/// Fetch `remote` and merge its `branch`, resolving conflicts in rule files
+/// by union. A conflict anywhere else aborts the merge, as does a secret in
+/// the combined rules.
+fn pull(policy: &PolicyConfig, root: &Path, remote: &str, branch: &str) -> Result<()> {
+    run_git(root, &["fetch", "--quiet", remote])?;
+    let upstream = format!("{remote}/{branch}");
+    if !git(root, &["rev-parse", "--verify", "--quiet", &upstream])?
+        .status
+        .success()
+    {
+        eprintln!("hookwise: {} does not exist yet; nothing to pull", upstream);
+        return Ok(());
+    }
+
+    let merge = git(root, &["merge", "--no-edit", &upstream])?;
+    if merge.status.success() {
+        eprintln!("hookwise: merged rules from {}", upstream);
+        return Ok(());
+    }
+
+    let conflicted: Vec<String> = stdout(&run_git(
+        root,
+        &["diff", "--name-only", "--relative", "--diff-filter=U"],
+    )?)
+    .lines()
+    .map(String::from)
+    .collect();
+    let rules = rules_dir(root);
+    let unmergeable = conflicted
+        .iter()
+        .find(|path| !(path.starts_with(&rules) && path.ends_with(".jsonl")));
+    if conflicted.is_empty() || unmergeable.is_some() {
+        let _ = git(root, &["merge", "--abort"]);
+        return Err(HookwiseError::Git {
+            command: format!("merge {upstream}"),
+            reason: match unmergeable {
+                Some(path) => format!("conflict in {path}; resolve it with git"),
+                None => String::from_utf8_lossy(&merge.stderr).trim().to_string(),
+            },
+        });
+    }
+
+    for path in &conflicted {
+        let ours = staged_blob(root, 2, path)?;
+        let theirs = staged_blob(root, 3, path)?;
+        std::fs::write(root.join(path), union_rules(&ours, &theirs))?;
+        run_git(root, &["add", "--", path])?;
+    }
+    let high = high_severity_secrets(policy, root)?;
+    if !high.is_empty() {
+        let _ = git(root, &["merge", "--abort"]);
+        report_secrets(&high);
+        std::process::exit(1);
+    }
+    run_git(root, &["commit", "--quiet", "--no-edit"])?;
+    eprintln!(
+        "hookwise: merged rules from {} ({} rule file(s) combined by key)",
+        upstream,
+        conflicted.len()
+    );
+    Ok(())
+}
+
+/// A conflicted file's content at merge `stage` (2 ours, 3 theirs), or
+/// empty if that side deleted it.
+fn staged_blob(root: &Path, stage: u8, path: &str) -> Result<String> {
+    let output = git(root, &["show", &format!(":{stage}:./{path}")])?;
+    Ok(if output.status.success() {
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    } else {
+        String::new()
+    })
+}
+
+/// Combine two versions of a rule file: one line per `CacheKey`, keeping the
+/// higher-precedence decision (DENY > ASK > ALLOW), then the newer one.
+/// Lines that don't parse are kept as they are.
+pub fn union_rules(ours: &str, theirs: &str) -> String {
+    let mut order: Vec<Option<CacheKey>> = Vec::new();
+    let mut unparsed: Vec<&str> = Vec::new();
+    let mut best: HashMap<CacheKey, (DecisionRecord, &str)> = HashMap::new();
+
+    for line in ours.lines().chain(theirs.lines()) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(record) = serde_json::from_str::<DecisionRecord>(line) else {
+            if !unparsed.contains(&line) {
+                unparsed.push(line);
+                order.push(None);
+            }
+            continue;
+        };
+        let key = record.key.clone();
+        match best.get(&key) {
+            None => {
+                order.push(Some(key.clone()));
+                best.insert(key, (record, line));
+            }
+            Some((current, _)) => {
+                let rank = |r: &DecisionRecord| (decision_priority(&r.decision), r.timestamp);
+                if rank(&record) > rank(current) {
+                    best.insert(key, (record, line));
+                }
+            }
+        }
+    }
+
+    let mut unparsed = unparsed.into_iter();
+    let mut merged = String::new();
+    for key in order {
+        let line = match key {
+            Some(key) => best[&key].1,
+            None => unparsed.next().unwrap_or_default(),
+        };
+        merged.push_str(line);
+        merged.push('\n');
+    }
+    merged
+}
+
+/// The project's rules directory, relative to the repository root, as a git
+/// pathspec.
+fn rules_dir(root: &Path) -> String {
+    let dir = crate::config::project_config_dir(root).join("rules");
+    dir.strip_prefix(root)
+        .unwrap_or(&dir)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Run git in `root`, returning its output whatever the exit status.
+fn git(root: &Path, args: &[&str]) -> Result<Output> {
+    Ok(Command::new("git").args(args).current_dir(root).output()?)
+}
+
+/// Run git in `root`, failing unless it exits successfully.
+fn run_git(root: &Path, args: &[&str]) -> Result<Output> {
+    let output = git(root, args)?;
+    if !output.status.success() {
+        return Err(HookwiseError::Git {
+            command: args.first().copied().unwrap_or_default().to_string(),
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output)
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{GlobalConfig, PolicyConfig};
+use crate::error::Result;
+
+/// Fields replaced as a whole rather than key by key. `supervisor` is tagged
+/// by `backend`, and mixing keys from two backends would describe neither.
+const ATOMIC_FIELDS: &[&str] = &["supervisor"];
+
+/// Which layer a setting's effective value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    Global,
+    Project,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Global => write!(f, "global"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// A setting's effective value and where it was set.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveSetting {
+    pub value: Value,
+    pub source: ConfigSource,
+}
+
+/// Built-in defaults overlaid by the global `config.yml`, then by the
+/// project's `policy.yml`, keyed by dotted field path
+/// (`similarity.jaccard_threshold`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct EffectiveConfig {
+    pub settings: BTreeMap<String, EffectiveSetting>,
+}
+
+impl EffectiveConfig {
+    /// Load and merge all three layers for the project at `project_root`.
+    /// A setting counts as set by a layer only if its file names it. Of the
+    /// global config only `api_key` is taken, since it is the one field
+    /// checks read from it.
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let mut defaults = to_json(&PolicyConfig::default());
+        if let Value::Object(map) = &mut defaults {
+            map.insert("api_key".into(), Value::Null);
+        }
+        let mut config = Self {
+            settings: BTreeMap::new(),
+        };
+        config.overlay(&defaults, None, ConfigSource::Default);
+
+        let global_path = super::dirs_global().join("config.yml");
+        if let Some(global) = GlobalConfig::load()? {
+            let raw: Value = super::read_yaml(&global_path)?;
+            let in_effect = serde_json::json!({ "api_key": global.api_key });
+            config.overlay(&in_effect, Some(&raw), ConfigSource::Global);
+        }
+
+        let project_path = super::project_config_dir(project_root).join("policy.yml");
+        if project_path.exists() {
+            let policy = PolicyConfig::load_from(&project_path)?;
+            let raw: Value = super::read_yaml_interpolated(&project_path)?;
+            config.overlay(&to_json(&policy), Some(&raw), ConfigSource::Project);
+        }
+
+        if let Some(setting) = config.settings.get_mut("api_key") {
+            if !setting.value.is_null() {
+                setting.value = Value::String("(set)".into());
+            }
+        }
+        Ok(config)
+    }
+
+    /// Take each leaf of `typed` that `raw` names (all of them when `raw`
+    /// is `None`). Values come from `typed` so serde defaults are filled in.
+    fn overlay(&mut self, typed: &Value, raw: Option<&Value>, source: ConfigSource) {
+        let mut leaves = Vec::new();
+        flatten("", typed, &mut leaves);
+        for (key, value) in leaves {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            if raw.is_none_or(|raw| raw.pointer(&pointer).is_some()) {
+                self.settings
+                    .insert(key, EffectiveSetting { value, source });
+            }
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) if !ATOMIC_FIELDS.contains(&prefix) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, child, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
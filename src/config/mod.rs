@@ -1,13 +1,139 @@
+pub mod effective;
 pub mod policy;
 pub mod roles;
 
+pub use effective::{ConfigSource, EffectiveConfig};
 pub use policy::*;
 pub use roles::*;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{HookwiseError, Result};
+
+/// Name of the per-project configuration directory.
+pub const PROJECT_DIR_NAME: &str = ".hookwise";
+
+/// The directory's name before the rename. Projects that still use it are
+/// found, but `init` never creates it.
+pub const LEGACY_PROJECT_DIR_NAME: &str = ".captain-hook";
+
+/// Read and parse a YAML file. Parse errors carry the file's path.
+pub fn read_yaml<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = std::fs::read_to_string(path)?;
+    serde_yaml::from_str(&contents).map_err(|e| HookwiseError::yaml(path, e))
+}
+
+/// Read and parse a YAML file, then expand `${VAR}` in every string value
+/// from the environment. An undefined variable is a parse error.
+pub fn read_yaml_interpolated<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let mut value: serde_yaml::Value = read_yaml(path)?;
+    interpolate_value(&mut value).map_err(|reason| HookwiseError::ConfigParse {
+        path: path.to_path_buf(),
+        reason,
+    })?;
+    serde_yaml::from_value(value).map_err(|e| HookwiseError::yaml(path, e))
+}
+
+fn interpolate_value(value: &mut serde_yaml::Value) -> std::result::Result<(), String> {
+    match value {
+        serde_yaml::Value::String(s) => *s = interpolate_env(s)?,
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                interpolate_value(item)?;
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (_, item) in map.iter_mut() {
+                interpolate_value(item)?;
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => interpolate_value(&mut tagged.value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replace each `${VAR}` in `s` with the variable's value. `$$` is a
+/// literal `$`, and a `$` not followed by `{` is left as it is.
+pub fn interpolate_env(s: &str) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(tail) = after.strip_prefix('$') {
+            out.push('$');
+            rest = tail;
+        } else if let Some(tail) = after.strip_prefix('{') {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| format!("unterminated '${{' in '{}'", s))?;
+            let name = &tail[..end];
+            let value = std::env::var(name)
+                .map_err(|_| format!("environment variable '{}' is not set", name))?;
+            out.push_str(&value);
+            rest = &tail[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
 
 /// Returns the global config directory path: `~/.config/hookwise/`
 pub fn dirs_global() -> PathBuf {
     let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".into());
     PathBuf::from(home).join(".config").join("hookwise")
 }
+
+/// The configuration directory of the project rooted at `root`.
+///
+/// `HOOKWISE_CONFIG_DIR` takes precedence when set, relative to `root` unless
+/// absolute. Otherwise `.hookwise/`, or `.captain-hook/` when only that one
+/// exists.
+pub fn project_config_dir(root: &Path) -> PathBuf {
+    if let Ok(dir) = std::env::var("HOOKWISE_CONFIG_DIR") {
+        if !dir.is_empty() {
+            return root.join(dir);
+        }
+    }
+    let dir = root.join(PROJECT_DIR_NAME);
+    let legacy = root.join(LEGACY_PROJECT_DIR_NAME);
+    if !dir.is_dir() && legacy.is_dir() {
+        return legacy;
+    }
+    dir
+}
+
+/// Find the nearest directory at or above `start` that contains a project
+/// configuration directory, the way git locates `.git`.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| project_config_dir(dir).is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Resolve the project root for a working directory.
+///
+/// `HOOKWISE_PROJECT_DIR` takes precedence when set; otherwise the nearest
+/// ancestor of `dir` with a configuration directory. Falls back to `dir` itself
+/// when no project is found.
+pub fn project_root_from(dir: &Path) -> PathBuf {
+    if let Ok(root) = std::env::var("HOOKWISE_PROJECT_DIR") {
+        if !root.is_empty() {
+            return PathBuf::from(root);
+        }
+    }
+    find_project_root(dir).unwrap_or_else(|| dir.to_path_buf())
+}
+
+/// Resolve the project root for the current working directory.
+pub fn project_root() -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    project_root_from(&cwd)
+}
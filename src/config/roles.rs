@@ -1,8 +1,9 @@
 use globset::GlobSet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
+use crate::config::{ConfidenceOverride, SimilarityOverride};
 use crate::error::{HookwiseError, Result};
 
 /// A role definition from `roles.yml`.
@@ -14,18 +15,112 @@ pub struct RoleDefinition {
     /// Natural language description of the role.
     pub description: String,
 
+    /// Roles whose path and tool lists this one inherits, before its own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
     /// Deterministic path policies for this role.
+    #[serde(default)]
     pub paths: PathPolicyConfig,
+
+    /// Name of a sanitize profile from `policy.yml` (`sanitize.profiles`).
+    /// Unset means the default pipeline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitize_profile: Option<String>,
+
+    /// Confidence thresholds for this role, over the `policy.yml` ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<ConfidenceOverride>,
+
+    /// Similarity thresholds for this role, over the `policy.yml` ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<SimilarityOverride>,
+
+    /// When non-empty, the only tools this role may call at all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools_allow: Vec<String>,
+
+    /// Tools this role may never call, whatever their input.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools_deny: Vec<String>,
+}
+
+impl RoleDefinition {
+    /// Whether `tool` is ruled out by `tools_deny`, or left out of a
+    /// non-empty `tools_allow`.
+    pub fn tool_blocked(&self, tool: &str) -> bool {
+        let listed = |tools: &[String]| tools.iter().any(|t| t == tool);
+        listed(&self.tools_deny) || (!self.tools_allow.is_empty() && !listed(&self.tools_allow))
+    }
 }
 
 /// Raw path policy from YAML (string globs, before compilation).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PathPolicyConfig {
     pub allow_write: Vec<String>,
     pub deny_write: Vec<String>,
     pub allow_read: Vec<String>,
 }
 
+impl PathPolicyConfig {
+    /// Append `other`'s globs, skipping any already present.
+    fn append(&mut self, other: &Self) {
+        for (ours, theirs) in [
+            (&mut self.allow_write, &other.allow_write),
+            (&mut self.deny_write, &other.deny_write),
+            (&mut self.allow_read, &other.allow_read),
+        ] {
+            for glob in theirs {
+                if !ours.contains(glob) {
+                    ours.push(glob.clone());
+                }
+            }
+        }
+    }
+}
+
+/// A role's lists with those of the roles it extends merged in.
+#[derive(Debug, Clone, Default)]
+struct InheritedLists {
+    paths: PathPolicyConfig,
+    /// `None` while no role along the way restricts tools.
+    tools_allow: Option<Vec<String>>,
+    tools_deny: Vec<String>,
+}
+
+impl InheritedLists {
+    /// Merge `other` in: paths and denied tools are appended, allowed tools
+    /// narrowed to those both allow.
+    fn append(&mut self, other: &Self) {
+        self.paths.append(&other.paths);
+        for tool in &other.tools_deny {
+            if !self.tools_deny.contains(tool) {
+                self.tools_deny.push(tool.clone());
+            }
+        }
+        if let Some(theirs) = &other.tools_allow {
+            self.tools_allow = Some(match self.tools_allow.take() {
+                Some(ours) => ours.into_iter().filter(|t| theirs.contains(t)).collect(),
+                None => theirs.clone(),
+            });
+        }
+    }
+}
+
+/// The write policy's verdict on a path, from [`CompiledPathPolicy::classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathVerdict {
+    /// Matches `deny_write`.
+    Deny,
+    /// A sensitive path not in `deny_write`.
+    Ask,
+    /// Matches `allow_write` and nothing stricter.
+    Allow,
+    /// Matches none of them; later tiers decide.
+    NoOpinion,
+}
+
 /// Compiled path policy -- globset instances ready for matching.
 /// GlobSet doesn't implement Debug, so we implement it manually.
 pub struct CompiledPathPolicy {
@@ -47,12 +142,15 @@ impl std::fmt::Debug for CompiledPathPolicy {
 }
 
 impl CompiledPathPolicy {
-    /// Compile a PathPolicyConfig into GlobSet instances.
+    /// Compile a PathPolicyConfig into GlobSet instances. Sensitive patterns
+    /// starting with `~/` match both as written and with `$HOME` expanded.
     pub fn compile(config: &PathPolicyConfig, sensitive_patterns: &[String]) -> Result<Self> {
         let allow_write = build_globset(&config.allow_write)?;
         let deny_write = build_globset(&config.deny_write)?;
         let allow_read = build_globset(&config.allow_read)?;
-        let sensitive_ask_write = build_globset(sensitive_patterns)?;
+        let mut sensitive = sensitive_patterns.to_vec();
+        sensitive.extend(sensitive_patterns.iter().filter_map(|p| expand_tilde(p)));
+        let sensitive_ask_write = build_globset(&sensitive)?;
 
         Ok(Self {
             allow_write,
@@ -61,6 +159,89 @@ impl CompiledPathPolicy {
             sensitive_ask_write,
         })
     }
+
+    /// What the write policy says about `path`: see [`Self::classify_in`].
+    pub fn classify(&self, path: &str) -> PathVerdict {
+        self.classify_in(path, None)
+    }
+
+    /// What the write policy says about `path`, relative to `cwd`. The
+    /// first list that matches decides, in the order `deny_write`, sensitive
+    /// paths, `allow_write`, so a denied path is denied even if it is also
+    /// sensitive or allowed.
+    pub fn classify_in(&self, path: &str, cwd: Option<&str>) -> PathVerdict {
+        let normalized = normalize_lexically(Path::new(path));
+        if self.deny_write.is_match(&normalized) {
+            PathVerdict::Deny
+        } else if self.is_sensitive(path, cwd) {
+            PathVerdict::Ask
+        } else if self.allow_write.is_match(&normalized) {
+            PathVerdict::Allow
+        } else {
+            PathVerdict::NoOpinion
+        }
+    }
+
+    /// Whether `path` is sensitive. It is tried as given, with a leading `~`
+    /// expanded, and resolved against `cwd` if relative, so `~/.claude/**`
+    /// catches `~/.claude/x`, `$HOME/.claude/x`, and `.claude/x` run from
+    /// the home directory alike.
+    pub fn is_sensitive(&self, path: &str, cwd: Option<&str>) -> bool {
+        if self
+            .sensitive_ask_write
+            .is_match(normalize_lexically(Path::new(path)))
+        {
+            return true;
+        }
+        let expanded = expand_tilde(path).unwrap_or_else(|| path.to_string());
+        if self
+            .sensitive_ask_write
+            .is_match(normalize_lexically(Path::new(&expanded)))
+        {
+            return true;
+        }
+        match cwd {
+            Some(cwd) if Path::new(&expanded).is_relative() => self
+                .sensitive_ask_write
+                .is_match(normalize_lexically(&Path::new(cwd).join(&expanded))),
+            _ => false,
+        }
+    }
+}
+
+/// `path` with a leading `~` replaced by `$HOME`, or `None` if it has none.
+fn expand_tilde(path: &str) -> Option<String> {
+    let rest = path.strip_prefix('~')?;
+    if !(rest.is_empty() || rest.starts_with('/')) {
+        return None; // `~user` is someone else's home
+    }
+    let home = std::env::var("HOME").ok()?;
+    Some(format!("{}{}", home.trim_end_matches('/'), rest))
+}
+
+/// Resolve `.` and `..` components without touching the filesystem, so
+/// `src/../.env` is matched as `.env`. A relative path keeps the `..`s that
+/// climb above its start; `..` at the root stays at the root. An empty
+/// result is `.`.
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
 }
 
 fn build_globset(patterns: &[String]) -> Result<GlobSet> {
@@ -335,7 +516,8 @@ pub struct RolesConfig {
 }
 
 impl RolesConfig {
-    /// Load roles from a YAML file. Expands `{{category}}` macros.
+    /// Load roles from a YAML file. Expands `${VAR}` from the environment
+    /// and `{{category}}` macros, and resolves `extends`.
     pub fn load_from(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self {
@@ -343,19 +525,16 @@ impl RolesConfig {
                 roles: HashMap::new(),
             });
         }
-        let contents = std::fs::read_to_string(path)?;
-        let mut config: Self =
-            serde_yaml::from_str(&contents).map_err(|e| HookwiseError::ConfigParse {
-                path: path.to_path_buf(),
-                reason: e.to_string(),
-            })?;
+        let mut config: Self = super::read_yaml_interpolated(path)?;
         config.expand_categories()?;
+        config.resolve_inheritance()?;
         Ok(config)
     }
 
-    /// Load roles from the project root. Checks `.hookwise/roles.yml`.
+    /// Load roles from the project root. Checks `roles.yml` in the project's
+    /// configuration directory.
     pub fn load_project(project_root: &Path) -> Result<Self> {
-        let path = project_root.join(".hookwise").join("roles.yml");
+        let path = super::project_config_dir(project_root).join("roles.yml");
         Self::load_from(&path)
     }
 
@@ -384,6 +563,81 @@ impl RolesConfig {
         Ok(())
     }
 
+    /// Give each role the path lists of the roles it extends, parents first
+    /// and each glob once, followed by its own. Deny is checked before allow
+    /// at match time, so a role's `deny_write` overrides an inherited
+    /// `allow_write`, and an inherited deny can't be lifted.
+    ///
+    /// Tool lists only ever narrow: `tools_deny` is the union of the role's
+    /// and its ancestors', and `tools_allow` keeps just the tools every
+    /// non-empty list along the way allows.
+    fn resolve_inheritance(&mut self) -> Result<()> {
+        let mut resolved: HashMap<String, InheritedLists> = HashMap::new();
+        let mut names: Vec<String> = self.roles.keys().cloned().collect();
+        names.sort();
+        for name in &names {
+            self.resolve_role(name, &mut Vec::new(), &mut resolved)?;
+        }
+        for (name, lists) in resolved {
+            if let Some(role) = self.roles.get_mut(&name) {
+                role.paths = lists.paths;
+                role.tools_allow = lists.tools_allow.unwrap_or_default();
+                role.tools_deny = lists.tools_deny;
+            }
+        }
+        Ok(())
+    }
+
+    /// The path and tool lists of role `name` with its ancestors' merged in.
+    /// `chain` holds the roles being resolved above it, to detect cycles.
+    fn resolve_role(
+        &self,
+        name: &str,
+        chain: &mut Vec<String>,
+        resolved: &mut HashMap<String, InheritedLists>,
+    ) -> Result<InheritedLists> {
+        if let Some(lists) = resolved.get(name) {
+            return Ok(lists.clone());
+        }
+        if chain.iter().any(|n| n == name) {
+            chain.push(name.to_string());
+            return Err(HookwiseError::InvalidPolicy {
+                reason: format!("roles extend each other in a cycle: {}", chain.join(" -> ")),
+            });
+        }
+        let role = &self.roles[name];
+
+        chain.push(name.to_string());
+        let mut lists = InheritedLists::default();
+        for parent in &role.extends {
+            if !self.roles.contains_key(parent) {
+                return Err(HookwiseError::InvalidPolicy {
+                    reason: format!("role '{}' extends unknown role '{}'", name, parent),
+                });
+            }
+            lists.append(&self.resolve_role(parent, chain, resolved)?);
+        }
+        chain.pop();
+
+        lists.append(&InheritedLists {
+            paths: role.paths.clone(),
+            tools_allow: (!role.tools_allow.is_empty()).then(|| role.tools_allow.clone()),
+            tools_deny: role.tools_deny.clone(),
+        });
+        // An empty `tools_allow` means any tool, so an intersection that
+        // leaves nothing can't be written back
+        if lists.tools_allow.as_ref().is_some_and(Vec::is_empty) {
+            return Err(HookwiseError::InvalidPolicy {
+                reason: format!(
+                    "role '{}' allows no tools: its tools_allow shares none with the roles it extends",
+                    name
+                ),
+            });
+        }
+        resolved.insert(name.to_string(), lists.clone());
+        Ok(lists)
+    }
+
     /// Merge user-specified categories over built-in defaults.
     fn merged_categories(&self) -> HashMap<String, Vec<String>> {
         let mut merged = default_categories();
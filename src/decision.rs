@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +29,8 @@ pub enum DecisionTier {
     Supervisor,
     /// Tier 4: human-in-the-loop
     Human,
+    /// Tier 4: one-shot human grant for a single call; never persisted or cached
+    HumanOnce,
     /// Sensitive path default (pre-cascade)
     SensitivePath,
     /// Explicit override (human-set, deterministic)
@@ -93,6 +97,7 @@ impl Decision {
 impl std::fmt::Display for ScopeLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            ScopeLevel::Global => write!(f, "global"),
             ScopeLevel::Org => write!(f, "org"),
             ScopeLevel::Project => write!(f, "project"),
             ScopeLevel::User => write!(f, "user"),
@@ -106,6 +111,7 @@ impl std::str::FromStr for ScopeLevel {
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
+            "global" => Ok(ScopeLevel::Global),
             "org" => Ok(ScopeLevel::Org),
             "project" => Ok(ScopeLevel::Project),
             "user" => Ok(ScopeLevel::User),
@@ -138,4 +144,86 @@ pub struct DecisionRecord {
 
     /// The session ID that triggered this decision (for audit trail).
     pub session_id: String,
+
+    /// Tags of the session that triggered this decision (for audit trail).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+
+    /// Pinned rule: never replaced by a learned decision or a narrower-scope
+    /// decision for the same key.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub immutable: bool,
+
+    /// Stable identifier, derived from the key, scope, and timestamp (see
+    /// [`DecisionRecord::derive_id`]). Empty until assigned; records written
+    /// before ids existed get theirs when loaded.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+
+    /// When a cache or similarity tier last resolved a call from this
+    /// record. `None` if it never has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Utc>>,
+
+    /// How many calls a cache or similarity tier has resolved from this
+    /// record.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub hit_count: u64,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl DecisionRecord {
+    /// Whether this record is immutable and governs `key`: same tool and
+    /// input, for the same role or for every role (`*`).
+    pub fn pins(&self, key: &CacheKey) -> bool {
+        self.immutable
+            && self.key.tool == key.tool
+            && self.key.sanitized_input == key.sanitized_input
+            && (self.key.role == key.role || self.key.role == "*")
+    }
+
+    /// Short identifier for this record, derived from its key, decision,
+    /// scope, and timestamp. Stable across reloads, so it can be used to refer back to a
+    /// past decision (e.g. `hookwise replay <id>`), and identical records
+    /// share it, so it doubles as a dedup key.
+    pub fn derive_id(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let material = format!(
+            "{}\0{}\0{}\0{}\0{}\0{}",
+            self.key.tool,
+            self.key.role,
+            self.key.sanitized_input,
+            self.decision,
+            self.scope,
+            self.timestamp.to_rfc3339()
+        );
+        let hash = format!("{:x}", Sha256::digest(material.as_bytes()));
+        hash[..12].to_string()
+    }
+
+    /// Count `hits` uses of this record, the latest at `at`.
+    pub fn record_hits(&mut self, hits: u64, at: DateTime<Utc>) {
+        self.hit_count += hits;
+        if self.last_used.is_none_or(|last| last < at) {
+            self.last_used = Some(at);
+        }
+    }
+
+    /// When this record was last used or created, whichever is later.
+    /// Usage recorded before a rule was re-learned doesn't make it look idle.
+    pub fn last_active(&self) -> DateTime<Utc> {
+        self.last_used
+            .map_or(self.timestamp, |used| used.max(self.timestamp))
+    }
+
+    /// Assign the derived id if the record does not have one yet.
+    pub fn ensure_id(&mut self) {
+        if self.id.is_empty() {
+            self.id = self.derive_id();
+        }
+    }
 }
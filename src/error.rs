@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, thiserror::Error)]
 pub enum HookwiseError {
@@ -50,9 +50,22 @@ pub enum HookwiseError {
         waited_secs: u64,
     },
 
+    #[error("decision for {tool} (role {role}) is pinned immutable at {scope} scope")]
+    ImmutableDecision {
+        tool: String,
+        role: String,
+        scope: String,
+    },
+
+    #[error("file watch error: {reason}")]
+    Watch { reason: String },
+
     #[error("glob pattern error: {pattern}: {reason}")]
     GlobPattern { pattern: String, reason: String },
 
+    #[error("git {command} failed: {reason}")]
+    Git { command: String, reason: String },
+
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -63,4 +76,14 @@ pub enum HookwiseError {
     Api { status: u16, body: String },
 }
 
+impl HookwiseError {
+    /// A YAML parse error, reported against the file it was read from.
+    pub fn yaml(path: &Path, err: serde_yaml::Error) -> Self {
+        Self::ConfigParse {
+            path: path.to_path_buf(),
+            reason: err.to_string(),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, HookwiseError>;
@@ -38,6 +38,9 @@ pub struct HookInput {
 pub struct HookOutput {
     #[serde(rename = "hookSpecificOutput")]
     pub hook_specific_output: HookSpecificOutput,
+    /// Set when hookwise could not evaluate the call at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<HookError>,
 }
 
 /// The permission decision output within Claude's HookOutput.
@@ -45,6 +48,12 @@ pub struct HookOutput {
 pub struct HookSpecificOutput {
     #[serde(rename = "permissionDecision")]
     pub permission_decision: String,
+    #[serde(
+        rename = "permissionDecisionReason",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub permission_decision_reason: Option<String>,
 }
 
 /// Gemini CLI hook output: flat `decision` field.
@@ -53,15 +62,53 @@ pub struct GeminiHookOutput {
     pub decision: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Set when hookwise could not evaluate the call at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<HookError>,
 }
 
+/// Why `check` could not evaluate a call, so callers can tell a failure apart
+/// from a deny.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookErrorCode {
+    /// The hook payload on stdin was not valid JSON or was missing fields.
+    InvalidInput,
+}
+
+/// Structured error attached to hook output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookError {
+    pub code: HookErrorCode,
+    pub reason: String,
+}
+
+/// Exit code for calls that could not be evaluated (e.g. a malformed payload).
+/// Distinct from the deny exit codes of every format.
+pub const ERROR_EXIT_CODE: i32 = 3;
+
 impl HookOutput {
     /// Create a new Claude HookOutput with the given decision.
     pub fn new(decision: Decision) -> Self {
         Self {
             hook_specific_output: HookSpecificOutput {
                 permission_decision: decision_str(decision),
+                permission_decision_reason: None,
             },
+            error: None,
+        }
+    }
+
+    /// Create a Claude HookOutput for a call that could not be evaluated.
+    pub fn error(decision: Decision, code: HookErrorCode, reason: &str) -> Self {
+        Self {
+            hook_specific_output: HookSpecificOutput {
+                permission_decision: decision_str(decision),
+                permission_decision_reason: Some(reason.to_string()),
+            },
+            error: Some(HookError {
+                code,
+                reason: reason.to_string(),
+            }),
         }
     }
 }
@@ -72,6 +119,19 @@ impl GeminiHookOutput {
         Self {
             decision: decision_str(decision),
             reason,
+            error: None,
+        }
+    }
+
+    /// Create a Gemini hook output for a call that could not be evaluated.
+    pub fn error(decision: Decision, code: HookErrorCode, reason: &str) -> Self {
+        Self {
+            decision: decision_str(decision),
+            reason: Some(reason.to_string()),
+            error: Some(HookError {
+                code,
+                reason: reason.to_string(),
+            }),
         }
     }
 }
@@ -112,6 +172,31 @@ pub fn write_hook_output(decision: Decision, format: HookFormat) -> Result<()> {
     Ok(())
 }
 
+/// Write a structured error decision to stdout, for calls that could not be
+/// evaluated. Callers should exit with [`ERROR_EXIT_CODE`] afterwards.
+pub fn write_hook_error(
+    decision: Decision,
+    code: HookErrorCode,
+    reason: &str,
+    format: HookFormat,
+) -> Result<()> {
+    use std::io::Write;
+    let stdout = std::io::stdout();
+    let mut handle = stdout.lock();
+    match format {
+        HookFormat::Claude => {
+            let output = HookOutput::error(decision, code, reason);
+            serde_json::to_writer(&mut handle, &output)?;
+        }
+        HookFormat::Gemini => {
+            let output = GeminiHookOutput::error(decision, code, reason);
+            serde_json::to_writer(&mut handle, &output)?;
+        }
+    }
+    handle.flush()?;
+    Ok(())
+}
+
 /// Get the appropriate exit code for a deny decision.
 /// Claude uses exit code 1, Gemini uses exit code 2 (emergency block).
 pub fn deny_exit_code(format: HookFormat) -> i32 {
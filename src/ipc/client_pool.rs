@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error::{HookwiseError, Result};
+use crate::ipc::{IpcRequest, IpcResponse};
+
+/// Requests awaiting a response, by id.
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<IpcResponse>>>>;
+
+/// One open connection to the supervisor and the task reading its replies.
+struct Connection {
+    writer: tokio::sync::Mutex<OwnedWriteHalf>,
+    pending: Pending,
+    closed: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+}
+
+impl Connection {
+    async fn open(socket_path: &PathBuf) -> Result<Self> {
+        if !socket_path.exists() {
+            return Err(HookwiseError::SocketNotFound {
+                path: socket_path.clone(),
+            });
+        }
+        let stream = UnixStream::connect(socket_path)
+            .await
+            .map_err(|e| HookwiseError::Ipc {
+                reason: format!("connect failed: {}", e),
+            })?;
+        let (reader, writer) = stream.into_split();
+
+        let pending: Pending = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let pending = pending.clone();
+            let closed = closed.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(reader).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let response: IpcResponse = match serde_json::from_str(&line) {
+                        Ok(response) => response,
+                        Err(e) => {
+                            tracing::warn!("ipc pool: invalid response JSON: {}", e);
+                            continue;
+                        }
+                    };
+                    let waiter = response.id.and_then(|id| {
+                        pending
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .remove(&id)
+                    });
+                    if let Some(waiter) = waiter {
+                        let _ = waiter.send(response);
+                    }
+                }
+                // Dropping the senders fails every request still waiting
+                closed.store(true, Ordering::SeqCst);
+                pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            })
+        };
+
+        Ok(Self {
+            writer: tokio::sync::Mutex::new(writer),
+            pending,
+            closed,
+            reader,
+        })
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Unix socket client that keeps one connection to the supervisor open and
+/// pipelines requests over it, matching responses to requests by `id`. For
+/// a process that checks many tool calls; one-shot hook invocations use
+/// `IpcClient`. A connection the supervisor closes is reopened on the next
+/// request.
+pub struct IpcClientPool {
+    socket_path: PathBuf,
+    timeout_secs: u64,
+    next_id: AtomicU64,
+    connection: tokio::sync::Mutex<Option<Arc<Connection>>>,
+}
+
+impl IpcClientPool {
+    /// A pool for the supervisor at `socket_path`. Nothing is connected
+    /// until the first request.
+    pub fn new(socket_path: PathBuf, timeout_secs: u64) -> Self {
+        Self {
+            socket_path,
+            timeout_secs,
+            next_id: AtomicU64::new(1),
+            connection: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Send a request and wait for its response. Other requests may be in
+    /// flight on the same connection at the same time.
+    pub async fn request(&self, req: &IpcRequest) -> Result<IpcResponse> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut req = req.clone();
+        req.id = Some(id);
+        let mut line = serde_json::to_string(&req)?;
+        line.push('\n');
+
+        let connection = self.connection().await?;
+        let (tx, rx) = oneshot::channel();
+        connection
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, tx);
+
+        let sent = {
+            let mut writer = connection.writer.lock().await;
+            writer.write_all(line.as_bytes()).await
+        };
+        if let Err(e) = sent {
+            connection
+                .pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&id);
+            return Err(HookwiseError::Ipc {
+                reason: format!("write failed: {}", e),
+            });
+        }
+
+        let timeout = Duration::from_secs(self.timeout_secs);
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(HookwiseError::Ipc {
+                reason: "connection closed before the response arrived".into(),
+            }),
+            Err(_) => {
+                connection
+                    .pending
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&id);
+                Err(HookwiseError::SupervisorTimeout {
+                    timeout_secs: self.timeout_secs,
+                })
+            }
+        }
+    }
+
+    /// The open connection, opening a new one if there is none or the last
+    /// was closed.
+    async fn connection(&self) -> Result<Arc<Connection>> {
+        let mut slot = self.connection.lock().await;
+        if let Some(connection) = slot.as_ref() {
+            if !connection.closed.load(Ordering::SeqCst) {
+                return Ok(connection.clone());
+            }
+        }
+        let connection = Arc::new(Connection::open(&self.socket_path).await?);
+        *slot = Some(connection.clone());
+        Ok(connection)
+    }
+}
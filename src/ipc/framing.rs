@@ -0,0 +1,69 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{HookwiseError, Result};
+
+/// First byte a client sends to select length-prefixed framing. It can never
+/// start a newline-delimited JSON message, so servers tell the two apart
+/// from the first byte alone.
+pub const LENGTH_PREFIX_MAGIC: u8 = 0xFF;
+
+/// Largest message body a client accepts in either framing mode.
+pub const MAX_FRAME_LEN: usize = 1_048_576;
+
+/// How messages are delimited on the socket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// One JSON document per line. The original protocol.
+    #[default]
+    Newline,
+    /// The client sends `LENGTH_PREFIX_MAGIC` once, then every message in
+    /// either direction is a 4-byte big-endian length followed by the body.
+    LengthPrefixed,
+}
+
+/// Write one length-prefixed frame.
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, body: &[u8]) -> Result<()> {
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|len| *len as usize <= MAX_FRAME_LEN)
+        .ok_or_else(|| HookwiseError::Ipc {
+            reason: format!("message of {} bytes exceeds frame limit", body.len()),
+        })?;
+    writer
+        .write_all(&len.to_be_bytes())
+        .await
+        .map_err(|e| HookwiseError::Ipc {
+            reason: format!("write failed: {}", e),
+        })?;
+    writer
+        .write_all(body)
+        .await
+        .map_err(|e| HookwiseError::Ipc {
+            reason: format!("write failed: {}", e),
+        })
+}
+
+/// Read one length-prefixed frame of at most `max_len` bytes.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, max_len: usize) -> Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader
+        .read_exact(&mut len)
+        .await
+        .map_err(|e| HookwiseError::Ipc {
+            reason: format!("read frame length failed: {}", e),
+        })?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_len {
+        return Err(HookwiseError::Ipc {
+            reason: format!("frame of {} bytes exceeds limit", len),
+        });
+    }
+    let mut body = vec![0u8; len];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| HookwiseError::Ipc {
+            reason: format!("read frame failed: {}", e),
+        })?;
+    Ok(body)
+}
@@ -1,14 +1,22 @@
+pub mod client_pool;
+pub mod framing;
 pub mod pending_queue;
 pub mod socket_client;
 pub mod socket_server;
 
 use serde::{Deserialize, Serialize};
 
+use crate::cascade::CascadeMetrics;
 use crate::decision::{Decision, DecisionMetadata};
+use crate::scope::ScopeLevel;
 
 /// IPC request sent from worker hook to supervisor via Unix socket.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpcRequest {
+    /// Correlates the response with this request when several are in flight
+    /// on one connection. Echoed back on the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub session_id: String,
     pub tool_name: String,
     pub tool_input: String,
@@ -22,6 +30,65 @@ pub struct IpcRequest {
 /// IPC response from supervisor to worker hook.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpcResponse {
+    /// The `id` of the request this answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub decision: Decision,
     pub metadata: DecisionMetadata,
 }
+
+/// Control message telling a live process to refresh its state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "control", rename_all = "snake_case")]
+pub enum IpcControl {
+    /// Storage for a role was cleared; drop and reload its in-memory entries.
+    InvalidateRole { scope: ScopeLevel, role: String },
+    /// A session was just registered; load it into the session cache before
+    /// its first check arrives. `cwd` is the session's project root.
+    SessionRegistered { session_id: String, cwd: String },
+}
+
+/// Question about the supervisor itself, answered without running a check.
+/// Sent as `{"query": "health"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "query", rename_all = "snake_case")]
+pub enum IpcQuery {
+    /// Is the supervisor up, and what is it running? Answered with a
+    /// [`HealthResponse`].
+    Health,
+}
+
+/// Anything a client may send over the socket: a control message, a query,
+/// or a decision request. Plain requests keep their original wire format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IpcMessage {
+    Control(IpcControl),
+    Query(IpcQuery),
+    Request(IpcRequest),
+}
+
+/// Reply to [`IpcQuery::Health`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// The supervisor's hookwise version.
+    pub version: String,
+    pub uptime_secs: u64,
+    /// Decisions waiting for a human in the supervisor's queue.
+    pub pending_queue_len: usize,
+    /// Cached decisions the supervisor has loaded, as last reported to the
+    /// server.
+    pub loaded_decisions: usize,
+    /// How often each cache and similarity tier has resolved calls, if the
+    /// supervisor runs a cascade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<CascadeMetrics>,
+}
+
+/// Reply to a control message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcAck {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -1,6 +1,7 @@
 // Pending queue management for IPC layer.
 // The primary queue logic lives in cascade::human::DecisionQueue.
-// This module provides IPC-specific queue serialization if needed.
+// This module provides IPC-specific queue serialization, also used to save
+// the queue across supervisor restarts.
 
 use crate::cascade::human::PendingDecision;
 use crate::error::Result;
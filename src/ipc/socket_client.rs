@@ -1,76 +1,152 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::UnixStream;
+use tokio::time::Instant;
 
 use crate::error::{HookwiseError, Result};
-use crate::ipc::{IpcRequest, IpcResponse};
+use crate::ipc::framing::{self, Framing, LENGTH_PREFIX_MAGIC, MAX_FRAME_LEN};
+use crate::ipc::{HealthResponse, IpcAck, IpcControl, IpcQuery, IpcRequest, IpcResponse};
+
+/// Delay before the first connection retry; doubled after each one.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
 
 /// Unix socket client for worker hooks to connect to the supervisor.
 pub struct IpcClient {
     socket_path: PathBuf,
     timeout_secs: u64,
+    max_retries: u32,
+    framing: Framing,
 }
 
 impl IpcClient {
+    /// A client that fails at once if the socket is not there.
     pub fn new(socket_path: PathBuf, timeout_secs: u64) -> Self {
+        Self::new_with_retries(socket_path, timeout_secs, 0)
+    }
+
+    /// A client that retries connecting up to `max_retries` times, waiting
+    /// 50ms, 100ms, 200ms, ... between attempts, for a supervisor that is
+    /// still starting. Retries stop early when `timeout_secs` runs out.
+    pub fn new_with_retries(socket_path: PathBuf, timeout_secs: u64, max_retries: u32) -> Self {
         Self {
             socket_path,
             timeout_secs,
+            max_retries,
+            framing: Framing::default(),
         }
     }
 
+    /// A client for a team's supervisor, connecting to `configured` or else
+    /// to the default socket path a supervisor server listens on.
+    pub fn for_supervisor(
+        configured: Option<&Path>,
+        team_id: Option<&str>,
+        timeout_secs: u64,
+    ) -> Self {
+        Self::new(
+            crate::cascade::supervisor::supervisor_socket_path(configured, team_id),
+            timeout_secs,
+        )
+    }
+
+    /// The socket this client connects to.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Delimit messages with `framing` instead of newlines.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
     /// Send a request and wait for a response.
     pub async fn request(&self, req: &IpcRequest) -> Result<IpcResponse> {
-        if !self.socket_path.exists() {
-            return Err(HookwiseError::SocketNotFound {
-                path: self.socket_path.clone(),
-            });
+        self.roundtrip(req).await
+    }
+
+    /// Send a control message and wait for it to be applied.
+    pub async fn send_control(&self, control: &IpcControl) -> Result<()> {
+        let ack: IpcAck = self.roundtrip(control).await?;
+        if ack.ok {
+            Ok(())
+        } else {
+            Err(HookwiseError::Ipc {
+                reason: ack
+                    .error
+                    .unwrap_or_else(|| "control message rejected".into()),
+            })
         }
+    }
 
-        let timeout = std::time::Duration::from_secs(self.timeout_secs);
+    /// Ask the supervisor whether it is up and what it is running.
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.roundtrip(&IpcQuery::Health).await
+    }
 
-        let result = tokio::time::timeout(timeout, async {
-            let mut stream = UnixStream::connect(&self.socket_path).await.map_err(|e| {
-                HookwiseError::Ipc {
-                    reason: format!("connect failed: {}", e),
-                }
-            })?;
+    /// Write one JSON line and read back one JSON reply.
+    async fn roundtrip<Req, Resp>(&self, req: &Req) -> Result<Resp>
+    where
+        Req: Serialize,
+        Resp: DeserializeOwned,
+    {
+        let deadline = Instant::now() + Duration::from_secs(self.timeout_secs);
+        let mut stream = self.connect(deadline).await?;
 
-            // Send request as JSON line
+        let result = tokio::time::timeout_at(deadline, async {
             let request_json = serde_json::to_string(req)?;
-            stream
-                .write_all(request_json.as_bytes())
-                .await
-                .map_err(|e| HookwiseError::Ipc {
-                    reason: format!("write failed: {}", e),
-                })?;
-            stream
-                .write_all(b"\n")
-                .await
-                .map_err(|e| HookwiseError::Ipc {
-                    reason: format!("write newline failed: {}", e),
-                })?;
-            stream.shutdown().await.map_err(|e| HookwiseError::Ipc {
-                reason: format!("shutdown write failed: {}", e),
-            })?;
-
-            // Read response (bounded to 1MB to prevent OOM)
-            let mut response_buf = Vec::new();
-            stream
-                .take(1_048_576)
-                .read_to_end(&mut response_buf)
-                .await
-                .map_err(|e| HookwiseError::Ipc {
-                    reason: format!("read failed: {}", e),
-                })?;
+            let response_buf = match self.framing {
+                Framing::Newline => {
+                    // Send request as JSON line
+                    stream
+                        .write_all(request_json.as_bytes())
+                        .await
+                        .map_err(|e| HookwiseError::Ipc {
+                            reason: format!("write failed: {}", e),
+                        })?;
+                    stream
+                        .write_all(b"\n")
+                        .await
+                        .map_err(|e| HookwiseError::Ipc {
+                            reason: format!("write newline failed: {}", e),
+                        })?;
+                    stream.shutdown().await.map_err(|e| HookwiseError::Ipc {
+                        reason: format!("shutdown write failed: {}", e),
+                    })?;
+
+                    // Read response (bounded to prevent OOM)
+                    let mut response_buf = Vec::new();
+                    (&mut stream)
+                        .take(MAX_FRAME_LEN as u64)
+                        .read_to_end(&mut response_buf)
+                        .await
+                        .map_err(|e| HookwiseError::Ipc {
+                            reason: format!("read failed: {}", e),
+                        })?;
+                    response_buf
+                }
+                Framing::LengthPrefixed => {
+                    stream
+                        .write_all(&[LENGTH_PREFIX_MAGIC])
+                        .await
+                        .map_err(|e| HookwiseError::Ipc {
+                            reason: format!("write failed: {}", e),
+                        })?;
+                    framing::write_frame(&mut stream, request_json.as_bytes()).await?;
+                    framing::read_frame(&mut stream, MAX_FRAME_LEN).await?
+                }
+            };
 
-            let response: IpcResponse =
+            let response: Resp =
                 serde_json::from_slice(&response_buf).map_err(|e| HookwiseError::Ipc {
                     reason: format!("invalid response JSON: {}", e),
                 })?;
 
-            Ok::<IpcResponse, HookwiseError>(response)
+            Ok::<Resp, HookwiseError>(response)
         })
         .await;
 
@@ -82,4 +158,40 @@ impl IpcClient {
             }),
         }
     }
+
+    /// Connect to the socket, retrying with backoff while retries and time
+    /// remain. Fails with `SocketNotFound` if the socket never appeared.
+    async fn connect(&self, deadline: Instant) -> Result<UnixStream> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let error = if self.socket_path.exists() {
+                match tokio::time::timeout_at(deadline, UnixStream::connect(&self.socket_path))
+                    .await
+                {
+                    Ok(Ok(stream)) => return Ok(stream),
+                    Ok(Err(e)) => HookwiseError::Ipc {
+                        reason: format!("connect failed: {}", e),
+                    },
+                    Err(_) => {
+                        return Err(HookwiseError::SupervisorTimeout {
+                            timeout_secs: self.timeout_secs,
+                        })
+                    }
+                }
+            } else {
+                HookwiseError::SocketNotFound {
+                    path: self.socket_path.clone(),
+                }
+            };
+
+            let retry_at = Instant::now() + backoff;
+            if attempt >= self.max_retries || retry_at >= deadline {
+                return Err(error);
+            }
+            tokio::time::sleep_until(retry_at).await;
+            attempt += 1;
+            backoff *= 2;
+        }
+    }
 }
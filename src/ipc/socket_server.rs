@@ -1,19 +1,76 @@
 use std::future::Future;
-use std::path::PathBuf;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::UnixListener;
-use tokio::sync::Notify;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{Mutex, Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
 
+use crate::cascade::human::DecisionQueue;
+use crate::cascade::CascadeMetrics;
+use crate::decision::{Decision, DecisionMetadata, DecisionTier};
 use crate::error::{HookwiseError, Result};
-use crate::ipc::{IpcRequest, IpcResponse};
+use crate::ipc::framing::{self, LENGTH_PREFIX_MAGIC};
+use crate::ipc::{
+    HealthResponse, IpcAck, IpcControl, IpcMessage, IpcQuery, IpcRequest, IpcResponse,
+};
+
+/// Handler for control messages, e.g. reloading a role after invalidation.
+pub type ControlHandler =
+    Arc<dyn Fn(IpcControl) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync>;
+
+/// Source of the cascade counters reported in health replies.
+pub type MetricsSource = Arc<dyn Fn() -> CascadeMetrics + Send + Sync>;
+
+/// Requests served at once, over all connections, unless overridden with
+/// `IpcServer::with_max_concurrent`.
+pub const DEFAULT_MAX_CONCURRENT: usize = 64;
+
+/// Largest request the server reads unless overridden with
+/// `IpcServer::with_max_request_bytes`.
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 1_048_576;
+
+/// How long connections still being handled at shutdown get to finish
+/// before the socket is removed.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
 
 /// Unix socket server for the supervisor agent.
 pub struct IpcServer {
     socket_path: PathBuf,
     shutdown_signal: Arc<Notify>,
+    control_handler: Option<ControlHandler>,
+    max_concurrent: usize,
+    max_request_bytes: usize,
+    decision_queue: Option<(Arc<DecisionQueue>, PathBuf)>,
+    loaded_decisions: Arc<AtomicUsize>,
+    metrics: Option<MetricsSource>,
+}
+
+/// What a health query reports, shared by every connection.
+struct HealthState {
+    started: Instant,
+    queue: Option<Arc<DecisionQueue>>,
+    loaded_decisions: Arc<AtomicUsize>,
+    metrics: Option<MetricsSource>,
+}
+
+impl HealthState {
+    fn report(&self) -> HealthResponse {
+        HealthResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            pending_queue_len: self.queue.as_ref().map_or(0, |q| q.list_pending().len()),
+            loaded_decisions: self.loaded_decisions.load(Ordering::Relaxed),
+            metrics: self.metrics.as_ref().map(|metrics| metrics()),
+        }
+    }
 }
 
 impl IpcServer {
@@ -21,10 +78,112 @@ impl IpcServer {
         Self {
             socket_path,
             shutdown_signal: Arc::new(Notify::new()),
+            control_handler: None,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            decision_queue: None,
+            loaded_decisions: Arc::new(AtomicUsize::new(0)),
+            metrics: None,
         }
     }
 
-    /// Start listening for connections. Each connection is handled in a spawned task.
+    /// A server for a team's supervisor, listening on `configured` or else
+    /// on the default socket path, the same one [`IpcClient::for_supervisor`]
+    /// connects to.
+    ///
+    /// [`IpcClient::for_supervisor`]: crate::ipc::socket_client::IpcClient::for_supervisor
+    pub fn for_supervisor(configured: Option<&Path>, team_id: Option<&str>) -> Self {
+        Self::new(crate::cascade::supervisor::supervisor_socket_path(
+            configured, team_id,
+        ))
+    }
+
+    /// The socket this server listens on.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Serve at most `max_concurrent` connections at once. Further
+    /// connections wait in the listen backlog until one closes. Requests
+    /// pipelined on one connection share the same limit.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Reject requests larger than `max_request_bytes`. The client is
+    /// answered with Ask and the connection closed.
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Keep the human-review queue across restarts: decisions saved at
+    /// `path` by a previous run are loaded now, and those still pending are
+    /// saved back when `serve` shuts down. Handlers get the queue from
+    /// `decision_queue`.
+    pub fn with_decision_queue(mut self, path: PathBuf) -> Result<Self> {
+        let queue = DecisionQueue::load(&path)?;
+        self.decision_queue = Some((Arc::new(queue), path));
+        Ok(self)
+    }
+
+    /// The queue set up by `with_decision_queue`.
+    pub fn decision_queue(&self) -> Option<Arc<DecisionQueue>> {
+        self.decision_queue.as_ref().map(|(queue, _)| queue.clone())
+    }
+
+    /// Report `loaded_decisions` in health replies. The caller keeps it up to
+    /// date as its caches load.
+    pub fn with_loaded_decisions(mut self, loaded_decisions: Arc<AtomicUsize>) -> Self {
+        self.loaded_decisions = loaded_decisions;
+        self
+    }
+
+    /// Report the counters `metrics` returns in health replies, typically
+    /// `CascadeRunner::metrics` of the runner answering requests.
+    pub fn with_metrics(mut self, metrics: MetricsSource) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Handle control messages with `handler`. Without one, control messages
+    /// are answered with an error.
+    pub fn with_control_handler<C>(mut self, handler: C) -> Self
+    where
+        C: Fn(IpcControl) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.control_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Like [`Self::serve`], but SIGINT or SIGTERM also shut the server
+    /// down, so a killed supervisor doesn't leave its socket behind.
+    pub async fn serve_with_signals<F>(&self, handler: F) -> Result<()>
+    where
+        F: Fn(IpcRequest) -> Pin<Box<dyn Future<Output = Result<IpcResponse>> + Send>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let shutdown = self.shutdown_signal.clone();
+        let signals = tokio::spawn(async move {
+            wait_for_termination().await;
+            eprintln!("hookwise: received shutdown signal");
+            shutdown.notify_one();
+        });
+        let result = self.serve(handler).await;
+        signals.abort();
+        result
+    }
+
+    /// Start listening for connections. Each connection is handled in a
+    /// spawned task, with at most `max_concurrent` connections and requests
+    /// running at once. On shutdown, requests in progress get
+    /// `SHUTDOWN_GRACE` to finish.
     pub async fn serve<F>(&self, handler: F) -> Result<()>
     where
         F: Fn(IpcRequest) -> Pin<Box<dyn Future<Output = Result<IpcResponse>> + Send>>
@@ -37,9 +196,12 @@ impl IpcServer {
             std::fs::remove_file(&self.socket_path)?;
         }
 
-        // Create parent directory if needed
+        // Create parent directory if needed, readable by the owner only
         if let Some(parent) = self.socket_path.parent() {
-            std::fs::create_dir_all(parent)?;
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(parent)?;
         }
 
         let listener =
@@ -50,6 +212,8 @@ impl IpcServer {
                     e
                 ),
             })?;
+        // Other local users must not be able to ask for, or answer, decisions
+        set_socket_permissions_0600(&self.socket_path)?;
 
         eprintln!(
             "hookwise: supervisor listening on {}",
@@ -57,18 +221,41 @@ impl IpcServer {
         );
 
         let handler = Arc::new(handler);
+        let health = Arc::new(HealthState {
+            started: Instant::now(),
+            queue: self.decision_queue(),
+            loaded_decisions: self.loaded_decisions.clone(),
+            metrics: self.metrics.clone(),
+        });
         let shutdown = self.shutdown_signal.clone();
+        let permits = Arc::new(Semaphore::new(self.max_concurrent));
 
         loop {
+            // Wait for a free slot before accepting, so excess clients queue
+            // in the kernel's backlog rather than as tasks here
+            let permit = tokio::select! {
+                permit = permits.clone().acquire_owned() => {
+                    permit.expect("connection semaphore is never closed")
+                }
+                _ = shutdown.notified() => {
+                    eprintln!("hookwise: supervisor shutting down");
+                    break;
+                }
+            };
             tokio::select! {
                 accept_result = listener.accept() => {
                     match accept_result {
                         Ok((stream, _addr)) => {
                             let handler = handler.clone();
+                            let control_handler = self.control_handler.clone();
+                            let health = health.clone();
+                            let max_request_bytes = self.max_request_bytes;
+                            let permits = permits.clone();
                             tokio::spawn(async move {
-                                if let Err(e) = handle_connection(stream, handler).await {
+                                if let Err(e) = handle_connection(stream, handler, control_handler, health, max_request_bytes, permits).await {
                                     eprintln!("hookwise: connection error: {}", e);
                                 }
+                                drop(permit);
                             });
                         }
                         Err(e) => {
@@ -83,8 +270,23 @@ impl IpcServer {
             }
         }
 
+        // Stop accepting, then wait for every permit to come back, which
+        // means every connection and every request on it has finished
+        drop(listener);
+        let all = u32::try_from(self.max_concurrent).unwrap_or(u32::MAX);
+        if tokio::time::timeout(SHUTDOWN_GRACE, permits.acquire_many(all))
+            .await
+            .is_err()
+        {
+            eprintln!("hookwise: requests still in flight after shutdown grace period");
+        }
+
         // Clean up socket file
         let _ = std::fs::remove_file(&self.socket_path);
+
+        if let Some((queue, path)) = &self.decision_queue {
+            queue.persist(path)?;
+        }
         Ok(())
     }
 
@@ -95,51 +297,296 @@ impl IpcServer {
     }
 }
 
-/// Handle a single client connection.
-async fn handle_connection<F>(stream: tokio::net::UnixStream, handler: Arc<F>) -> Result<()>
+/// Wait for SIGINT or SIGTERM.
+async fn wait_for_termination() {
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        Err(e) => {
+            eprintln!("hookwise: cannot listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+/// Set the socket file's permissions to 0600 (owner read/write only).
+fn set_socket_permissions_0600(path: &Path) -> Result<()> {
+    let perms = std::fs::Permissions::from_mode(0o600);
+    std::fs::set_permissions(path, perms).map_err(|e| HookwiseError::Ipc {
+        reason: format!(
+            "failed to restrict permissions on {}: {}",
+            path.display(),
+            e
+        ),
+    })
+}
+
+/// Handle a single client connection. The first byte selects the framing:
+/// `LENGTH_PREFIX_MAGIC` for length-prefixed frames, anything else for JSON
+/// lines. Messages are read until the client closes its end, each handled
+/// in its own task, so several requests can be in flight on one connection;
+/// responses are written as they complete and carry their request's `id`.
+/// The first request in flight runs on the connection's own permit, and each
+/// further one holds another from `permits`, so pipelined requests count
+/// against `max_concurrent` and the shutdown grace waits for them too.
+/// A request that is oversized or unreadable is answered with Ask and ends
+/// the connection, since the rest of the stream can't be trusted.
+async fn handle_connection<F>(
+    stream: tokio::net::UnixStream,
+    handler: Arc<F>,
+    control_handler: Option<ControlHandler>,
+    health: Arc<HealthState>,
+    max_request_bytes: usize,
+    permits: Arc<Semaphore>,
+) -> Result<()>
 where
     F: Fn(IpcRequest) -> Pin<Box<dyn Future<Output = Result<IpcResponse>> + Send>>
         + Send
         + Sync
         + 'static,
 {
-    let (reader, mut writer) = stream.into_split();
+    let (reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
     let mut buf_reader = BufReader::new(reader);
-    let mut line = String::new();
 
-    // Read request as a JSON line
-    buf_reader
-        .read_line(&mut line)
+    let first = buf_reader
+        .fill_buf()
         .await
         .map_err(|e| HookwiseError::Ipc {
             reason: format!("read failed: {}", e),
         })?;
+    let length_prefixed = first.first() == Some(&LENGTH_PREFIX_MAGIC);
+    if length_prefixed {
+        buf_reader.consume(1);
+    }
 
-    let request: IpcRequest =
-        serde_json::from_str(line.trim()).map_err(|e| HookwiseError::Ipc {
-            reason: format!("invalid request JSON: {}", e),
-        })?;
-
-    // Process request
-    let response = handler(request).await?;
+    // Finished requests are reaped while the next message is read, so a
+    // long-lived connection doesn't pile up completed tasks.
+    let mut in_flight = JoinSet::new();
+    let mut reading = Box::pin(read_next(buf_reader, length_prefixed, max_request_bytes));
+    let read_result = loop {
+        tokio::select! {
+            Some(result) = in_flight.join_next() => report_request_result(result),
+            (buf_reader, next) = &mut reading => {
+                let message = match next {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break Ok(()),
+                    Err(e) => break Err(e),
+                };
+                reading.set(read_next(buf_reader, length_prefixed, max_request_bytes));
+                let permit = request_permit(&permits, &mut in_flight).await;
+                let handler = handler.clone();
+                let control_handler = control_handler.clone();
+                let health = health.clone();
+                let writer = writer.clone();
+                in_flight.spawn(async move {
+                    let response_json =
+                        respond(message, handler.as_ref(), &control_handler, &health).await?;
+                    let mut writer = writer.lock().await;
+                    let written = write_message(&mut writer, &response_json, length_prefixed).await;
+                    drop(permit);
+                    written
+                });
+            }
+        }
+    };
 
-    // Write response as JSON
-    let response_json = serde_json::to_string(&response)?;
+    while let Some(result) = in_flight.join_next().await {
+        report_request_result(result);
+    }
+    if let Err(e) = &read_result {
+        let fallback = serde_json::to_string(&unreadable_request_response(e))?;
+        let mut writer = writer.lock().await;
+        write_message(&mut writer, &fallback, length_prefixed).await?;
+    }
     writer
-        .write_all(response_json.as_bytes())
+        .lock()
+        .await
+        .shutdown()
         .await
         .map_err(|e| HookwiseError::Ipc {
-            reason: format!("write failed: {}", e),
+            reason: format!("shutdown failed: {}", e),
         })?;
+
+    read_result
+}
+
+/// Log a request task that failed to answer.
+fn report_request_result(result: std::result::Result<Result<()>, tokio::task::JoinError>) {
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("hookwise: request error: {}", e),
+        Err(e) => eprintln!("hookwise: request task failed: {}", e),
+    }
+}
+
+/// A permit for one more request on a connection that already has some in
+/// flight, reaping those that finish while waiting. None once none are left,
+/// since the next request can then run on the connection's own permit.
+async fn request_permit(
+    permits: &Arc<Semaphore>,
+    in_flight: &mut JoinSet<Result<()>>,
+) -> Option<OwnedSemaphorePermit> {
+    while !in_flight.is_empty() {
+        tokio::select! {
+            permit = permits.clone().acquire_owned() => {
+                return Some(permit.expect("connection semaphore is never closed"));
+            }
+            Some(result) = in_flight.join_next() => report_request_result(result),
+        }
+    }
+    None
+}
+
+/// [`read_message`] on an owned reader, handing it back with the result so
+/// a pending read can be kept across `select!` iterations without losing
+/// buffered input.
+async fn read_next(
+    mut reader: BufReader<OwnedReadHalf>,
+    length_prefixed: bool,
+    max_bytes: usize,
+) -> (BufReader<OwnedReadHalf>, Result<Option<IpcMessage>>) {
+    let next = read_message(&mut reader, length_prefixed, max_bytes).await;
+    (reader, next)
+}
+
+/// Read the next message, or None once the client has closed its end.
+/// Reads at most `max_bytes` (plus the delimiter) of any one message.
+async fn read_message(
+    reader: &mut BufReader<OwnedReadHalf>,
+    length_prefixed: bool,
+    max_bytes: usize,
+) -> Result<Option<IpcMessage>> {
+    if length_prefixed {
+        let at_eof = reader
+            .fill_buf()
+            .await
+            .map_err(|e| HookwiseError::Ipc {
+                reason: format!("read failed: {}", e),
+            })?
+            .is_empty();
+        if at_eof {
+            return Ok(None);
+        }
+        let body = framing::read_frame(reader, max_bytes).await?;
+        return serde_json::from_slice(&body)
+            .map(Some)
+            .map_err(|e| HookwiseError::Ipc {
+                reason: format!("invalid request JSON: {}", e),
+            });
+    }
+
+    // Read request as a JSON line, skipping blank ones
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = (&mut *reader)
+            .take(max_bytes as u64 + 1)
+            .read_line(&mut line)
+            .await
+            .map_err(|e| HookwiseError::Ipc {
+                reason: format!("read failed: {}", e),
+            })?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if line.trim_end_matches('\n').len() > max_bytes {
+            return Err(HookwiseError::Ipc {
+                reason: format!("request exceeds {} bytes", max_bytes),
+            });
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    serde_json::from_str(line.trim())
+        .map(Some)
+        .map_err(|e| HookwiseError::Ipc {
+            reason: format!("invalid request JSON: {}", e),
+        })
+}
+
+/// The reply to a request that could not be read: not an answer to any
+/// particular request, so it carries no `id` and leaves the call to a human.
+fn unreadable_request_response(error: &HookwiseError) -> IpcResponse {
+    ask_response(None, format!("unreadable request: {}", error))
+}
+
+/// An Ask that leaves the call to a human, for when no decision was reached.
+fn ask_response(id: Option<u64>, reason: String) -> IpcResponse {
+    IpcResponse {
+        id,
+        decision: Decision::Ask,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Supervisor,
+            confidence: 0.0,
+            reason,
+            matched_key: None,
+            similarity_score: None,
+        },
+    }
+}
+
+/// Handle one message and serialize the reply.
+async fn respond<F>(
+    message: IpcMessage,
+    handler: &F,
+    control_handler: &Option<ControlHandler>,
+    health: &HealthState,
+) -> Result<String>
+where
+    F: Fn(IpcRequest) -> Pin<Box<dyn Future<Output = Result<IpcResponse>> + Send>>,
+{
+    match message {
+        IpcMessage::Request(request) => {
+            // A failed check is still answered, so the client waiting on
+            // this id isn't left to time out
+            let id = request.id;
+            let response = match handler(request).await {
+                Ok(response) => IpcResponse { id, ..response },
+                Err(e) => {
+                    eprintln!("hookwise: request error: {}", e);
+                    ask_response(id, format!("supervisor error: {}", e))
+                }
+            };
+            Ok(serde_json::to_string(&response)?)
+        }
+        IpcMessage::Control(control) => {
+            let result = match control_handler {
+                Some(control_handler) => control_handler(control).await,
+                None => Err(HookwiseError::Ipc {
+                    reason: "control messages are not supported by this server".into(),
+                }),
+            };
+            Ok(serde_json::to_string(&IpcAck {
+                ok: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            })?)
+        }
+        IpcMessage::Query(IpcQuery::Health) => Ok(serde_json::to_string(&health.report())?),
+    }
+}
+
+/// Write one reply in the connection's framing.
+async fn write_message(
+    writer: &mut OwnedWriteHalf,
+    response_json: &str,
+    length_prefixed: bool,
+) -> Result<()> {
+    if length_prefixed {
+        return framing::write_frame(writer, response_json.as_bytes()).await;
+    }
+    let mut line = String::with_capacity(response_json.len() + 1);
+    line.push_str(response_json);
+    line.push('\n');
     writer
-        .write_all(b"\n")
+        .write_all(line.as_bytes())
         .await
         .map_err(|e| HookwiseError::Ipc {
-            reason: format!("write newline failed: {}", e),
-        })?;
-    writer.shutdown().await.map_err(|e| HookwiseError::Ipc {
-        reason: format!("shutdown failed: {}", e),
-    })?;
-
-    Ok(())
+            reason: format!("write failed: {}", e),
+        })
 }
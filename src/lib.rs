@@ -26,6 +26,17 @@ pub enum Commands {
         /// Output format: claude (default) or gemini
         #[arg(long, default_value = "claude")]
         format: HookFormat,
+        /// Print to stderr which scopes' rules matched and which one won.
+        #[arg(long)]
+        explain: bool,
+        /// Report the decision on stderr but always allow the call. Nothing
+        /// is persisted and no human is asked.
+        #[arg(long)]
+        dry_run: bool,
+        /// With `--dry-run`, also append each computed decision to this
+        /// JSONL file.
+        #[arg(long, requires = "dry_run")]
+        audit_log: Option<std::path::PathBuf>,
     },
 
     /// Check if session is registered (user_prompt_submit / BeforeAgent hook).
@@ -45,6 +56,15 @@ pub enum Commands {
         task: Option<String>,
         #[arg(long)]
         prompt_file: Option<String>,
+        /// Who registered the session, for audit. Default: the current OS user.
+        #[arg(long)]
+        registered_by: Option<String>,
+        /// Metadata tag recorded on the session's decisions (repeatable).
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = crate::session::parse_tag)]
+        tags: Vec<(String, String)>,
+        /// Wait until a running socket supervisor has loaded the session.
+        #[arg(long)]
+        wait: bool,
     },
 
     /// Disable hookwise for a session.
@@ -59,29 +79,98 @@ pub enum Commands {
         session_id: String,
     },
 
+    /// Remove a session's registration, including any disabled state.
+    Unregister {
+        #[arg(long)]
+        session_id: String,
+    },
+
+    /// List registered and disabled sessions.
+    Sessions {
+        /// Print the sessions as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove registrations older than `session_ttl_secs`.
+    Prune,
+
     /// List pending permission decisions.
-    Queue,
+    Queue {
+        /// Truncate inputs to this many characters.
+        #[arg(long, default_value_t = 80, conflicts_with = "full")]
+        width: usize,
+        /// Print inputs in full, without truncation.
+        #[arg(long)]
+        full: bool,
+        /// Keep running, printing decisions as they are queued and resolved.
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between polls of the queue in `--watch` mode.
+        #[arg(long, default_value_t = 1, requires = "watch")]
+        interval: u64,
+    },
 
-    /// Approve a pending decision.
+    /// Approve a pending decision, or every one matching a filter.
     Approve {
-        id: String,
+        /// Pending decision id. Omit to select by `--all`, `--role` or `--tool`.
+        #[arg(
+            required_unless_present_any = ["all", "role", "tool"],
+            conflicts_with_all = ["all", "role", "tool"]
+        )]
+        id: Option<String>,
         #[arg(long)]
         always_ask: bool,
         #[arg(long)]
         add_rule: bool,
         #[arg(long, default_value = "project")]
         scope: String,
+        /// Also allow future inputs matching this glob (e.g. "npm install *").
+        #[arg(long, conflicts_with_all = ["all", "role", "tool"])]
+        generalize: Option<String>,
+        /// Allow this call only, without caching or writing a rule.
+        #[arg(long, conflicts_with_all = ["always_ask", "add_rule", "generalize"])]
+        once: bool,
+        /// Resolve every pending decision.
+        #[arg(long, conflicts_with_all = ["role", "tool"])]
+        all: bool,
+        /// Resolve the pending decisions of this role.
+        #[arg(long)]
+        role: Option<String>,
+        /// Resolve the pending decisions for this tool.
+        #[arg(long)]
+        tool: Option<String>,
+        /// Don't ask for confirmation before resolving with `--all`.
+        #[arg(long)]
+        yes: bool,
     },
 
-    /// Deny a pending decision.
+    /// Deny a pending decision, or every one matching a filter.
     Deny {
-        id: String,
+        /// Pending decision id. Omit to select by `--all`, `--role` or `--tool`.
+        #[arg(
+            required_unless_present_any = ["all", "role", "tool"],
+            conflicts_with_all = ["all", "role", "tool"]
+        )]
+        id: Option<String>,
         #[arg(long)]
         always_ask: bool,
         #[arg(long)]
         add_rule: bool,
         #[arg(long, default_value = "project")]
         scope: String,
+        /// Resolve every pending decision.
+        #[arg(long, conflicts_with_all = ["role", "tool"])]
+        all: bool,
+        /// Resolve the pending decisions of this role.
+        #[arg(long)]
+        role: Option<String>,
+        /// Resolve the pending decisions for this tool.
+        #[arg(long)]
+        tool: Option<String>,
+        /// Don't ask for confirmation before resolving with `--all`.
+        #[arg(long)]
+        yes: bool,
     },
 
     /// Rebuild vector indexes from rules.
@@ -97,6 +186,24 @@ pub enum Commands {
         all: bool,
     },
 
+    /// Collapse duplicate decisions for the same key to the newest one.
+    Compact {
+        /// Scope to compact (default: every scope).
+        #[arg(long)]
+        scope: Option<String>,
+    },
+
+    /// Delete rules no call has matched for a while.
+    PruneRules {
+        /// How long a rule may go unmatched, e.g. `90d`, `12w` or `36h`.
+        /// Rules never matched count from when they were made.
+        #[arg(long, value_name = "AGE", value_parser = crate::cli::build::parse_age)]
+        unused_for: chrono::Duration,
+        /// Scope to prune (default: every scope).
+        #[arg(long)]
+        scope: Option<String>,
+    },
+
     /// Set an explicit permission override.
     Override {
         #[arg(long)]
@@ -115,13 +222,86 @@ pub enum Commands {
         ask: bool,
         #[arg(long, default_value = "project")]
         scope: String,
+        /// Pin the rule: learned and narrower-scope decisions never replace it.
+        #[arg(long)]
+        immutable: bool,
+    },
+
+    /// Seed decisions from a YAML file of rules (idempotent).
+    Seed {
+        /// Path to the seed file.
+        file: String,
     },
 
     /// Stream decisions in real time.
-    Monitor,
+    Monitor {
+        /// Output format: pretty (default), json (one record per line), or compact
+        #[arg(long, default_value = "pretty")]
+        format: cli::monitor::MonitorFormat,
+    },
 
     /// Show cache hit rates and decision distribution.
-    Stats,
+    Stats {
+        /// Only count decisions carrying this session tag (repeatable).
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = crate::session::parse_tag)]
+        tags: Vec<(String, String)>,
+        /// Print the counts as JSON.
+        #[arg(long)]
+        json: bool,
+        /// Only count decisions at this scope (default: every scope).
+        #[arg(long)]
+        scope: Option<String>,
+    },
+
+    /// Write every decision at a scope to a portable JSON bundle.
+    Export {
+        #[arg(long, default_value = "project")]
+        scope: String,
+        /// Bundle file to write.
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+
+    /// Load the decisions in a bundle written by `export` into a scope.
+    Import {
+        /// Bundle file to read.
+        file: std::path::PathBuf,
+        #[arg(long, default_value = "project")]
+        scope: String,
+        /// Keep the bundle's pinned decisions pinned. Without this they are
+        /// imported unpinned.
+        #[arg(long)]
+        keep_pins: bool,
+    },
+
+    /// Inspect the roles configured in roles.yml.
+    Roles {
+        #[command(subcommand)]
+        command: RolesCommand,
+    },
+
+    /// Re-run a past decision against the current rules (read-only).
+    Replay {
+        /// Decision id (or a unique prefix), as printed by `hookwise monitor`.
+        id: String,
+    },
+
+    /// Trace a tool call through the cascade, tier by tier (read-only).
+    Explain {
+        /// Tool name, e.g. Bash or Write.
+        #[arg(long)]
+        tool: String,
+        /// The tool input: a JSON object, or the command (Bash), pattern
+        /// (Glob/Grep) or content (Write/Edit).
+        #[arg(long)]
+        input: String,
+        /// Role from roles.yml to evaluate as.
+        #[arg(long)]
+        role: String,
+        /// Target file path, for file tools.
+        #[arg(long)]
+        file: Option<String>,
+    },
 
     /// Pre-commit secret scan on staged files.
     Scan {
@@ -130,14 +310,49 @@ pub enum Commands {
         path: Option<String>,
     },
 
+    /// Report rule file lines that could not be parsed and were quarantined.
+    Doctor,
+
     /// Initialize .hookwise/ in the current repo.
     Init,
 
+    /// Check that the supervisor is up and show what it is running.
+    Ping,
+
+    /// Rename a project's legacy .captain-hook/ directory to .hookwise/.
+    Migrate {
+        /// Move .hookwise/ back to .captain-hook/ instead.
+        #[arg(long)]
+        to_legacy: bool,
+        /// Replace the destination even if it is not empty.
+        #[arg(long)]
+        force: bool,
+    },
+
     /// View/edit global configuration.
-    Config,
+    Config {
+        /// Show every setting's effective value after merging defaults, the
+        /// global config and the project policy, with where it came from.
+        #[arg(long)]
+        effective: bool,
+        /// Print the effective configuration as JSON.
+        #[arg(long, requires = "effective")]
+        json: bool,
+    },
 
-    /// Pull latest org-level rules.
-    Sync,
+    /// Share project rules through git: commit local rule changes, merge
+    /// the remote's, and push.
+    Sync {
+        /// Only fetch and merge the remote's rules.
+        #[arg(long, conflicts_with = "push")]
+        pull: bool,
+        /// Only push local rules.
+        #[arg(long)]
+        push: bool,
+        /// Git remote to sync with.
+        #[arg(long, default_value = "origin")]
+        remote: String,
+    },
 
     /// Start MCP server over stdio (for Gemini CLI extension).
     McpServer,
@@ -149,3 +364,19 @@ pub enum Commands {
         check: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum RolesCommand {
+    /// List roles with their descriptions.
+    List,
+
+    /// Show a role's path policy and check that its globs compile.
+    Show {
+        /// Role name.
+        name: String,
+    },
+
+    /// Check roles.yml and policy.yml, reporting every error. Exits non-zero
+    /// if any are found.
+    Validate,
+}
@@ -1,11 +1,12 @@
 use aho_corasick::AhoCorasick;
 
-use super::Sanitizer;
+use super::{RedactionStyle, Sanitizer};
 
 /// Layer 1: Literal prefix matching via aho-corasick.
 pub struct AhoCorasickSanitizer {
     automaton: AhoCorasick,
     prefixes: Vec<String>,
+    style: RedactionStyle,
 }
 
 impl AhoCorasickSanitizer {
@@ -15,65 +16,70 @@ impl AhoCorasickSanitizer {
         Self {
             automaton,
             prefixes,
+            style: RedactionStyle::default(),
         }
     }
 
     /// Default known secret prefixes.
     pub fn default_prefixes() -> Vec<String> {
-        vec![
-            // Anthropic
-            "sk-ant-".into(),
-            // OpenAI
-            "sk-proj-".into(),
-            // GitHub
-            "ghp_".into(),
-            "gho_".into(),
-            "ghs_".into(),
-            "github_pat_".into(),
-            // AWS
-            "AKIA".into(),
-            "ASIA".into(),
-            // Slack
-            "xoxb-".into(),
-            "xoxp-".into(),
-            "xoxs-".into(),
-            "xoxa-".into(),
-            // GitLab
-            "glpat-".into(),
-            "glsa-".into(),
-            // Package registries
-            "npm_".into(),
-            "pypi-".into(),
-            // Age encryption
-            "AGE-SECRET-KEY-".into(),
-            // PEM keys
-            "-----BEGIN".into(),
-            "PRIVATE KEY".into(),
-            // Stripe
-            "whsec_".into(),
-            "sk_live_".into(),
-            "sk_test_".into(),
-            "rk_live_".into(),
-            "rk_test_".into(),
-            // SendGrid
-            "SG.".into(),
-            // DigitalOcean
-            "dop_v1_".into(),
-            // New Relic
-            "nrk-".into(),
-            "NRAK-".into(),
-            // Hugging Face
-            "hf_".into(),
-            // HashiCorp Vault
-            "vlt_".into(),
-            "hvs.".into(),
-            // 1Password
-            "op_".into(),
-            // Google
-            "AIzaSy".into(),
-            "ya29.".into(),
-        ]
+        KNOWN_PREFIXES
+            .iter()
+            .map(|(prefix, _)| prefix.to_string())
+            .collect()
     }
+
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// Known secret prefixes and the kind of secret each one starts, as shown
+/// by typed placeholders.
+const KNOWN_PREFIXES: &[(&str, &str)] = &[
+    ("sk-ant-", "anthropic-key"),
+    ("sk-proj-", "openai-key"),
+    ("ghp_", "github-token"),
+    ("gho_", "github-token"),
+    ("ghs_", "github-token"),
+    ("github_pat_", "github-token"),
+    ("AKIA", "aws-key"),
+    ("ASIA", "aws-key"),
+    ("xoxb-", "slack-token"),
+    ("xoxp-", "slack-token"),
+    ("xoxs-", "slack-token"),
+    ("xoxa-", "slack-token"),
+    ("glpat-", "gitlab-token"),
+    ("glsa-", "gitlab-token"),
+    ("npm_", "npm-token"),
+    ("pypi-", "pypi-token"),
+    ("AGE-SECRET-KEY-", "age-key"),
+    ("-----BEGIN", "private-key"),
+    ("PRIVATE KEY", "private-key"),
+    ("whsec_", "stripe-key"),
+    ("sk_live_", "stripe-key"),
+    ("sk_test_", "stripe-key"),
+    ("rk_live_", "stripe-key"),
+    ("rk_test_", "stripe-key"),
+    ("SG.", "sendgrid-key"),
+    ("dop_v1_", "digitalocean-token"),
+    ("nrk-", "newrelic-key"),
+    ("NRAK-", "newrelic-key"),
+    ("hf_", "huggingface-token"),
+    ("vlt_", "vault-token"),
+    ("hvs.", "vault-token"),
+    ("op_", "1password-token"),
+    ("AIzaSy", "google-key"),
+    ("ya29.", "google-token"),
+];
+
+/// The kind of secret a prefix starts. Custom prefixes are just "prefix".
+fn prefix_kind(prefix: &str) -> &'static str {
+    KNOWN_PREFIXES
+        .iter()
+        .find(|(known, _)| *known == prefix)
+        .map_or("prefix", |(_, kind)| kind)
 }
 
 impl Sanitizer for AhoCorasickSanitizer {
@@ -85,6 +91,7 @@ impl Sanitizer for AhoCorasickSanitizer {
         let mut result = input.to_string();
         // Find all matches and collect them in reverse order to replace from end to start.
         let mut matches: Vec<(usize, usize)> = Vec::new();
+        let mut kinds: Vec<(usize, &str)> = Vec::new();
 
         for mat in self.automaton.find_iter(input) {
             let start = mat.start();
@@ -112,20 +119,31 @@ impl Sanitizer for AhoCorasickSanitizer {
             let prefix_len = mat.end() - mat.start();
             if token_end > prefix_len {
                 matches.push((start, end));
+                kinds.push((start, prefix_kind(&self.prefixes[mat.pattern().as_usize()])));
             }
         }
 
         // Deduplicate overlapping ranges and apply replacements in reverse order.
-        matches.sort_by(|a, b| a.0.cmp(&b.0));
+        // A merged range is typed by the prefix that starts it.
+        matches.sort_by_key(|a| a.0);
         let merged = merge_ranges(&matches);
 
         for &(start, end) in merged.iter().rev() {
-            result.replace_range(start..end, "<REDACTED>");
+            let kind = kinds
+                .iter()
+                .find(|&&(s, _)| s == start)
+                .map_or("prefix", |&(_, kind)| kind);
+            let placeholder = self.style.placeholder(kind, &input[start..end]);
+            result.replace_range(start..end, &placeholder);
         }
 
         result
     }
 
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+
     fn name(&self) -> &str {
         "aho-corasick"
     }
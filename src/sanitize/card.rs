@@ -0,0 +1,194 @@
+use super::{RedactionStyle, Sanitizer};
+
+/// Fewest and most digits in a payment card number.
+const MIN_DIGITS: usize = 13;
+const MAX_DIGITS: usize = 19;
+
+/// Digit groups of separated card numbers: most cards, and Amex.
+const GROUPINGS: [&[usize]; 2] = [&[4, 4, 4, 4], &[4, 6, 5]];
+
+/// Payment card numbers (PANs), validated with the Luhn checksum.
+///
+/// Finds 13-19 contiguous digits, or digits in the groupings printed on
+/// cards, split by single spaces or dashes: 4-4-4-4 (`4111 1111 1111 1111`)
+/// and 4-6-5 (`3782-822463-10005`). Other digit groups next to a card (an
+/// expiry date, a quantity) don't hide it, since each card-shaped stretch of
+/// a run is checked on its own. Only digits that pass the Luhn check are
+/// redacted, so order numbers, timestamps, and phone numbers pass through.
+pub struct CreditCardSanitizer {
+    style: RedactionStyle,
+}
+
+impl CreditCardSanitizer {
+    pub fn new() -> Self {
+        Self {
+            style: RedactionStyle::default(),
+        }
+    }
+
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Byte ranges of the card numbers in `input`.
+    fn find_cards(input: &str) -> Vec<(usize, usize)> {
+        let bytes = input.as_bytes();
+        let len = bytes.len();
+        let mut spans = Vec::new();
+        let mut i = 0;
+
+        while i < len {
+            // A run starts at a digit that is not glued to a word or number
+            if !bytes[i].is_ascii_digit() || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+                i += 1;
+                continue;
+            }
+            // Byte ranges of the run's digit groups
+            let mut groups = Vec::new();
+            loop {
+                let start = i;
+                while i < len && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                groups.push((start, i));
+                if i + 1 < len
+                    && (bytes[i] == b' ' || bytes[i] == b'-')
+                    && bytes[i + 1].is_ascii_digit()
+                {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            // A last group glued to a word can't be part of a card
+            if i < len && bytes[i].is_ascii_alphanumeric() {
+                groups.pop();
+            }
+
+            // Take each card-shaped stretch of groups that passes Luhn
+            let mut g = 0;
+            while g < groups.len() {
+                let card = GROUPINGS
+                    .iter()
+                    .map(|grouping| grouping.len())
+                    .chain([1])
+                    .filter(|&n| g + n <= groups.len())
+                    .find(|&n| is_card(input, &groups[g..g + n]));
+                match card {
+                    Some(n) => {
+                        spans.push((groups[g].0, groups[g + n - 1].1));
+                        g += n;
+                    }
+                    None => g += 1,
+                }
+            }
+            // Skip the rest of a word the run ran into rather than rescanning it
+            while i < len && bytes[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+        }
+
+        spans
+    }
+}
+
+impl Default for CreditCardSanitizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sanitizer for CreditCardSanitizer {
+    fn sanitize(&self, input: &str) -> String {
+        let mut result = input.to_string();
+        for (start, end) in Self::find_cards(input).into_iter().rev() {
+            let placeholder = self.style.placeholder("credit-card", &input[start..end]);
+            result.replace_range(start..end, &placeholder);
+        }
+        result
+    }
+
+    fn name(&self) -> &str {
+        "credit-card"
+    }
+
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+}
+
+/// Whether the digit groups at byte ranges `groups` of `input` are laid out
+/// like a card number and pass the Luhn check.
+fn is_card(input: &str, groups: &[(usize, usize)]) -> bool {
+    let lengths: Vec<usize> = groups.iter().map(|(start, end)| end - start).collect();
+    let card_shaped = match lengths.as_slice() {
+        [single] => (MIN_DIGITS..=MAX_DIGITS).contains(single),
+        grouped => GROUPINGS.contains(&grouped),
+    };
+    let digits: String = groups
+        .iter()
+        .map(|&(start, end)| &input[start..end])
+        .collect();
+    card_shaped && luhn_valid(&digits)
+}
+
+/// Whether a string of ASCII digits passes the Luhn checksum.
+fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = u32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_luhn() {
+        assert!(luhn_valid("4111111111111111"));
+        assert!(luhn_valid("378282246310005"));
+        assert!(!luhn_valid("4111111111111112"));
+    }
+
+    #[test]
+    fn test_digits_inside_words_are_ignored() {
+        let san = CreditCardSanitizer::new();
+        let input = "id4111111111111111 4111111111111111x";
+        assert_eq!(san.sanitize(input), input);
+    }
+
+    #[test]
+    fn test_only_card_groupings_are_redacted() {
+        let san = CreditCardSanitizer::new();
+        assert_eq!(san.sanitize("3782-822463-10005"), "<REDACTED>");
+        // Luhn-valid digits, but not grouped like a card
+        let input = "ids 41 11 11 11 11 11 11 11 and 411-111-111-111-1111";
+        assert_eq!(san.sanitize(input), input);
+    }
+
+    #[test]
+    fn test_over_long_run_is_ignored() {
+        let san = CreditCardSanitizer::new();
+        // 4111111111111111 followed by more digits: 20 digits in total
+        let input = "ref 41111111111111110000";
+        assert_eq!(san.sanitize(input), input);
+    }
+}
@@ -1,4 +1,4 @@
-use super::Sanitizer;
+use super::{RedactionStyle, Sanitizer};
 
 /// Pre-processing sanitizer that detects base64 and URL-encoded secrets.
 ///
@@ -7,64 +7,98 @@ use super::Sanitizer;
 ///    the decoded content against a sub-sanitizer.
 /// 2. URL-decodes %XX sequences and re-checks.
 ///
+/// Decoded content that is itself encoded (base64 of base64, URL-encoded
+/// then base64-encoded, ...) is decoded again, up to `max_depth` layers.
 /// If the decoded content would be redacted by the sub-sanitizer, the
 /// original encoded string is redacted.
 pub struct EncodingSanitizer {
     /// The sub-sanitizer used to check decoded content.
     /// Typically the aho-corasick + regex layers (not entropy, to avoid false positives).
     inner: Vec<Box<dyn Sanitizer>>,
+    /// Most layers of encoding unwrapped from one token. Default: 3.
+    max_depth: usize,
+    style: RedactionStyle,
 }
 
+/// Default number of encoding layers unwrapped from one token.
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Base64 tokens that would decode to more than this many bytes are not
+/// inspected, bounding the work a single token can cause.
+const MAX_DECODED_LEN: usize = 64 * 1024;
+
 impl EncodingSanitizer {
     pub fn new(inner: Vec<Box<dyn Sanitizer>>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            max_depth: DEFAULT_MAX_DEPTH,
+            style: RedactionStyle::default(),
+        }
+    }
+
+    /// Unwrap at most `max_depth` layers of encoding (at least one).
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.max(1);
+        self
     }
 
-    /// Check if decoded content would be flagged by any inner sanitizer.
-    fn would_redact(&self, decoded: &str) -> bool {
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Check if decoded content would be flagged by any inner sanitizer,
+    /// either as is or once its own encoding is unwrapped. `depth` is the
+    /// number of layers already decoded to get `decoded`.
+    fn would_redact(&self, decoded: &str, depth: usize) -> bool {
         for sanitizer in &self.inner {
             let result = sanitizer.sanitize(decoded);
             if result != decoded {
                 return true;
             }
         }
-        false
-    }
+        if depth >= self.max_depth {
+            return false;
+        }
 
-    /// Detect and check base64 strings in the input.
-    /// Looks for tokens of 40+ chars matching [A-Za-z0-9+/=].
-    fn check_base64(&self, input: &str) -> Vec<(usize, usize)> {
-        let mut redactions = Vec::new();
-        let bytes = input.as_bytes();
-        let len = bytes.len();
-        let mut i = 0;
-
-        while i < len {
-            // Find start of a potential base64 token
-            if is_base64_char(bytes[i]) {
-                let start = i;
-                while i < len && is_base64_char(bytes[i]) {
-                    i += 1;
-                }
-                let token = &input[start..i];
-                if token.len() >= 40 {
-                    // Attempt base64 decode
-                    if let Some(decoded_bytes) = base64_decode(token) {
-                        if let Ok(decoded_str) = String::from_utf8(decoded_bytes) {
-                            if self.would_redact(&decoded_str) {
-                                redactions.push((start, i));
-                            }
-                        }
-                    }
-                }
-            } else {
-                i += 1;
+        for alphabet in [Alphabet::Standard, Alphabet::UrlSafe] {
+            if base64_tokens(decoded, alphabet)
+                .iter()
+                .any(|(_, _, inner)| self.would_redact(inner, depth + 1))
+            {
+                return true;
+            }
+        }
+        if decoded.contains('%') {
+            let unescaped = url_decode(decoded);
+            if unescaped != decoded && self.would_redact(&unescaped, depth + 1) {
+                return true;
             }
         }
+        false
+    }
 
+    /// Detect and check base64 strings in the input, in both the standard
+    /// and the URL-safe alphabet.
+    fn check_base64(&self, input: &str) -> Vec<(usize, usize)> {
+        let mut redactions = self.scan_base64(input, Alphabet::Standard);
+        redactions.extend(self.scan_base64(input, Alphabet::UrlSafe));
         redactions
     }
 
+    /// Looks for tokens of 40+ chars in the alphabet (e.g. [A-Za-z0-9+/=]).
+    /// `-` and `_` are everywhere in shell text, so a URL-safe token is only
+    /// considered if it uses them, and only redacted if it decodes to UTF-8
+    /// that an inner sanitizer would redact.
+    fn scan_base64(&self, input: &str, alphabet: Alphabet) -> Vec<(usize, usize)> {
+        base64_tokens(input, alphabet)
+            .into_iter()
+            .filter(|(_, _, decoded)| self.would_redact(decoded, 1))
+            .map(|(start, end, _)| (start, end))
+            .collect()
+    }
+
     /// Find spans in the input that contain URL-encoded secret prefixes.
     /// We re-check token-by-token to only redact the relevant tokens.
     fn find_url_encoded_spans(&self, input: &str) -> Vec<(usize, usize)> {
@@ -78,7 +112,7 @@ impl EncodingSanitizer {
 
             if segment.contains('%') {
                 let decoded = url_decode(segment);
-                if decoded != segment && self.would_redact(&decoded) {
+                if decoded != segment && self.would_redact(&decoded, 1) {
                     redactions.push((seg_start, seg_end));
                 }
             }
@@ -107,24 +141,85 @@ impl Sanitizer for EncodingSanitizer {
 
         // Apply redactions in reverse order
         for &(start, end) in merged.iter().rev() {
-            result.replace_range(start..end, "<REDACTED>");
+            let placeholder = self.style.placeholder("encoded", &input[start..end]);
+            result.replace_range(start..end, &placeholder);
         }
 
         result
     }
 
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+
     fn name(&self) -> &str {
         "encoding"
     }
 }
 
-fn is_base64_char(b: u8) -> bool {
-    b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'='
+/// The two base64 alphabets. They differ only in the last two symbols:
+/// `+`/`/` in the standard one, `-`/`_` in the URL-safe one (RFC 4648 §5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Alphabet {
+    Standard,
+    UrlSafe,
 }
 
-/// Simple base64 decoder (standard alphabet with padding).
-/// Returns None if the input is not valid base64.
-fn base64_decode(input: &str) -> Option<Vec<u8>> {
+impl Alphabet {
+    /// The symbols for values 62 and 63.
+    fn symbols(self) -> (u8, u8) {
+        match self {
+            Alphabet::Standard => (b'+', b'/'),
+            Alphabet::UrlSafe => (b'-', b'_'),
+        }
+    }
+}
+
+/// Base64 tokens of 40+ chars in `input` that decode to UTF-8, as
+/// `(start, end, decoded)`. Tokens decoding to more than [`MAX_DECODED_LEN`]
+/// bytes are skipped.
+fn base64_tokens(input: &str, alphabet: Alphabet) -> Vec<(usize, usize, String)> {
+    let mut tokens = Vec::new();
+    let bytes = input.as_bytes();
+    let len = bytes.len();
+    let mut i = 0;
+
+    while i < len {
+        // Find start of a potential base64 token
+        if !is_base64_char(bytes[i], alphabet) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < len && is_base64_char(bytes[i], alphabet) {
+            i += 1;
+        }
+        let token = &input[start..i];
+        if alphabet == Alphabet::UrlSafe && !token.contains(['-', '_']) {
+            // Plain alphanumerics are the standard scan's job
+            continue;
+        }
+        if token.len() < 40 || token.len() / 4 * 3 > MAX_DECODED_LEN {
+            continue;
+        }
+        if let Some(decoded) = base64_decode(token, alphabet) {
+            if let Ok(decoded) = String::from_utf8(decoded) {
+                tokens.push((start, i, decoded));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_base64_char(b: u8, alphabet: Alphabet) -> bool {
+    let (s62, s63) = alphabet.symbols();
+    b.is_ascii_alphanumeric() || b == s62 || b == s63 || b == b'='
+}
+
+/// Simple base64 decoder (with or without padding).
+/// Returns None if the input is not valid base64 in the given alphabet.
+pub(super) fn base64_decode(input: &str, alphabet: Alphabet) -> Option<Vec<u8>> {
     // Strip padding for length check
     let stripped = input.trim_end_matches('=');
 
@@ -132,28 +227,36 @@ fn base64_decode(input: &str) -> Option<Vec<u8>> {
     // Validate all chars are valid base64
     if !stripped
         .bytes()
-        .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+        .all(|b| b != b'=' && is_base64_char(b, alphabet))
     {
         return None;
     }
 
+    // URL-safe encoders usually drop the padding; restore it so the last
+    // group is decoded too.
+    let mut padded = stripped.to_string();
+    while padded.len() % 4 > 1 {
+        padded.push('=');
+    }
+    let input = padded.as_str();
+
     // Use a simple lookup table approach
     let mut output = Vec::with_capacity(input.len() * 3 / 4);
     let bytes = input.as_bytes();
     let mut i = 0;
 
     while i + 3 < bytes.len() {
-        let a = b64_val(bytes[i])?;
-        let b = b64_val(bytes[i + 1])?;
+        let a = b64_val(bytes[i], alphabet)?;
+        let b = b64_val(bytes[i + 1], alphabet)?;
         let c = if bytes[i + 2] == b'=' {
             0
         } else {
-            b64_val(bytes[i + 2])?
+            b64_val(bytes[i + 2], alphabet)?
         };
         let d = if bytes[i + 3] == b'=' {
             0
         } else {
-            b64_val(bytes[i + 3])?
+            b64_val(bytes[i + 3], alphabet)?
         };
 
         output.push((a << 2) | (b >> 4));
@@ -170,14 +273,15 @@ fn base64_decode(input: &str) -> Option<Vec<u8>> {
     Some(output)
 }
 
-fn b64_val(b: u8) -> Option<u8> {
+fn b64_val(b: u8, alphabet: Alphabet) -> Option<u8> {
+    let (s62, s63) = alphabet.symbols();
     match b {
         b'A'..=b'Z' => Some(b - b'A'),
         b'a'..=b'z' => Some(b - b'a' + 26),
         b'0'..=b'9' => Some(b - b'0' + 52),
-        b'+' => Some(62),
-        b'/' => Some(63),
         b'=' => Some(0),
+        _ if b == s62 => Some(62),
+        _ if b == s63 => Some(63),
         _ => None,
     }
 }
@@ -267,6 +371,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_url_safe_base64_encoded_secret_detected() {
+        let san = make_encoding_sanitizer();
+        // "sk-ant-api03-abc>>>def???ghi~~~jkl" via `base64 -w0 | tr '+/' '-_'`
+        let input = "echo c2stYW50LWFwaTAzLWFiYz4-PmRlZj8_P2doaX5-fmprbA== | base64 -d";
+        assert_eq!(san.sanitize(input), "echo <REDACTED> | base64 -d");
+
+        // The same, with the padding stripped
+        let input = "echo c2stYW50LWFwaTAzLWFiYz4-PmRlZj8_P2doaX5-fmprbA | base64 -d";
+        assert_eq!(san.sanitize(input), "echo <REDACTED> | base64 -d");
+    }
+
+    #[test]
+    fn test_dashed_shell_text_not_redacted() {
+        let san = make_encoding_sanitizer();
+        let input = "cargo test --workspace --all-targets --no-fail-fast -- --test-threads_1";
+        assert_eq!(san.sanitize(input), input);
+    }
+
+    #[test]
+    fn test_double_base64_encoded_secret_detected() {
+        let san = make_encoding_sanitizer();
+        // "sk-proj-AbCdEf1234567890GhIjKl" base64-encoded twice
+        let input =
+            "echo YzJzdGNISnZhaTFCWWtOa1JXWXhNak0wTlRZM09Ea3dSMmhKYWt0cw== | base64 -d | base64 -d";
+        assert_eq!(
+            san.sanitize(input),
+            "echo <REDACTED> | base64 -d | base64 -d"
+        );
+
+        // Not found when only one layer is unwrapped
+        let shallow = make_encoding_sanitizer().with_max_depth(1);
+        assert_eq!(shallow.sanitize(input), input);
+    }
+
+    #[test]
+    fn test_url_encoded_then_base64_encoded_secret_detected() {
+        let san = make_encoding_sanitizer();
+        // "sk%2Dproj%2DAbCdEf1234567890GhIjKl" base64-encoded
+        let input = "echo c2slMkRwcm9qJTJEQWJDZEVmMTIzNDU2Nzg5MEdoSWpLbA==";
+        assert_eq!(san.sanitize(input), "echo <REDACTED>");
+    }
+
     #[test]
     fn test_normal_base64_not_redacted() {
         let san = make_encoding_sanitizer();
@@ -1,4 +1,6 @@
-use super::Sanitizer;
+use std::ops::Range;
+
+use super::{RedactionStyle, Sanitizer};
 
 /// Layer 3: Shannon entropy detection for unknown secret formats.
 pub struct EntropySanitizer {
@@ -6,16 +8,51 @@ pub struct EntropySanitizer {
     pub min_length: usize,
     /// Minimum Shannon entropy to flag. Default: 4.0.
     pub min_entropy: f64,
+    /// Minimum number of character classes (upper, lower, digit, symbol) a
+    /// token must mix to be flagged. Default: 2.
+    pub min_char_classes: usize,
+    /// Leave pure-hex tokens of digest length (40 for git SHAs, 64 for
+    /// sha256) alone unless they are assigned to a secret-looking name such
+    /// as `api_key=`. Default: true.
+    pub skip_hex_digests: bool,
+    style: RedactionStyle,
 }
 
+/// Lengths of the hex digests `skip_hex_digests` leaves alone.
+const HEX_DIGEST_LENGTHS: [usize; 2] = [40, 64];
+
+/// Name fragments that mark an assignment as holding a secret.
+const SECRET_NAME_HINTS: [&str; 6] = ["secret", "token", "key", "pass", "auth", "cred"];
+
 impl EntropySanitizer {
     pub fn new(min_length: usize, min_entropy: f64) -> Self {
         Self {
             min_length,
             min_entropy,
+            min_char_classes: 2,
+            skip_hex_digests: true,
+            style: RedactionStyle::default(),
         }
     }
 
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Whether a token should be redacted. `secret_name` is true when the
+    /// token is assigned to a secret-looking name.
+    fn flags(&self, token: &str, secret_name: bool) -> bool {
+        if token.len() < self.min_length || Self::shannon_entropy(token) <= self.min_entropy {
+            return false;
+        }
+        if char_classes(token) < self.min_char_classes {
+            return false;
+        }
+        !(self.skip_hex_digests && !secret_name && is_hex_digest(token))
+    }
+
     /// Calculate Shannon entropy of a string.
     fn shannon_entropy(s: &str) -> f64 {
         if s.is_empty() {
@@ -38,6 +75,18 @@ impl EntropySanitizer {
 
 impl Sanitizer for EntropySanitizer {
     fn sanitize(&self, input: &str) -> String {
+        self.sanitize_after(input, &[])
+    }
+
+    fn sanitize_after(&self, input: &str, redacted: &[Range<usize>]) -> String {
+        // An earlier layer already redacted part of a token that overlaps
+        // one of its placeholders
+        let seen = |start: usize, end: usize| {
+            redacted
+                .iter()
+                .any(|range| range.start < end && start < range.end)
+        };
+
         // Look for tokens after '=' or ':' that are long and high-entropy.
         let mut result = input.to_string();
         let mut replacements: Vec<(usize, usize)> = Vec::new();
@@ -49,6 +98,7 @@ impl Sanitizer for EntropySanitizer {
         while i < len {
             // Look for '=' or ':' delimiters
             if bytes[i] == b'=' || bytes[i] == b':' {
+                let secret_name = is_secret_name(name_before(input, i));
                 i += 1;
                 // Skip optional whitespace after delimiter
                 while i < len && bytes[i] == b' ' {
@@ -80,11 +130,8 @@ impl Sanitizer for EntropySanitizer {
                 let token_end = i;
                 let token = &input[token_start..token_end];
 
-                if token.len() >= self.min_length {
-                    let entropy = Self::shannon_entropy(token);
-                    if entropy > self.min_entropy {
-                        replacements.push((token_start, token_end));
-                    }
+                if self.flags(token, secret_name) && !seen(token_start, token_end) {
+                    replacements.push((token_start, token_end));
                 }
             } else {
                 i += 1;
@@ -112,16 +159,20 @@ impl Sanitizer for EntropySanitizer {
                 let token_end = pos;
                 let token = &input[token_start..token_end];
 
-                if token.len() >= self.min_length {
-                    let entropy = Self::shannon_entropy(token);
-                    if entropy > self.min_entropy {
-                        // Check it's not already covered by a delimiter-based redaction
-                        let already_covered = replacements
-                            .iter()
-                            .any(|&(s, e)| s <= token_start && token_end <= e);
-                        if !already_covered {
-                            replacements.push((token_start, token_end));
-                        }
+                // `NAME=value` was handled by pass 1, which redacts only the
+                // value. Redacting the whole token would drop the variable
+                // name and make unrelated assignments look identical.
+                if is_env_assignment(token) {
+                    continue;
+                }
+
+                if self.flags(token, false) && !seen(token_start, token_end) {
+                    // Check it's not already covered by a delimiter-based redaction
+                    let already_covered = replacements
+                        .iter()
+                        .any(|&(s, e)| s <= token_start && token_end <= e);
+                    if !already_covered {
+                        replacements.push((token_start, token_end));
                     }
                 }
             }
@@ -133,17 +184,74 @@ impl Sanitizer for EntropySanitizer {
 
         // Apply replacements in reverse order
         for &(start, end) in merged.iter().rev() {
-            result.replace_range(start..end, "<REDACTED>");
+            let placeholder = self.style.placeholder("entropy", &input[start..end]);
+            result.replace_range(start..end, &placeholder);
         }
 
         result
     }
 
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+
     fn name(&self) -> &str {
         "entropy"
     }
 }
 
+/// Whether a token starts with an environment-variable assignment
+/// (`NAME=...`, where NAME is a shell identifier).
+fn is_env_assignment(token: &str) -> bool {
+    let Some((name, _)) = token.split_once('=') else {
+        return false;
+    };
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The number of character classes (upper, lower, digit, symbol) in a token.
+fn char_classes(token: &str) -> usize {
+    let mut classes = [false; 4];
+    for c in token.chars() {
+        let class = if c.is_ascii_uppercase() {
+            0
+        } else if c.is_ascii_lowercase() {
+            1
+        } else if c.is_ascii_digit() {
+            2
+        } else {
+            3
+        };
+        classes[class] = true;
+    }
+    classes.iter().filter(|&&seen| seen).count()
+}
+
+/// Whether a token is a hex digest such as a git SHA or a sha256 sum.
+fn is_hex_digest(token: &str) -> bool {
+    HEX_DIGEST_LENGTHS.contains(&token.len()) && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The name an `=` or `:` at `delimiter` assigns to, e.g. `API_KEY` in
+/// `API_KEY=...` or `"token"` in `"token": ...` (quotes stripped).
+fn name_before(input: &str, delimiter: usize) -> &str {
+    let before = input[..delimiter].trim_end().trim_end_matches(['"', '\'']);
+    let start = before
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'))
+        .map_or(0, |i| i + 1);
+    &before[start..]
+}
+
+/// Whether an assigned name suggests the value is a secret.
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAME_HINTS.iter().any(|hint| name.contains(hint))
+}
+
 /// Merge overlapping or adjacent ranges.
 fn merge_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
     if ranges.is_empty() {
@@ -174,6 +282,13 @@ mod tests {
         assert!(result.contains("<REDACTED>"));
     }
 
+    #[test]
+    fn test_env_assignment_keeps_name() {
+        let san = EntropySanitizer::new(20, 4.0);
+        let input = "export DB_PASS=aB3dE5fG7hI9jK1lM3nO5pQ7rS9tU1v";
+        assert_eq!(san.sanitize(input), "export DB_PASS=<REDACTED>");
+    }
+
     #[test]
     fn test_low_entropy_token_passes() {
         let san = EntropySanitizer::new(20, 4.0);
@@ -199,6 +314,34 @@ mod tests {
         assert_eq!(result, input);
     }
 
+    #[test]
+    fn test_single_class_token_passes() {
+        let san = EntropySanitizer::new(20, 3.0);
+        // High entropy, but lowercase letters only
+        let input = "echo thequickbrownfxjmpsvlazydg";
+        assert_eq!(san.sanitize(input), input);
+    }
+
+    #[test]
+    fn test_hex_digest_needs_secret_name() {
+        let mut san = EntropySanitizer::new(20, 3.5);
+        let sha = "3f786850e387550fdab836ed7e6dc881de23001b";
+        assert_eq!(
+            san.sanitize(&format!("git checkout {sha}")),
+            format!("git checkout {sha}")
+        );
+        assert_eq!(
+            san.sanitize(&format!("API_TOKEN={sha}")),
+            "API_TOKEN=<REDACTED>"
+        );
+
+        san.skip_hex_digests = false;
+        assert_eq!(
+            san.sanitize(&format!("git checkout {sha}")),
+            "git checkout <REDACTED>"
+        );
+    }
+
     #[test]
     fn test_shannon_entropy_uniform() {
         // 256 unique bytes should give ~8.0 entropy
@@ -0,0 +1,122 @@
+use super::encoding::{base64_decode, Alphabet};
+use super::{RedactionStyle, Sanitizer};
+
+/// JSON Web Tokens (`header.payload.signature`).
+///
+/// Only the payload and signature are redacted. The header (`alg`, `typ`,
+/// `kid`) carries nothing secret and is often what a debugging session needs
+/// to see. A dotted token counts as a JWT only if its first segment decodes
+/// to a JSON object with an `alg` field, so version strings, hostnames, and
+/// other dotted text pass through.
+pub struct JwtSanitizer {
+    style: RedactionStyle,
+}
+
+impl JwtSanitizer {
+    pub fn new() -> Self {
+        Self {
+            style: RedactionStyle::default(),
+        }
+    }
+
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Byte ranges of the payload and signature segments of each JWT.
+    fn find_jwts(input: &str) -> Vec<(usize, usize)> {
+        let bytes = input.as_bytes();
+        let len = bytes.len();
+        let mut spans = Vec::new();
+        let mut i = 0;
+
+        while i < len {
+            if !is_jwt_char(bytes[i]) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < len && is_jwt_char(bytes[i]) {
+                i += 1;
+            }
+
+            let token = &input[start..i];
+            let segments: Vec<&str> = token.split('.').collect();
+            if segments.len() != 3 || segments.iter().any(|s| s.is_empty()) {
+                continue;
+            }
+            if !is_jwt_header(segments[0]) {
+                continue;
+            }
+            let payload = start + segments[0].len() + 1;
+            let signature = payload + segments[1].len() + 1;
+            spans.push((payload, payload + segments[1].len()));
+            spans.push((signature, i));
+        }
+
+        spans
+    }
+}
+
+impl Default for JwtSanitizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sanitizer for JwtSanitizer {
+    fn sanitize(&self, input: &str) -> String {
+        let mut result = input.to_string();
+        for (start, end) in Self::find_jwts(input).into_iter().rev() {
+            let placeholder = self.style.placeholder("jwt", &input[start..end]);
+            result.replace_range(start..end, &placeholder);
+        }
+        result
+    }
+
+    fn name(&self) -> &str {
+        "jwt"
+    }
+
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+}
+
+/// Characters of a compact JWT: base64url segments joined by dots.
+fn is_jwt_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.'
+}
+
+/// Whether a segment is a base64url-encoded JOSE header.
+fn is_jwt_header(segment: &str) -> bool {
+    base64_decode(segment, Alphabet::UrlSafe)
+        .and_then(|decoded| serde_json::from_slice::<serde_json::Value>(&decoded).ok())
+        .is_some_and(|header| header.get("alg").is_some_and(|alg| alg.is_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+
+    #[test]
+    fn test_header_must_be_json_with_alg() {
+        assert!(is_jwt_header(HEADER));
+        // {"typ":"JWT"}
+        assert!(!is_jwt_header("eyJ0eXAiOiJKV1QifQ"));
+        assert!(!is_jwt_header("api"));
+    }
+
+    #[test]
+    fn test_two_and_four_segment_tokens_are_ignored() {
+        let san = JwtSanitizer::new();
+        let two = format!("{HEADER}.payload");
+        let four = format!("{HEADER}.a.b.c");
+        assert_eq!(san.sanitize(&two), two);
+        assert_eq!(san.sanitize(&four), four);
+    }
+}
@@ -1,62 +1,459 @@
 pub mod aho;
+pub mod card;
 pub mod encoding;
 pub mod entropy;
+pub mod jwt;
 pub mod regex_san;
+pub mod style;
+
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+
+use crate::config::{PolicyConfig, RoleDefinition, SanitizeProfile};
+use crate::error::{HookwiseError, Result};
+
+pub use style::RedactionStyle;
 
 /// A single sanitization layer.
 pub trait Sanitizer: Send + Sync {
-    /// Sanitize the input string, replacing detected secrets with `<REDACTED>`.
+    /// Sanitize the input string, replacing detected secrets with a
+    /// placeholder in the layer's [`RedactionStyle`] (`<REDACTED>` by default).
     fn sanitize(&self, input: &str) -> String;
 
     /// Name of this sanitizer layer (for logging/debugging).
     fn name(&self) -> &str;
+
+    /// How this layer writes its redactions.
+    fn style(&self) -> &RedactionStyle {
+        &DEFAULT_STYLE
+    }
+
+    /// Sanitize `input`, where `redacted` holds the byte ranges of the
+    /// placeholders earlier layers wrote into it during this run. Layers
+    /// that leave text next to an earlier redaction alone override this; a
+    /// placeholder that was already in the input is not in `redacted`.
+    fn sanitize_after(&self, input: &str, _redacted: &[Range<usize>]) -> String {
+        self.sanitize(input)
+    }
+
+    /// Sanitize the input and report each redaction this layer made. The
+    /// default implementation recovers the redactions by diffing the input
+    /// against the output of [`Sanitizer::sanitize_after`].
+    fn sanitize_detailed(
+        &self,
+        input: &str,
+        redacted: &[Range<usize>],
+    ) -> (String, Vec<RedactionEvent>) {
+        let output = self.sanitize_after(input, redacted);
+        let events = diff_redactions(self.name(), self.style(), input, &output);
+        (output, events)
+    }
 }
 
+/// One redaction made by a sanitizer layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionEvent {
+    /// Name of the layer that made the redaction.
+    pub layer: String,
+    /// Byte range of the redacted text in the layer's input. Layers run in
+    /// sequence, so this is relative to what the previous layers left.
+    pub span: Range<usize>,
+    /// Length of the redacted text, in characters.
+    pub original_len: usize,
+}
+
+static DEFAULT_STYLE: LazyLock<RedactionStyle> = LazyLock::new(RedactionStyle::default);
+
+/// Replaces the whole input when a layer exceeds the redaction cap. Input
+/// with that many secrets is almost certainly a dump, not a command.
+pub const BULK_REDACTION_MARKER: &str = "<REDACTED:bulk>";
+
+/// Default cap on the redactions a single layer may make in one input.
+pub const DEFAULT_MAX_REDACTIONS: usize = 64;
+
+/// [`SanitizePipeline::sanitize_lines`] spreads inputs with at least this
+/// many lines across threads.
+pub const PARALLEL_LINES_THRESHOLD: usize = 1000;
+
 /// The complete sanitization pipeline. Runs all layers in sequence.
 pub struct SanitizePipeline {
     layers: Vec<Box<dyn Sanitizer>>,
+    max_redactions: usize,
+    allowlist: Vec<String>,
+    /// Tools whose input is sanitized; `None` means every tool.
+    tools: Option<Vec<String>>,
 }
 
 impl SanitizePipeline {
     /// Create the default pipeline with all layers and built-in patterns.
-    /// Order: encoding pre-process -> aho-corasick -> regex -> entropy.
+    /// Order: JWTs -> encoding pre-process -> aho-corasick -> regex -> card
+    /// numbers -> entropy.
     pub fn default_pipeline() -> Self {
-        let aho_for_encoding =
-            aho::AhoCorasickSanitizer::new(aho::AhoCorasickSanitizer::default_prefixes());
-        let regex_for_encoding =
-            regex_san::RegexSanitizer::new(regex_san::RegexSanitizer::default_patterns())
-                .expect("default regex patterns should compile");
-        let encoding_layer = encoding::EncodingSanitizer::new(vec![
-            Box::new(aho_for_encoding),
-            Box::new(regex_for_encoding),
-        ]);
-
-        let aho = aho::AhoCorasickSanitizer::new(aho::AhoCorasickSanitizer::default_prefixes());
-        let regex = regex_san::RegexSanitizer::new(regex_san::RegexSanitizer::default_patterns())
-            .expect("default regex patterns should compile");
-        let entropy = entropy::EntropySanitizer::new(20, 4.0);
+        Self::from_profile(&SanitizeProfile::default(), &RedactionStyle::default())
+    }
 
-        Self {
-            layers: vec![
-                Box::new(encoding_layer),
-                Box::new(aho),
-                Box::new(regex),
-                Box::new(entropy),
-            ],
+    /// Create a pipeline running only the layers a profile enables, in the
+    /// same order as the default pipeline, writing redactions in `style`.
+    pub fn from_profile(profile: &SanitizeProfile, style: &RedactionStyle) -> Self {
+        Self::build(profile, style, &[])
+    }
+
+    /// Like [`SanitizePipeline::from_profile`], with `extra_prefixes` added to
+    /// the built-in secret prefixes.
+    fn build(profile: &SanitizeProfile, style: &RedactionStyle, extra_prefixes: &[String]) -> Self {
+        let mut layers: Vec<Box<dyn Sanitizer>> = Vec::new();
+        if profile.jwt {
+            // First, so later layers see the kept header next to placeholders
+            // and leave it alone.
+            layers.push(Box::new(jwt::JwtSanitizer::new().with_style(style.clone())));
         }
+        if profile.encoding {
+            // The inner layers only detect; their output is discarded.
+            layers.push(Box::new(
+                encoding::EncodingSanitizer::new(vec![
+                    Box::new(prefix_layer(extra_prefixes)),
+                    Box::new(default_regex()),
+                ])
+                .with_style(style.clone()),
+            ));
+        }
+        if profile.prefixes {
+            layers.push(Box::new(
+                prefix_layer(extra_prefixes).with_style(style.clone()),
+            ));
+        }
+        if profile.regex {
+            layers.push(Box::new(default_regex().with_style(style.clone())));
+        }
+        if profile.cards {
+            layers.push(Box::new(
+                card::CreditCardSanitizer::new().with_style(style.clone()),
+            ));
+        }
+        if profile.entropy {
+            layers.push(Box::new(
+                entropy::EntropySanitizer::new(
+                    profile.entropy_min_length,
+                    profile.entropy_threshold,
+                )
+                .with_style(style.clone()),
+            ));
+        }
+        Self::new(layers)
+    }
+
+    /// Create the pipeline for a session's role under a project policy: the
+    /// role's named profile if it sets one, otherwise the default layers,
+    /// plus the policy's `secret_prefixes` and `sanitize_allowlist`. With no
+    /// role this is the project's default pipeline.
+    pub fn for_role(policy: &PolicyConfig, role: Option<&RoleDefinition>) -> Result<Self> {
+        let config = &policy.sanitize;
+        let default_profile = SanitizeProfile::default();
+        let profile = match role.and_then(|r| r.sanitize_profile.as_deref()) {
+            Some(name) => {
+                config
+                    .profiles
+                    .get(name)
+                    .ok_or_else(|| HookwiseError::InvalidPolicy {
+                        reason: format!("unknown sanitize profile '{}'", name),
+                    })?
+            }
+            None => &default_profile,
+        };
+        Ok(
+            Self::build(profile, &config.redaction, &policy.secret_prefixes)
+                .with_max_redactions(config.max_redactions)
+                .with_allowlist(policy.sanitize_allowlist.clone())
+                .with_tools(config.tools.clone()),
+        )
     }
 
     /// Create a pipeline from custom layers.
     pub fn new(layers: Vec<Box<dyn Sanitizer>>) -> Self {
-        Self { layers }
+        Self {
+            layers,
+            max_redactions: DEFAULT_MAX_REDACTIONS,
+            allowlist: Vec::new(),
+            tools: None,
+        }
+    }
+
+    /// Cap the redactions any single layer may make. Past the cap the whole
+    /// input is replaced with [`BULK_REDACTION_MARKER`].
+    pub fn with_max_redactions(mut self, max_redactions: usize) -> Self {
+        self.max_redactions = max_redactions;
+        self
+    }
+
+    /// Never redact these literals. After each layer, any redaction whose
+    /// original text exactly matches an entry is undone. Blank entries are
+    /// skipped.
+    pub fn with_allowlist(mut self, allowlist: Vec<String>) -> Self {
+        self.allowlist = allowlist
+            .into_iter()
+            .filter(|literal| !literal.trim().is_empty())
+            .collect();
+        self
+    }
+
+    /// Only sanitize the input of these tools (see
+    /// [`SanitizePipeline::sanitize_tool_input`]). A `"*"` entry keeps every
+    /// tool sanitized.
+    pub fn with_tools(mut self, tools: Vec<String>) -> Self {
+        self.tools = (!tools.iter().any(|t| t == "*")).then_some(tools);
+        self
+    }
+
+    /// Whether `tool`'s input goes through the layers.
+    pub fn sanitizes_tool(&self, tool: &str) -> bool {
+        self.tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == tool))
+    }
+
+    /// Sanitize a call's serialized input, or return it verbatim if `tool`
+    /// is not one the pipeline is limited to.
+    pub fn sanitize_tool_input(&self, tool: &str, input: &str) -> String {
+        if self.sanitizes_tool(tool) {
+            self.sanitize(input)
+        } else {
+            input.to_string()
+        }
     }
 
     /// Run all sanitization layers in sequence.
     pub fn sanitize(&self, input: &str) -> String {
         let mut result = input.to_string();
+        let mut redacted = Vec::new();
         for layer in &self.layers {
-            result = layer.sanitize(&result);
+            let before = layer.style().count(&result);
+            let output = layer.sanitize_after(&result, &redacted);
+            let output = self.restore_allowlisted(layer.style(), &result, output);
+            let count = layer.style().count(&output);
+            if self.over_cap(layer.as_ref(), count.saturating_sub(before)) {
+                return BULK_REDACTION_MARKER.to_string();
+            }
+            redacted = produced_placeholders(layer.style(), &result, &redacted, &output);
+            result = output;
         }
         result
     }
+
+    /// Sanitize independent lines (e.g. of a JSONL file), each as its own
+    /// input. The output is in input order; large inputs are processed in
+    /// parallel.
+    pub fn sanitize_lines(&self, lines: &[&str]) -> Vec<String> {
+        if lines.len() < PARALLEL_LINES_THRESHOLD {
+            lines.iter().map(|line| self.sanitize(line)).collect()
+        } else {
+            lines.par_iter().map(|line| self.sanitize(line)).collect()
+        }
+    }
+
+    /// Like [`SanitizePipeline::sanitize`], but also report which layer made
+    /// each redaction. When a layer exceeds the redaction cap, the events up
+    /// to and including that layer's are returned with the bulk marker.
+    pub fn sanitize_with_report(&self, input: &str) -> (String, Vec<RedactionEvent>) {
+        let mut result = input.to_string();
+        let mut redacted = Vec::new();
+        let mut events = Vec::new();
+        for layer in &self.layers {
+            let (output, mut layer_events) = layer.sanitize_detailed(&result, &redacted);
+            layer_events.retain(|event| !self.is_allowlisted(&result[event.span.clone()]));
+            let output = self.restore_allowlisted(layer.style(), &result, output);
+            let over_cap = self.over_cap(layer.as_ref(), layer_events.len());
+            events.extend(layer_events);
+            if over_cap {
+                return (BULK_REDACTION_MARKER.to_string(), events);
+            }
+            redacted = produced_placeholders(layer.style(), &result, &redacted, &output);
+            result = output;
+        }
+        (result, events)
+    }
+
+    fn is_allowlisted(&self, text: &str) -> bool {
+        self.allowlist.iter().any(|literal| literal == text)
+    }
+
+    /// Put back the allowlisted text a layer redacted from `input`. If the
+    /// layer's `output` cannot be aligned with its input, it is kept as is.
+    fn restore_allowlisted(&self, style: &RedactionStyle, input: &str, output: String) -> String {
+        if self.allowlist.is_empty() || input == output {
+            return output;
+        }
+        let Some(spans) = align_placeholders(style, input, &output) else {
+            return output;
+        };
+        let placeholders = style.find(&output);
+        let mut output = output;
+        for (placeholder, span) in placeholders.into_iter().zip(spans).rev() {
+            if self.is_allowlisted(&input[span.clone()]) {
+                output.replace_range(placeholder, &input[span]);
+            }
+        }
+        output
+    }
+
+    /// Whether `redacted` new redactions by `layer` exceed the cap. Warns if so.
+    fn over_cap(&self, layer: &dyn Sanitizer, redacted: usize) -> bool {
+        if redacted <= self.max_redactions {
+            return false;
+        }
+        tracing::warn!(
+            "{} layer exceeded {} redactions; redacting the whole input",
+            layer.name(),
+            self.max_redactions
+        );
+        true
+    }
+}
+
+/// Recover a layer's redactions from its input and output. Layers copy the
+/// input through and put a placeholder in place of each secret, so the text
+/// between placeholders in the output is located in the input in turn;
+/// whatever lies between is what was redacted. Placeholders already present
+/// in the input are not reported again. If the output cannot be aligned this
+/// way, the whole changed region is reported as one redaction.
+fn diff_redactions(
+    layer: &str,
+    style: &RedactionStyle,
+    input: &str,
+    output: &str,
+) -> Vec<RedactionEvent> {
+    if input == output {
+        return Vec::new();
+    }
+    let spans = align_placeholders(style, input, output)
+        .unwrap_or_else(|| vec![changed_region(input, output)]);
+    spans
+        .into_iter()
+        .filter(|span| !style.is_placeholder(&input[span.clone()]))
+        .map(|span| RedactionEvent {
+            layer: layer.to_string(),
+            original_len: input[span.clone()].chars().count(),
+            span,
+        })
+        .collect()
+}
+
+/// Byte ranges, in `output`, of the placeholders written by this run's
+/// layers: those the last layer wrote into `input`, and those it copied
+/// through from `redacted` (the earlier layers'). Placeholder text that was
+/// part of the original input is left out. If the output cannot be aligned
+/// with the input, only earlier placeholders in the unchanged prefix are
+/// kept, which at worst lets a later layer redact more.
+fn produced_placeholders(
+    style: &RedactionStyle,
+    input: &str,
+    redacted: &[Range<usize>],
+    output: &str,
+) -> Vec<Range<usize>> {
+    if input == output {
+        return redacted.to_vec();
+    }
+    match align_placeholders(style, input, output) {
+        Some(spans) => style
+            .find(output)
+            .into_iter()
+            .zip(spans)
+            .filter(|(placeholder, span)| {
+                input[span.clone()] != output[placeholder.clone()] || redacted.contains(span)
+            })
+            .map(|(placeholder, _)| placeholder)
+            .collect(),
+        None => {
+            let unchanged = changed_region(input, output).start;
+            redacted
+                .iter()
+                .filter(|range| range.end <= unchanged)
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+/// Byte ranges of the input replaced by each placeholder in the output, or
+/// `None` if the output is not the input with some spans replaced.
+fn align_placeholders(
+    style: &RedactionStyle,
+    input: &str,
+    output: &str,
+) -> Option<Vec<Range<usize>>> {
+    let placeholders = style.find(output);
+    if placeholders.is_empty() {
+        return None;
+    }
+    let mut segments = Vec::with_capacity(placeholders.len() + 1);
+    let mut prev = 0;
+    for placeholder in &placeholders {
+        segments.push(&output[prev..placeholder.start]);
+        prev = placeholder.end;
+    }
+    segments.push(&output[prev..]);
+
+    let first = segments[0];
+    if !input.starts_with(first) {
+        return None;
+    }
+    let rest = &segments[1..];
+    let mut pos = first.len();
+    let mut spans = Vec::with_capacity(rest.len());
+    for (i, segment) in rest.iter().enumerate() {
+        let end = if i + 1 == rest.len() {
+            // The last segment is the tail of the input.
+            let end = input.len().checked_sub(segment.len())?;
+            if end <= pos || !input.is_char_boundary(end) || &input[end..] != *segment {
+                return None;
+            }
+            end
+        } else {
+            // A redacted span is never empty, so search past its first char.
+            let from = pos + input[pos..].chars().next()?.len_utf8();
+            from + input[from..].find(segment)?
+        };
+        spans.push(pos..end);
+        pos = end + segment.len();
+    }
+    Some(spans)
+}
+
+/// The byte range of the input between the longest common prefix and suffix
+/// of input and output.
+fn changed_region(input: &str, output: &str) -> Range<usize> {
+    let prefix: usize = input
+        .chars()
+        .zip(output.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = input[prefix..]
+        .chars()
+        .rev()
+        .zip(output[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    prefix..input.len() - suffix
+}
+
+/// The prefix layer: built-in prefixes plus `extra`. Blank and duplicate
+/// entries are skipped. A prefix overlapping a built-in one marks the same
+/// token, and overlapping matches are merged, so it is redacted only once.
+fn prefix_layer(extra: &[String]) -> aho::AhoCorasickSanitizer {
+    let mut prefixes = aho::AhoCorasickSanitizer::default_prefixes();
+    for prefix in extra {
+        let prefix = prefix.trim();
+        if !prefix.is_empty() && !prefixes.iter().any(|known| known == prefix) {
+            prefixes.push(prefix.to_string());
+        }
+    }
+    aho::AhoCorasickSanitizer::new(prefixes)
+}
+
+fn default_regex() -> regex_san::RegexSanitizer {
+    regex_san::RegexSanitizer::new(regex_san::RegexSanitizer::default_patterns())
+        .expect("default regex patterns should compile")
 }
@@ -1,13 +1,16 @@
+use std::ops::Range;
+
 use regex::RegexSet;
 
 use crate::error::HookwiseError;
 
-use super::Sanitizer;
+use super::{RedactionStyle, Sanitizer};
 
 /// Layer 2: Positional/contextual pattern matching via RegexSet.
 pub struct RegexSanitizer {
     regex_set: RegexSet,
     patterns: Vec<regex::Regex>,
+    style: RedactionStyle,
 }
 
 impl RegexSanitizer {
@@ -27,9 +30,16 @@ impl RegexSanitizer {
         Ok(Self {
             regex_set,
             patterns: compiled,
+            style: RedactionStyle::default(),
         })
     }
 
+    /// Write redactions in `style` instead of the plain `<REDACTED>`.
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
     /// Default regex patterns for secret detection.
     pub fn default_patterns() -> Vec<String> {
         vec![
@@ -48,36 +58,76 @@ impl RegexSanitizer {
 
 impl Sanitizer for RegexSanitizer {
     fn sanitize(&self, input: &str) -> String {
-        // Use the RegexSet for fast matching, then apply individual regexes for replacement.
+        self.sanitize_after(input, &[])
+    }
+
+    fn sanitize_after(&self, input: &str, redacted: &[Range<usize>]) -> String {
+        // Use the RegexSet for fast matching, then apply individual regexes to locate spans.
         let matching: Vec<usize> = self.regex_set.matches(input).into_iter().collect();
         if matching.is_empty() {
             return input.to_string();
         }
 
-        let mut result = input.to_string();
+        // Collect the secret span of every match across all patterns against the
+        // original input. Each pattern is designed so that group 1 is the prefix to
+        // keep; the rest of the match is the secret.
+        let mut spans: Vec<(usize, usize)> = Vec::new();
         for &idx in &matching {
-            let re = &self.patterns[idx];
-            // Replace the secret part (the capture group after the prefix) with <REDACTED>.
-            // Each pattern is designed so that group 1 is the prefix to keep.
-            result = re
-                .replace_all(&result, |caps: &regex::Captures| {
-                    if let Some(prefix) = caps.get(1) {
-                        format!("{}<REDACTED>", prefix.as_str())
-                    } else {
-                        "<REDACTED>".to_string()
-                    }
-                })
-                .into_owned();
+            for caps in self.patterns[idx].captures_iter(input) {
+                let whole = caps.get(0).expect("group 0 always participates");
+                let start = caps.get(1).map_or(whole.start(), |prefix| prefix.end());
+                // Text running up to an earlier layer's placeholder is what
+                // that layer chose to keep of a token it redacted, e.g. a JWT
+                // header.
+                if redacted.iter().any(|range| range.start == whole.end()) {
+                    continue;
+                }
+                if start < whole.end() {
+                    spans.push((start, whole.end()));
+                }
+            }
+        }
+
+        // Merge overlapping spans and apply replacements in reverse order, so each
+        // secret is redacted exactly once.
+        spans.sort_by_key(|a| a.0);
+        let merged = merge_ranges(&spans);
+
+        let mut result = input.to_string();
+        for &(start, end) in merged.iter().rev() {
+            let placeholder = self.style.placeholder("regex", &input[start..end]);
+            result.replace_range(start..end, &placeholder);
         }
 
         result
     }
 
+    fn style(&self) -> &RedactionStyle {
+        &self.style
+    }
+
     fn name(&self) -> &str {
         "regex"
     }
 }
 
+/// Merge overlapping or adjacent ranges.
+fn merge_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    if ranges.is_empty() {
+        return Vec::new();
+    }
+    let mut merged = vec![ranges[0]];
+    for &(start, end) in &ranges[1..] {
+        let last = merged.last_mut().unwrap();
+        if start <= last.1 {
+            last.1 = last.1.max(end);
+        } else {
+            merged.push((start, end));
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,4 +175,24 @@ mod tests {
         let result = san.sanitize(input);
         assert_eq!(result, "echo hello world");
     }
+
+    #[test]
+    fn test_overlapping_patterns_redact_once() {
+        let san = RegexSanitizer::new(RegexSanitizer::default_patterns()).unwrap();
+        // Both the `token=` assignment and the `--password` flag patterns match,
+        // on overlapping regions.
+        let input = "mysql --password token=abcdefghijkl";
+        let result = san.sanitize(input);
+        assert_eq!(result, "mysql --password <REDACTED>");
+    }
+
+    #[test]
+    fn test_placeholders_are_not_rematched() {
+        // The second pattern would match the `REDACTED` text injected for the
+        // first if spans were not collected from the original input.
+        let san = RegexSanitizer::new(vec![r"(id=)\S{8,}".into(), r"([A-Z]{3})[A-Z]{5,}".into()])
+            .unwrap();
+        let result = san.sanitize("id=abcdefghij CONSTANTVALUE");
+        assert_eq!(result, "id=<REDACTED> CON<REDACTED>");
+    }
 }
@@ -0,0 +1,132 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// The placeholder written in place of a secret by default.
+pub const DEFAULT_PLACEHOLDER: &str = "<REDACTED>";
+
+/// Opening of the typed and hashed placeholders, e.g. `<REDACTED:aws-key>`.
+const TAGGED_OPEN: &str = "<REDACTED:";
+
+/// How a sanitizer writes the secrets it redacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionStyle {
+    /// Every secret becomes the same placeholder (default: `<REDACTED>`).
+    Fixed(String),
+    /// The placeholder names what was found, e.g. `<REDACTED:aws-key>`.
+    Typed,
+    /// The placeholder carries a short SHA-256 prefix of the secret, e.g.
+    /// `<REDACTED:3f9a12c4b7de>`, so repeats of one secret can be told apart
+    /// from other secrets without revealing it.
+    Hashed,
+}
+
+impl Default for RedactionStyle {
+    fn default() -> Self {
+        RedactionStyle::Fixed(DEFAULT_PLACEHOLDER.to_string())
+    }
+}
+
+impl RedactionStyle {
+    /// The placeholder for one redacted `secret`. `kind` names what the
+    /// layer found (e.g. `aws-key`, `entropy`) and is used by `Typed`.
+    pub fn placeholder(&self, kind: &str, secret: &str) -> String {
+        match self {
+            RedactionStyle::Fixed(text) => text.clone(),
+            RedactionStyle::Typed => format!("{TAGGED_OPEN}{kind}>"),
+            RedactionStyle::Hashed => {
+                use sha2::{Digest, Sha256};
+
+                let hash = format!("{:x}", Sha256::digest(secret.as_bytes()));
+                format!("{TAGGED_OPEN}{}>", &hash[..12])
+            }
+        }
+    }
+
+    /// Byte ranges of the placeholders of this style in `text`.
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            RedactionStyle::Fixed(placeholder) if placeholder.is_empty() => Vec::new(),
+            RedactionStyle::Fixed(placeholder) => text
+                .match_indices(placeholder.as_str())
+                .map(|(start, m)| start..start + m.len())
+                .collect(),
+            RedactionStyle::Typed | RedactionStyle::Hashed => find_tagged(text),
+        }
+    }
+
+    /// Whether `text` is exactly one placeholder of this style.
+    pub fn is_placeholder(&self, text: &str) -> bool {
+        matches!(self.find(text).as_slice(), [only] if *only == (0..text.len()))
+    }
+
+    /// Number of placeholders of this style in `text`.
+    pub fn count(&self, text: &str) -> usize {
+        self.find(text).len()
+    }
+}
+
+/// Byte ranges of `<REDACTED:tag>` placeholders, where the tag is a
+/// non-empty run of ASCII alphanumerics, `-`, `_`, or `.`.
+fn find_tagged(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = text[pos..].find(TAGGED_OPEN) {
+        let start = pos + found;
+        let tag_start = start + TAGGED_OPEN.len();
+        let tag_len = text[tag_start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(text.len() - tag_start);
+        let close = tag_start + tag_len;
+        if tag_len > 0 && text[close..].starts_with('>') {
+            ranges.push(start..close + 1);
+            pos = close + 1;
+        } else {
+            pos = tag_start;
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_plain_placeholder() {
+        let style = RedactionStyle::default();
+        assert_eq!(style.placeholder("aws-key", "AKIA1234"), "<REDACTED>");
+        assert_eq!(style.count("a <REDACTED> b <REDACTED>"), 2);
+    }
+
+    #[test]
+    fn test_typed_placeholder_names_kind() {
+        let style = RedactionStyle::Typed;
+        assert_eq!(
+            style.placeholder("aws-key", "AKIA1234"),
+            "<REDACTED:aws-key>"
+        );
+        assert_eq!(
+            style.find("x <REDACTED:aws-key> <REDACTED:> y"),
+            vec![2..20]
+        );
+    }
+
+    #[test]
+    fn test_hashed_placeholder_is_stable_per_secret() {
+        let style = RedactionStyle::Hashed;
+        let a = style.placeholder("entropy", "first-secret");
+        assert_eq!(a, style.placeholder("regex", "first-secret"));
+        assert_ne!(a, style.placeholder("entropy", "second-secret"));
+        assert_eq!(style.count(&a), 1);
+    }
+
+    #[test]
+    fn test_deserializes_from_yaml() {
+        let typed: RedactionStyle = serde_yaml::from_str("typed").unwrap();
+        assert_eq!(typed, RedactionStyle::Typed);
+        let fixed: RedactionStyle = serde_yaml::from_str("!fixed '[secret]'").unwrap();
+        assert_eq!(fixed, RedactionStyle::Fixed("[secret]".into()));
+    }
+}
@@ -4,6 +4,7 @@ impl ScopeLevel {
     /// Returns the precedence rank (higher = more authoritative).
     pub fn precedence(&self) -> u8 {
         match self {
+            ScopeLevel::Global => 5,
             ScopeLevel::Org => 4,
             ScopeLevel::Project => 3,
             ScopeLevel::User => 2,
@@ -1,13 +1,28 @@
 use super::ScopedDecision;
-use crate::decision::Decision;
+use crate::decision::{Decision, DecisionTier};
 
 /// Merge decisions from multiple scopes, applying precedence:
 /// DENY > ASK > ALLOW > silent
+///
+/// Immutable records are authoritative: if any are present, the others are
+/// ignored and precedence applies among the immutable ones only.
+///
+/// Otherwise, `Override` records are deliberate: the narrowest scope holding
+/// one shadows every decision from broader scopes, override or not, and
+/// precedence applies among what is left.
 pub fn merge_decisions(decisions: Vec<ScopedDecision>) -> Option<ScopedDecision> {
     if decisions.is_empty() {
         return None;
     }
 
+    let (pinned, rest): (Vec<_>, Vec<_>) =
+        decisions.into_iter().partition(|sd| sd.record.immutable);
+    let decisions = if pinned.is_empty() {
+        shadow_by_override(rest)
+    } else {
+        pinned
+    };
+
     let mut best: Option<ScopedDecision> = None;
 
     for sd in decisions {
@@ -24,7 +39,24 @@ pub fn merge_decisions(decisions: Vec<ScopedDecision>) -> Option<ScopedDecision>
     best
 }
 
-fn decision_priority(d: &Decision) -> u8 {
+/// Drop the decisions from scopes broader than the narrowest override.
+fn shadow_by_override(decisions: Vec<ScopedDecision>) -> Vec<ScopedDecision> {
+    let narrowest_override = decisions
+        .iter()
+        .filter(|sd| sd.record.metadata.tier == DecisionTier::Override)
+        .map(|sd| sd.scope.precedence())
+        .min();
+    match narrowest_override {
+        Some(ceiling) => decisions
+            .into_iter()
+            .filter(|sd| sd.scope.precedence() <= ceiling)
+            .collect(),
+        None => decisions,
+    }
+}
+
+/// Rank of a decision under DENY > ASK > ALLOW.
+pub(crate) fn decision_priority(d: &Decision) -> u8 {
     match d {
         Decision::Deny => 3,
         Decision::Ask => 2,
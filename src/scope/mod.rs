@@ -11,10 +11,12 @@ use crate::error::Result;
 use crate::session::SessionContext;
 use crate::storage::StorageBackend;
 
-/// The four scope levels, ordered from broadest to narrowest.
+/// The five scope levels, ordered from broadest to narrowest.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ScopeLevel {
+    /// Baseline rules shared across every org, e.g. a platform team's denies.
+    Global,
     Org,
     Project,
     User,
@@ -32,12 +34,102 @@ pub struct ScopedDecision {
     pub record: DecisionRecord,
 }
 
+/// How a key resolved: the winning decision and every scope match that was
+/// merged to reach it.
+#[derive(Debug, Clone)]
+pub struct ResolvedExplanation {
+    /// The effective decision, as `ScopeResolver::resolve` returns it.
+    pub winner: ScopedDecision,
+    /// All matching decisions, narrowest scope first.
+    pub candidates: Vec<ScopedDecision>,
+}
+
+/// Scopes in resolution order, narrowest first.
+pub(crate) const SCOPES: [ScopeLevel; 5] = [
+    ScopeLevel::Role,
+    ScopeLevel::User,
+    ScopeLevel::Project,
+    ScopeLevel::Org,
+    ScopeLevel::Global,
+];
+
+/// Lookup key of the exact index: (tool, sanitized_input, role).
+type ExactKey = (String, String, String);
+
+/// Lookup key of the wildcard-role index: (tool, sanitized_input).
+type WildcardKey = (String, String);
+
+/// The cached decisions of one scope, indexed for hashed lookup.
+///
+/// Both indexes hold positions into `records`, in ascending order, so the
+/// matches for a key come out in the order they were loaded.
+#[derive(Default)]
+struct ScopeIndex {
+    records: Vec<DecisionRecord>,
+    exact: HashMap<ExactKey, Vec<usize>>,
+    /// Records with `role == "*"`, which match a key of any role.
+    wildcard: HashMap<WildcardKey, Vec<usize>>,
+}
+
+impl ScopeIndex {
+    fn new(records: Vec<DecisionRecord>) -> Self {
+        let mut exact: HashMap<ExactKey, Vec<usize>> = HashMap::new();
+        let mut wildcard: HashMap<WildcardKey, Vec<usize>> = HashMap::new();
+        for (position, record) in records.iter().enumerate() {
+            let key = &record.key;
+            exact
+                .entry((
+                    key.tool.clone(),
+                    key.sanitized_input.clone(),
+                    key.role.clone(),
+                ))
+                .or_default()
+                .push(position);
+            if key.role == "*" {
+                wildcard
+                    .entry((key.tool.clone(), key.sanitized_input.clone()))
+                    .or_default()
+                    .push(position);
+            }
+        }
+        Self {
+            records,
+            exact,
+            wildcard,
+        }
+    }
+
+    /// Records matching `key` exactly or through a wildcard role, in load
+    /// order.
+    fn lookup(&self, key: &CacheKey) -> impl Iterator<Item = &DecisionRecord> {
+        let exact_key = (
+            key.tool.clone(),
+            key.sanitized_input.clone(),
+            key.role.clone(),
+        );
+        let mut positions: Vec<usize> = self.exact.get(&exact_key).cloned().unwrap_or_default();
+        // A "*" key already found the wildcard records through the exact index
+        if key.role != "*" {
+            if let Some(wildcard) = self
+                .wildcard
+                .get(&(key.tool.clone(), key.sanitized_input.clone()))
+            {
+                positions.extend(wildcard);
+                positions.sort_unstable();
+            }
+        }
+        positions
+            .into_iter()
+            .map(|position| &self.records[position])
+    }
+}
+
 /// Resolves the effective decision across all scopes.
 ///
 /// Precedence: DENY > ASK > ALLOW > silent
 pub struct ScopeResolver {
     storage: Box<dyn StorageBackend>,
-    cache: RwLock<Option<HashMap<ScopeLevel, Vec<DecisionRecord>>>>,
+    cache: RwLock<Option<HashMap<ScopeLevel, ScopeIndex>>>,
 }
 
 impl ScopeResolver {
@@ -48,7 +140,8 @@ impl ScopeResolver {
         }
     }
 
-    /// Populate the in-memory cache from storage. Called lazily on first resolve().
+    /// Populate the in-memory cache and its indexes from storage. Called
+    /// lazily on first resolve().
     fn ensure_cache(&self) -> Result<()> {
         {
             let guard = self.cache.read().unwrap_or_else(|e| e.into_inner());
@@ -57,14 +150,9 @@ impl ScopeResolver {
             }
         }
         let mut map = HashMap::new();
-        for &scope in &[
-            ScopeLevel::Role,
-            ScopeLevel::User,
-            ScopeLevel::Project,
-            ScopeLevel::Org,
-        ] {
+        for &scope in &SCOPES {
             let decisions = self.storage.load_decisions(scope)?;
-            map.insert(scope, decisions);
+            map.insert(scope, ScopeIndex::new(decisions));
         }
         let mut guard = self.cache.write().unwrap_or_else(|e| e.into_inner());
         *guard = Some(map);
@@ -80,49 +168,98 @@ impl ScopeResolver {
         self.ensure_cache()
     }
 
+    /// Replace the cached decisions for `role` with what storage holds now.
+    /// Every scope is refreshed, since a backend may keep several scopes in
+    /// one place (JSONL shares a directory between Role and Project). A no-op
+    /// until the cache has been populated.
+    pub fn invalidate_role(&self, role: &str) -> Result<()> {
+        let mut guard = self.cache.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(cache_map) = guard.as_mut() {
+            for &scope in &SCOPES {
+                let mut decisions = cache_map
+                    .remove(&scope)
+                    .map(|index| index.records)
+                    .unwrap_or_default();
+                decisions.retain(|r| r.key.role != role);
+                decisions.extend(
+                    self.storage
+                        .load_decisions(scope)?
+                        .into_iter()
+                        .filter(|r| r.key.role == role),
+                );
+                cache_map.insert(scope, ScopeIndex::new(decisions));
+            }
+        }
+        Ok(())
+    }
+
     /// Resolve the effective decision across all scopes for a given cache key.
     ///
-    /// Checks scopes in order: Role -> User -> Project -> Org.
+    /// Checks scopes in order: Role -> User -> Project -> Org -> Global.
     /// Applies precedence: DENY > ASK > ALLOW > silent.
     ///
     /// Returns None if no scope has a matching decision (novel command).
     pub fn resolve(
         &self,
         key: &CacheKey,
-        _session: &SessionContext,
+        session: &SessionContext,
     ) -> Result<Option<ScopedDecision>> {
+        Ok(self
+            .resolve_batch(std::slice::from_ref(key), session)?
+            .pop()
+            .flatten())
+    }
+
+    /// Resolve many cache keys at once, in the same order as `keys`, under a
+    /// single read of the cache. Each key matches its exact records plus the
+    /// `role == "*"` records for the same tool and input, merged with the
+    /// same precedence as `resolve`.
+    pub fn resolve_batch(
+        &self,
+        keys: &[CacheKey],
+        _session: &SessionContext,
+    ) -> Result<Vec<Option<ScopedDecision>>> {
         self.ensure_cache()?;
 
-        let scopes = [
-            ScopeLevel::Role,
-            ScopeLevel::User,
-            ScopeLevel::Project,
-            ScopeLevel::Org,
-        ];
+        let guard = self.cache.read().unwrap_or_else(|e| e.into_inner());
+        let cache_map = guard.as_ref().expect("cache populated by ensure_cache");
+
+        let resolved = keys
+            .iter()
+            .map(|key| merge::merge_decisions(candidates(cache_map, key)))
+            .collect();
+        Ok(resolved)
+    }
 
-        let mut found: Vec<ScopedDecision> = Vec::new();
+    /// Resolve a cache key and keep every scope match alongside the winner,
+    /// e.g. to show that an org deny beat a project allow.
+    pub fn resolve_explained(
+        &self,
+        key: &CacheKey,
+        _session: &SessionContext,
+    ) -> Result<Option<ResolvedExplanation>> {
+        self.ensure_cache()?;
 
         let guard = self.cache.read().unwrap_or_else(|e| e.into_inner());
         let cache_map = guard.as_ref().expect("cache populated by ensure_cache");
 
-        for &scope in &scopes {
-            if let Some(decisions) = cache_map.get(&scope) {
-                for record in decisions {
-                    if record.key == *key
-                        || (record.key.role == "*"
-                            && record.key.tool == key.tool
-                            && record.key.sanitized_input == key.sanitized_input)
-                    {
-                        found.push(ScopedDecision {
-                            decision: record.decision,
-                            scope,
-                            record: record.clone(),
-                        });
-                    }
-                }
-            }
-        }
+        let candidates = candidates(cache_map, key);
+        Ok(merge::merge_decisions(candidates.clone())
+            .map(|winner| ResolvedExplanation { winner, candidates }))
+    }
+}
 
-        Ok(merge::merge_decisions(found))
+/// Every cached decision matching `key`, narrowest scope first.
+fn candidates(cache_map: &HashMap<ScopeLevel, ScopeIndex>, key: &CacheKey) -> Vec<ScopedDecision> {
+    let mut found = Vec::new();
+    for &scope in &SCOPES {
+        if let Some(index) = cache_map.get(&scope) {
+            found.extend(index.lookup(key).map(|record| ScopedDecision {
+                decision: record.decision,
+                scope,
+                record: record.clone(),
+            }));
+        }
     }
+    found
 }
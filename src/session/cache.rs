@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use dashmap::mapref::one::{Ref, RefMut};
+use dashmap::DashMap;
+
+use super::SessionContext;
+
+/// Default number of sessions kept in memory.
+pub const DEFAULT_SESSION_CACHE_CAPACITY: usize = 1024;
+
+/// In-memory session contexts, bounded with least-recently-used eviction.
+///
+/// Evicting a session only drops its cached context; the registration on
+/// disk is untouched, so the next lookup re-populates it from the file.
+pub struct SessionCache {
+    entries: DashMap<String, SessionContext>,
+    /// Logical time of each session's last insert or lookup.
+    last_used: DashMap<String, u64>,
+    clock: AtomicU64,
+    capacity: AtomicUsize,
+}
+
+impl SessionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            last_used: DashMap::new(),
+            clock: AtomicU64::new(0),
+            capacity: AtomicUsize::new(capacity.max(1)),
+        }
+    }
+
+    /// Change the capacity (at least 1). A smaller capacity takes effect on
+    /// the next insert.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Ref<'_, String, SessionContext>> {
+        let entry = self.entries.get(session_id)?;
+        self.touch(session_id);
+        Some(entry)
+    }
+
+    pub fn get_mut(&self, session_id: &str) -> Option<RefMut<'_, String, SessionContext>> {
+        let entry = self.entries.get_mut(session_id)?;
+        self.touch(session_id);
+        Some(entry)
+    }
+
+    pub fn contains_key(&self, session_id: &str) -> bool {
+        self.entries.contains_key(session_id)
+    }
+
+    /// Cache a session's context, evicting the least recently used sessions
+    /// if the cache is over capacity.
+    pub fn insert(&self, session_id: String, ctx: SessionContext) -> Option<SessionContext> {
+        self.touch(&session_id);
+        let previous = self.entries.insert(session_id, ctx);
+        self.evict();
+        previous
+    }
+
+    pub fn remove(&self, session_id: &str) -> Option<(String, SessionContext)> {
+        self.last_used.remove(session_id);
+        self.entries.remove(session_id)
+    }
+
+    /// Drop every cached session.
+    pub fn clear(&self) {
+        self.entries.clear();
+        self.last_used.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn touch(&self, session_id: &str) {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.last_used.insert(session_id.to_string(), now);
+    }
+
+    fn evict(&self) {
+        while self.entries.len() > self.capacity() {
+            let oldest = self
+                .last_used
+                .iter()
+                .min_by_key(|entry| *entry.value())
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(session_id) => {
+                    self.remove(&session_id);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl Default for SessionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> SessionContext {
+        SessionContext::new_minimal("user".into(), "org".into(), "project".into())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = SessionCache::new(2);
+        cache.insert("a".into(), ctx());
+        cache.insert("b".into(), ctx());
+        // Looking "a" up makes "b" the least recently used
+        assert!(cache.get("a").is_some());
+        cache.insert("c".into(), ctx());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key("a"));
+        assert!(!cache.contains_key("b"));
+        assert!(cache.contains_key("c"));
+    }
+
+    #[test]
+    fn test_reinsert_does_not_evict() {
+        let cache = SessionCache::new(2);
+        cache.insert("a".into(), ctx());
+        cache.insert("b".into(), ctx());
+        cache.insert("a".into(), ctx());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key("b"));
+    }
+}
@@ -15,6 +15,7 @@ impl SessionContext {
             task_description: None,
             registered_at: None,
             disabled: false,
+            tags: Default::default(),
         }
     }
 }
@@ -1,9 +1,12 @@
+pub mod cache;
 pub mod context;
+pub mod rate_limit;
 pub mod registration;
+pub mod watch;
 
 use chrono::{DateTime, Utc};
-use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
@@ -24,15 +27,23 @@ pub struct SessionContext {
     pub task_description: Option<String>,
     pub registered_at: Option<DateTime<Utc>>,
     pub disabled: bool,
+    /// Free-form metadata from registration (e.g. `pr=1234`), stamped onto
+    /// every decision the session triggers.
+    pub tags: HashMap<String, String>,
 }
 
-/// Global concurrent session cache.
-pub static SESSIONS: LazyLock<DashMap<String, SessionContext>> = LazyLock::new(DashMap::new);
+pub use cache::SessionCache;
+pub use rate_limit::RateLimiter;
+pub use watch::WatchHandle;
+
+/// Global concurrent session cache, bounded by `session_cache_capacity`.
+pub static SESSIONS: LazyLock<SessionCache> = LazyLock::new(SessionCache::default);
 
 /// Manages session registration and lookup.
 pub struct SessionManager {
     registration_file: PathBuf,
     exclusion_file: PathBuf,
+    session_ttl_secs: Option<u64>,
 }
 
 impl SessionManager {
@@ -42,9 +53,16 @@ impl SessionManager {
         Self {
             registration_file: runtime_dir.join(format!("hookwise-{suffix}-sessions.json")),
             exclusion_file: runtime_dir.join(format!("hookwise-{suffix}-exclusions.json")),
+            session_ttl_secs: None,
         }
     }
 
+    /// Expire registrations older than `ttl_secs` (`policy.session_ttl_secs`).
+    pub fn with_session_ttl(mut self, ttl_secs: Option<u64>) -> Self {
+        self.session_ttl_secs = ttl_secs;
+        self
+    }
+
     /// Resolve a session's role. Checks in order:
     /// 1. In-memory cache (SESSIONS DashMap)
     /// 2. Registration file on disk
@@ -56,17 +74,17 @@ impl SessionManager {
         }
 
         // 2. Check registration file on disk
-        let entries = registration::read_registration_file(&self.registration_file)?;
+        let entries = self.live_entries()?;
         if let Some(entry) = entries.get(session_id) {
             // We have a registration entry -- resolve the role from roles.yml
-            let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            let cwd = crate::config::project_root();
             let roles = RolesConfig::load_project(&cwd)?;
             return Ok(roles.get_role(&entry.role).cloned());
         }
 
         // 3. Check env var fallback
         if let Ok(role_name) = std::env::var("HOOKWISE_ROLE") {
-            let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            let cwd = crate::config::project_root();
             let roles = RolesConfig::load_project(&cwd)?;
             return Ok(roles.get_role(&role_name).cloned());
         }
@@ -74,11 +92,14 @@ impl SessionManager {
         Ok(None)
     }
 
-    /// Get the full session context, populating if needed.
+    /// Get the full session context, populating if needed. With a session
+    /// TTL, the lookup also counts as activity and restarts the TTL.
     pub fn get_or_populate(&self, session_id: &str, cwd: &str) -> Result<SessionContext> {
         // Check in-memory cache first
         if let Some(ctx) = SESSIONS.get(session_id) {
-            return Ok(ctx.clone());
+            let ctx = ctx.clone();
+            self.touch(session_id);
+            return Ok(ctx);
         }
 
         // Populate from registration file + git info
@@ -98,6 +119,7 @@ impl SessionManager {
             task_description: None,
             registered_at: None,
             disabled: false,
+            tags: HashMap::new(),
         };
 
         // Check if disabled
@@ -108,7 +130,7 @@ impl SessionManager {
         }
 
         // Check registration file
-        let entries = registration::read_registration_file(&self.registration_file)?;
+        let entries = self.live_entries()?;
         if let Some(entry) = entries.get(session_id) {
             let cwd_path = PathBuf::from(cwd);
             let roles = RolesConfig::load_project(&cwd_path)?;
@@ -127,6 +149,8 @@ impl SessionManager {
             ctx.agent_prompt_hash = entry.prompt_hash.clone();
             ctx.agent_prompt_path = entry.prompt_path.as_ref().map(PathBuf::from);
             ctx.registered_at = Some(entry.registered_at);
+            ctx.tags = entry.tags.clone();
+            self.touch(session_id);
         } else if let Ok(role_name) = std::env::var("HOOKWISE_ROLE") {
             // Env var fallback
             let cwd_path = PathBuf::from(cwd);
@@ -148,13 +172,42 @@ impl SessionManager {
         Ok(ctx)
     }
 
-    /// Register a session with a role.
+    /// Drop any cached context for a session and load it again from the
+    /// registration file, e.g. when told the session was just registered.
+    pub fn refresh(&self, session_id: &str, cwd: &str) -> Result<SessionContext> {
+        SESSIONS.remove(session_id);
+        self.get_or_populate(session_id, cwd)
+    }
+
+    /// Register a session with a role. `registered_by` names who registered
+    /// it, for audit; it defaults to the current OS user.
     pub fn register(
         &self,
         session_id: &str,
         role_name: &str,
         task: Option<&str>,
         prompt_file: Option<&str>,
+        registered_by: Option<&str>,
+    ) -> Result<()> {
+        self.register_with_tags(
+            session_id,
+            role_name,
+            task,
+            prompt_file,
+            registered_by,
+            HashMap::new(),
+        )
+    }
+
+    /// Register a session with a role and metadata tags.
+    pub fn register_with_tags(
+        &self,
+        session_id: &str,
+        role_name: &str,
+        task: Option<&str>,
+        prompt_file: Option<&str>,
+        registered_by: Option<&str>,
+        tags: HashMap<String, String>,
     ) -> Result<()> {
         let prompt_hash = prompt_file.and_then(|p| {
             std::fs::read(p).ok().map(|bytes| {
@@ -170,7 +223,9 @@ impl SessionManager {
             prompt_hash,
             prompt_path: prompt_file.map(String::from),
             registered_at: Utc::now(),
-            registered_by: None,
+            registered_by: Some(registered_by.map_or_else(whoami, String::from)),
+            tags,
+            last_seen: None,
         };
 
         registration::write_registration_entry(&self.registration_file, session_id, &entry)?;
@@ -208,24 +263,42 @@ impl SessionManager {
         Ok(())
     }
 
-    /// Switch a session's role. Clears the session's cache entries.
-    pub fn switch_role(&self, session_id: &str, new_role: &str) -> Result<()> {
-        // Read existing entry to preserve task/prompt info
-        let entries = registration::read_registration_file(&self.registration_file)?;
-        let (task, prompt_file) = if let Some(existing) = entries.get(session_id) {
-            (existing.task.as_deref(), existing.prompt_path.as_deref())
-        } else {
-            (None, None)
-        };
+    /// Forget a session entirely: its registration (role, task, prompt, and
+    /// tags), any exclusion entry, and its in-memory cache entry.
+    pub fn unregister(&self, session_id: &str) -> Result<()> {
+        registration::remove_registration_entry(&self.registration_file, session_id)?;
+        if self.is_disabled(session_id) {
+            self.remove_exclusion(session_id)?;
+        }
+        SESSIONS.remove(session_id);
 
-        // Re-register with new role (owned copies to avoid borrow issues)
-        let task_owned = task.map(String::from);
-        let prompt_owned = prompt_file.map(String::from);
-        self.register(
+        Ok(())
+    }
+
+    /// Switch a session's role. Clears the session's cache entries. The
+    /// original registrant is kept unless `registered_by` names a new one.
+    pub fn switch_role(
+        &self,
+        session_id: &str,
+        new_role: &str,
+        registered_by: Option<&str>,
+    ) -> Result<()> {
+        // Read existing entry to preserve task/prompt info, registrant, and tags
+        let entries = registration::read_registration_file(&self.registration_file)?;
+        let existing = entries.get(session_id);
+        let task = existing.and_then(|e| e.task.as_deref());
+        let prompt_file = existing.and_then(|e| e.prompt_path.as_deref());
+        let original_by = existing.and_then(|e| e.registered_by.as_deref());
+        let tags = existing.map(|e| e.tags.clone()).unwrap_or_default();
+
+        // Re-register with new role
+        self.register_with_tags(
             session_id,
             new_role,
-            task_owned.as_deref(),
-            prompt_owned.as_deref(),
+            task,
+            prompt_file,
+            registered_by.or(original_by),
+            tags,
         )?;
 
         Ok(())
@@ -239,7 +312,7 @@ impl SessionManager {
         }
 
         // Check registration file
-        if let Ok(entries) = registration::read_registration_file(&self.registration_file) {
+        if let Ok(entries) = self.live_entries() {
             if entries.contains_key(session_id) {
                 return true;
             }
@@ -271,6 +344,84 @@ impl SessionManager {
         false
     }
 
+    /// Remove registrations older than the session TTL from the registration
+    /// file and return how many were removed. Sessions in the in-memory cache
+    /// are kept. Without a TTL nothing expires.
+    pub fn prune_expired(&self) -> Result<usize> {
+        if self.session_ttl_secs.is_none() {
+            return Ok(0);
+        }
+        registration::prune_registration_entries(&self.registration_file, |session_id, entry| {
+            self.is_expired(session_id, entry)
+        })
+    }
+
+    /// Registration entries that have not expired. Expired entries found
+    /// along the way are pruned from the file.
+    fn live_entries(&self) -> Result<HashMap<String, RegistrationEntry>> {
+        let mut entries = registration::read_registration_file(&self.registration_file)?;
+        if entries.iter().any(|(id, entry)| self.is_expired(id, entry)) {
+            self.prune_expired()?;
+            entries.retain(|id, entry| !self.is_expired(id, entry));
+        }
+        Ok(entries)
+    }
+
+    /// Record that `session_id` is in use, so the TTL runs from now. Only
+    /// done when a TTL is set; a failure is logged, not returned, since it
+    /// must not block the hook.
+    fn touch(&self, session_id: &str) {
+        if self.session_ttl_secs.is_none() {
+            return;
+        }
+        if let Err(e) =
+            registration::touch_registration_entry(&self.registration_file, session_id, Utc::now())
+        {
+            tracing::warn!("could not refresh session {}: {}", session_id, e);
+        }
+    }
+
+    /// Whether a registration has been idle longer than the TTL and is not in
+    /// use in this process.
+    fn is_expired(&self, session_id: &str, entry: &RegistrationEntry) -> bool {
+        let Some(ttl) = self.session_ttl_secs else {
+            return false;
+        };
+        let age = Utc::now().signed_duration_since(entry.last_seen.unwrap_or(entry.registered_at));
+        u64::try_from(age.num_seconds()).is_ok_and(|age| age > ttl)
+            && !SESSIONS.contains_key(session_id)
+    }
+
+    /// Every session in the registration and exclusion files, sorted by id.
+    pub fn list(&self) -> Result<Vec<SessionListing>> {
+        let entries = self.live_entries()?;
+        let exclusions = read_exclusion_file(&self.exclusion_file)?;
+
+        let mut listings: Vec<SessionListing> = entries
+            .into_iter()
+            .map(|(session_id, entry)| SessionListing {
+                disabled: exclusions.contains(&session_id),
+                session_id,
+                role: Some(entry.role),
+                task: entry.task,
+                registered_at: Some(entry.registered_at),
+            })
+            .collect();
+        for session_id in exclusions {
+            if !listings.iter().any(|l| l.session_id == session_id) {
+                listings.push(SessionListing {
+                    session_id,
+                    role: None,
+                    task: None,
+                    registered_at: None,
+                    disabled: true,
+                });
+            }
+        }
+        listings.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        Ok(listings)
+    }
+
     /// Wait for a session to be registered, polling every 200ms.
     pub async fn wait_for_registration(&self, session_id: &str, timeout_secs: u64) -> Result<()> {
         let start = std::time::Instant::now();
@@ -292,6 +443,13 @@ impl SessionManager {
         }
     }
 
+    /// Watch the session files and invalidate cached sessions whose entry
+    /// another process changes. A no-op handle is returned if the runtime
+    /// directory does not exist yet.
+    pub fn watch(&self) -> Result<WatchHandle> {
+        watch::watch(self.registration_file.clone(), self.exclusion_file.clone())
+    }
+
     fn add_exclusion(&self, session_id: &str) -> Result<()> {
         let mut exclusions = read_exclusion_file(&self.exclusion_file)?;
         if !exclusions.contains(&session_id.to_string()) {
@@ -307,8 +465,19 @@ impl SessionManager {
     }
 }
 
+/// A session as listed by `hookwise sessions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionListing {
+    pub session_id: String,
+    /// `None` for a session that was disabled without being registered.
+    pub role: Option<String>,
+    pub task: Option<String>,
+    pub registered_at: Option<DateTime<Utc>>,
+    pub disabled: bool,
+}
+
 /// A registration entry from the on-disk sessions file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RegistrationEntry {
     pub role: String,
     pub task: Option<String>,
@@ -316,10 +485,26 @@ pub struct RegistrationEntry {
     pub prompt_path: Option<String>,
     pub registered_at: DateTime<Utc>,
     pub registered_by: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+    /// When a hook last looked the session up. The session TTL runs from
+    /// here, or from `registered_at` if it has not been seen yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// Parse a `key=value` tag. The key must be non-empty; the value may be empty.
+pub fn parse_tag(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("invalid tag '{s}': expected key=value")),
+    }
 }
 
 /// Extract org and project name from git remote origin URL.
-fn extract_git_org_project(cwd: &str) -> (String, String) {
+pub(crate) fn extract_git_org_project(cwd: &str) -> (String, String) {
     let output = std::process::Command::new("git")
         .arg("remote")
         .arg("get-url")
@@ -336,45 +521,100 @@ fn extract_git_org_project(cwd: &str) -> (String, String) {
     }
 }
 
-/// Parse a git remote URL into (org, project).
+/// Parse a git remote URL into (org, project). The project is the last path
+/// segment and the org everything before it, so nested GitLab groups
+/// (`group/subgroup`) and Azure DevOps `org/project` pairs are kept whole.
 fn parse_git_remote_url(url: &str) -> (String, String) {
-    // Handle SSH: git@github.com:org/repo.git
-    if let Some(path) = url.strip_prefix("git@") {
-        if let Some(colon_pos) = path.find(':') {
-            let path_part = &path[colon_pos + 1..];
-            let path_part = path_part.strip_suffix(".git").unwrap_or(path_part);
-            let parts: Vec<&str> = path_part.splitn(2, '/').collect();
-            if parts.len() == 2 {
-                return (parts[0].to_string(), parts[1].to_string());
-            }
-        }
+    let path = if let Some(rest) = url.strip_prefix("ssh://") {
+        // ssh://git@host:2222/org/repo.git -- the port is part of the host
+        rest.split_once('/').map(|(_, path)| path)
+    } else if let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    {
+        // https://github.com/org/repo.git
+        rest.split_once('/').map(|(_, path)| path)
+    } else if let Some(rest) = url.strip_prefix("git@") {
+        // git@github.com:org/repo.git
+        rest.split_once(':').map(|(_, path)| path)
+    } else {
+        None
+    };
+
+    path.and_then(split_repo_path)
+        .unwrap_or_else(|| ("unknown".into(), "unknown".into()))
+}
+
+/// Split a repository path into (org, project). Azure DevOps's `_git`
+/// segment is not part of either.
+fn split_repo_path(path: &str) -> Option<(String, String)> {
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "_git")
+        .collect();
+    match segments.split_last() {
+        Some((project, org)) if !org.is_empty() => Some((org.join("/"), project.to_string())),
+        _ => None,
     }
+}
+
+/// Get the current OS username: `$USER` or `%USERNAME%` if set, otherwise
+/// (on Unix) the passwd entry of the current uid. On Windows the result is
+/// "unknown" when `%USERNAME%` is not set.
+pub(crate) fn whoami() -> String {
+    whoami_from(|name| std::env::var(name).ok())
+}
 
-    // Handle HTTPS: https://github.com/org/repo.git
-    if url.starts_with("https://") || url.starts_with("http://") {
-        let path = url
-            .split("//")
-            .nth(1)
-            .and_then(|s| s.split('/').skip(1).collect::<Vec<_>>().join("/").into());
+/// [`whoami`], reading environment variables through `var`.
+fn whoami_from(var: impl Fn(&str) -> Option<String>) -> String {
+    var("USER")
+        .or_else(|| var("USERNAME"))
+        .or_else(uid_name)
+        .unwrap_or_else(|| "unknown".into())
+}
 
-        if let Some(path_str) = path {
-            let path_str: &str = &path_str;
-            let path_str = path_str.strip_suffix(".git").unwrap_or(path_str);
-            let parts: Vec<&str> = path_str.splitn(2, '/').collect();
-            if parts.len() == 2 {
-                return (parts[0].to_string(), parts[1].to_string());
-            }
+/// The login name of the current uid, from the passwd database.
+#[cfg(unix)]
+fn uid_name() -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf_len = 1024;
+    loop {
+        let mut buf = vec![0 as libc::c_char; buf_len];
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        // The reentrant getpwuid: the static buffer of getpwuid is not
+        // safe to use from the supervisor's worker threads.
+        let ret = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if ret == libc::ERANGE && buf_len < 1 << 20 {
+            buf_len *= 2;
+            continue;
+        }
+        if ret != 0 || result.is_null() || pwd.pw_name.is_null() {
+            return None;
         }
+        let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+        return name
+            .to_str()
+            .ok()
+            .filter(|name| !name.is_empty())
+            .map(String::from);
     }
-
-    ("unknown".into(), "unknown".into())
 }
 
-/// Get the current OS username.
-fn whoami() -> String {
-    std::env::var("USER")
-        .or_else(|_| std::env::var("USERNAME"))
-        .unwrap_or_else(|_| "unknown".into())
+#[cfg(not(unix))]
+fn uid_name() -> Option<String> {
+    None
 }
 
 /// Read exclusion file (JSON array of session IDs).
@@ -410,15 +650,97 @@ fn runtime_dir() -> PathBuf {
         .unwrap_or_else(|_| PathBuf::from("/tmp"))
 }
 
+/// File holding the per-session call counts of `rate_limit`, shared by every
+/// `check` of a team (`solo` outside one).
+pub fn rate_limit_path(team_id: Option<&str>) -> PathBuf {
+    let suffix = team_id.unwrap_or("solo");
+    runtime_dir().join(format!("hookwise-{suffix}-rate-limit.json"))
+}
+
+/// Default path of the supervisor's Unix socket for a team (`solo` outside
+/// one), named like the session files beside it. Servers and clients both
+/// fall back to this when no `socket_path` is configured.
+pub fn supervisor_socket_path(team_id: Option<&str>) -> PathBuf {
+    let suffix = team_id.unwrap_or("solo");
+    runtime_dir().join(format!("hookwise-{suffix}-supervisor.sock"))
+}
+
 /// Set file permissions to 0600 (owner read/write only).
 #[cfg(unix)]
-fn set_file_permissions_0600(path: &std::path::Path) {
+pub(crate) fn set_file_permissions_0600(path: &std::path::Path) {
     use std::os::unix::fs::PermissionsExt;
     let perms = std::fs::Permissions::from_mode(0o600);
     let _ = std::fs::set_permissions(path, perms);
 }
 
 #[cfg(not(unix))]
-fn set_file_permissions_0600(_path: &std::path::Path) {
+pub(crate) fn set_file_permissions_0600(_path: &std::path::Path) {
     // No-op on non-Unix platforms
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(url: &str) -> (String, String) {
+        parse_git_remote_url(url)
+    }
+
+    fn pair(org: &str, project: &str) -> (String, String) {
+        (org.to_string(), project.to_string())
+    }
+
+    #[test]
+    fn test_github_urls() {
+        let expected = pair("Epiphytic", "hookwise");
+        assert_eq!(parsed("git@github.com:Epiphytic/hookwise.git"), expected);
+        assert_eq!(
+            parsed("https://github.com/Epiphytic/hookwise.git"),
+            expected
+        );
+        assert_eq!(parsed("https://github.com/Epiphytic/hookwise"), expected);
+        assert_eq!(parsed("http://github.com/Epiphytic/hookwise/"), expected);
+    }
+
+    #[test]
+    fn test_gitlab_nested_subgroup() {
+        let expected = pair("group/subgroup", "repo");
+        assert_eq!(
+            parsed("https://gitlab.com/group/subgroup/repo.git"),
+            expected
+        );
+        assert_eq!(parsed("git@gitlab.com:group/subgroup/repo.git"), expected);
+    }
+
+    #[test]
+    fn test_azure_devops_git_url() {
+        assert_eq!(
+            parsed("https://dev.azure.com/contoso/platform/_git/billing"),
+            pair("contoso/platform", "billing")
+        );
+    }
+
+    #[test]
+    fn test_ssh_url_with_port() {
+        assert_eq!(
+            parsed("ssh://git@git.example.com:2222/infra/deploy.git"),
+            pair("infra", "deploy")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_whoami_falls_back_to_uid_name() {
+        let name = whoami_from(|_| None);
+        assert_ne!(name, "unknown");
+        assert_eq!(Some(name), uid_name());
+    }
+
+    #[test]
+    fn test_unparseable_urls_are_unknown() {
+        let unknown = pair("unknown", "unknown");
+        assert_eq!(parsed("https://github.com/only-org"), unknown);
+        assert_eq!(parsed("/srv/git/repo.git"), unknown);
+        assert_eq!(parsed(""), unknown);
+    }
+}
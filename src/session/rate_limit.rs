@@ -0,0 +1,116 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+
+use super::registration::FileLock;
+use super::set_file_permissions_0600;
+
+/// Length of the sliding window, in seconds.
+const WINDOW_SECS: f64 = 60.0;
+
+/// Per-session limit on tool calls within a sliding one-minute window.
+///
+/// Only calls let through count against the window, so a session that backs
+/// off gets its full budget back a minute after its burst. Like the
+/// supervisor's token bucket, the state can be kept in a file so short-lived
+/// `hookwise check` processes share it. Each update holds a lock on the file
+/// and replaces it by rename, so concurrent checks don't lose each other's
+/// calls.
+pub struct RateLimiter {
+    max_per_minute: u32,
+    calls: Mutex<HashMap<String, VecDeque<f64>>>,
+    state_path: Option<PathBuf>,
+}
+
+impl RateLimiter {
+    /// An in-memory limiter letting each session make `max_per_minute` calls
+    /// in any one-minute window.
+    pub fn per_minute(max_per_minute: u32) -> Self {
+        Self {
+            max_per_minute,
+            calls: Mutex::new(HashMap::new()),
+            state_path: None,
+        }
+    }
+
+    /// A limiter whose state is shared through the file at `path`.
+    pub fn persisted(max_per_minute: u32, path: PathBuf) -> Self {
+        Self {
+            state_path: Some(path),
+            ..Self::per_minute(max_per_minute)
+        }
+    }
+
+    /// Count a call by `session_id` if the session is under its limit.
+    pub fn try_acquire(&self, session_id: &str) -> bool {
+        let now = Utc::now().timestamp_millis() as f64 / 1000.0;
+        self.try_acquire_at(session_id, now)
+    }
+
+    /// Count a call by `session_id` at time `now` (Unix seconds) if the
+    /// session is under its limit.
+    pub fn try_acquire_at(&self, session_id: &str, now: f64) -> bool {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        // Held until the updated state is written back
+        let _file_lock = self.state_path.as_deref().and_then(lock_state);
+        if let Some(on_disk) = self.state_path.as_deref().and_then(read_state) {
+            *calls = on_disk;
+        }
+
+        // Forget calls that have left the window, and sessions with none left
+        for window in calls.values_mut() {
+            while window.front().is_some_and(|&t| now - t >= WINDOW_SECS) {
+                window.pop_front();
+            }
+        }
+        calls.retain(|_, window| !window.is_empty());
+
+        let window = calls.entry(session_id.to_string()).or_default();
+        let granted = window.len() < self.max_per_minute as usize;
+        if granted {
+            window.push_back(now);
+        }
+        if window.is_empty() {
+            calls.remove(session_id);
+        }
+
+        if let Some(path) = &self.state_path {
+            if let Err(e) = write_state(path, &calls) {
+                tracing::warn!("could not save rate limit state {}: {}", path.display(), e);
+            }
+        }
+
+        granted
+    }
+}
+
+/// Lock the state file for a read-modify-write. Without the lock the limiter
+/// still works, only without protection from concurrent checks.
+fn lock_state(path: &Path) -> Option<FileLock> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match FileLock::acquire(path) {
+        Ok(lock) => Some(lock),
+        Err(e) => {
+            tracing::warn!("could not lock rate limit state {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Replace the state file with `calls`, readable only by its owner.
+fn write_state(path: &Path, calls: &HashMap<String, VecDeque<f64>>) -> crate::error::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_string(calls)?)?;
+    set_file_permissions_0600(&tmp_path);
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn read_state(path: &Path) -> Option<HashMap<String, VecDeque<f64>>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
@@ -4,7 +4,7 @@ use std::io::Write;
 use std::path::Path;
 
 use crate::error::Result;
-use crate::session::RegistrationEntry;
+use crate::session::{set_file_permissions_0600, RegistrationEntry};
 
 /// Read all registration entries from a file.
 pub fn read_registration_file(path: &Path) -> Result<HashMap<String, RegistrationEntry>> {
@@ -71,26 +71,79 @@ pub fn remove_registration_entry(path: &Path, session_id: &str) -> Result<()> {
     Ok(())
 }
 
-/// Set file permissions to 0600 (owner read/write only).
-#[cfg(unix)]
-fn set_file_permissions_0600(path: &Path) {
-    use std::os::unix::fs::PermissionsExt;
-    let perms = fs::Permissions::from_mode(0o600);
-    let _ = fs::set_permissions(path, perms);
+/// Remove every entry for which `expired` returns true, with file locking.
+/// Returns the number of entries removed; the file is only rewritten if
+/// that is non-zero.
+pub fn prune_registration_entries(
+    path: &Path,
+    expired: impl Fn(&str, &RegistrationEntry) -> bool,
+) -> Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let _lock = FileLock::acquire(path)?;
+
+    let mut entries = read_registration_file(path)?;
+    let before = entries.len();
+    entries.retain(|session_id, entry| !expired(session_id, entry));
+    let removed = before - entries.len();
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    let json = serde_json::to_string_pretty(&entries)?;
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+    }
+    set_file_permissions_0600(&tmp_path);
+    fs::rename(&tmp_path, path)?;
+    Ok(removed)
 }
 
-#[cfg(not(unix))]
-fn set_file_permissions_0600(_path: &Path) {
-    // No-op on non-Unix platforms
+/// Set `last_seen` on a session's entry, with file locking. A session that
+/// is not registered is left alone.
+pub fn touch_registration_entry(
+    path: &Path,
+    session_id: &str,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let _lock = FileLock::acquire(path)?;
+
+    let mut entries = read_registration_file(path)?;
+    let Some(entry) = entries.get_mut(session_id) else {
+        return Ok(());
+    };
+    entry.last_seen = Some(at);
+
+    let json = serde_json::to_string_pretty(&entries)?;
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+    }
+    set_file_permissions_0600(&tmp_path);
+    fs::rename(&tmp_path, path)?;
+    Ok(())
 }
 
 /// Advisory file lock using flock(2) on a .lock file.
-struct FileLock {
+pub(crate) struct FileLock {
     _file: fs::File,
 }
 
 impl FileLock {
-    fn acquire(path: &Path) -> Result<Self> {
+    /// Block until the lock beside `path` (`path` with a `.lock` extension)
+    /// is held.
+    pub(crate) fn acquire(path: &Path) -> Result<Self> {
         let lock_path = path.with_extension("lock");
         let file = fs::OpenOptions::new()
             .create(true)
@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{read_exclusion_file, registration, RegistrationEntry, SESSIONS};
+use crate::error::{HookwiseError, Result};
+
+/// A running watcher on the session files. Dropping it stops the watcher.
+pub struct WatchHandle {
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl WatchHandle {
+    /// Whether the files are being watched. False when the runtime directory
+    /// did not exist yet when the watch was requested.
+    pub fn is_active(&self) -> bool {
+        self.watcher.is_some()
+    }
+}
+
+/// The session files as the watcher last read them.
+struct Snapshot {
+    registrations: HashMap<String, RegistrationEntry>,
+    exclusions: HashSet<String>,
+}
+
+impl Snapshot {
+    /// Read both files. A file that cannot be read or parsed (e.g. caught
+    /// mid-write) reads as empty, which at worst drops more cache entries
+    /// than needed. `last_seen` is cleared: a session being used is not a
+    /// change to it.
+    fn read(registration_file: &Path, exclusion_file: &PathBuf) -> Self {
+        let mut registrations =
+            registration::read_registration_file(registration_file).unwrap_or_default();
+        for entry in registrations.values_mut() {
+            entry.last_seen = None;
+        }
+        Self {
+            registrations,
+            exclusions: read_exclusion_file(exclusion_file)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Sessions whose registration or exclusion differs between `self` and
+    /// `other`.
+    fn changed_sessions(&self, other: &Snapshot) -> HashSet<String> {
+        let mut changed: HashSet<String> = self
+            .exclusions
+            .symmetric_difference(&other.exclusions)
+            .cloned()
+            .collect();
+        for (session_id, entry) in &self.registrations {
+            if other.registrations.get(session_id) != Some(entry) {
+                changed.insert(session_id.clone());
+            }
+        }
+        for session_id in other.registrations.keys() {
+            if !self.registrations.contains_key(session_id) {
+                changed.insert(session_id.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Watch the registration and exclusion files and drop the cached context of
+/// every session whose entry changes on disk. The directory is watched
+/// rather than the files, since both are replaced rather than edited in
+/// place.
+pub(super) fn watch(registration_file: PathBuf, exclusion_file: PathBuf) -> Result<WatchHandle> {
+    let Some(dir) = registration_file.parent().filter(|dir| dir.is_dir()) else {
+        return Ok(WatchHandle { watcher: None });
+    };
+    let dir = dir.to_path_buf();
+
+    let watched: Vec<_> = [&registration_file, &exclusion_file]
+        .iter()
+        .filter_map(|path| path.file_name().map(|name| name.to_os_string()))
+        .collect();
+    let snapshot = Mutex::new(Snapshot::read(&registration_file, &exclusion_file));
+
+    let handler = move |event: notify::Result<Event>| {
+        let event = match event {
+            Ok(event) if !event.need_rescan() => event,
+            // Events were lost, so any session may be stale
+            _ => {
+                SESSIONS.clear();
+                return;
+            }
+        };
+        let relevant = event.paths.iter().any(|path| {
+            path.file_name()
+                .is_some_and(|name| watched.iter().any(|w| w == name))
+        });
+        if !relevant {
+            return;
+        }
+
+        let current = Snapshot::read(&registration_file, &exclusion_file);
+        let mut previous = snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        for session_id in previous.changed_sessions(&current) {
+            SESSIONS.remove(&session_id);
+        }
+        *previous = current;
+    };
+
+    let mut watcher = notify::recommended_watcher(handler).map_err(watch_error)?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+    Ok(WatchHandle {
+        watcher: Some(watcher),
+    })
+}
+
+fn watch_error(err: notify::Error) -> HookwiseError {
+    HookwiseError::Watch {
+        reason: err.to_string(),
+    }
+}
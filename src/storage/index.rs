@@ -26,14 +26,18 @@ impl HnswIndexStore {
         Ok(())
     }
 
-    /// Save an index to disk.
+    /// Save an index to disk. The file is replaced by rename, so a process
+    /// loading it concurrently never reads a partial index.
     pub fn save(&self, name: &str, data: &[u8]) -> Result<()> {
         Self::validate_name(name)?;
         fs::create_dir_all(&self.index_dir)?;
         let path = self.index_dir.join(name);
-        fs::write(&path, data).map_err(|e| HookwiseError::Storage {
-            reason: format!("failed to write index {}: {}", path.display(), e),
-        })
+        let tmp_path = self.index_dir.join(format!("{name}.tmp"));
+        fs::write(&tmp_path, data)
+            .and_then(|()| fs::rename(&tmp_path, &path))
+            .map_err(|e| HookwiseError::Storage {
+                reason: format!("failed to write index {}: {}", path.display(), e),
+            })
     }
 
     /// Load an index from disk.
@@ -1,19 +1,32 @@
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
-use crate::decision::{Decision, DecisionRecord};
-use crate::error::Result;
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::decision::{CacheKey, Decision, DecisionRecord};
+use crate::error::{HookwiseError, Result};
 use crate::sanitize::SanitizePipeline;
 use crate::scope::ScopeLevel;
 
-use super::{SecretFinding, StorageBackend};
+use super::usage::{apply_usage, UsageFile};
+use super::{RuleUsage, SecretFinding, StorageBackend};
 
 /// JSONL-based storage implementation.
+///
+/// Rule files may be plain (`allow.jsonl`) or gzip-compressed
+/// (`allow.jsonl.gz`). Both are always read; new records are appended to the
+/// compressed file only when compression is enabled. Each appended record is
+/// its own gzip member, so appends never rewrite the file.
 pub struct JsonlStorage {
     project_root: PathBuf,
     global_root: PathBuf,
     org_name: Option<String>,
+    compress: bool,
 }
 
 impl JsonlStorage {
@@ -22,13 +35,21 @@ impl JsonlStorage {
             project_root,
             global_root,
             org_name,
+            compress: false,
         }
     }
 
+    /// Write new records to gzip-compressed `.jsonl.gz` files.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
     /// Resolve the directory path for a given scope.
     fn scope_dir(&self, scope: ScopeLevel) -> PathBuf {
         match scope {
             ScopeLevel::Project => self.project_root.join("rules"),
+            ScopeLevel::Global => self.global_root.join("global").join("rules"),
             ScopeLevel::Org => {
                 let org = self.org_name.as_deref().unwrap_or("default");
                 self.global_root.join("org").join(org).join("rules")
@@ -38,25 +59,85 @@ impl JsonlStorage {
         }
     }
 
-    /// Resolve the JSONL file path for a given scope and decision type.
+    /// Resolve the JSONL file path new records are written to for a given
+    /// scope and decision type.
     fn jsonl_path(&self, scope: ScopeLevel, decision: Decision) -> PathBuf {
-        let dir = self.scope_dir(scope);
-        let filename = match decision {
-            Decision::Allow => "allow.jsonl",
-            Decision::Deny => "deny.jsonl",
-            Decision::Ask => "ask.jsonl",
-        };
-        dir.join(filename)
+        let [plain, gz] = self.jsonl_paths(scope, decision);
+        if self.compress {
+            gz
+        } else {
+            plain
+        }
+    }
+
+    /// Both the plain and compressed file paths for a scope and decision type.
+    fn jsonl_paths(&self, scope: ScopeLevel, decision: Decision) -> [PathBuf; 2] {
+        rule_paths(&self.scope_dir(scope), decision)
+    }
+
+    /// Find an immutable record pinning `record`'s key at its scope or any
+    /// broader one.
+    fn find_pinned(&self, record: &DecisionRecord) -> Result<Option<DecisionRecord>> {
+        Ok(self
+            .pins_from(record.scope)?
+            .into_iter()
+            .find(|pin| pin.pins(&record.key)))
+    }
+
+    /// The immutable records at `narrowest` and every broader scope,
+    /// broadest first. Only lines mentioning `immutable` are parsed, and a
+    /// directory shared by two scopes is read once, so a pin check costs
+    /// one pass over the bytes of the relevant rule files.
+    fn pins_from(&self, narrowest: ScopeLevel) -> Result<Vec<DecisionRecord>> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for scope in [
+            ScopeLevel::Global,
+            ScopeLevel::Org,
+            ScopeLevel::Project,
+            ScopeLevel::User,
+            ScopeLevel::Role,
+        ] {
+            let dir = self.scope_dir(scope);
+            if scope.precedence() >= narrowest.precedence() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+
+        let mut pins = Vec::new();
+        for dir in dirs {
+            for decision in [Decision::Allow, Decision::Deny, Decision::Ask] {
+                for path in rule_paths(&dir, decision) {
+                    if !path.exists() {
+                        continue;
+                    }
+                    for line in open_reader(&path)?.lines() {
+                        let line = line?;
+                        if !line.contains("immutable") {
+                            continue;
+                        }
+                        if let Ok(record) = serde_json::from_str::<DecisionRecord>(line.trim()) {
+                            if record.immutable {
+                                pins.push(record);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(pins)
     }
 
-    /// Read all decision records from a JSONL file.
+    /// Read all decision records from a JSONL file (plain or `.gz`). Lines
+    /// that do not parse are skipped and quarantined in the file's
+    /// `.corrupt` sidecar; if the sidecar cannot be written, that is logged
+    /// and the load goes on.
     fn read_jsonl_file(path: &Path) -> Result<Vec<DecisionRecord>> {
         if !path.exists() {
             return Ok(Vec::new());
         }
-        let file = fs::File::open(path)?;
-        let reader = BufReader::new(file);
+        let reader = open_reader(path)?;
         let mut records = Vec::new();
+        let mut corrupt = Vec::new();
 
         for (line_num, line) in reader.lines().enumerate() {
             let line = line?;
@@ -65,7 +146,10 @@ impl JsonlStorage {
                 continue;
             }
             match serde_json::from_str::<DecisionRecord>(trimmed) {
-                Ok(record) => records.push(record),
+                Ok(mut record) => {
+                    record.ensure_id();
+                    records.push(record);
+                }
                 Err(e) => {
                     tracing::warn!(
                         "skipping malformed line {} in {}: {}",
@@ -73,21 +157,106 @@ impl JsonlStorage {
                         path.display(),
                         e
                     );
+                    corrupt.push((line_num + 1, line));
                 }
             }
         }
 
+        if !corrupt.is_empty() {
+            if let Err(e) = quarantine(path, &corrupt) {
+                tracing::warn!(
+                    "could not quarantine {} line(s) of {}: {}",
+                    corrupt.len(),
+                    path.display(),
+                    e
+                );
+            }
+        }
         Ok(records)
     }
 
+    /// Every quarantined line under the rule directories this storage reads,
+    /// including the rules of every org, not just the current one.
+    pub fn quarantined_lines(&self) -> Result<Vec<QuarantinedLine>> {
+        let mut dirs = vec![
+            self.scope_dir(ScopeLevel::Project),
+            self.scope_dir(ScopeLevel::User),
+            self.scope_dir(ScopeLevel::Global),
+        ];
+        let org_root = self.global_root.join("org");
+        if org_root.is_dir() {
+            for entry in fs::read_dir(&org_root)? {
+                dirs.push(entry?.path().join("rules"));
+            }
+        }
+
+        let mut lines = Vec::new();
+        for dir in dirs {
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut sidecars: Vec<PathBuf> = fs::read_dir(&dir)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == CORRUPT_EXT))
+                .collect();
+            sidecars.sort();
+            for sidecar in sidecars {
+                lines.extend(read_quarantine(&sidecar)?);
+            }
+        }
+        Ok(lines)
+    }
+
     /// Append a record to a JSONL file, creating parent dirs if needed.
     fn append_jsonl_file(path: &Path, record: &DecisionRecord) -> Result<()> {
+        Self::append_jsonl_records(path, std::slice::from_ref(record))
+    }
+
+    /// Append records to a JSONL file through one buffered writer, creating
+    /// parent dirs if needed. A compressed file gets them as a single gzip
+    /// member.
+    fn append_jsonl_records(path: &Path, records: &[DecisionRecord]) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
-        let json = serde_json::to_string(record)?;
-        writeln!(file, "{}", json)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Self::write_records(file, is_gzip(path), records)
+    }
+
+    /// Copy `path` to `staging` and append `records` to the copy, in
+    /// `path`'s format.
+    fn stage_append(path: &Path, staging: &Path, records: &[DecisionRecord]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if path.exists() {
+            fs::copy(path, staging)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(staging)?;
+        Self::write_records(file, is_gzip(path), records)
+    }
+
+    /// Write records to `file` through one buffered writer, compressed as a
+    /// single gzip member if `gzip` is set.
+    fn write_records<'a>(
+        file: fs::File,
+        gzip: bool,
+        records: impl IntoIterator<Item = &'a DecisionRecord>,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(file);
+        if gzip {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::default());
+            for record in records {
+                writeln!(encoder, "{}", serde_json::to_string(record)?)?;
+            }
+            encoder.finish()?;
+        } else {
+            for record in records {
+                writeln!(writer, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+        writer.flush()?;
         Ok(())
     }
 
@@ -101,27 +270,238 @@ impl JsonlStorage {
         }
         let records = Self::read_jsonl_file(path)?;
         let kept: Vec<&DecisionRecord> = records.iter().filter(|r| predicate(r)).collect();
+        Self::write_jsonl_file(path, &kept)
+    }
+
+    /// Keep only the newest record per key (pinned and learned records are
+    /// kept apart), preserving file order. Returns how many were dropped.
+    fn compact_jsonl_file(path: &Path) -> Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let records = Self::read_jsonl_file(path)?;
+        let mut newest: HashMap<(&CacheKey, bool), usize> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            let slot = newest.entry((&record.key, record.immutable)).or_insert(i);
+            // On equal timestamps the later line wins
+            if record.timestamp >= records[*slot].timestamp {
+                *slot = i;
+            }
+        }
+        let dropped = records.len() - newest.len();
+        if dropped > 0 {
+            let keep: HashSet<usize> = newest.into_values().collect();
+            let kept: Vec<&DecisionRecord> = records
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| keep.contains(i))
+                .map(|(_, r)| r)
+                .collect();
+            Self::write_jsonl_file(path, &kept)?;
+        }
+        Ok(dropped)
+    }
+
+    /// The usage file holding hits on `scope`'s rules.
+    fn usage_file(&self, scope: ScopeLevel) -> UsageFile {
+        UsageFile::for_rules_dir(&self.scope_dir(scope))
+    }
+
+    /// Drop records unused since `cutoff`, going by their hits in `usage`,
+    /// from a JSONL file. Returns how many were dropped.
+    fn prune_jsonl_file(path: &Path, cutoff: DateTime<Utc>, usage: &[RuleUsage]) -> Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let records = Self::read_jsonl_file(path)?;
+        let kept: Vec<&DecisionRecord> = records
+            .iter()
+            .filter(|r| {
+                let mut used = (*r).clone();
+                apply_usage(&mut used, usage);
+                !super::is_prunable(&used, cutoff)
+            })
+            .collect();
+        let dropped = records.len() - kept.len();
+        if dropped > 0 {
+            Self::write_jsonl_file(path, &kept)?;
+        }
+        Ok(dropped)
+    }
+
+    /// Drop the unpinned records at `scope` whose id is in `ids` from a JSONL
+    /// file, rewriting it only if one matched.
+    fn delete_from_jsonl_file(
+        path: &Path,
+        scope: ScopeLevel,
+        ids: &HashSet<&str>,
+    ) -> Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let records = Self::read_jsonl_file(path)?;
+        let kept: Vec<&DecisionRecord> = records
+            .iter()
+            .filter(|r| r.scope != scope || r.immutable || !ids.contains(r.id.as_str()))
+            .collect();
+        let dropped = records.len() - kept.len();
+        if dropped > 0 {
+            Self::write_jsonl_file(path, &kept)?;
+        }
+        Ok(dropped)
+    }
 
-        // Write the filtered records back
+    /// Replace a JSONL file's contents with `records`, in the file's own
+    /// format. The new contents are written to a staging file and renamed
+    /// over the old, so readers never see a half-written file.
+    fn write_jsonl_file(path: &Path, records: &[&DecisionRecord]) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        let mut file = fs::File::create(path)?;
-        for record in kept {
-            let json = serde_json::to_string(record)?;
-            writeln!(file, "{}", json)?;
+        let staging = staging_path(path);
+        let written = fs::File::create(&staging)
+            .map_err(HookwiseError::from)
+            .and_then(|file| Self::write_records(file, is_gzip(path), records.iter().copied()));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&staging);
+            return Err(e);
         }
+        fs::rename(&staging, path)?;
         Ok(())
     }
 }
 
+/// Extension of the sidecar holding a rule file's unparseable lines.
+const CORRUPT_EXT: &str = "corrupt";
+
+/// A rule file line that could not be parsed and was quarantined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedLine {
+    /// The rule file the line was read from.
+    pub file: PathBuf,
+    /// 1-based line number in that file.
+    pub line: usize,
+    /// The line as it was read.
+    pub content: String,
+}
+
+/// The sidecar next to a rule file, e.g. `allow.jsonl.corrupt`.
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(CORRUPT_EXT);
+    path.with_file_name(name)
+}
+
+/// Append `lines` (line number, content) to `path`'s sidecar as
+/// `<line>\t<content>`, skipping any already there, so reloading the same
+/// damaged file does not grow it.
+fn quarantine(path: &Path, lines: &[(usize, String)]) -> Result<()> {
+    let sidecar = corrupt_path(path);
+    let known: HashSet<(usize, String)> = read_quarantine(&sidecar)?
+        .into_iter()
+        .map(|q| (q.line, q.content))
+        .collect();
+    let mut body = String::new();
+    for (line, content) in lines {
+        if !known.contains(&(*line, content.clone())) {
+            body.push_str(&format!("{line}\t{content}\n"));
+        }
+    }
+    if !body.is_empty() {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&sidecar)?;
+        file.write_all(body.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read a `.corrupt` sidecar. A missing sidecar has no lines.
+fn read_quarantine(sidecar: &Path) -> Result<Vec<QuarantinedLine>> {
+    if !sidecar.exists() {
+        return Ok(Vec::new());
+    }
+    let file = sidecar.with_extension("");
+    let mut lines = Vec::new();
+    for entry in fs::read_to_string(sidecar)?.lines() {
+        if let Some((line, content)) = entry.split_once('\t') {
+            if let Ok(line) = line.parse() {
+                lines.push(QuarantinedLine {
+                    file: file.clone(),
+                    line,
+                    content: content.to_string(),
+                });
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// The plain and compressed rule files in `dir` for a decision type.
+fn rule_paths(dir: &Path, decision: Decision) -> [PathBuf; 2] {
+    let filename = match decision {
+        Decision::Allow => "allow.jsonl",
+        Decision::Deny => "deny.jsonl",
+        Decision::Ask => "ask.jsonl",
+    };
+    [dir.join(filename), dir.join(format!("{filename}.gz"))]
+}
+
+/// Where the next contents of the rule file at `path` are written before
+/// being renamed over it, e.g. `.allow.jsonl.1234.tmp`. Hidden, and not
+/// named like a rule file, so nothing loads it by mistake.
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+/// Whether a path names a gzip-compressed rule file.
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Open a rule file for line reading, decompressing `.gz` files.
+fn open_reader(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file = fs::File::open(path)?;
+    let reader: Box<dyn Read> = if is_gzip(path) {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
 impl StorageBackend for JsonlStorage {
     fn load_decisions(&self, scope: ScopeLevel) -> Result<Vec<DecisionRecord>> {
         let mut all = Vec::new();
         for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
-            let path = self.jsonl_path(scope, *decision);
-            let records = Self::read_jsonl_file(&path)?;
-            all.extend(records);
+            for path in self.jsonl_paths(scope, *decision) {
+                all.extend(Self::read_jsonl_file(&path)?);
+            }
+        }
+        // The same record saved twice (e.g. an imported file) is loaded once.
+        // Different records sharing an id are still deduplicated, but loudly.
+        let mut seen: HashMap<String, (CacheKey, Decision)> = HashMap::new();
+        all.retain(|r| match seen.get(&r.id) {
+            None => {
+                seen.insert(r.id.clone(), (r.key.clone(), r.decision));
+                true
+            }
+            Some((key, decision)) => {
+                if *key != r.key || *decision != r.decision {
+                    tracing::warn!(
+                        "decision id {} is shared by different records; keeping the first",
+                        r.id
+                    );
+                }
+                false
+            }
+        });
+        let usage = self.usage_file(scope).read()?;
+        for record in &mut all {
+            apply_usage(record, &usage);
         }
         Ok(all)
     }
@@ -139,28 +519,151 @@ impl StorageBackend for JsonlStorage {
     }
 
     fn save_decision(&self, record: &DecisionRecord) -> Result<()> {
+        if !record.immutable {
+            if let Some(pinned) = self.find_pinned(record)? {
+                return Err(HookwiseError::ImmutableDecision {
+                    tool: record.key.tool.clone(),
+                    role: record.key.role.clone(),
+                    scope: pinned.scope.to_string(),
+                });
+            }
+        }
+        let mut record = record.clone();
+        record.ensure_id();
         let path = self.jsonl_path(record.scope, record.decision);
-        Self::append_jsonl_file(&path, record)
+        Self::append_jsonl_file(&path, &record)
+    }
+
+    /// Every record is checked against pinned rules before anything is
+    /// written, so a refused record leaves storage untouched. Records are
+    /// then grouped by target file; each file is staged as a copy with its
+    /// records appended, and the copies are renamed into place only once all
+    /// of them are written, so a failure leaves every file as it was.
+    fn save_decisions(&self, records: &[DecisionRecord]) -> Result<()> {
+        let narrowest = records
+            .iter()
+            .filter(|r| !r.immutable)
+            .map(|r| r.scope)
+            .min_by_key(|scope| scope.precedence());
+        if let Some(narrowest) = narrowest {
+            let pins = self.pins_from(narrowest)?;
+            for record in records.iter().filter(|r| !r.immutable) {
+                if let Some(pin) = pins.iter().find(|p| {
+                    p.scope.precedence() >= record.scope.precedence() && p.pins(&record.key)
+                }) {
+                    return Err(HookwiseError::ImmutableDecision {
+                        tool: record.key.tool.clone(),
+                        role: record.key.role.clone(),
+                        scope: pin.scope.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut by_file: HashMap<PathBuf, Vec<DecisionRecord>> = HashMap::new();
+        for record in records {
+            let mut record = record.clone();
+            record.ensure_id();
+            by_file
+                .entry(self.jsonl_path(record.scope, record.decision))
+                .or_default()
+                .push(record);
+        }
+        let mut staged = Vec::new();
+        let mut written = Ok(());
+        for (path, records) in &by_file {
+            let staging = staging_path(path);
+            staged.push((staging.clone(), path));
+            written = Self::stage_append(path, &staging, records);
+            if written.is_err() {
+                break;
+            }
+        }
+        if let Err(e) = written {
+            for (staging, _) in &staged {
+                let _ = fs::remove_file(staging);
+            }
+            return Err(e);
+        }
+        for (staging, path) in staged {
+            fs::rename(staging, path)?;
+        }
+        Ok(())
     }
 
     fn invalidate_role(&self, scope: ScopeLevel, role: &str) -> Result<()> {
         for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
-            let path = self.jsonl_path(scope, *decision);
-            Self::filter_jsonl_file(&path, |r| r.key.role != role)?;
+            for path in self.jsonl_paths(scope, *decision) {
+                Self::filter_jsonl_file(&path, |r| r.key.role != role)?;
+            }
         }
         Ok(())
     }
 
     fn invalidate_all(&self, scope: ScopeLevel) -> Result<()> {
         for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
-            let path = self.jsonl_path(scope, *decision);
-            if path.exists() {
-                fs::remove_file(&path)?;
+            for path in self.jsonl_paths(scope, *decision) {
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn compact(&self, scope: ScopeLevel) -> Result<usize> {
+        let mut dropped = 0;
+        for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
+            for path in self.jsonl_paths(scope, *decision) {
+                dropped += Self::compact_jsonl_file(&path)?;
             }
         }
+        Ok(dropped)
+    }
+
+    /// Hits go to the usage file beside each scope's rules, never to the
+    /// rule files themselves; loads add them back onto the records.
+    fn record_usage(&self, usage: &[RuleUsage]) -> Result<()> {
+        let mut by_file: HashMap<PathBuf, (UsageFile, Vec<&RuleUsage>)> = HashMap::new();
+        for entry in usage {
+            let file = self.usage_file(entry.scope);
+            by_file
+                .entry(file.path().to_path_buf())
+                .or_insert_with(|| (file, Vec::new()))
+                .1
+                .push(entry);
+        }
+        for (file, entries) in by_file.into_values() {
+            file.add(&entries)?;
+        }
         Ok(())
     }
 
+    fn prune_unused(&self, scope: ScopeLevel, cutoff: DateTime<Utc>) -> Result<usize> {
+        let usage = self.usage_file(scope).read()?;
+        let mut dropped = 0;
+        for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
+            for path in self.jsonl_paths(scope, *decision) {
+                dropped += Self::prune_jsonl_file(&path, cutoff, &usage)?;
+            }
+        }
+        Ok(dropped)
+    }
+
+    fn delete_decisions(&self, scope: ScopeLevel, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let mut dropped = 0;
+        for decision in &[Decision::Allow, Decision::Deny, Decision::Ask] {
+            for path in self.jsonl_paths(scope, *decision) {
+                dropped += Self::delete_from_jsonl_file(&path, scope, &ids)?;
+            }
+        }
+        Ok(dropped)
+    }
+
     fn rebuild_index(&self, _scope: ScopeLevel) -> Result<()> {
         // Index rebuild is handled by the embedding/jaccard tiers, not storage.
         // This is a no-op placeholder that the cascade engine will call into
@@ -172,12 +675,15 @@ impl StorageBackend for JsonlStorage {
         let pipeline = SanitizePipeline::default_pipeline();
         let mut findings = Vec::new();
 
-        // Scan all JSONL files at the given path
+        // Scan all JSONL files (plain or compressed) at the given path
         let entries = if path.is_dir() {
             fs::read_dir(path)?
                 .filter_map(|e| e.ok())
-                .filter(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
                 .map(|e| e.path())
+                .filter(|p| {
+                    let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                    name.ends_with(".jsonl") || name.ends_with(".jsonl.gz")
+                })
                 .collect::<Vec<_>>()
         } else if path.exists() {
             vec![path.to_path_buf()]
@@ -186,19 +692,23 @@ impl StorageBackend for JsonlStorage {
         };
 
         for file_path in entries {
-            let file = fs::File::open(&file_path)?;
-            let reader = BufReader::new(file);
+            let lines = open_reader(&file_path)?
+                .lines()
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+            let sanitized = pipeline.sanitize_lines(&lines);
 
-            for (line_num, line) in reader.lines().enumerate() {
-                let line = line?;
-                let sanitized = pipeline.sanitize(&line);
+            for (line_num, (line, sanitized)) in lines.iter().zip(&sanitized).enumerate() {
                 if sanitized != line {
-                    findings.push(SecretFinding {
-                        file: file_path.clone(),
-                        line: line_num + 1,
-                        description: "potential secret detected in stored decision".into(),
-                        detector: "sanitize-pipeline".into(),
-                    });
+                    let (_, events) = pipeline.sanitize_with_report(line);
+                    findings.push(SecretFinding::from_report(
+                        file_path.clone(),
+                        line_num + 1,
+                        "potential secret detected in stored decision".into(),
+                        line,
+                        sanitized,
+                        &events,
+                    ));
                 }
             }
         }
@@ -210,67 +720,160 @@ impl StorageBackend for JsonlStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::decision::{CacheKey, DecisionMetadata, DecisionTier};
-    use chrono::Utc;
+    use crate::storage::tests::make_record;
+    use crate::storage::Severity;
     use tempfile::TempDir;
 
-    fn make_record(decision: Decision, role: &str) -> DecisionRecord {
-        DecisionRecord {
-            key: CacheKey {
-                sanitized_input: "test command".into(),
-                tool: "Bash".into(),
-                role: role.into(),
-            },
-            decision,
-            metadata: DecisionMetadata {
-                tier: DecisionTier::Human,
-                confidence: 1.0,
-                reason: "test".into(),
-                matched_key: None,
-                similarity_score: None,
-            },
-            timestamp: Utc::now(),
-            scope: ScopeLevel::Project,
-            file_path: None,
-            session_id: "test-session".into(),
-        }
+    #[test]
+    fn test_save_decisions_compressed_writes_one_member_per_file() {
+        let tmp = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None)
+            .with_compression(true);
+        let records: Vec<DecisionRecord> = (0..10)
+            .map(|i| {
+                let mut record = make_record(Decision::Allow, "coder");
+                record.key.sanitized_input = format!("command {i}");
+                record
+            })
+            .collect();
+        storage.save_decisions(&records).unwrap();
+
+        let path = tmp.path().join("rules/allow.jsonl.gz");
+        let mut decoder = flate2::read::GzDecoder::new(fs::File::open(&path).unwrap());
+        let mut first_member = String::new();
+        decoder.read_to_string(&mut first_member).unwrap();
+        assert_eq!(first_member.lines().count(), 10);
+        assert_eq!(
+            storage.load_decisions(ScopeLevel::Project).unwrap().len(),
+            10
+        );
     }
 
     #[test]
-    fn test_save_and_load() {
+    fn test_save_decisions_writes_no_file_if_one_fails() {
         let tmp = TempDir::new().unwrap();
-        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        // The global root is a plain file, so global rules cannot be written
+        let global_root = tmp.path().join("global");
+        fs::write(&global_root, "").unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), global_root, None);
+        storage
+            .save_decision(&make_record(Decision::Allow, "coder"))
+            .unwrap();
+        let rules = tmp.path().join("rules/allow.jsonl");
+        let before = fs::read(&rules).unwrap();
+
+        let mut global = make_record(Decision::Allow, "tester");
+        global.scope = ScopeLevel::Global;
+        let records = [make_record(Decision::Allow, "tester"), global];
+        assert!(storage.save_decisions(&records).is_err());
+
+        assert_eq!(fs::read(&rules).unwrap(), before);
+        let leftovers: Vec<_> = fs::read_dir(tmp.path().join("rules"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers, ["allow.jsonl"]);
+    }
 
-        let record = make_record(Decision::Allow, "coder");
-        storage.save_decision(&record).unwrap();
+    #[test]
+    fn test_pin_lookup_skips_narrower_scopes() {
+        let tmp = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None)
+            .with_compression(true);
+        let mut pinned = make_record(Decision::Deny, "*");
+        pinned.immutable = true;
+        storage.save_decision(&pinned).unwrap();
+
+        // A project pin, read from the compressed file, binds project records...
+        let pins = storage.pins_from(ScopeLevel::Project).unwrap();
+        assert_eq!(pins.len(), 1);
+        assert!(pins[0].pins(&make_record(Decision::Allow, "coder").key));
+        assert!(storage
+            .find_pinned(&make_record(Decision::Allow, "coder"))
+            .unwrap()
+            .is_some());
+        // ...but not broader ones
+        let mut global = make_record(Decision::Allow, "coder");
+        global.scope = ScopeLevel::Global;
+        assert!(storage.find_pinned(&global).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_malformed_line_is_quarantined() {
+        let tmp = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        let valid = serde_json::to_string(&make_record(Decision::Allow, "coder")).unwrap();
+        let truncated = &valid[..valid.len() / 2];
+        let path = tmp.path().join("rules/allow.jsonl");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("{valid}\n{truncated}\n")).unwrap();
 
         let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
         assert_eq!(loaded.len(), 1);
-        assert_eq!(loaded[0].decision, Decision::Allow);
+        assert_eq!(loaded[0].key.role, "coder");
+
+        let sidecar = fs::read_to_string(tmp.path().join("rules/allow.jsonl.corrupt")).unwrap();
+        assert_eq!(sidecar, format!("2\t{truncated}\n"));
+
+        // Loading again does not quarantine the same line twice
+        storage.load_decisions(ScopeLevel::Project).unwrap();
+        let quarantined = storage.quarantined_lines().unwrap();
+        assert_eq!(
+            quarantined,
+            vec![QuarantinedLine {
+                file: path,
+                line: 2,
+                content: truncated.to_string(),
+            }]
+        );
     }
 
     #[test]
-    fn test_load_for_role() {
+    fn test_failed_quarantine_does_not_fail_the_load() {
         let tmp = TempDir::new().unwrap();
         let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        let path = tmp.path().join("rules/allow.jsonl");
+        // A directory where the sidecar should go cannot be written to
+        fs::create_dir_all(tmp.path().join("rules/allow.jsonl.corrupt")).unwrap();
+        let valid = serde_json::to_string(&make_record(Decision::Allow, "coder")).unwrap();
+        fs::write(&path, format!("{valid}\nnot json\n")).unwrap();
 
-        storage
-            .save_decision(&make_record(Decision::Allow, "coder"))
-            .unwrap();
-        storage
-            .save_decision(&make_record(Decision::Allow, "tester"))
-            .unwrap();
-
-        let coder_records = storage
-            .load_decisions_for_role(ScopeLevel::Project, "coder")
-            .unwrap();
-        assert_eq!(coder_records.len(), 1);
+        let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+        assert_eq!(loaded.len(), 1);
     }
 
     #[test]
-    fn test_invalidate_role() {
+    fn test_compact_rewrites_file_in_place() {
         let tmp = TempDir::new().unwrap();
         let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        let now = Utc::now();
+        for (hours_ago, reason) in [(2, "oldest"), (0, "newest"), (1, "middle")] {
+            let mut record = make_record(Decision::Allow, "coder");
+            record.timestamp = now - chrono::Duration::hours(hours_ago);
+            record.metadata.reason = reason.into();
+            storage.save_decision(&record).unwrap();
+        }
+        let mut other = make_record(Decision::Allow, "tester");
+        other.timestamp = now;
+        storage.save_decision(&other).unwrap();
+
+        assert_eq!(storage.compact(ScopeLevel::Project).unwrap(), 2);
+        let path = tmp.path().join("rules/allow.jsonl");
+        let lines = fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        let records = JsonlStorage::read_jsonl_file(&path).unwrap();
+        let coder: Vec<_> = records.iter().filter(|r| r.key.role == "coder").collect();
+        assert_eq!(coder.len(), 1);
+        assert_eq!(coder[0].metadata.reason, "newest");
+
+        assert_eq!(storage.compact(ScopeLevel::Project).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None)
+            .with_compression(true);
 
         storage
             .save_decision(&make_record(Decision::Allow, "coder"))
@@ -279,30 +882,69 @@ mod tests {
             .save_decision(&make_record(Decision::Allow, "tester"))
             .unwrap();
 
+        let gz_path = tmp.path().join("rules").join("allow.jsonl.gz");
+        assert!(gz_path.exists());
+        assert!(!tmp.path().join("rules").join("allow.jsonl").exists());
+
+        // Two appended gzip members decode as one stream
+        let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+        assert_eq!(loaded.len(), 2);
+
         storage
             .invalidate_role(ScopeLevel::Project, "coder")
             .unwrap();
-
         let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
         assert_eq!(loaded.len(), 1);
         assert_eq!(loaded[0].key.role, "tester");
     }
 
     #[test]
-    fn test_invalidate_all() {
+    fn test_reads_plain_and_compressed_side_by_side() {
         let tmp = TempDir::new().unwrap();
-        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
-
-        storage
+        let plain = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        plain
             .save_decision(&make_record(Decision::Allow, "coder"))
             .unwrap();
-        storage
-            .save_decision(&make_record(Decision::Deny, "tester"))
+
+        // Enabling compression later keeps existing plain records visible
+        let compressed =
+            JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None)
+                .with_compression(true);
+        compressed
+            .save_decision(&make_record(Decision::Deny, "coder"))
             .unwrap();
 
-        storage.invalidate_all(ScopeLevel::Project).unwrap();
+        let loaded = compressed.load_decisions(ScopeLevel::Project).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(plain.load_decisions(ScopeLevel::Project).unwrap().len(), 2);
 
-        let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
-        assert_eq!(loaded.len(), 0);
+        compressed.invalidate_all(ScopeLevel::Project).unwrap();
+        assert!(compressed
+            .load_decisions(ScopeLevel::Project)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_scan_for_secrets_rates_severity() {
+        let tmp = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+        let file = tmp.path().join("leaked.jsonl");
+        fs::write(
+            &file,
+            "{\"input\":\"echo hello\"}\n\
+             {\"input\":\"git push https://ghp_abc123def456ghi789@github.com\"}\n\
+             {\"input\":\"run x7Kp2mN9qR4sW1tY6uV3bE8cF5gH0jA\"}\n",
+        )
+        .unwrap();
+
+        let findings = storage.scan_for_secrets(&file).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].line, 2);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[0].detector, "aho-corasick");
+        assert!(findings[0].matched_excerpt.contains("<REDACTED>"));
+        assert!(!findings[0].matched_excerpt.contains("ghp_"));
+        assert_eq!(findings[1].severity, Severity::Medium);
     }
 }
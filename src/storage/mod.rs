@@ -1,12 +1,41 @@
+pub mod audit;
 pub mod index;
 pub mod jsonl;
+pub mod sqlite;
+pub mod usage;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::decision::DecisionRecord;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{StorageBackendKind, StorageConfig};
+use crate::decision::{CacheKey, DecisionRecord};
 use crate::error::Result;
+use crate::sanitize::RedactionEvent;
 use crate::scope::ScopeLevel;
 
+/// Open the storage backend `config` selects, rooted at the project's
+/// `.hookwise` directory and the global config directory.
+pub fn open_backend(
+    config: &StorageConfig,
+    project_root: PathBuf,
+    global_root: PathBuf,
+    org_name: Option<String>,
+) -> Result<Box<dyn StorageBackend>> {
+    Ok(match config.backend {
+        StorageBackendKind::Jsonl => Box::new(
+            jsonl::JsonlStorage::new(project_root, global_root, org_name)
+                .with_compression(config.compress),
+        ),
+        StorageBackendKind::Sqlite => Box::new(sqlite::SqliteStorage::open(
+            &project_root,
+            &global_root,
+            org_name,
+        )?),
+    })
+}
+
 /// Backend for loading and saving decision records.
 pub trait StorageBackend: Send + Sync {
     /// Load all decisions from storage for a given scope.
@@ -19,12 +48,49 @@ pub trait StorageBackend: Send + Sync {
     /// Save a single decision.
     fn save_decision(&self, record: &DecisionRecord) -> Result<()>;
 
+    /// Save many decisions. Backends that can batch writes should override
+    /// this; the default saves them one at a time.
+    fn save_decisions(&self, records: &[DecisionRecord]) -> Result<()> {
+        for record in records {
+            self.save_decision(record)?;
+        }
+        Ok(())
+    }
+
     /// Delete all decisions for a specific role within a scope.
     fn invalidate_role(&self, scope: ScopeLevel, role: &str) -> Result<()>;
 
     /// Delete all decisions within a scope.
     fn invalidate_all(&self, scope: ScopeLevel) -> Result<()>;
 
+    /// Collapse repeated records for the same key within a scope down to the
+    /// newest one, returning how many were removed. The default has nothing
+    /// to collapse.
+    fn compact(&self, _scope: ScopeLevel) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Add accumulated hits to the stored records they were resolved from.
+    /// Every record with a usage entry's key at its scope is updated. The
+    /// default does not track usage.
+    fn record_usage(&self, _usage: &[RuleUsage]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Delete the records within a scope that have not been used (or, if
+    /// never used, created) since `cutoff`, returning how many were removed.
+    /// Pinned records are always kept. The default has nothing to prune.
+    fn prune_unused(&self, _scope: ScopeLevel, _cutoff: DateTime<Utc>) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Delete the records with these ids within a scope, returning how many
+    /// were removed. Pinned records are always kept. The default deletes
+    /// nothing.
+    fn delete_decisions(&self, _scope: ScopeLevel, _ids: &[String]) -> Result<usize> {
+        Ok(0)
+    }
+
     /// Rebuild the HNSW index from stored decisions.
     fn rebuild_index(&self, scope: ScopeLevel) -> Result<()>;
 
@@ -32,11 +98,498 @@ pub trait StorageBackend: Send + Sync {
     fn scan_for_secrets(&self, path: &Path) -> Result<Vec<SecretFinding>>;
 }
 
+/// Hits on one stored rule since usage was last written back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleUsage {
+    /// Key of the record the hits resolved from.
+    pub key: CacheKey,
+    pub scope: ScopeLevel,
+    pub hits: u64,
+    pub last_used: DateTime<Utc>,
+}
+
+impl RuleUsage {
+    /// Whether these hits belong to `record`.
+    pub fn applies_to(&self, record: &DecisionRecord) -> bool {
+        record.scope == self.scope && record.key == self.key
+    }
+}
+
+/// Whether `record` is unused since `cutoff` and may be pruned.
+pub(crate) fn is_prunable(record: &DecisionRecord, cutoff: DateTime<Utc>) -> bool {
+    !record.immutable && record.last_active() < cutoff
+}
+
 /// A potential secret found during scanning.
 #[derive(Debug, Clone)]
 pub struct SecretFinding {
-    pub file: std::path::PathBuf,
+    pub file: PathBuf,
     pub line: usize,
     pub description: String,
     pub detector: String,
+    pub severity: Severity,
+    /// The line around the first redaction, with secrets already redacted.
+    pub matched_excerpt: String,
+}
+
+impl SecretFinding {
+    /// A finding for a line the sanitizer changed from `original` to
+    /// `sanitized`. It is as severe as the most severe layer in `events`,
+    /// which also name the detector.
+    pub fn from_report(
+        file: PathBuf,
+        line: usize,
+        description: String,
+        original: &str,
+        sanitized: &str,
+        events: &[RedactionEvent],
+    ) -> Self {
+        let mut layers: Vec<&str> = Vec::new();
+        for event in events {
+            if !layers.contains(&event.layer.as_str()) {
+                layers.push(&event.layer);
+            }
+        }
+        let severity = layers
+            .iter()
+            .map(|layer| Severity::of_layer(layer))
+            .max()
+            .unwrap_or(Severity::Medium);
+        let detector = if layers.is_empty() {
+            "sanitize-pipeline".to_string()
+        } else {
+            layers.join(", ")
+        };
+        Self {
+            file,
+            line,
+            description,
+            detector,
+            severity,
+            matched_excerpt: redacted_excerpt(original, sanitized),
+        }
+    }
+}
+
+/// How urgently a finding needs attention. Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Found only after decoding base64 or URL-encoded text.
+    Low,
+    /// A high-entropy token that may or may not be a secret.
+    Medium,
+    /// A known vendor prefix or a credential-shaped pattern.
+    High,
+}
+
+impl Severity {
+    /// The severity of a redaction made by the named sanitizer layer. Layers
+    /// this does not know (custom pipelines) are rated medium.
+    pub fn of_layer(layer: &str) -> Self {
+        match layer {
+            "aho-corasick" | "regex" | "jwt" | "credit-card" => Severity::High,
+            "encoding" => Severity::Low,
+            _ => Severity::Medium,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Characters of context kept before the first redaction in an excerpt.
+const EXCERPT_LEAD: usize = 20;
+/// Maximum length of an excerpt, in characters.
+const EXCERPT_LEN: usize = 80;
+
+/// A short window of `sanitized` starting just before its first difference
+/// from `original`, with `...` marking cut ends.
+fn redacted_excerpt(original: &str, sanitized: &str) -> String {
+    let first_change = original
+        .chars()
+        .zip(sanitized.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start = first_change.saturating_sub(EXCERPT_LEAD);
+    let total = sanitized.chars().count();
+    let mut excerpt: String = sanitized.chars().skip(start).take(EXCERPT_LEN).collect();
+    if start > 0 {
+        excerpt.insert_str(0, "...");
+    }
+    if start + EXCERPT_LEN < total {
+        excerpt.push_str("...");
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision::{CacheKey, Decision, DecisionMetadata, DecisionTier};
+    use crate::error::HookwiseError;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// A JSONL and a SQLite backend, each in its own temp dir.
+    fn backends() -> Vec<(TempDir, Box<dyn StorageBackend>)> {
+        let jsonl = TempDir::new().unwrap();
+        let sqlite = TempDir::new().unwrap();
+        let open = |tmp: &TempDir, kind| {
+            let config = StorageConfig {
+                backend: kind,
+                compress: false,
+            };
+            open_backend(
+                &config,
+                tmp.path().to_path_buf(),
+                tmp.path().join("global"),
+                None,
+            )
+            .unwrap()
+        };
+        let jsonl_storage = open(&jsonl, StorageBackendKind::Jsonl);
+        let sqlite_storage = open(&sqlite, StorageBackendKind::Sqlite);
+        vec![(jsonl, jsonl_storage), (sqlite, sqlite_storage)]
+    }
+
+    /// A learned project-scope `Bash` record of `test command`, shared by the
+    /// backends' tests.
+    pub(super) fn make_record(decision: Decision, role: &str) -> DecisionRecord {
+        DecisionRecord {
+            key: CacheKey {
+                sanitized_input: "test command".into(),
+                tool: "Bash".into(),
+                role: role.into(),
+            },
+            decision,
+            metadata: DecisionMetadata {
+                tier: DecisionTier::Human,
+                confidence: 1.0,
+                reason: "test".into(),
+                matched_key: None,
+                similarity_score: None,
+            },
+            timestamp: Utc::now(),
+            scope: ScopeLevel::Project,
+            file_path: None,
+            session_id: "test-session".into(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        for (_tmp, storage) in backends() {
+            let record = make_record(Decision::Allow, "coder");
+            storage.save_decision(&record).unwrap();
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 1);
+            assert_eq!(loaded[0].decision, Decision::Allow);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_records_load_once() {
+        for (_tmp, storage) in backends() {
+            let record = make_record(Decision::Allow, "coder");
+            assert!(record.id.is_empty());
+            storage.save_decision(&record).unwrap();
+            // JSONL appends the copy and skips it on load; SQLite refuses it
+            if let Err(e) = storage.save_decision(&record) {
+                assert!(e.to_string().contains("already stored"), "{e}");
+            }
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 1);
+            assert_eq!(loaded[0].id, record.derive_id());
+        }
+    }
+
+    #[test]
+    fn test_load_for_role() {
+        for (_tmp, storage) in backends() {
+            storage
+                .save_decision(&make_record(Decision::Allow, "coder"))
+                .unwrap();
+            storage
+                .save_decision(&make_record(Decision::Allow, "tester"))
+                .unwrap();
+
+            let coder_records = storage
+                .load_decisions_for_role(ScopeLevel::Project, "coder")
+                .unwrap();
+            assert_eq!(coder_records.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_invalidate_role() {
+        for (_tmp, storage) in backends() {
+            storage
+                .save_decision(&make_record(Decision::Allow, "coder"))
+                .unwrap();
+            storage
+                .save_decision(&make_record(Decision::Allow, "tester"))
+                .unwrap();
+
+            storage
+                .invalidate_role(ScopeLevel::Project, "coder")
+                .unwrap();
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 1);
+            assert_eq!(loaded[0].key.role, "tester");
+        }
+    }
+
+    #[test]
+    fn test_invalidate_all() {
+        for (_tmp, storage) in backends() {
+            storage
+                .save_decision(&make_record(Decision::Allow, "coder"))
+                .unwrap();
+            storage
+                .save_decision(&make_record(Decision::Deny, "tester"))
+                .unwrap();
+
+            storage.invalidate_all(ScopeLevel::Project).unwrap();
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_immutable_record_is_not_replaced() {
+        for (_tmp, storage) in backends() {
+            let mut pinned = make_record(Decision::Deny, "*");
+            pinned.scope = ScopeLevel::Org;
+            pinned.immutable = true;
+            storage.save_decision(&pinned).unwrap();
+
+            // Neither a learned decision at the same scope nor a narrower-scope
+            // role allow may be written over it.
+            let mut learned = make_record(Decision::Allow, "*");
+            learned.scope = ScopeLevel::Org;
+            assert!(matches!(
+                storage.save_decision(&learned),
+                Err(HookwiseError::ImmutableDecision { .. })
+            ));
+            assert!(matches!(
+                storage.save_decision(&make_record(Decision::Allow, "coder")),
+                Err(HookwiseError::ImmutableDecision { .. })
+            ));
+
+            // Another immutable record is an explicit re-pin and is allowed.
+            let mut repinned = make_record(Decision::Ask, "*");
+            repinned.scope = ScopeLevel::Org;
+            repinned.immutable = true;
+            storage.save_decision(&repinned).unwrap();
+
+            assert!(storage
+                .load_decisions(ScopeLevel::Project)
+                .unwrap()
+                .is_empty());
+            assert_eq!(storage.load_decisions(ScopeLevel::Org).unwrap().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_save_decisions_writes_nothing_if_one_is_pinned() {
+        for (_tmp, storage) in backends() {
+            let mut pinned = make_record(Decision::Deny, "*");
+            pinned.scope = ScopeLevel::Org;
+            pinned.immutable = true;
+            storage.save_decision(&pinned).unwrap();
+
+            let mut unrelated = make_record(Decision::Allow, "coder");
+            unrelated.key.sanitized_input = "cargo build".into();
+            let batch = [unrelated, make_record(Decision::Allow, "coder")];
+            assert!(matches!(
+                storage.save_decisions(&batch),
+                Err(HookwiseError::ImmutableDecision { .. })
+            ));
+            assert!(storage
+                .load_decisions(ScopeLevel::Project)
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_save_decisions_in_bulk() {
+        for (_tmp, storage) in backends() {
+            let records: Vec<DecisionRecord> = (0..100)
+                .map(|i| {
+                    let decision = [Decision::Allow, Decision::Deny, Decision::Ask][i % 3];
+                    let mut record = make_record(decision, "coder");
+                    record.key.sanitized_input = format!("command {i}");
+                    record
+                })
+                .collect();
+            storage.save_decisions(&records).unwrap();
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 100);
+            let count = |d: Decision| loaded.iter().filter(|r| r.decision == d).count();
+            assert_eq!(count(Decision::Allow), 34);
+            assert_eq!(count(Decision::Deny), 33);
+            assert_eq!(count(Decision::Ask), 33);
+        }
+    }
+
+    #[test]
+    fn test_compact_keeps_newest_record_per_key() {
+        for (_tmp, storage) in backends() {
+            let now = Utc::now();
+            for (hours_ago, reason) in [(2, "oldest"), (0, "newest"), (1, "middle")] {
+                let mut record = make_record(Decision::Allow, "coder");
+                record.timestamp = now - chrono::Duration::hours(hours_ago);
+                record.metadata.reason = reason.into();
+                storage.save_decision(&record).unwrap();
+            }
+            storage
+                .save_decision(&make_record(Decision::Allow, "tester"))
+                .unwrap();
+
+            assert_eq!(storage.compact(ScopeLevel::Project).unwrap(), 2);
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            assert_eq!(loaded.len(), 2);
+            let coder: Vec<_> = loaded.iter().filter(|r| r.key.role == "coder").collect();
+            assert_eq!(coder[0].metadata.reason, "newest");
+            assert_eq!(storage.compact(ScopeLevel::Project).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_record_usage_updates_matching_records() {
+        for (_tmp, storage) in backends() {
+            let coder = make_record(Decision::Allow, "coder");
+            let tester = make_record(Decision::Allow, "tester");
+            storage
+                .save_decisions(&[coder.clone(), tester.clone()])
+                .unwrap();
+
+            let at = Utc::now();
+            let usage = |hits, last_used| RuleUsage {
+                key: coder.key.clone(),
+                scope: ScopeLevel::Project,
+                hits,
+                last_used,
+            };
+            storage.record_usage(&[usage(3, at)]).unwrap();
+            // An older batch adds hits without moving last_used back
+            storage
+                .record_usage(&[usage(1, at - chrono::Duration::hours(1))])
+                .unwrap();
+
+            let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+            let find = |role: &str| loaded.iter().find(|r| r.key.role == role).unwrap();
+            assert_eq!(find("coder").hit_count, 4);
+            assert_eq!(find("coder").last_used, Some(at));
+            assert_eq!(find("tester").hit_count, 0);
+            assert_eq!(find("tester").last_used, None);
+        }
+    }
+
+    #[test]
+    fn test_delete_decisions_keeps_pinned_records() {
+        for (_tmp, storage) in backends() {
+            let mut records = [Decision::Allow, Decision::Deny, Decision::Ask].map(|decision| {
+                let mut record = make_record(decision, "coder");
+                record.key.sanitized_input = decision.to_string();
+                record.ensure_id();
+                record
+            });
+            records[2].immutable = true;
+            let [kept, deleted, pinned] = records;
+            storage
+                .save_decisions(&[kept.clone(), deleted.clone(), pinned.clone()])
+                .unwrap();
+
+            let removed = storage
+                .delete_decisions(ScopeLevel::Project, &[deleted.id, pinned.id])
+                .unwrap();
+            assert_eq!(removed, 1);
+            let mut left: Vec<Decision> = storage
+                .load_decisions(ScopeLevel::Project)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.decision)
+                .collect();
+            left.sort_by_key(|d| d.to_string());
+            assert_eq!(left, [Decision::Allow, Decision::Ask]);
+        }
+    }
+
+    #[test]
+    fn test_prune_unused_keeps_recent_and_pinned_records() {
+        for (_tmp, storage) in backends() {
+            let now = Utc::now();
+            let days_ago = |days| now - chrono::Duration::days(days);
+            let rule = |input: &str, created: i64| {
+                let mut record = make_record(Decision::Allow, "coder");
+                record.key.sanitized_input = input.into();
+                record.timestamp = days_ago(created);
+                record
+            };
+
+            let stale = rule("stale", 200);
+            let mut used = rule("used recently", 200);
+            used.record_hits(5, days_ago(10));
+            let mut used_long_ago = rule("used long ago", 200);
+            used_long_ago.record_hits(5, days_ago(120));
+            let fresh = rule("new and unused", 10);
+            let mut pinned = rule("pinned", 200);
+            pinned.immutable = true;
+            storage
+                .save_decisions(&[stale, used, used_long_ago, fresh, pinned])
+                .unwrap();
+
+            let pruned = storage
+                .prune_unused(ScopeLevel::Project, days_ago(90))
+                .unwrap();
+            assert_eq!(pruned, 2);
+            let mut left: Vec<String> = storage
+                .load_decisions(ScopeLevel::Project)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.key.sanitized_input)
+                .collect();
+            left.sort();
+            assert_eq!(left, ["new and unused", "pinned", "used recently"]);
+        }
+    }
+
+    #[test]
+    fn test_scopes_are_stored_apart() {
+        for (_tmp, storage) in backends() {
+            for scope in [ScopeLevel::User, ScopeLevel::Org, ScopeLevel::Global] {
+                let mut record = make_record(Decision::Deny, "*");
+                record.scope = scope;
+                storage.save_decision(&record).unwrap();
+            }
+            for scope in [ScopeLevel::User, ScopeLevel::Org, ScopeLevel::Global] {
+                let loaded = storage.load_decisions(scope).unwrap();
+                assert_eq!(loaded.len(), 1);
+                assert_eq!(loaded[0].scope, scope);
+            }
+            storage.invalidate_all(ScopeLevel::Org).unwrap();
+            assert!(storage.load_decisions(ScopeLevel::Org).unwrap().is_empty());
+            assert_eq!(storage.load_decisions(ScopeLevel::User).unwrap().len(), 1);
+        }
+    }
 }
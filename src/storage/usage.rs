@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::decision::DecisionRecord;
+use crate::error::Result;
+use crate::session::registration::FileLock;
+
+use super::RuleUsage;
+
+/// Hit counts for JSONL rules, kept apart from the rule files so counting
+/// hits never rewrites files under version control. Each entry holds the
+/// totals for one key at one scope.
+pub struct UsageFile {
+    path: PathBuf,
+}
+
+impl UsageFile {
+    /// The usage file beside a scope's rule directory `rules_dir`, in the
+    /// gitignored `.index/` next to it.
+    pub fn for_rules_dir(rules_dir: &Path) -> Self {
+        let parent = rules_dir.parent().unwrap_or(rules_dir);
+        Self {
+            path: parent.join(".index").join("usage.json"),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The totals saved so far. A missing file has none; one that can't be
+    /// parsed is logged and treated as empty, since usage is only advisory.
+    pub fn read(&self) -> Result<Vec<RuleUsage>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        match serde_json::from_str(&contents) {
+            Ok(usage) => Ok(usage),
+            Err(e) => {
+                tracing::warn!(
+                    "ignoring unreadable usage file {}: {}",
+                    self.path.display(),
+                    e
+                );
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Add `usage` to the saved totals. The file is rewritten under a lock
+    /// and replaced by rename, so concurrent hooks don't lose each other's
+    /// hits.
+    pub fn add(&self, usage: &[&RuleUsage]) -> Result<()> {
+        if usage.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = FileLock::acquire(&self.path)?;
+
+        let mut totals = self.read()?;
+        for entry in usage {
+            match totals
+                .iter_mut()
+                .find(|total| total.scope == entry.scope && total.key == entry.key)
+            {
+                Some(total) => {
+                    total.hits += entry.hits;
+                    total.last_used = total.last_used.max(entry.last_used);
+                }
+                None => totals.push((*entry).clone()),
+            }
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(&totals)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Count the hits in `usage` that belong to `record`.
+pub fn apply_usage(record: &mut DecisionRecord, usage: &[RuleUsage]) {
+    for entry in usage {
+        if entry.applies_to(record) {
+            record.record_hits(entry.hits, entry.last_used);
+        }
+    }
+}
@@ -4,6 +4,7 @@
 //! These tests build a CascadeRunner with real tiers (except supervisor/human
 //! which are stubbed) and verify the full pipeline.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -11,8 +12,12 @@ use chrono::Utc;
 use tempfile::TempDir;
 
 use hookwise::cascade::cache::ExactCache;
-use hookwise::cascade::embed_sim::EmbeddingSimilarity;
+use hookwise::cascade::embed_sim::{EmbeddingSimilarity, DEFAULT_PENDING_REBUILD_THRESHOLD};
 use hookwise::cascade::path_policy::PathPolicyEngine;
+use hookwise::cascade::supervisor::{
+    ApiSupervisor, RateLimitedSupervisor, SupervisorBackend, SupervisorRequest, SupervisorTier,
+    TokenBucket,
+};
 use hookwise::cascade::token_sim::TokenJaccard;
 use hookwise::cascade::{CascadeInput, CascadeRunner, CascadeTier};
 use hookwise::config::policy::PolicyConfig;
@@ -20,6 +25,8 @@ use hookwise::config::roles::{CompiledPathPolicy, PathPolicyConfig, RoleDefiniti
 use hookwise::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
 };
+use hookwise::error::HookwiseError;
+use hookwise::sanitize::SanitizePipeline;
 use hookwise::session::SessionContext;
 use hookwise::storage::jsonl::JsonlStorage;
 
@@ -98,6 +105,11 @@ impl CascadeTier for AllowSupervisor {
             scope: ScopeLevel::Project,
             file_path: input.file_path.clone(),
             session_id: String::new(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
         }))
     }
     fn tier(&self) -> DecisionTier {
@@ -129,7 +141,13 @@ fn make_session(role_name: &str) -> SessionContext {
         role: Some(RoleDefinition {
             name: role_name.into(),
             description: "test role".into(),
+            extends: vec![],
             paths: path_config,
+            sanitize_profile: None,
+            confidence: None,
+            similarity: None,
+            tools_allow: vec![],
+            tools_deny: vec![],
         }),
         path_policy: Some(Arc::new(compiled)),
         agent_prompt_hash: None,
@@ -137,6 +155,7 @@ fn make_session(role_name: &str) -> SessionContext {
         task_description: None,
         registered_at: Some(Utc::now()),
         disabled: false,
+        tags: HashMap::new(),
     }
 }
 
@@ -148,29 +167,34 @@ fn make_runner(
     let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
 
     // Try embedding similarity; if model fails, use noop
-    let embedding_sim = match EmbeddingSimilarity::new("default", 0.85) {
-        Ok(es) => Arc::new(es),
-        Err(_) => {
-            // Create with impossible threshold so it never matches
-            Arc::new(
-                EmbeddingSimilarity::new("default", 999.0).unwrap_or_else(|_| {
-                    panic!("EmbeddingSimilarity should not fail twice");
-                }),
-            )
-        }
-    };
+    let embedding_sim =
+        match EmbeddingSimilarity::new("default", 0.85, DEFAULT_PENDING_REBUILD_THRESHOLD) {
+            Ok(es) => Arc::new(es),
+            Err(_) => {
+                // Create with impossible threshold so it never matches
+                Arc::new(
+                    EmbeddingSimilarity::new("default", 999.0, DEFAULT_PENDING_REBUILD_THRESHOLD)
+                        .unwrap_or_else(|_| {
+                            panic!("EmbeddingSimilarity should not fail twice");
+                        }),
+                )
+            }
+        };
 
     CascadeRunner {
         sanitizer: hookwise::sanitize::SanitizePipeline::default_pipeline(),
         path_policy: Box::new(PathPolicyEngine::new().unwrap()),
         exact_cache: Arc::new(ExactCache::new()),
-        token_jaccard: Arc::new(TokenJaccard::new(0.7, 3)),
+        token_jaccard: Arc::new(TokenJaccard::new(0.7, 3, 1)),
         embedding_similarity: embedding_sim,
         supervisor,
         human,
         storage: Box::new(storage),
         policy: PolicyConfig::default(),
         normalizer: None,
+        dry_run: false,
+        usage: Default::default(),
+        scopes: None,
     }
 }
 
@@ -294,6 +318,50 @@ async fn cascade_sanitizes_secrets_before_caching() {
     assert!(record.key.sanitized_input.contains("<REDACTED>"));
 }
 
+#[tokio::test]
+async fn cascade_leaves_unlisted_tools_unsanitized() {
+    let tmp = TempDir::new().unwrap();
+    let mut runner = make_runner_with_allow_supervisor(&tmp);
+    let mut policy = PolicyConfig::default();
+    policy.sanitize.tools = vec!["Bash".into()];
+    runner.sanitizer = SanitizePipeline::for_role(&policy, None).unwrap();
+    let session = make_session("coder");
+
+    let read = serde_json::json!({"file_path": "src/ghp_secret123456789.rs"});
+    let record = runner.evaluate(&session, "Read", &read).await.unwrap();
+    assert_eq!(record.key.sanitized_input, read.to_string());
+
+    let bash = serde_json::json!({"command": "git push ghp_secret987654321"});
+    let record = runner.evaluate(&session, "Bash", &bash).await.unwrap();
+    assert!(!record.key.sanitized_input.contains("ghp_secret987654321"));
+
+    // The rule file keeps the unlisted tool's input as it came in
+    let rules = std::fs::read_to_string(tmp.path().join("rules/allow.jsonl")).unwrap();
+    assert!(rules.contains("src/ghp_secret123456789.rs"));
+    assert!(!rules.contains("ghp_secret987654321"));
+}
+
+#[tokio::test]
+async fn cascade_traversal_cannot_bypass_sensitive_paths() {
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let session = make_session("coder");
+
+    for path in [
+        "src/../.env",
+        "./tests/../src/../.env",
+        "/repo/src/../.claude/settings.json",
+    ] {
+        let tool_input = serde_json::json!({"file_path": path, "content": "x"});
+        let record = runner
+            .evaluate_with_cwd(&session, "Write", &tool_input, Some("/repo"))
+            .await
+            .unwrap();
+        assert_eq!(record.decision, Decision::Ask, "{path}");
+        assert_eq!(record.metadata.tier, DecisionTier::PathPolicy, "{path}");
+    }
+}
+
 #[tokio::test]
 async fn cascade_deny_wins_over_ask() {
     let tmp = TempDir::new().unwrap();
@@ -316,7 +384,13 @@ async fn cascade_deny_wins_over_ask() {
         role: Some(RoleDefinition {
             name: "custom".into(),
             description: "test".into(),
+            extends: vec![],
             paths: path_config,
+            sanitize_profile: None,
+            confidence: None,
+            similarity: None,
+            tools_allow: vec![],
+            tools_deny: vec![],
         }),
         path_policy: Some(Arc::new(compiled)),
         agent_prompt_hash: None,
@@ -324,25 +398,18 @@ async fn cascade_deny_wins_over_ask() {
         task_description: None,
         registered_at: Some(Utc::now()),
         disabled: false,
+        tags: HashMap::new(),
     };
 
-    // .env matches both deny_write and sensitive_ask_write.
-    // BUG DOCUMENTATION: The path policy engine checks sensitive_ask_write BEFORE
-    // deny_write in its if-else chain (path_policy.rs:139-147). So when a path
-    // matches both, sensitive wins and returns Ask, even though deny has higher
-    // precedence. The precedence logic (worst_decision) only applies across
-    // different paths, not within a single path's match.
-    // This means deny_write patterns that overlap with sensitive_ask_write are
-    // effectively shadowed. This could be an intentional design choice (sensitive
-    // paths always prompt) or a bug where the if-else order should check deny first.
+    // .env matches both deny_write and sensitive_ask_write; deny_write is
+    // checked first, so the role's deny isn't shadowed by the prompt.
     let tool_input = serde_json::json!({"file_path": ".env.local", "content": "x"});
     let record = runner
         .evaluate(&session, "Write", &tool_input)
         .await
         .unwrap();
 
-    // Current behavior: Ask wins because sensitive is checked first in the if-else
-    assert_eq!(record.decision, Decision::Ask);
+    assert_eq!(record.decision, Decision::Deny);
 }
 
 #[tokio::test]
@@ -364,6 +431,59 @@ async fn cascade_read_always_allowed_for_read_tools() {
     assert_ne!(record.metadata.tier, DecisionTier::PathPolicy);
 }
 
+#[tokio::test]
+async fn cascade_restricted_allow_read_denies_reads_outside_it() {
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let mut session = make_session("reviewer");
+    let paths = PathPolicyConfig {
+        allow_write: vec![],
+        deny_write: vec![],
+        allow_read: vec!["src/**".into()],
+    };
+    session.path_policy = Some(Arc::new(CompiledPathPolicy::compile(&paths, &[]).unwrap()));
+    session.role.as_mut().unwrap().paths = paths;
+
+    let record = runner
+        .evaluate(&session, "Read", &serde_json::json!({"file_path": ".env"}))
+        .await
+        .unwrap();
+    assert_eq!(record.decision, Decision::Deny);
+    assert_eq!(record.metadata.tier, DecisionTier::PathPolicy);
+    assert!(record
+        .metadata
+        .reason
+        .contains("outside the role's allow_read"));
+
+    let record = runner
+        .evaluate(
+            &session,
+            "Read",
+            &serde_json::json!({"file_path": "src/main.rs"}),
+        )
+        .await
+        .unwrap();
+    assert_ne!(record.metadata.tier, DecisionTier::PathPolicy);
+
+    // Searching src is fine; searching the whole tree would reach .env
+    let record = runner
+        .evaluate(
+            &session,
+            "Grep",
+            &serde_json::json!({"pattern": "TODO", "path": "src"}),
+        )
+        .await
+        .unwrap();
+    assert_ne!(record.metadata.tier, DecisionTier::PathPolicy);
+
+    let record = runner
+        .evaluate(&session, "Grep", &serde_json::json!({"pattern": "SECRET"}))
+        .await
+        .unwrap();
+    assert_eq!(record.decision, Decision::Deny);
+    assert_eq!(record.metadata.tier, DecisionTier::PathPolicy);
+}
+
 #[tokio::test]
 async fn cascade_persists_decisions() {
     let tmp = TempDir::new().unwrap();
@@ -384,6 +504,119 @@ async fn cascade_persists_decisions() {
     assert!(!loaded.is_empty(), "decision should be persisted to JSONL");
 }
 
+#[tokio::test]
+async fn cascade_invalidate_role_drops_and_reloads_entries() {
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let coder = make_session("coder");
+    let tester = make_session("tester");
+    let tool_input = serde_json::json!({"command": "cargo test"});
+
+    // Learn the same command for two roles; both now hit the exact cache.
+    for session in [&coder, &tester] {
+        runner.evaluate(session, "Bash", &tool_input).await.unwrap();
+        let cached = runner.evaluate(session, "Bash", &tool_input).await.unwrap();
+        assert_eq!(cached.metadata.tier, DecisionTier::ExactCache);
+    }
+
+    // Clear the coder's rules on disk, as `hookwise invalidate --role` does,
+    // then tell the live runner.
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    storage
+        .invalidate_role(ScopeLevel::Project, "coder")
+        .unwrap();
+    runner.invalidate_role("coder").unwrap();
+
+    let record = runner.evaluate(&coder, "Bash", &tool_input).await.unwrap();
+    assert_eq!(record.metadata.tier, DecisionTier::Supervisor);
+
+    let record = runner.evaluate(&tester, "Bash", &tool_input).await.unwrap();
+    assert_eq!(record.metadata.tier, DecisionTier::ExactCache);
+}
+
+#[tokio::test]
+async fn cascade_invalidate_role_reloads_every_scope() {
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_simple(&tmp);
+    let coder = make_session("coder");
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+
+    let mut project_rule = cargo_build_rule();
+    project_rule.ensure_id();
+    let mut global_rule = cargo_build_rule();
+    global_rule.key.sanitized_input = r#"{"command":"cargo fmt --check"}"#.into();
+    global_rule.scope = ScopeLevel::Global;
+    global_rule.ensure_id();
+    for rule in [&project_rule, &global_rule] {
+        storage.save_decision(rule).unwrap();
+    }
+
+    runner.invalidate_role("coder").unwrap();
+
+    for rule in [&project_rule, &global_rule] {
+        let input: serde_json::Value = serde_json::from_str(&rule.key.sanitized_input).unwrap();
+        let record = runner.evaluate(&coder, "Bash", &input).await.unwrap();
+        assert_eq!(record.metadata.tier, DecisionTier::ExactCache);
+        assert_eq!(record.decision, Decision::Allow);
+    }
+}
+
+#[tokio::test]
+async fn cascade_invalidate_role_refreshes_the_scope_resolver() {
+    use hookwise::scope::ScopeResolver;
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let storage = || JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    let mut runner = make_runner_with_allow_supervisor(&tmp);
+    let resolver = Arc::new(ScopeResolver::new(Box::new(storage())));
+    runner.scopes = Some(resolver.clone());
+    let coder = make_session("coder");
+    let tool_input = serde_json::json!({"command": "cargo test"});
+
+    let record = runner.evaluate(&coder, "Bash", &tool_input).await.unwrap();
+    resolver.reload().unwrap();
+    assert!(resolver.resolve(&record.key, &coder).unwrap().is_some());
+
+    storage()
+        .invalidate_role(ScopeLevel::Project, "coder")
+        .unwrap();
+    runner.invalidate_role("coder").unwrap();
+    assert!(resolver.resolve(&record.key, &coder).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn cascade_stamps_session_tags_on_persisted_decisions() {
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let mut session = make_session("coder");
+    session.tags = HashMap::from([
+        ("pr".to_string(), "1234".to_string()),
+        ("env".to_string(), "staging".to_string()),
+    ]);
+
+    let tool_input = serde_json::json!({"command": "cargo test"});
+    let record = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_eq!(record.tags, session.tags);
+
+    use hookwise::storage::StorageBackend;
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    let loaded = storage.load_decisions(ScopeLevel::Project).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].tags.get("pr").map(String::as_str), Some("1234"));
+    assert_eq!(
+        loaded[0].tags.get("env").map(String::as_str),
+        Some("staging")
+    );
+}
+
 #[tokio::test]
 async fn cascade_token_similarity_auto_approves() {
     let tmp = TempDir::new().unwrap();
@@ -412,6 +645,599 @@ async fn cascade_token_similarity_auto_approves() {
     assert_eq!(record_2.decision, Decision::Allow);
 }
 
+#[tokio::test]
+async fn cascade_writes_rule_usage_back_to_storage() {
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let session = make_session("coder");
+
+    // The supervisor decides first; two exact cache hits follow
+    let tool_input = serde_json::json!({"command": "cargo build --release"});
+    let before = Utc::now();
+    for _ in 0..3 {
+        runner
+            .evaluate(&session, "Bash", &tool_input)
+            .await
+            .unwrap();
+    }
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    let stored = storage.load_decisions(ScopeLevel::Project).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].hit_count, 0, "hits are batched until flushed");
+
+    // Hits land in the usage file under .index/; the rule file is untouched
+    let rules = tmp.path().join("rules/allow.jsonl");
+    let rules_before = std::fs::read_to_string(&rules).unwrap();
+    runner.flush_usage().unwrap();
+    assert_eq!(std::fs::read_to_string(&rules).unwrap(), rules_before);
+    assert!(tmp.path().join(".index/usage.json").exists());
+    let stored = storage.load_decisions(ScopeLevel::Project).unwrap();
+    assert_eq!(stored[0].hit_count, 2);
+    assert!(stored[0].last_used.unwrap() >= before);
+
+    // Nothing is pending after a flush
+    runner.flush_usage().unwrap();
+    let stored = storage.load_decisions(ScopeLevel::Project).unwrap();
+    assert_eq!(stored[0].hit_count, 2);
+}
+
+/// A human-allowed `cargo build` for the coder role.
+fn cargo_build_rule() -> DecisionRecord {
+    DecisionRecord {
+        key: CacheKey {
+            sanitized_input: r#"{"command":"cargo build --release --target linux"}"#.into(),
+            tool: "Bash".into(),
+            role: "coder".into(),
+        },
+        decision: Decision::Allow,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Human,
+            confidence: 1.0,
+            reason: "test".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Project,
+        file_path: None,
+        session_id: String::new(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    }
+}
+
+/// A runner whose Jaccard tier holds [`cargo_build_rule`] and matches down
+/// to 0.5, with the given project confidence floor.
+fn runner_with_confidence_floor(tmp: &TempDir, project_floor: f64) -> CascadeRunner {
+    let mut runner = make_runner_simple(tmp);
+    let token_jaccard = TokenJaccard::new(0.5, 3, 1);
+    token_jaccard.insert(&cargo_build_rule());
+    runner.token_jaccard = Arc::new(token_jaccard);
+    runner.policy.confidence.project = project_floor;
+    runner
+}
+
+#[tokio::test]
+async fn cascade_similarity_match_clears_low_confidence_floor() {
+    let tmp = TempDir::new().unwrap();
+    let runner = runner_with_confidence_floor(&tmp, 0.6);
+    let session = make_session("coder");
+
+    // Shares 5 of 7 tokens with the cached input: 0.714
+    let tool_input = serde_json::json!({"command": "cargo build --release --target macos"});
+    let record = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_eq!(record.metadata.tier, DecisionTier::TokenJaccard);
+    assert_eq!(record.decision, Decision::Allow);
+}
+
+#[tokio::test]
+async fn cascade_similarity_hit_counts_against_the_matched_rule() {
+    let tmp = TempDir::new().unwrap();
+    let runner = runner_with_confidence_floor(&tmp, 0.6);
+    runner.storage.save_decision(&cargo_build_rule()).unwrap();
+    let session = make_session("coder");
+
+    let tool_input = serde_json::json!({"command": "cargo build --release --target macos"});
+    let record = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_eq!(record.metadata.tier, DecisionTier::TokenJaccard);
+    runner.flush_usage().unwrap();
+
+    let stored = runner.storage.load_decisions(ScopeLevel::Project).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].key, cargo_build_rule().key);
+    assert_eq!(stored[0].hit_count, 1);
+    assert!(stored[0].last_used.is_some());
+}
+
+#[tokio::test]
+async fn cascade_similarity_match_below_confidence_floor_falls_through() {
+    let tmp = TempDir::new().unwrap();
+    let runner = runner_with_confidence_floor(&tmp, 0.9);
+    let session = make_session("coder");
+
+    let tool_input = serde_json::json!({"command": "cargo build --release --target macos"});
+    let record = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_ne!(record.metadata.tier, DecisionTier::TokenJaccard);
+}
+
+#[test]
+fn confidence_floor_defaults_to_project() {
+    let confidence = hookwise::config::policy::ConfidenceConfig {
+        org: 0.9,
+        project: 0.7,
+        user: 0.6,
+    };
+    assert_eq!(confidence.floor(ScopeLevel::Org), 0.9);
+    assert_eq!(confidence.floor(ScopeLevel::User), 0.6);
+    assert_eq!(confidence.floor(ScopeLevel::Global), 0.7);
+    assert_eq!(confidence.floor(ScopeLevel::Role), 0.7);
+}
+
+#[tokio::test]
+async fn cascade_empty_input_escalates_to_ask() {
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let session = make_session("coder");
+
+    // Seed an allow for the empty command, as a prior bad decision might have
+    let empty = serde_json::json!({"command": ""});
+    runner.exact_cache.insert(DecisionRecord {
+        key: CacheKey {
+            sanitized_input: serde_json::to_string(&empty).unwrap(),
+            tool: "Bash".into(),
+            role: "coder".into(),
+        },
+        decision: Decision::Allow,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Human,
+            confidence: 1.0,
+            reason: "seeded".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Project,
+        file_path: None,
+        session_id: String::new(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    });
+
+    for input in [
+        empty,
+        serde_json::json!({"command": "   \n\t"}),
+        serde_json::json!({}),
+    ] {
+        let record = runner.evaluate(&session, "Bash", &input).await.unwrap();
+        assert_eq!(record.decision, Decision::Ask, "input: {input}");
+        assert_eq!(record.metadata.tier, DecisionTier::Default);
+    }
+
+    // Unknown tools with an empty payload are treated the same way
+    let record = runner
+        .evaluate(&session, "mcp__custom__tool", &serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(record.decision, Decision::Ask);
+
+    // Nothing learned from empty inputs
+    use hookwise::storage::StorageBackend;
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    assert!(storage
+        .load_decisions(ScopeLevel::Project)
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn cascade_bulk_redacted_input_escalates_to_ask() {
+    let tmp = TempDir::new().unwrap();
+    let mut runner = make_runner_with_allow_supervisor(&tmp);
+    runner.sanitizer =
+        hookwise::sanitize::SanitizePipeline::default_pipeline().with_max_redactions(2);
+    let session = make_session("coder");
+
+    let input = serde_json::json!({
+        "command": "cat <<EOF\nA=sk-ant-one1 B=sk-ant-two2 C=sk-ant-three3\nEOF"
+    });
+    let record = runner.evaluate(&session, "Bash", &input).await.unwrap();
+    assert_eq!(record.decision, Decision::Ask);
+    assert_eq!(record.metadata.tier, DecisionTier::Default);
+    assert_eq!(
+        record.key.sanitized_input,
+        hookwise::sanitize::BULK_REDACTION_MARKER
+    );
+
+    use hookwise::storage::StorageBackend;
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    assert!(storage
+        .load_decisions(ScopeLevel::Project)
+        .unwrap()
+        .is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// Replay
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn replay_matches_original_while_rule_exists() {
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let session = make_session("coder");
+
+    let tool_input = serde_json::json!({"command": "cargo build --release"});
+    let original = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_eq!(original.metadata.tier, DecisionTier::Supervisor);
+
+    let outcome = runner.replay(&session, &original).await.unwrap();
+    assert!(!outcome.decision_changed());
+    assert_eq!(outcome.current_tier(), Some(DecisionTier::ExactCache));
+}
+
+#[tokio::test]
+async fn replay_after_rule_deleted_shows_changed_outcome() {
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let runner = make_runner_with_allow_supervisor(&tmp);
+    let session = make_session("coder");
+
+    let tool_input = serde_json::json!({"command": "cargo build --release"});
+    let original = runner
+        .evaluate(&session, "Bash", &tool_input)
+        .await
+        .unwrap();
+    assert_eq!(original.decision, Decision::Allow);
+
+    // Delete the learned rule from storage and every in-memory tier
+    runner
+        .storage
+        .invalidate_role(ScopeLevel::Project, "coder")
+        .unwrap();
+    runner.exact_cache.invalidate_role("coder");
+    runner.token_jaccard.invalidate_role("coder");
+    runner
+        .embedding_similarity
+        .invalidate_role("coder")
+        .unwrap();
+
+    let outcome = runner.replay(&session, &original).await.unwrap();
+    assert!(outcome.decision_changed());
+    assert!(
+        outcome.current.is_none(),
+        "should escalate past local tiers"
+    );
+
+    // Replay is read-only: nothing was written back
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    assert!(storage
+        .load_decisions(ScopeLevel::Project)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn decision_id_is_stable_and_distinguishes_records() {
+    let record = DecisionRecord {
+        key: CacheKey {
+            sanitized_input: "{\"command\":\"ls\"}".into(),
+            tool: "Bash".into(),
+            role: "coder".into(),
+        },
+        decision: Decision::Allow,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Human,
+            confidence: 1.0,
+            reason: "test".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Project,
+        file_path: None,
+        session_id: String::new(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    };
+    let reloaded: DecisionRecord =
+        serde_json::from_str(&serde_json::to_string(&record).unwrap()).unwrap();
+    assert_eq!(record.derive_id(), reloaded.derive_id());
+    assert_eq!(record.derive_id().len(), 12);
+
+    let mut other = record.clone();
+    other.key.role = "tester".into();
+    assert_ne!(record.derive_id(), other.derive_id());
+
+    // The same call answered differently is a different record
+    let mut overturned = record.clone();
+    overturned.decision = Decision::Deny;
+    assert_ne!(record.derive_id(), overturned.derive_id());
+
+    // Same key, scope, and timestamp: same id, whatever else differs
+    let mut twin = record.clone();
+    twin.session_id = "another-session".into();
+    twin.metadata.reason = "re-imported".into();
+    assert_eq!(record.derive_id(), twin.derive_id());
+
+    // Records stored before ids existed are assigned the derived id
+    let mut legacy = record.clone();
+    legacy.ensure_id();
+    assert_eq!(legacy.id, record.derive_id());
+}
+
+// ---------------------------------------------------------------------------
+// Supervisor rate limiting
+// ---------------------------------------------------------------------------
+
+/// A supervisor backend that allows everything and counts its calls.
+struct CountingBackend {
+    calls: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait]
+impl SupervisorBackend for CountingBackend {
+    async fn evaluate(
+        &self,
+        request: &SupervisorRequest,
+        _policy: &PolicyConfig,
+    ) -> hookwise::error::Result<DecisionRecord> {
+        self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(DecisionRecord {
+            key: CacheKey {
+                sanitized_input: request.sanitized_input.clone(),
+                tool: request.tool_name.clone(),
+                role: request.role.clone(),
+            },
+            decision: Decision::Allow,
+            metadata: DecisionMetadata {
+                tier: DecisionTier::Supervisor,
+                confidence: 0.95,
+                reason: "counting backend".into(),
+                matched_key: None,
+                similarity_score: None,
+            },
+            timestamp: Utc::now(),
+            scope: ScopeLevel::Project,
+            file_path: None,
+            session_id: String::new(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
+        })
+    }
+}
+
+#[tokio::test]
+async fn supervisor_rate_limit_falls_through_when_budget_exhausted() {
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let backend = RateLimitedSupervisor::new(
+        Box::new(CountingBackend {
+            calls: calls.clone(),
+        }),
+        TokenBucket::per_minute(2),
+    );
+    let tier = SupervisorTier::new(Box::new(backend), PolicyConfig::default());
+
+    let mut resolved = 0;
+    for i in 0..5 {
+        let input = CascadeInput {
+            session: make_session("coder"),
+            tool_name: "Bash".into(),
+            tool_input: serde_json::json!({"command": format!("echo {i}")}),
+            sanitized_input: format!("{{\"command\":\"echo {i}\"}}"),
+            file_path: None,
+            cwd: None,
+        };
+        if tier.evaluate(&input).await.unwrap().is_some() {
+            resolved += 1;
+        }
+    }
+
+    // Only the budgeted calls reach the backend; the rest fall through
+    assert_eq!(resolved, 2);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn token_bucket_refills_over_time() {
+    let bucket = TokenBucket::per_minute(60);
+    let start = 1_000.0;
+    for _ in 0..60 {
+        assert!(bucket.try_acquire_at(start));
+    }
+    assert!(!bucket.try_acquire_at(start));
+    // One token per second at 60/minute
+    assert!(bucket.try_acquire_at(start + 1.0));
+    assert!(!bucket.try_acquire_at(start + 1.0));
+}
+
+#[test]
+fn token_bucket_state_is_shared_through_file() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("budget.json");
+
+    let first = TokenBucket::persisted(1, path.clone());
+    assert!(first.try_acquire_at(1_000.0));
+
+    // A second process sees the spent budget
+    let second = TokenBucket::persisted(1, path);
+    assert!(!second.try_acquire_at(1_000.5));
+    assert!(second.try_acquire_at(1_060.0));
+}
+
+#[cfg(unix)]
+#[test]
+fn token_bucket_state_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("budget.json");
+
+    let bucket = TokenBucket::persisted(5, path.clone());
+    assert!(bucket.try_acquire_at(1_000.0));
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!path.with_extension("tmp").exists());
+}
+
+// ---------------------------------------------------------------------------
+// API supervisor
+// ---------------------------------------------------------------------------
+
+/// Serve one HTTP request on a local port, answering with `status` and
+/// `body`. Returns the base URL and a handle yielding the request received.
+async fn serve_once(status: u16, body: String) -> (String, tokio::task::JoinHandle<String>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|l| {
+                        l.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length || n == 0 {
+                    break;
+                }
+            }
+        }
+        let response = format!(
+            "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    });
+    (url, handle)
+}
+
+/// A Messages API reply whose text is the supervisor verdict.
+fn verdict_reply(decision: &str, confidence: f64) -> String {
+    let text = format!(
+        r#"{{"decision": "{decision}", "confidence": {confidence}, "reason": "mock verdict"}}"#
+    );
+    serde_json::json!({"content": [{"type": "text", "text": text}]}).to_string()
+}
+
+fn api_request() -> SupervisorRequest {
+    SupervisorRequest {
+        session_id: "session-1".into(),
+        role: "coder".into(),
+        role_description: "writes code".into(),
+        tool_name: "Bash".into(),
+        sanitized_input: r#"{"command":"cargo test"}"#.into(),
+        file_path: None,
+        task_description: Some("fix the build".into()),
+        agent_prompt_path: None,
+        cwd: "/repo".into(),
+    }
+}
+
+#[tokio::test]
+async fn api_supervisor_maps_each_verdict() {
+    for (verdict, expected) in [
+        ("allow", Decision::Allow),
+        ("deny", Decision::Deny),
+        ("ask", Decision::Ask),
+    ] {
+        let (url, server) = serve_once(200, verdict_reply(verdict, 0.85)).await;
+        let supervisor = ApiSupervisor::new(url, "test-key".into(), "test-model".into(), 256);
+
+        let record = supervisor
+            .evaluate(&api_request(), &PolicyConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(record.decision, expected);
+        assert_eq!(record.metadata.tier, DecisionTier::Supervisor);
+        assert_eq!(record.metadata.confidence, 0.85);
+        assert_eq!(record.key.role, "coder");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/messages"));
+        assert!(request.contains("x-api-key: test-key"));
+        assert!(request.contains("fix the build"));
+    }
+}
+
+#[tokio::test]
+async fn api_supervisor_maps_http_error_to_api_error() {
+    let (url, _server) = serve_once(529, r#"{"error":"overloaded"}"#.into()).await;
+    let supervisor = ApiSupervisor::new(url, "test-key".into(), "test-model".into(), 256);
+
+    let err = supervisor
+        .evaluate(&api_request(), &PolicyConfig::default())
+        .await
+        .unwrap_err();
+    match err {
+        HookwiseError::Api { status, body } => {
+            assert_eq!(status, 529);
+            assert!(body.contains("overloaded"));
+        }
+        other => panic!("expected Api error, got {other}"),
+    }
+}
+
+#[tokio::test]
+async fn api_supervisor_times_out() {
+    // Accepts the connection but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let _server = tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    });
+    let supervisor =
+        ApiSupervisor::new(url, "test-key".into(), "test-model".into(), 256).with_timeout(1);
+
+    let err = supervisor
+        .evaluate(&api_request(), &PolicyConfig::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        HookwiseError::SupervisorTimeout { timeout_secs: 1 }
+    ));
+}
+
 // ---------------------------------------------------------------------------
 // HookOutput integration
 // ---------------------------------------------------------------------------
@@ -476,6 +1302,11 @@ fn scope_merge_deny_wins_over_allow() {
         scope: ScopeLevel::User,
         file_path: None,
         session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     };
 
     let deny_record = DecisionRecord {
@@ -492,6 +1323,11 @@ fn scope_merge_deny_wins_over_allow() {
         scope: ScopeLevel::Org,
         file_path: None,
         session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     };
 
     let decisions = vec![
@@ -511,6 +1347,60 @@ fn scope_merge_deny_wins_over_allow() {
     assert_eq!(result.decision, Decision::Deny);
 }
 
+#[test]
+fn scope_merge_immutable_record_is_authoritative() {
+    use hookwise::scope::merge::merge_decisions;
+    use hookwise::scope::ScopedDecision;
+
+    let pinned = DecisionRecord {
+        key: CacheKey {
+            sanitized_input: "test".into(),
+            tool: "Bash".into(),
+            role: "*".into(),
+        },
+        decision: Decision::Allow,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Override,
+            confidence: 1.0,
+            reason: "org rule".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Org,
+        file_path: None,
+        session_id: "override".into(),
+        tags: HashMap::new(),
+        immutable: true,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    };
+
+    let mut role_deny = pinned.clone();
+    role_deny.key.role = "coder".into();
+    role_deny.decision = Decision::Deny;
+    role_deny.scope = ScopeLevel::Role;
+    role_deny.immutable = false;
+
+    let decisions = vec![
+        ScopedDecision {
+            decision: Decision::Deny,
+            scope: ScopeLevel::Role,
+            record: role_deny,
+        },
+        ScopedDecision {
+            decision: Decision::Allow,
+            scope: ScopeLevel::Org,
+            record: pinned,
+        },
+    ];
+
+    let result = merge_decisions(decisions).unwrap();
+    assert_eq!(result.decision, Decision::Allow);
+    assert_eq!(result.scope, ScopeLevel::Org);
+}
+
 #[test]
 fn scope_merge_ask_wins_over_allow() {
     use hookwise::scope::merge::merge_decisions;
@@ -534,6 +1424,11 @@ fn scope_merge_ask_wins_over_allow() {
         scope: ScopeLevel::User,
         file_path: None,
         session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     };
 
     let ask_record = DecisionRecord {
@@ -550,6 +1445,11 @@ fn scope_merge_ask_wins_over_allow() {
         scope: ScopeLevel::Project,
         file_path: None,
         session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     };
 
     let decisions = vec![
@@ -569,6 +1469,364 @@ fn scope_merge_ask_wins_over_allow() {
     assert_eq!(result.decision, Decision::Ask);
 }
 
+fn scoped(
+    decision: Decision,
+    scope: ScopeLevel,
+    tier: DecisionTier,
+) -> hookwise::scope::ScopedDecision {
+    hookwise::scope::ScopedDecision {
+        decision,
+        scope,
+        record: DecisionRecord {
+            key: CacheKey {
+                sanitized_input: "npm publish".into(),
+                tool: "Bash".into(),
+                role: "coder".into(),
+            },
+            decision,
+            metadata: DecisionMetadata {
+                tier,
+                confidence: 1.0,
+                reason: "test".into(),
+                matched_key: None,
+                similarity_score: None,
+            },
+            timestamp: Utc::now(),
+            scope,
+            file_path: None,
+            session_id: "test".into(),
+            tags: HashMap::new(),
+            immutable: false,
+            id: String::new(),
+            last_used: None,
+            hit_count: 0,
+        },
+    }
+}
+
+#[test]
+fn scope_merge_role_override_allow_beats_org_deny() {
+    use hookwise::scope::merge::merge_decisions;
+
+    let result = merge_decisions(vec![
+        scoped(Decision::Allow, ScopeLevel::Role, DecisionTier::Override),
+        scoped(Decision::Deny, ScopeLevel::Org, DecisionTier::Human),
+    ])
+    .unwrap();
+    assert_eq!(result.decision, Decision::Allow);
+    assert_eq!(result.scope, ScopeLevel::Role);
+
+    // Without the override, plain precedence lets the org deny win
+    let result = merge_decisions(vec![
+        scoped(Decision::Allow, ScopeLevel::Role, DecisionTier::Human),
+        scoped(Decision::Deny, ScopeLevel::Org, DecisionTier::Human),
+    ])
+    .unwrap();
+    assert_eq!(result.decision, Decision::Deny);
+}
+
+#[test]
+fn scope_merge_narrower_override_beats_broader_override() {
+    use hookwise::scope::merge::merge_decisions;
+
+    let result = merge_decisions(vec![
+        scoped(Decision::Deny, ScopeLevel::Project, DecisionTier::Override),
+        scoped(Decision::Allow, ScopeLevel::User, DecisionTier::Override),
+    ])
+    .unwrap();
+    assert_eq!(result.decision, Decision::Allow);
+    assert_eq!(result.scope, ScopeLevel::User);
+
+    // A non-override at a narrower scope still competes on precedence
+    let result = merge_decisions(vec![
+        scoped(Decision::Allow, ScopeLevel::User, DecisionTier::Override),
+        scoped(Decision::Ask, ScopeLevel::Role, DecisionTier::Human),
+    ])
+    .unwrap();
+    assert_eq!(result.decision, Decision::Ask);
+}
+
+#[test]
+fn scope_resolve_batch_matches_single_key_resolve() {
+    use hookwise::scope::ScopeResolver;
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    let record = |input: &str, role: &str, decision: Decision, scope: ScopeLevel| DecisionRecord {
+        key: CacheKey {
+            sanitized_input: input.into(),
+            tool: "Bash".into(),
+            role: role.into(),
+        },
+        decision,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Human,
+            confidence: 1.0,
+            reason: "test".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope,
+        file_path: None,
+        session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    };
+    for r in [
+        record("cargo test", "coder", Decision::Allow, ScopeLevel::Project),
+        record("cargo test", "*", Decision::Ask, ScopeLevel::Org),
+        record("git push", "coder", Decision::Allow, ScopeLevel::User),
+        record("git push", "coder", Decision::Deny, ScopeLevel::Project),
+        record("ls", "*", Decision::Allow, ScopeLevel::Project),
+        record("make", "tester", Decision::Allow, ScopeLevel::Project),
+    ] {
+        storage.save_decision(&r).unwrap();
+    }
+    let resolver = ScopeResolver::new(Box::new(storage));
+    let session = make_session("coder");
+
+    let keys: Vec<CacheKey> = [
+        ("cargo test", "coder"),
+        ("git push", "coder"),
+        ("ls", "coder"),
+        ("ls", "*"),
+        ("make", "coder"),
+        ("make", "tester"),
+        ("unknown", "coder"),
+    ]
+    .iter()
+    .map(|(input, role)| CacheKey {
+        sanitized_input: (*input).into(),
+        tool: "Bash".into(),
+        role: (*role).into(),
+    })
+    .collect();
+
+    let summary = |sd: &Option<hookwise::scope::ScopedDecision>| {
+        sd.as_ref()
+            .map(|sd| (sd.decision, sd.scope, sd.record.key.clone()))
+    };
+    let batch = resolver.resolve_batch(&keys, &session).unwrap();
+    assert_eq!(batch.len(), keys.len());
+    for (key, batched) in keys.iter().zip(&batch) {
+        let single = resolver.resolve(key, &session).unwrap();
+        assert_eq!(summary(batched), summary(&single), "key {key:?}");
+    }
+
+    let decisions: Vec<_> = batch
+        .iter()
+        .map(|sd| sd.as_ref().map(|sd| sd.decision))
+        .collect();
+    assert_eq!(
+        decisions,
+        vec![
+            Some(Decision::Ask),
+            Some(Decision::Deny),
+            Some(Decision::Allow),
+            Some(Decision::Allow),
+            None,
+            Some(Decision::Allow),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn scope_resolver_finds_wildcard_only_org_rule() {
+    use hookwise::scope::ScopeResolver;
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    let key = CacheKey {
+        sanitized_input: "terraform apply".into(),
+        tool: "Bash".into(),
+        role: "*".into(),
+    };
+    let mut org_rule = DecisionRecord {
+        key: key.clone(),
+        decision: Decision::Deny,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Override,
+            confidence: 1.0,
+            reason: "org rule".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Org,
+        file_path: None,
+        session_id: "override".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    };
+    storage.save_decision(&org_rule).unwrap();
+
+    let resolver = ScopeResolver::new(Box::new(JsonlStorage::new(
+        tmp.path().to_path_buf(),
+        tmp.path().join("global"),
+        None,
+    )));
+    let session = make_session("coder");
+    let coder_key = CacheKey {
+        role: "coder".into(),
+        ..key.clone()
+    };
+
+    let resolved = resolver.resolve(&coder_key, &session).unwrap().unwrap();
+    assert_eq!(resolved.decision, Decision::Deny);
+    assert_eq!(resolved.scope, ScopeLevel::Org);
+    assert_eq!(resolved.record.key.role, "*");
+
+    // A rule saved after the index was built is only seen after a reload
+    org_rule.key.sanitized_input = "terraform destroy".into();
+    storage.save_decision(&org_rule).unwrap();
+    let destroy_key = CacheKey {
+        sanitized_input: "terraform destroy".into(),
+        ..coder_key
+    };
+    assert!(resolver.resolve(&destroy_key, &session).unwrap().is_none());
+    resolver.reload().unwrap();
+    assert!(resolver.resolve(&destroy_key, &session).unwrap().is_some());
+}
+
+/// Storage holding a fixed set of records per scope, so each scope is loaded
+/// independently (JSONL storage shares one directory for Role and Project).
+struct ScopedStorage(HashMap<ScopeLevel, Vec<DecisionRecord>>);
+
+impl hookwise::storage::StorageBackend for ScopedStorage {
+    fn load_decisions(&self, scope: ScopeLevel) -> hookwise::error::Result<Vec<DecisionRecord>> {
+        Ok(self.0.get(&scope).cloned().unwrap_or_default())
+    }
+
+    fn load_decisions_for_role(
+        &self,
+        scope: ScopeLevel,
+        role: &str,
+    ) -> hookwise::error::Result<Vec<DecisionRecord>> {
+        let mut records = self.load_decisions(scope)?;
+        records.retain(|r| r.key.role == role || r.key.role == "*");
+        Ok(records)
+    }
+
+    fn save_decision(&self, _record: &DecisionRecord) -> hookwise::error::Result<()> {
+        Ok(())
+    }
+
+    fn invalidate_role(&self, _scope: ScopeLevel, _role: &str) -> hookwise::error::Result<()> {
+        Ok(())
+    }
+
+    fn invalidate_all(&self, _scope: ScopeLevel) -> hookwise::error::Result<()> {
+        Ok(())
+    }
+
+    fn rebuild_index(&self, _scope: ScopeLevel) -> hookwise::error::Result<()> {
+        Ok(())
+    }
+
+    fn scan_for_secrets(
+        &self,
+        _path: &std::path::Path,
+    ) -> hookwise::error::Result<Vec<hookwise::storage::SecretFinding>> {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn scope_resolver_global_deny_beats_project_allow() {
+    use hookwise::scope::ScopeResolver;
+    use hookwise::storage::StorageBackend;
+
+    let tmp = TempDir::new().unwrap();
+    let storage = || {
+        JsonlStorage::new(
+            tmp.path().join("project"),
+            tmp.path().join("global"),
+            Some("acme".into()),
+        )
+    };
+    for (scope, decision) in [
+        (ScopeLevel::Project, Decision::Allow),
+        (ScopeLevel::Global, Decision::Deny),
+    ] {
+        let record = scoped(decision, scope, DecisionTier::Human).record;
+        storage().save_decision(&record).unwrap();
+    }
+    assert!(tmp.path().join("global/global/rules/deny.jsonl").exists());
+
+    let resolver = ScopeResolver::new(Box::new(storage()));
+    let key = scoped(Decision::Allow, ScopeLevel::Project, DecisionTier::Human)
+        .record
+        .key;
+    let resolved = resolver
+        .resolve(&key, &make_session("coder"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved.decision, Decision::Deny);
+    assert_eq!(resolved.scope, ScopeLevel::Global);
+}
+
+#[test]
+fn scope_resolve_explained_lists_every_scope_match() {
+    use hookwise::scope::ScopeResolver;
+
+    let records = [
+        (ScopeLevel::Role, Decision::Allow),
+        (ScopeLevel::User, Decision::Ask),
+        (ScopeLevel::Project, Decision::Allow),
+        (ScopeLevel::Org, Decision::Deny),
+    ]
+    .into_iter()
+    .map(|(scope, decision)| {
+        let record = scoped(decision, scope, DecisionTier::Human).record;
+        (scope, vec![record])
+    })
+    .collect();
+    let resolver = ScopeResolver::new(Box::new(ScopedStorage(records)));
+    let key = scoped(Decision::Allow, ScopeLevel::Role, DecisionTier::Human)
+        .record
+        .key;
+
+    let explanation = resolver
+        .resolve_explained(&key, &make_session("coder"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(explanation.winner.decision, Decision::Deny);
+    assert_eq!(explanation.winner.scope, ScopeLevel::Org);
+    let candidates: Vec<_> = explanation
+        .candidates
+        .iter()
+        .map(|sd| (sd.scope, sd.decision))
+        .collect();
+    assert_eq!(
+        candidates,
+        vec![
+            (ScopeLevel::Role, Decision::Allow),
+            (ScopeLevel::User, Decision::Ask),
+            (ScopeLevel::Project, Decision::Allow),
+            (ScopeLevel::Org, Decision::Deny),
+        ]
+    );
+
+    let unknown = CacheKey {
+        sanitized_input: "unknown".into(),
+        ..key
+    };
+    assert!(resolver
+        .resolve_explained(&unknown, &make_session("coder"))
+        .unwrap()
+        .is_none());
+}
+
 // ---------------------------------------------------------------------------
 // Human tier: decision queue integration
 // ---------------------------------------------------------------------------
@@ -634,6 +1892,7 @@ fn decision_queue_respond_removes_pending() {
                 always_ask: false,
                 add_rule: true,
                 rule_scope: Some(ScopeLevel::Project),
+                once: false,
             },
         )
         .unwrap();
@@ -4,6 +4,7 @@
 use std::collections::HashMap;
 
 use hookwise::config::roles::{default_categories, PathNormalizer, RolesConfig};
+use hookwise::error::HookwiseError;
 
 // ---------------------------------------------------------------------------
 // default_categories()
@@ -409,3 +410,14 @@ fn project_roles_yml_loads_with_categories() {
     let normalizer = config.normalizer().unwrap();
     assert_eq!(normalizer.normalize("src/main.rs"), "source:main.rs");
 }
+
+#[test]
+fn roles_config_malformed_yaml_reports_path() {
+    let tmp = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(tmp.path(), "roles:\n  coder: [unterminated\n").unwrap();
+
+    match RolesConfig::load_from(tmp.path()) {
+        Err(HookwiseError::ConfigParse { path, .. }) => assert_eq!(path, tmp.path()),
+        other => panic!("expected ConfigParse, got {:?}", other.map(|_| ())),
+    }
+}
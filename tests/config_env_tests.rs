@@ -0,0 +1,91 @@
+//! Tests for `${VAR}` interpolation in policy.yml and roles.yml.
+
+use hookwise::config::roles::RolesConfig;
+use hookwise::config::{PolicyConfig, SupervisorConfig};
+use hookwise::error::HookwiseError;
+
+fn write(yaml: &str) -> tempfile::NamedTempFile {
+    let tmp = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(tmp.path(), yaml).unwrap();
+    tmp
+}
+
+#[test]
+fn set_variables_are_interpolated() {
+    std::env::set_var("HOOKWISE_TEST_API_HOST", "llm.internal:8080");
+    std::env::set_var("HOOKWISE_TEST_SRC", "crates");
+
+    let policy = write(
+        r#"
+supervisor:
+  backend: api
+  api_base_url: "https://${HOOKWISE_TEST_API_HOST}/v1"
+"#,
+    );
+    match PolicyConfig::load_from(policy.path()).unwrap().supervisor {
+        SupervisorConfig::Api { api_base_url, .. } => {
+            assert_eq!(
+                api_base_url.as_deref(),
+                Some("https://llm.internal:8080/v1")
+            );
+        }
+        other => panic!("expected api backend, got {:?}", other),
+    }
+
+    let roles = write(
+        r#"
+roles:
+  coder:
+    name: coder
+    description: "writes ${HOOKWISE_TEST_SRC}"
+    paths:
+      allow_write: ["${HOOKWISE_TEST_SRC}/**"]
+"#,
+    );
+    let roles = RolesConfig::load_from(roles.path()).unwrap();
+    let coder = roles.get_role("coder").unwrap();
+    assert_eq!(coder.description, "writes crates");
+    assert_eq!(coder.paths.allow_write, vec!["crates/**".to_string()]);
+}
+
+#[test]
+fn unset_variable_is_a_parse_error() {
+    std::env::remove_var("HOOKWISE_TEST_UNSET");
+    let roles = write(
+        r#"
+roles:
+  coder:
+    name: coder
+    description: "test"
+    paths:
+      allow_write: ["${HOOKWISE_TEST_UNSET}/**"]
+"#,
+    );
+
+    match RolesConfig::load_from(roles.path()) {
+        Err(HookwiseError::ConfigParse { path, reason }) => {
+            assert_eq!(path, roles.path());
+            assert!(reason.contains("HOOKWISE_TEST_UNSET"), "{}", reason);
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn dollar_escapes_are_left_literal() {
+    let roles = write(
+        r#"
+roles:
+  coder:
+    name: coder
+    description: "costs $$5, ends in $, and $${NOT_EXPANDED}"
+    paths:
+      allow_write: ["src/**"]
+"#,
+    );
+    let roles = RolesConfig::load_from(roles.path()).unwrap();
+    assert_eq!(
+        roles.get_role("coder").unwrap().description,
+        "costs $5, ends in $, and ${NOT_EXPANDED}"
+    );
+}
@@ -0,0 +1,522 @@
+//! Unit tests for Tier 2b: embedding similarity search.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use hookwise::cascade::embed_sim::{EmbeddingSimilarity, DEFAULT_PENDING_REBUILD_THRESHOLD};
+use hookwise::cli::monitor::format_index_health;
+use hookwise::decision::{
+    CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
+};
+use hookwise::HookwiseError;
+
+fn make_record(input: &str, decision: Decision) -> DecisionRecord {
+    DecisionRecord {
+        key: CacheKey {
+            sanitized_input: input.into(),
+            tool: "Bash".into(),
+            role: "coder".into(),
+        },
+        decision,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::Human,
+            confidence: 1.0,
+            reason: "test".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc::now(),
+        scope: ScopeLevel::Project,
+        file_path: None,
+        session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Dimension handling
+// ---------------------------------------------------------------------------
+
+#[test]
+fn search_finds_matching_dimension_entry() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+
+    let (similarity, entry) = es.search(&[1.0, 0.0, 0.0]).unwrap();
+    assert!(similarity > 0.99);
+    assert_eq!(entry.record.key.sanitized_input, "cargo test");
+}
+
+#[test]
+fn search_skips_pending_entries_of_other_dimension() {
+    let es = EmbeddingSimilarity::without_model(0.5);
+    // A 4-dim vector whose 3-dim prefix is identical to the query: a naive
+    // zip would call this a perfect match.
+    es.insert_embedding(
+        &make_record("old model", Decision::Allow),
+        vec![1.0, 0.0, 0.0, 5.0],
+    )
+    .unwrap();
+
+    assert!(es.search(&[1.0, 0.0, 0.0]).is_none());
+}
+
+#[test]
+fn search_skips_index_of_other_dimension() {
+    let es = EmbeddingSimilarity::without_model(0.5);
+    es.insert_embedding(
+        &make_record("old model", Decision::Allow),
+        vec![1.0, 0.0, 0.0, 5.0],
+    )
+    .unwrap();
+    es.rebuild().unwrap();
+    assert_eq!(es.index_dim(), Some(4));
+
+    assert!(es.search(&[1.0, 0.0, 0.0]).is_none());
+    assert!(es.search(&[1.0, 0.0, 0.0, 5.0]).is_some());
+}
+
+#[test]
+fn insert_rejects_mismatched_dimension() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(&make_record("old model", Decision::Allow), vec![0.0, 1.0])
+        .unwrap();
+    assert_eq!(es.dim(), Some(2));
+
+    let err = es
+        .insert_embedding(
+            &make_record("new model", Decision::Allow),
+            vec![1.0, 0.0, 0.0],
+        )
+        .unwrap_err();
+    assert!(matches!(err, HookwiseError::Embedding { .. }), "{err}");
+
+    es.rebuild().unwrap();
+    assert_eq!(es.health().entries, 1);
+    assert_eq!(es.index_dim(), Some(2));
+    assert!(es.search(&[1.0, 0.0, 0.0]).is_none());
+}
+
+#[test]
+fn invalidate_all_resets_dimension() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(&make_record("old model", Decision::Allow), vec![0.0, 1.0])
+        .unwrap();
+    es.invalidate_all();
+    assert_eq!(es.dim(), None);
+
+    es.insert_embedding(
+        &make_record("new model", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    assert_eq!(es.dim(), Some(3));
+}
+
+// ---------------------------------------------------------------------------
+// Index health
+// ---------------------------------------------------------------------------
+
+#[test]
+fn health_reports_built_index() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    es.insert_embedding(
+        &make_record("cargo build", Decision::Allow),
+        vec![0.0, 1.0, 0.0],
+    )
+    .unwrap();
+
+    let before = es.health();
+    assert_eq!(before.pending, 2);
+    assert!(!before.is_built());
+
+    es.rebuild().unwrap();
+    let after = es.health();
+    assert_eq!(after.entries, 2);
+    assert_eq!(after.pending, 0);
+    assert_eq!(after.index_dim, Some(3));
+    assert!(after.is_built());
+}
+
+#[test]
+fn health_reports_noop_without_model() {
+    let health = EmbeddingSimilarity::new_noop(DEFAULT_PENDING_REBUILD_THRESHOLD).health();
+    assert!(!health.model_available);
+    assert_eq!(health.entries, 0);
+    assert!(!health.is_built());
+    assert!(format_index_health(&health).starts_with("noop"));
+}
+
+// ---------------------------------------------------------------------------
+// Degenerate embeddings
+// ---------------------------------------------------------------------------
+
+#[test]
+fn degenerate_embeddings_are_skipped() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(
+        &make_record("zero vector", Decision::Allow),
+        vec![0.0, 0.0, 0.0],
+    )
+    .unwrap();
+    es.insert_embedding(&make_record("empty vector", Decision::Allow), vec![])
+        .unwrap();
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    assert_eq!(es.health().pending, 1);
+
+    es.rebuild().unwrap();
+    assert_eq!(es.health().entries, 1);
+    let (_, entry) = es.search(&[1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(entry.record.key.sanitized_input, "cargo test");
+    assert!(es.search(&[0.0, 0.0, 0.0]).is_none());
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+#[test]
+fn saved_index_loads_without_model() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = tmp.path().join("index/embeddings.jsonl");
+
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    es.insert_embedding(
+        &make_record("rm -rf target", Decision::Ask),
+        vec![0.0, 1.0, 0.0],
+    )
+    .unwrap();
+    es.rebuild().unwrap();
+    es.save_index(&path).unwrap();
+
+    // A noop tier has no model to re-embed with, so the stored embeddings
+    // are all it has; give it a usable threshold to search with.
+    let loaded = EmbeddingSimilarity::without_model(0.9);
+    loaded.load_index(&path).unwrap();
+    assert_eq!(loaded.health().entries, 2);
+    assert_eq!(loaded.index_dim(), Some(3));
+
+    let query = [0.1, 0.9, 0.0];
+    let (_, expected) = es.search(&query).unwrap();
+    let (_, found) = loaded.search(&query).unwrap();
+    assert_eq!(found.record.key, expected.record.key);
+    assert_eq!(found.record.key.sanitized_input, "rm -rf target");
+}
+
+#[test]
+fn load_index_with_mixed_dimensions_errors_without_model() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = tmp.path().join("embeddings.jsonl");
+    let lines: Vec<String> = [("a", vec![1.0, 0.0]), ("b", vec![1.0, 0.0, 0.0])]
+        .into_iter()
+        .map(|(input, embedding)| {
+            serde_json::json!({
+                "embedding": embedding,
+                "record": make_record(input, Decision::Allow),
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+
+    let es = EmbeddingSimilarity::without_model(0.9);
+    let err = es.load_index(&path).unwrap_err();
+    assert!(matches!(err, HookwiseError::Embedding { .. }), "{err}");
+    assert!(!es.health().is_built());
+}
+
+#[test]
+fn load_index_rejects_an_index_saved_by_another_model() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = tmp.path().join("embeddings.jsonl");
+    let es = EmbeddingSimilarity::without_model(0.9).with_model_name("model-a");
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    es.save_index(&path).unwrap();
+
+    // Same dimension, different model: the vectors are not comparable
+    let other = EmbeddingSimilarity::without_model(0.9).with_model_name("model-b");
+    let err = other.load_index(&path).unwrap_err();
+    assert!(err.to_string().contains("model-a"), "{err}");
+    assert_eq!(other.health().entries, 0);
+
+    let same = EmbeddingSimilarity::without_model(0.9).with_model_name("model-a");
+    same.load_index(&path).unwrap();
+    assert_eq!(same.health().entries, 1);
+}
+
+#[test]
+fn load_index_of_missing_file_is_a_no_op() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let es = EmbeddingSimilarity::new_noop(DEFAULT_PENDING_REBUILD_THRESHOLD);
+    es.load_index(&tmp.path().join("missing.jsonl")).unwrap();
+    assert!(!es.health().is_built());
+}
+
+// ---------------------------------------------------------------------------
+// Pending rebuild threshold
+// ---------------------------------------------------------------------------
+
+#[test]
+fn pending_threshold_triggers_rebuild() {
+    let es = EmbeddingSimilarity::new_noop(2);
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    assert_eq!(es.health().pending, 1);
+
+    es.insert_embedding(
+        &make_record("cargo build", Decision::Allow),
+        vec![0.0, 1.0, 0.0],
+    )
+    .unwrap();
+    let health = es.health();
+    assert_eq!(health.pending, 0);
+    assert_eq!(health.entries, 2);
+    assert!(health.is_built());
+}
+
+// ---------------------------------------------------------------------------
+// Top-K search
+// ---------------------------------------------------------------------------
+
+#[test]
+fn search_k_orders_matches_best_first() {
+    let es = EmbeddingSimilarity::without_model(0.5);
+    es.insert_embedding(&make_record("far", Decision::Allow), vec![0.6, 0.8, 0.0])
+        .unwrap();
+    es.insert_embedding(&make_record("exact", Decision::Allow), vec![1.0, 0.0, 0.0])
+        .unwrap();
+    es.rebuild().unwrap();
+    // Left pending, so both the index and the pending buffer contribute
+    es.insert_embedding(&make_record("near", Decision::Allow), vec![0.9, 0.1, 0.0])
+        .unwrap();
+    es.insert_embedding(
+        &make_record("opposite", Decision::Allow),
+        vec![0.0, 0.0, 1.0],
+    )
+    .unwrap();
+
+    let matches = es.search_k(&[1.0, 0.0, 0.0], 2);
+    let inputs: Vec<&str> = matches
+        .iter()
+        .map(|(_, e)| e.record.key.sanitized_input.as_str())
+        .collect();
+    assert_eq!(inputs, ["exact", "near"]);
+    assert_eq!(
+        es.search(&[1.0, 0.0, 0.0])
+            .unwrap()
+            .1
+            .record
+            .key
+            .sanitized_input,
+        "exact"
+    );
+}
+
+#[test]
+fn search_k_larger_than_corpus_returns_every_match() {
+    let es = EmbeddingSimilarity::without_model(0.5);
+    es.insert_embedding(&make_record("far", Decision::Allow), vec![0.6, 0.8, 0.0])
+        .unwrap();
+    es.insert_embedding(&make_record("exact", Decision::Allow), vec![1.0, 0.0, 0.0])
+        .unwrap();
+    es.insert_embedding(
+        &make_record("opposite", Decision::Allow),
+        vec![0.0, 0.0, 1.0],
+    )
+    .unwrap();
+    es.rebuild().unwrap();
+
+    let matches = es.search_k(&[1.0, 0.0, 0.0], 10);
+    let inputs: Vec<&str> = matches
+        .iter()
+        .map(|(_, e)| e.record.key.sanitized_input.as_str())
+        .collect();
+    assert_eq!(inputs, ["exact", "far"]);
+}
+
+// ---------------------------------------------------------------------------
+// Deny on similarity
+// ---------------------------------------------------------------------------
+
+fn query(input: &str) -> hookwise::cascade::CascadeInput {
+    hookwise::cascade::CascadeInput {
+        session: hookwise::session::SessionContext::new_minimal(
+            "user".into(),
+            "org".into(),
+            "project".into(),
+        ),
+        tool_name: "Bash".into(),
+        tool_input: serde_json::json!({ "command": input }),
+        sanitized_input: input.into(),
+        file_path: None,
+        cwd: None,
+    }
+}
+
+/// A denied `rm -rf /` for every role.
+fn denied_rm() -> DecisionRecord {
+    let mut record = make_record("rm -rf /", Decision::Deny);
+    record.key.role = "*".into();
+    record
+}
+
+#[test]
+fn deny_match_falls_through_by_default() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(&denied_rm(), vec![1.0, 0.0, 0.0])
+        .unwrap();
+    let decided = es
+        .evaluate_embedding(&query("rm -rf / "), &[0.99, 0.01, 0.0])
+        .unwrap();
+    assert!(decided.is_none());
+}
+
+#[test]
+fn deny_match_denies_when_enabled() {
+    let es = EmbeddingSimilarity::without_model(0.9).with_deny_on_similarity(true);
+    es.insert_embedding(&denied_rm(), vec![1.0, 0.0, 0.0])
+        .unwrap();
+    let decided = es
+        .evaluate_embedding(&query("rm -rf / "), &[0.99, 0.01, 0.0])
+        .unwrap()
+        .unwrap();
+    assert_eq!(decided.decision, Decision::Deny);
+    assert!(decided.metadata.reason.contains("deny_on_match"));
+}
+
+// ---------------------------------------------------------------------------
+// Tier metrics
+// ---------------------------------------------------------------------------
+
+#[test]
+fn tier_metrics_count_matches_and_fall_throughs() {
+    let es = EmbeddingSimilarity::without_model(0.9);
+    let mut record = make_record("cargo test", Decision::Allow);
+    record.key.role = "*".into();
+    es.insert_embedding(&record, vec![1.0, 0.0, 0.0]).unwrap();
+    es.insert_embedding(&denied_rm(), vec![0.0, 0.0, 1.0])
+        .unwrap();
+
+    let sequence: [(&str, [f32; 3], bool); 4] = [
+        ("cargo test", [0.99, 0.01, 0.0], true),
+        ("ls", [0.0, 1.0, 0.0], false),
+        ("ls -la", [0.0, 1.0, 0.0], false),
+        // A similar deny that is not acted on still falls through.
+        ("rm -rf / ", [0.0, 0.01, 0.99], false),
+    ];
+    for (input, embedding, hit) in sequence {
+        let decided = es.evaluate_embedding(&query(input), &embedding).unwrap();
+        assert_eq!(decided.is_some(), hit, "{}", input);
+    }
+
+    let metrics = es.tier_metrics();
+    assert_eq!(metrics.matches, 1);
+    assert_eq!(metrics.fall_throughs, 3);
+}
+
+#[test]
+fn saved_index_is_reused_only_while_it_holds_the_records() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = EmbeddingSimilarity::index_path(tmp.path());
+    let mut records: Vec<DecisionRecord> = ["cargo test", "cargo build"]
+        .into_iter()
+        .map(|input| {
+            let mut record = make_record(input, Decision::Allow);
+            record.ensure_id();
+            record
+        })
+        .collect();
+    let es = EmbeddingSimilarity::without_model(0.9).with_model_name("model-a");
+    es.insert_embedding(&records[0], vec![1.0, 0.0, 0.0])
+        .unwrap();
+    es.insert_embedding(&records[1], vec![0.0, 1.0, 0.0])
+        .unwrap();
+    es.save_index(&path).unwrap();
+
+    // Nothing to embed: the saved entries are used as they are
+    let loaded = EmbeddingSimilarity::without_model(0.9).with_model_name("model-a");
+    loaded.load_or_build_index(&path, &records).unwrap();
+    assert_eq!(loaded.health().entries, 2);
+
+    // A rule learned since the save makes it stale, and rebuilding needs
+    // the model this engine lacks
+    let mut learned = make_record("cargo fmt", Decision::Allow);
+    learned.ensure_id();
+    records.push(learned);
+    let stale = EmbeddingSimilarity::without_model(0.9).with_model_name("model-a");
+    assert!(stale.load_or_build_index(&path, &records).is_err());
+}
+
+#[test]
+fn saved_health_reads_the_index_file() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = EmbeddingSimilarity::index_path(tmp.path());
+    let health = EmbeddingSimilarity::saved_health(&path).unwrap();
+    assert!(!health.is_built());
+    assert!(format_index_health(&health).contains("not built"));
+
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.insert_embedding(
+        &make_record("cargo test", Decision::Allow),
+        vec![1.0, 0.0, 0.0],
+    )
+    .unwrap();
+    es.save_index(&path).unwrap();
+    let health = EmbeddingSimilarity::saved_health(&path).unwrap();
+    assert_eq!(health.entries, 1);
+    assert_eq!(health.index_dim, Some(3));
+}
+
+#[test]
+fn pattern_rules_are_never_embedded() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let path = EmbeddingSimilarity::index_path(tmp.path());
+    let mut literal = make_record("cargo test", Decision::Allow);
+    literal.ensure_id();
+    let mut pattern = make_record("pattern:git push *", Decision::Allow);
+    pattern.ensure_id();
+
+    // Only a glob to index: nothing to embed, so no model is needed
+    let es = EmbeddingSimilarity::without_model(0.9);
+    es.build_index(std::slice::from_ref(&pattern)).unwrap();
+    es.insert_embedding(&pattern, vec![1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(es.health().entries + es.health().pending, 0);
+
+    // A saved index of the literal rules still holds every indexable record
+    es.insert_embedding(&literal, vec![1.0, 0.0, 0.0]).unwrap();
+    es.save_index(&path).unwrap();
+    let loaded = EmbeddingSimilarity::without_model(0.9);
+    loaded
+        .load_or_build_index(&path, &[literal, pattern])
+        .unwrap();
+    assert_eq!(loaded.health().entries, 1);
+}
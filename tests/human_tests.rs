@@ -0,0 +1,147 @@
+//! Tests for Tier 4: human-in-the-loop responses through the decision queue.
+
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tempfile::TempDir;
+
+use hookwise::cascade::cache::ExactCache;
+use hookwise::cascade::embed_sim::{EmbeddingSimilarity, DEFAULT_PENDING_REBUILD_THRESHOLD};
+use hookwise::cascade::human::{DecisionQueue, HumanResponse, HumanTier};
+use hookwise::cascade::path_policy::PathPolicyEngine;
+use hookwise::cascade::token_sim::TokenJaccard;
+use hookwise::cascade::{CascadeInput, CascadeRunner, CascadeTier};
+use hookwise::config::policy::PolicyConfig;
+use hookwise::decision::{Decision, DecisionRecord, DecisionTier, ScopeLevel};
+use hookwise::session::SessionContext;
+use hookwise::storage::jsonl::JsonlStorage;
+use hookwise::storage::StorageBackend;
+
+/// Point the file-backed queue at a private runtime dir, so this process does
+/// not share queue state with other test binaries.
+fn isolated_runtime_dir() -> &'static PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = TempDir::new().unwrap().keep();
+        std::env::set_var("XDG_RUNTIME_DIR", &dir);
+        std::env::remove_var("CLAUDE_TEAM_ID");
+        dir
+    })
+}
+
+/// A supervisor tier that always falls through to the human.
+struct NoopSupervisor;
+
+#[async_trait]
+impl CascadeTier for NoopSupervisor {
+    async fn evaluate(
+        &self,
+        _input: &CascadeInput,
+    ) -> hookwise::error::Result<Option<DecisionRecord>> {
+        Ok(None)
+    }
+    fn tier(&self) -> DecisionTier {
+        DecisionTier::Supervisor
+    }
+    fn name(&self) -> &str {
+        "noop-supervisor"
+    }
+}
+
+fn make_runner(tmp: &TempDir, queue: Arc<DecisionQueue>) -> CascadeRunner {
+    CascadeRunner {
+        sanitizer: hookwise::sanitize::SanitizePipeline::default_pipeline(),
+        path_policy: Box::new(PathPolicyEngine::new().unwrap()),
+        exact_cache: Arc::new(ExactCache::new()),
+        token_jaccard: Arc::new(TokenJaccard::new(0.7, 3, 1)),
+        embedding_similarity: Arc::new(EmbeddingSimilarity::new_noop(
+            DEFAULT_PENDING_REBUILD_THRESHOLD,
+        )),
+        supervisor: Box::new(NoopSupervisor),
+        human: Box::new(HumanTier::new(queue, 10)),
+        storage: Box::new(JsonlStorage::new(
+            tmp.path().to_path_buf(),
+            tmp.path().join("global"),
+            None,
+        )),
+        policy: PolicyConfig::default(),
+        normalizer: None,
+        dry_run: false,
+        usage: Default::default(),
+        scopes: None,
+    }
+}
+
+/// Wait for the cascade to enqueue a decision and return its id.
+async fn wait_for_pending(queue: &DecisionQueue) -> String {
+    for _ in 0..100 {
+        if let Some(pending) = queue.list_pending().into_iter().next() {
+            return pending.id;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("no decision was queued");
+}
+
+#[tokio::test]
+async fn allow_once_unblocks_without_persisting_or_caching() {
+    isolated_runtime_dir();
+    let tmp = TempDir::new().unwrap();
+    let queue = Arc::new(DecisionQueue::new());
+    let runner = Arc::new(make_runner(&tmp, queue.clone()));
+    let session = SessionContext::new_minimal("user".into(), "org".into(), "project".into());
+    let tool_input = serde_json::json!({"command": "terraform apply"});
+
+    let pending_call = {
+        let runner = runner.clone();
+        let session = session.clone();
+        let tool_input = tool_input.clone();
+        tokio::spawn(async move { runner.evaluate(&session, "Bash", &tool_input).await })
+    };
+
+    let id = wait_for_pending(&queue).await;
+    queue
+        .respond(
+            &id,
+            HumanResponse {
+                decision: Decision::Allow,
+                always_ask: false,
+                add_rule: false,
+                rule_scope: None,
+                once: true,
+            },
+        )
+        .unwrap();
+
+    let record = pending_call.await.unwrap().unwrap();
+    assert_eq!(record.decision, Decision::Allow);
+    assert_eq!(record.metadata.tier, DecisionTier::HumanOnce);
+
+    // Nothing written to storage...
+    let storage = JsonlStorage::new(tmp.path().to_path_buf(), tmp.path().join("global"), None);
+    assert!(storage
+        .load_decisions(ScopeLevel::Project)
+        .unwrap()
+        .is_empty());
+    assert!(!tmp.path().join("rules").join("allow.jsonl").exists());
+
+    // ...and nothing cached: the same call is not resolved locally again.
+    assert_eq!(runner.exact_cache.stats().total_entries, 0);
+    let input = CascadeInput {
+        session,
+        tool_name: "Bash".into(),
+        tool_input: tool_input.clone(),
+        sanitized_input: serde_json::to_string(&tool_input).unwrap(),
+        file_path: None,
+        cwd: None,
+    };
+    assert!(runner.exact_cache.evaluate(&input).await.unwrap().is_none());
+    assert!(runner
+        .token_jaccard
+        .evaluate(&input)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -3,14 +3,18 @@
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 use tempfile::TempDir;
 
 use hookwise::decision::{Decision, DecisionMetadata, DecisionTier};
-use hookwise::error::Result as CHResult;
+use hookwise::error::{HookwiseError, Result as CHResult};
+use hookwise::ipc::client_pool::IpcClientPool;
+use hookwise::ipc::framing::Framing;
 use hookwise::ipc::socket_client::IpcClient;
 use hookwise::ipc::socket_server::IpcServer;
-use hookwise::ipc::{IpcRequest, IpcResponse};
+use hookwise::ipc::{IpcControl, IpcMessage, IpcQuery, IpcRequest, IpcResponse};
+use hookwise::scope::ScopeLevel;
 
 // ---------------------------------------------------------------------------
 // IPC message serialization
@@ -19,6 +23,7 @@ use hookwise::ipc::{IpcRequest, IpcResponse};
 #[test]
 fn ipc_request_serialization_roundtrip() {
     let request = IpcRequest {
+        id: None,
         session_id: "session-123".into(),
         tool_name: "Bash".into(),
         tool_input: r#"{"command": "echo hello"}"#.into(),
@@ -45,6 +50,7 @@ fn ipc_request_serialization_roundtrip() {
 #[test]
 fn ipc_response_serialization_roundtrip() {
     let response = IpcResponse {
+        id: None,
         decision: Decision::Allow,
         metadata: DecisionMetadata {
             tier: DecisionTier::Supervisor,
@@ -66,6 +72,7 @@ fn ipc_response_serialization_roundtrip() {
 #[test]
 fn ipc_request_with_all_fields() {
     let request = IpcRequest {
+        id: None,
         session_id: "s1".into(),
         tool_name: "Write".into(),
         tool_input: r#"{"file_path": "src/main.rs"}"#.into(),
@@ -96,6 +103,7 @@ async fn ipc_server_client_roundtrip() {
         |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
             Box::pin(async move {
                 Ok(IpcResponse {
+                    id: None,
                     decision: Decision::Allow,
                     metadata: DecisionMetadata {
                         tier: DecisionTier::Supervisor,
@@ -121,6 +129,7 @@ async fn ipc_server_client_roundtrip() {
     // Send a request from the client
     let client = IpcClient::new(socket_path.clone(), 5);
     let request = IpcRequest {
+        id: None,
         session_id: "test-session".into(),
         tool_name: "Bash".into(),
         tool_input: r#"{"command": "echo hello"}"#.into(),
@@ -140,6 +149,145 @@ async fn ipc_server_client_roundtrip() {
     let _ = std::fs::remove_file(&socket_path);
 }
 
+#[tokio::test]
+async fn ipc_control_message_reaches_handler() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("control.sock");
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async move { unreachable!("no decision requests are sent") })
+        };
+    let control_received = received.clone();
+    let control_handler =
+        move |control: IpcControl| -> Pin<Box<dyn Future<Output = CHResult<()>> + Send>> {
+            control_received.lock().unwrap().push(control);
+            Box::pin(async { Ok(()) })
+        };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket).with_control_handler(control_handler);
+        let _ = srv.serve(handler).await;
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let control = IpcControl::InvalidateRole {
+        scope: ScopeLevel::Project,
+        role: "coder".into(),
+    };
+    IpcClient::new(socket_path.clone(), 5)
+        .send_control(&control)
+        .await
+        .unwrap();
+    assert_eq!(*received.lock().unwrap(), vec![control]);
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn ipc_control_message_rejected_without_handler() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("nocontrol.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async move { unreachable!("no decision requests are sent") })
+        };
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let result = IpcClient::new(socket_path.clone(), 5)
+        .send_control(&IpcControl::InvalidateRole {
+            scope: ScopeLevel::Project,
+            role: "coder".into(),
+        })
+        .await;
+    assert!(result.is_err());
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[test]
+fn ipc_message_tells_health_queries_from_bare_requests() {
+    let health: IpcMessage = serde_json::from_str(r#"{"query":"health"}"#).unwrap();
+    assert!(matches!(health, IpcMessage::Query(IpcQuery::Health)));
+    assert_eq!(
+        serde_json::to_string(&IpcQuery::Health).unwrap(),
+        r#"{"query":"health"}"#
+    );
+
+    let bare = r#"{"session_id":"s","tool_name":"Bash","tool_input":"{}","role":"coder","file_path":null,"task_description":null,"prompt_path":null,"cwd":"/tmp"}"#;
+    let request: IpcMessage = serde_json::from_str(bare).unwrap();
+    assert!(matches!(request, IpcMessage::Request(r) if r.session_id == "s"));
+}
+
+#[tokio::test]
+async fn ipc_health_and_check_share_a_server() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("health.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async move {
+                Ok(IpcResponse {
+                    id: None,
+                    decision: Decision::Deny,
+                    metadata: DecisionMetadata {
+                        tier: DecisionTier::Supervisor,
+                        confidence: 0.9,
+                        reason: "checked".into(),
+                        matched_key: None,
+                        similarity_score: None,
+                    },
+                })
+            })
+        };
+    let loaded = Arc::new(std::sync::atomic::AtomicUsize::new(42));
+    let server_socket = socket_path.clone();
+    let server_loaded = loaded.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket).with_loaded_decisions(server_loaded);
+        let _ = srv.serve(handler).await;
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let client = IpcClient::new(socket_path.clone(), 5);
+    let health = client.health().await.unwrap();
+    assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(health.pending_queue_len, 0);
+    assert_eq!(health.loaded_decisions, 42);
+
+    loaded.store(43, std::sync::atomic::Ordering::Relaxed);
+    assert_eq!(client.health().await.unwrap().loaded_decisions, 43);
+
+    let response = client
+        .request(&IpcRequest {
+            id: None,
+            session_id: "test-session".into(),
+            tool_name: "Bash".into(),
+            tool_input: r#"{"command": "rm -rf /"}"#.into(),
+            role: "coder".into(),
+            file_path: None,
+            task_description: None,
+            prompt_path: None,
+            cwd: "/tmp".into(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.decision, Decision::Deny);
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
 #[tokio::test]
 async fn ipc_server_handles_deny_response() {
     let tmp = TempDir::new().unwrap();
@@ -150,6 +298,7 @@ async fn ipc_server_handles_deny_response() {
         |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
             Box::pin(async move {
                 Ok(IpcResponse {
+                    id: None,
                     decision: Decision::Deny,
                     metadata: DecisionMetadata {
                         tier: DecisionTier::Supervisor,
@@ -172,6 +321,7 @@ async fn ipc_server_handles_deny_response() {
 
     let client = IpcClient::new(socket_path.clone(), 5);
     let request = IpcRequest {
+        id: None,
         session_id: "test".into(),
         tool_name: "Bash".into(),
         tool_input: r#"{"command": "rm -rf /"}"#.into(),
@@ -200,6 +350,7 @@ async fn ipc_server_handles_ask_response() {
         |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
             Box::pin(async move {
                 Ok(IpcResponse {
+                    id: None,
                     decision: Decision::Ask,
                     metadata: DecisionMetadata {
                         tier: DecisionTier::Supervisor,
@@ -222,6 +373,7 @@ async fn ipc_server_handles_ask_response() {
 
     let client = IpcClient::new(socket_path.clone(), 5);
     let request = IpcRequest {
+        id: None,
         session_id: "test".into(),
         tool_name: "Write".into(),
         tool_input: r#"{"file_path": ".env"}"#.into(),
@@ -248,6 +400,7 @@ async fn ipc_client_nonexistent_socket_errors() {
     let client = IpcClient::new(PathBuf::from("/tmp/nonexistent-hookwise-test.sock"), 1);
 
     let request = IpcRequest {
+        id: None,
         session_id: "test".into(),
         tool_name: "Bash".into(),
         tool_input: "{}".into(),
@@ -262,6 +415,360 @@ async fn ipc_client_nonexistent_socket_errors() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn ipc_client_without_retries_reports_missing_socket() {
+    let tmp = TempDir::new().unwrap();
+    let client = IpcClient::new(tmp.path().join("missing.sock"), 1);
+
+    let request = IpcRequest {
+        id: None,
+        session_id: "test".into(),
+        tool_name: "Bash".into(),
+        tool_input: "{}".into(),
+        role: "coder".into(),
+        file_path: None,
+        task_description: None,
+        prompt_path: None,
+        cwd: "/tmp".into(),
+    };
+
+    let start = std::time::Instant::now();
+    let err = client.request(&request).await.unwrap_err();
+    assert!(matches!(err, HookwiseError::SocketNotFound { .. }), "{err}");
+    assert!(start.elapsed() < std::time::Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn ipc_retrying_client_waits_for_late_server() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("late.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async move {
+                Ok(IpcResponse {
+                    id: None,
+                    decision: Decision::Allow,
+                    metadata: DecisionMetadata {
+                        tier: DecisionTier::Supervisor,
+                        confidence: 0.9,
+                        reason: "late but here".into(),
+                        matched_key: None,
+                        similarity_score: None,
+                    },
+                })
+            })
+        };
+
+    // The server binds only after the client has started trying
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+
+    let client = IpcClient::new_with_retries(socket_path, 5, 6);
+    let request = IpcRequest {
+        id: None,
+        session_id: "test-session".into(),
+        tool_name: "Bash".into(),
+        tool_input: r#"{"command": "echo hello"}"#.into(),
+        role: "coder".into(),
+        file_path: None,
+        task_description: None,
+        prompt_path: None,
+        cwd: "/tmp".into(),
+    };
+
+    let response = client.request(&request).await.unwrap();
+    assert_eq!(response.metadata.reason, "late but here");
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn ipc_length_prefixed_roundtrip_keeps_control_characters() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("framed.sock");
+
+    // Echo the tool input back as the reason
+    let handler = |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+        Box::pin(async move {
+            Ok(IpcResponse {
+                id: None,
+                decision: Decision::Allow,
+                metadata: DecisionMetadata {
+                    tier: DecisionTier::Supervisor,
+                    confidence: 0.9,
+                    reason: req.tool_input,
+                    matched_key: None,
+                    similarity_score: None,
+                },
+            })
+        })
+    };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+
+    let tool_input = "line one\nline two\r\n\ttabbed\u{0}nul\u{1b}[0m";
+    let request = IpcRequest {
+        id: None,
+        session_id: "test-session".into(),
+        tool_name: "Bash".into(),
+        tool_input: tool_input.into(),
+        role: "coder".into(),
+        file_path: None,
+        task_description: None,
+        prompt_path: None,
+        cwd: "/tmp".into(),
+    };
+
+    // Both framings are served by the same server
+    for framing in [Framing::LengthPrefixed, Framing::Newline] {
+        let client = IpcClient::new_with_retries(socket_path.clone(), 5, 6).with_framing(framing);
+        let response = client.request(&request).await.unwrap();
+        assert_eq!(response.metadata.reason, tool_input, "{framing:?}");
+    }
+
+    server_handle.abort();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ipc_server_socket_is_owner_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = TempDir::new().unwrap();
+    let socket_dir = tmp.path().join("run");
+    let socket_path = socket_dir.join("perms.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async { unreachable!("no requests are sent") })
+        };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let socket_mode = std::fs::metadata(&socket_path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(socket_mode & 0o777, 0o600);
+    let dir_mode = std::fs::metadata(&socket_dir).unwrap().permissions().mode();
+    assert_eq!(dir_mode & 0o777, 0o700);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn ipc_server_caps_concurrent_connections() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("capped.sock");
+
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let active = active.clone();
+        let peak = peak.clone();
+        move |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            let active = active.clone();
+            let peak = peak.clone();
+            Box::pin(async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok(IpcResponse {
+                    id: None,
+                    decision: Decision::Allow,
+                    metadata: DecisionMetadata {
+                        tier: DecisionTier::Supervisor,
+                        confidence: 0.9,
+                        reason: "slow but fine".into(),
+                        matched_key: None,
+                        similarity_score: None,
+                    },
+                })
+            })
+        }
+    };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket).with_max_concurrent(2);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let mut clients = tokio::task::JoinSet::new();
+    for n in 0..6 {
+        let client = IpcClient::new(socket_path.clone(), 5);
+        clients.spawn(async move {
+            let request = IpcRequest {
+                id: None,
+                session_id: format!("s-{n}"),
+                tool_name: "Bash".into(),
+                tool_input: "{}".into(),
+                role: "coder".into(),
+                file_path: None,
+                task_description: None,
+                prompt_path: None,
+                cwd: "/tmp".into(),
+            };
+            client.request(&request).await
+        });
+    }
+
+    // Every client is answered, just never more than two at a time
+    while let Some(joined) = clients.join_next().await {
+        let response = joined.unwrap().unwrap();
+        assert_eq!(response.decision, Decision::Allow);
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn ipc_server_caps_requests_pipelined_on_one_connection() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("pipelined.sock");
+
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let active = active.clone();
+        let peak = peak.clone();
+        move |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            let active = active.clone();
+            let peak = peak.clone();
+            Box::pin(async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok(IpcResponse {
+                    id: None,
+                    decision: Decision::Allow,
+                    metadata: DecisionMetadata {
+                        tier: DecisionTier::Supervisor,
+                        confidence: 0.9,
+                        reason: req.session_id,
+                        matched_key: None,
+                        similarity_score: None,
+                    },
+                })
+            })
+        }
+    };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket).with_max_concurrent(3);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    // One pooled connection carries all six requests, but never more than
+    // the server's limit run at once
+    let pool = Arc::new(IpcClientPool::new(socket_path.clone(), 5));
+    let mut requests = tokio::task::JoinSet::new();
+    for n in 0..6 {
+        let pool = pool.clone();
+        requests.spawn(async move {
+            let request = IpcRequest {
+                id: None,
+                session_id: format!("s-{n}"),
+                tool_name: "Bash".into(),
+                tool_input: "{}".into(),
+                role: "coder".into(),
+                file_path: None,
+                task_description: None,
+                prompt_path: None,
+                cwd: "/tmp".into(),
+            };
+            (n, pool.request(&request).await.unwrap())
+        });
+    }
+
+    while let Some(joined) = requests.join_next().await {
+        let (n, response) = joined.unwrap();
+        assert_eq!(response.metadata.reason, format!("s-{n}"));
+    }
+    let peak = peak.load(Ordering::SeqCst);
+    assert!((2..=3).contains(&peak), "peak was {peak}");
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn ipc_server_answers_oversized_request_with_ask() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("oversized.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async { unreachable!("the request is never parsed") })
+        };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket).with_max_request_bytes(1024);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    // A megabyte with no newline; the server stops reading well before the end
+    let stream = tokio::net::UnixStream::connect(&socket_path).await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let flood = tokio::spawn(async move {
+        let _ = writer.write_all(&vec![b'x'; 1_048_576]).await;
+    });
+
+    // The server closes with part of the flood unread, so only its reply
+    // line is read back; reading on would hit the connection reset
+    let mut response = String::new();
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        tokio::io::BufReader::new(reader).read_line(&mut response),
+    )
+    .await
+    .expect("server answers instead of buffering the whole request")
+    .unwrap();
+    let response: IpcResponse = serde_json::from_str(&response).unwrap();
+    assert_eq!(response.decision, Decision::Ask);
+    assert!(
+        response.metadata.reason.contains("exceeds 1024 bytes"),
+        "{}",
+        response.metadata.reason
+    );
+
+    flood.abort();
+    server_handle.abort();
+}
+
 #[tokio::test]
 async fn ipc_multiple_sequential_requests() {
     let tmp = TempDir::new().unwrap();
@@ -271,6 +778,7 @@ async fn ipc_multiple_sequential_requests() {
     let handler = |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
         Box::pin(async move {
             Ok(IpcResponse {
+                id: None,
                 decision: Decision::Allow,
                 metadata: DecisionMetadata {
                     tier: DecisionTier::Supervisor,
@@ -296,6 +804,7 @@ async fn ipc_multiple_sequential_requests() {
     // Send multiple requests
     for tool in &["Bash", "Write", "Read", "Edit"] {
         let request = IpcRequest {
+            id: None,
             session_id: "test".into(),
             tool_name: tool.to_string(),
             tool_input: "{}".into(),
@@ -314,15 +823,292 @@ async fn ipc_multiple_sequential_requests() {
     let _ = std::fs::remove_file(&socket_path);
 }
 
+#[tokio::test]
+async fn ipc_pool_multiplexes_concurrent_requests_on_one_connection() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("pool.sock");
+
+    // Earlier requests take longer, so responses come back out of order
+    let handler = |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+        Box::pin(async move {
+            let n: u64 = req.session_id.trim_start_matches("s-").parse().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis((10 - n) * 20)).await;
+            Ok(IpcResponse {
+                id: None,
+                decision: Decision::Allow,
+                metadata: DecisionMetadata {
+                    tier: DecisionTier::Supervisor,
+                    confidence: 0.9,
+                    reason: req.session_id,
+                    matched_key: None,
+                    similarity_score: None,
+                },
+            })
+        })
+    };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let pool = Arc::new(IpcClientPool::new(socket_path.clone(), 5));
+    let started = std::time::Instant::now();
+    let mut requests = tokio::task::JoinSet::new();
+    for n in 0..10 {
+        let pool = pool.clone();
+        requests.spawn(async move {
+            let request = IpcRequest {
+                id: None,
+                session_id: format!("s-{n}"),
+                tool_name: "Bash".into(),
+                tool_input: "{}".into(),
+                role: "coder".into(),
+                file_path: None,
+                task_description: None,
+                prompt_path: None,
+                cwd: "/tmp".into(),
+            };
+            (n, pool.request(&request).await.unwrap())
+        });
+    }
+
+    let mut ids = Vec::new();
+    while let Some(joined) = requests.join_next().await {
+        let (n, response) = joined.unwrap();
+        assert_eq!(response.metadata.reason, format!("s-{n}"));
+        ids.push(response.id.expect("response carries the request id"));
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 10);
+
+    // Served one after another the handlers would take 1.1s in total
+    assert!(started.elapsed() < std::time::Duration::from_millis(900));
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn ipc_handler_error_is_answered_with_ask_for_that_request() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("error.sock");
+
+    let handler = |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+        Box::pin(async move {
+            if req.session_id == "broken" {
+                return Err(HookwiseError::Ipc {
+                    reason: "storage unavailable".into(),
+                });
+            }
+            Ok(IpcResponse {
+                id: None,
+                decision: Decision::Allow,
+                metadata: DecisionMetadata {
+                    tier: DecisionTier::Supervisor,
+                    confidence: 0.9,
+                    reason: req.session_id,
+                    matched_key: None,
+                    similarity_score: None,
+                },
+            })
+        })
+    };
+
+    let server_socket = socket_path.clone();
+    let server_handle = tokio::spawn(async move {
+        let srv = IpcServer::new(server_socket);
+        let _ = srv.serve(handler).await;
+    });
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let pool = IpcClientPool::new(socket_path.clone(), 5);
+    let request = |session: &str| IpcRequest {
+        id: None,
+        session_id: session.into(),
+        tool_name: "Bash".into(),
+        tool_input: "{}".into(),
+        role: "coder".into(),
+        file_path: None,
+        task_description: None,
+        prompt_path: None,
+        cwd: "/tmp".into(),
+    };
+
+    let failed = pool.request(&request("broken")).await.unwrap();
+    assert_eq!(failed.decision, Decision::Ask);
+    assert!(failed.metadata.reason.contains("storage unavailable"));
+    assert!(failed.id.is_some());
+
+    // The connection keeps serving after the failure
+    let ok = pool.request(&request("fine")).await.unwrap();
+    assert_eq!(ok.decision, Decision::Allow);
+    assert_eq!(ok.metadata.reason, "fine");
+
+    server_handle.abort();
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[tokio::test]
+async fn ipc_shutdown_waits_for_in_flight_requests_then_removes_socket() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("shutdown.sock");
+
+    let handler =
+        |_req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+            Box::pin(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                Ok(IpcResponse {
+                    id: None,
+                    decision: Decision::Allow,
+                    metadata: DecisionMetadata {
+                        tier: DecisionTier::Supervisor,
+                        confidence: 0.9,
+                        reason: "finished during shutdown".into(),
+                        matched_key: None,
+                        similarity_score: None,
+                    },
+                })
+            })
+        };
+
+    let server = Arc::new(IpcServer::new(socket_path.clone()));
+    let serving = tokio::spawn({
+        let server = server.clone();
+        async move { server.serve_with_signals(handler).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(socket_path.exists());
+
+    let client = IpcClient::new(socket_path.clone(), 5);
+    let in_flight = tokio::spawn(async move {
+        client
+            .request(&IpcRequest {
+                id: None,
+                session_id: "test-session".into(),
+                tool_name: "Bash".into(),
+                tool_input: r#"{"command": "echo hello"}"#.into(),
+                role: "coder".into(),
+                file_path: None,
+                task_description: None,
+                prompt_path: None,
+                cwd: "/tmp".into(),
+            })
+            .await
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    server.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+
+    assert!(!socket_path.exists());
+    let response = in_flight.await.unwrap().unwrap();
+    assert_eq!(response.metadata.reason, "finished during shutdown");
+}
+
+#[tokio::test]
+async fn ipc_shutdown_waits_for_requests_pipelined_on_one_connection() {
+    let tmp = TempDir::new().unwrap();
+    let socket_path = tmp.path().join("pipelined-shutdown.sock");
+
+    let handler = |req: IpcRequest| -> Pin<Box<dyn Future<Output = CHResult<IpcResponse>> + Send>> {
+        Box::pin(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            Ok(IpcResponse {
+                id: None,
+                decision: Decision::Allow,
+                metadata: DecisionMetadata {
+                    tier: DecisionTier::Supervisor,
+                    confidence: 0.9,
+                    reason: req.session_id,
+                    matched_key: None,
+                    similarity_score: None,
+                },
+            })
+        })
+    };
+
+    let server = Arc::new(IpcServer::new(socket_path.clone()));
+    let serving = tokio::spawn({
+        let server = server.clone();
+        async move { server.serve_with_signals(handler).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let pool = Arc::new(IpcClientPool::new(socket_path.clone(), 5));
+    let mut requests = tokio::task::JoinSet::new();
+    for n in 0..3 {
+        let pool = pool.clone();
+        requests.spawn(async move {
+            let request = IpcRequest {
+                id: None,
+                session_id: format!("s-{n}"),
+                tool_name: "Bash".into(),
+                tool_input: "{}".into(),
+                role: "coder".into(),
+                file_path: None,
+                task_description: None,
+                prompt_path: None,
+                cwd: "/tmp".into(),
+            };
+            (n, pool.request(&request).await)
+        });
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    server.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+
+    // Every request was still running when shutdown began, yet all answered
+    while let Some(joined) = requests.join_next().await {
+        let (n, response) = joined.unwrap();
+        assert_eq!(response.unwrap().metadata.reason, format!("s-{n}"));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Supervisor socket path
+// ---------------------------------------------------------------------------
+
+#[test]
+fn supervisor_server_and_client_share_the_default_socket_path() {
+    for team in [Some("alpha"), None] {
+        let server = IpcServer::for_supervisor(None, team);
+        let client = IpcClient::for_supervisor(None, team, 5);
+        assert_eq!(server.socket_path(), client.socket_path());
+        assert_eq!(
+            server.socket_path(),
+            hookwise::session::supervisor_socket_path(team)
+        );
+    }
+    assert!(IpcServer::for_supervisor(None, Some("alpha"))
+        .socket_path()
+        .ends_with("hookwise-alpha-supervisor.sock"));
+    assert!(IpcClient::for_supervisor(None, None, 5)
+        .socket_path()
+        .ends_with("hookwise-solo-supervisor.sock"));
+
+    let configured = PathBuf::from("/run/custom.sock");
+    let server = IpcServer::for_supervisor(Some(&configured), Some("alpha"));
+    let client = IpcClient::for_supervisor(Some(&configured), Some("alpha"), 5);
+    assert_eq!(server.socket_path(), configured);
+    assert_eq!(client.socket_path(), configured);
+}
+
 // ---------------------------------------------------------------------------
 // Pending queue serialization
 // ---------------------------------------------------------------------------
 
 #[test]
 fn pending_queue_serialization_roundtrip() {
+    use chrono::Utc;
     use hookwise::cascade::human::PendingDecision;
     use hookwise::ipc::pending_queue;
-    use chrono::Utc;
 
     let decisions = vec![
         PendingDecision {
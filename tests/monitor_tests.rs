@@ -0,0 +1,155 @@
+//! Tests for `monitor`: line formats and following the rule files.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use chrono::{TimeZone, Utc};
+use hookwise::cli::monitor::{format_record, DecisionStream, MonitorFormat};
+use hookwise::decision::{
+    CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
+};
+
+fn sample_record() -> DecisionRecord {
+    DecisionRecord {
+        key: CacheKey {
+            sanitized_input: r#"{"command":"cargo test"}"#.into(),
+            tool: "Bash".into(),
+            role: "coder".into(),
+        },
+        decision: Decision::Allow,
+        metadata: DecisionMetadata {
+            tier: DecisionTier::ExactCache,
+            confidence: 0.875,
+            reason: "seeded".into(),
+            matched_key: None,
+            similarity_score: None,
+        },
+        timestamp: Utc.with_ymd_and_hms(2026, 1, 1, 9, 30, 5).unwrap(),
+        scope: ScopeLevel::Project,
+        file_path: None,
+        session_id: "session-1".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: "rec-1".into(),
+        last_used: None,
+        hit_count: 0,
+    }
+}
+
+#[test]
+fn pretty_format_is_one_readable_line() {
+    assert_eq!(
+        format_record(&sample_record(), MonitorFormat::Pretty),
+        "[09:30:05] rec-1 allow Bash coder (tier: ExactCache, confidence: 0.88) -- seeded"
+    );
+}
+
+#[test]
+fn json_format_is_the_raw_record() {
+    let line = format_record(&sample_record(), MonitorFormat::Json);
+    assert!(!line.contains('\n'));
+    let parsed: DecisionRecord = serde_json::from_str(&line).unwrap();
+    assert_eq!(parsed.id, "rec-1");
+    assert_eq!(parsed.key, sample_record().key);
+    assert_eq!(parsed.metadata.tier, DecisionTier::ExactCache);
+}
+
+#[test]
+fn compact_format_is_terse() {
+    assert_eq!(
+        format_record(&sample_record(), MonitorFormat::Compact),
+        "09:30:05 allow Bash coder ExactCache"
+    );
+}
+
+#[tokio::test]
+async fn stream_emits_appended_record_before_the_poll_interval() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let allow = tmp.path().join("allow.jsonl");
+    // An existing rule is not replayed
+    let mut old = sample_record();
+    old.id = "old".into();
+    std::fs::write(
+        &allow,
+        format!("{}\n", serde_json::to_string(&old).unwrap()),
+    )
+    .unwrap();
+
+    let mut stream = DecisionStream::open(tmp.path());
+    assert!(stream.is_watching());
+
+    let started = Instant::now();
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&allow)
+        .unwrap();
+    writeln!(file, "{}", serde_json::to_string(&sample_record()).unwrap()).unwrap();
+
+    let mut emitted = Vec::new();
+    while emitted.is_empty() {
+        emitted = tokio::time::timeout(Duration::from_millis(900), stream.next_batch())
+            .await
+            .expect("no event within the old one-second poll");
+    }
+    assert!(started.elapsed() < Duration::from_secs(1));
+    let ids: Vec<_> = emitted.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["rec-1"]);
+}
+
+#[tokio::test]
+async fn stream_polls_when_the_rules_dir_cannot_be_watched() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let rules_dir = tmp.path().join("rules");
+    let mut stream = DecisionStream::open(&rules_dir);
+    assert!(!stream.is_watching());
+
+    std::fs::create_dir_all(&rules_dir).unwrap();
+    std::fs::write(
+        rules_dir.join("deny.jsonl"),
+        format!("{}\n", serde_json::to_string(&sample_record()).unwrap()),
+    )
+    .unwrap();
+    let emitted = stream.next_batch().await;
+    assert_eq!(emitted.len(), 1);
+}
+
+#[tokio::test]
+async fn stream_waits_for_a_line_to_be_finished() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let rules_dir = tmp.path().join("rules");
+    let mut stream = DecisionStream::open(&rules_dir);
+    std::fs::create_dir_all(&rules_dir).unwrap();
+
+    let line = serde_json::to_string(&sample_record()).unwrap();
+    let (head, tail) = line.split_at(line.len() / 2);
+    let allow = rules_dir.join("allow.jsonl");
+    std::fs::write(&allow, head).unwrap();
+    assert!(stream.next_batch().await.is_empty());
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&allow)
+        .unwrap();
+    writeln!(file, "{tail}").unwrap();
+    let emitted = stream.next_batch().await;
+    assert_eq!(emitted.len(), 1);
+    assert_eq!(emitted[0].id, "rec-1");
+}
+
+#[tokio::test]
+async fn stream_rereads_a_file_that_shrank() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let rules_dir = tmp.path().join("rules");
+    let mut stream = DecisionStream::open(&rules_dir);
+    std::fs::create_dir_all(&rules_dir).unwrap();
+
+    let line = serde_json::to_string(&sample_record()).unwrap();
+    let allow = rules_dir.join("allow.jsonl");
+    std::fs::write(&allow, format!("{line}\n{line}\n")).unwrap();
+    assert_eq!(stream.next_batch().await.len(), 2);
+
+    // Rewritten shorter, as `prune` does
+    std::fs::write(&allow, format!("{line}\n")).unwrap();
+    assert_eq!(stream.next_batch().await.len(), 1);
+}
@@ -1,6 +1,6 @@
 //! Unit tests for path policy: globset matching, deny-wins, sensitive paths.
 
-use hookwise::config::roles::{CompiledPathPolicy, PathPolicyConfig};
+use hookwise::config::roles::{CompiledPathPolicy, PathPolicyConfig, PathVerdict};
 
 fn compile_policy(
     allow_write: Vec<&str>,
@@ -146,6 +146,75 @@ fn deny_and_allow_both_match_same_path() {
     // Deny should be checked first in the cascade engine (deny wins)
 }
 
+// ---------------------------------------------------------------------------
+// Home-relative sensitive paths
+// ---------------------------------------------------------------------------
+
+#[test]
+fn tilde_pattern_matches_tilde_and_expanded_paths() {
+    let home = std::env::var("HOME").unwrap();
+    let policy = compile_policy(vec!["**"], vec![], vec!["**"], vec!["~/.claude/**"]);
+
+    assert!(policy.is_sensitive("~/.claude/settings.json", None));
+    assert!(policy.is_sensitive(&format!("{home}/.claude/settings.json"), None));
+    assert!(policy
+        .sensitive_ask_write
+        .is_match(format!("{home}/.claude/settings.json")));
+    assert!(!policy.is_sensitive(&format!("{home}/project/.claude/x"), None));
+}
+
+#[test]
+fn relative_path_is_resolved_against_cwd() {
+    let home = std::env::var("HOME").unwrap();
+    let policy = compile_policy(vec!["**"], vec![], vec!["**"], vec!["~/.config/**"]);
+
+    assert!(policy.is_sensitive(".config/gh/hosts.yml", Some(&home)));
+    assert!(policy.is_sensitive("../.config/gh/hosts.yml", Some(&format!("{home}/project"))));
+    assert!(!policy.is_sensitive(".config/gh/hosts.yml", Some("/srv/app")));
+}
+
+// ---------------------------------------------------------------------------
+// Traversal normalization
+// ---------------------------------------------------------------------------
+
+#[test]
+fn normalize_lexically_resolves_dot_segments() {
+    use hookwise::config::roles::normalize_lexically;
+    use std::path::Path;
+
+    let cases = [
+        ("./tests/../src/../.env", ".env"),
+        ("src/../.env", ".env"),
+        ("src/./../.claude/./settings.json", ".claude/settings.json"),
+        ("../outside/.env", "../outside/.env"),
+        ("src/..", "."),
+        ("/../etc/passwd", "/etc/passwd"),
+    ];
+    for (path, expected) in cases {
+        assert_eq!(
+            normalize_lexically(Path::new(path)),
+            Path::new(expected),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn traversal_encodings_are_still_sensitive() {
+    let policy = compile_policy(vec!["**"], vec![], vec!["**"], vec![".env*", ".claude/**"]);
+    for path in [
+        "./.env",
+        "src/../.env",
+        "./tests/../src/../.env",
+        "src//../.env.local",
+        "docs/./../.claude/settings.json",
+        "a/b/../../.claude/./settings.json",
+    ] {
+        assert!(policy.is_sensitive(path, None), "{path}");
+    }
+    assert!(!policy.is_sensitive("src/.env/../main.rs", None));
+}
+
 // ---------------------------------------------------------------------------
 // Sensitive + deny overlap
 // ---------------------------------------------------------------------------
@@ -158,6 +227,25 @@ fn sensitive_and_deny_both_match() {
     let path = ".env.local";
     assert!(policy.deny_write.is_match(path));
     assert!(policy.sensitive_ask_write.is_match(path));
+    assert_eq!(policy.classify(path), PathVerdict::Deny);
+}
+
+#[test]
+fn classify_sensitive_only_asks() {
+    let policy = compile_policy(vec!["**"], vec!["tests/**"], vec!["**"], vec![".env*"]);
+    assert_eq!(policy.classify(".env"), PathVerdict::Ask);
+}
+
+#[test]
+fn classify_allowed_path_allows() {
+    let policy = compile_policy(vec!["src/**"], vec!["tests/**"], vec!["**"], vec![".env*"]);
+    assert_eq!(policy.classify("src/main.rs"), PathVerdict::Allow);
+}
+
+#[test]
+fn classify_unmatched_path_has_no_opinion() {
+    let policy = compile_policy(vec!["src/**"], vec!["tests/**"], vec!["**"], vec![".env*"]);
+    assert_eq!(policy.classify("docs/guide.md"), PathVerdict::NoOpinion);
 }
 
 // ---------------------------------------------------------------------------
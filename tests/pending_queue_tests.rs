@@ -0,0 +1,86 @@
+//! Pending-decision queue persistence across supervisor restarts.
+//!
+//! Kept in its own test binary because it points `XDG_RUNTIME_DIR` at a
+//! temporary directory for the whole process.
+
+use std::sync::Arc;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use chrono::{Duration, Utc};
+use tempfile::TempDir;
+
+use hookwise::cascade::human::{pending_queue_path, PendingDecision};
+use hookwise::ipc::socket_server::IpcServer;
+
+fn pending(id: &str, input: &str, age_secs: i64) -> PendingDecision {
+    PendingDecision {
+        id: id.into(),
+        session_id: "s1".into(),
+        role: "coder".into(),
+        tool_name: "Bash".into(),
+        sanitized_input: input.into(),
+        file_path: None,
+        recommendation: None,
+        is_ask_reprompt: false,
+        ask_reason: None,
+        queued_at: Utc::now() - Duration::seconds(age_secs),
+    }
+}
+
+#[tokio::test]
+async fn pending_decisions_survive_supervisor_restart() {
+    let runtime = TempDir::new().unwrap();
+    std::env::set_var("XDG_RUNTIME_DIR", runtime.path());
+    std::env::remove_var("CLAUDE_TEAM_ID");
+    let saved = runtime.path().join("saved-queue.json");
+    let socket_path = runtime.path().join("supervisor.sock");
+
+    let server = Arc::new(
+        IpcServer::new(socket_path.clone())
+            .with_decision_queue(saved.clone())
+            .unwrap(),
+    );
+    let queue = server.decision_queue().unwrap();
+    queue.enqueue(pending("older", "cargo publish", 60));
+    queue.enqueue(pending("newer", "git push --force", 0));
+
+    let serving = tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .serve(|_req| Box::pin(async { unreachable!("no requests are sent") }))
+                .await
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    server.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&saved).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // A crash takes the shared queue file with it; the saved copy remains
+    std::fs::remove_file(pending_queue_path()).unwrap();
+
+    let restarted = IpcServer::new(socket_path)
+        .with_decision_queue(saved)
+        .unwrap();
+    assert_eq!(restarted.decision_queue().unwrap().list_pending().len(), 2);
+
+    let output = cargo_bin_cmd!("hookwise")
+        .arg("queue")
+        .env("XDG_RUNTIME_DIR", runtime.path())
+        .env_remove("CLAUDE_TEAM_ID")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let listing = String::from_utf8(output.stdout).unwrap();
+    let older = listing.find("ID: older").expect("older decision listed");
+    let newer = listing.find("ID: newer").expect("newer decision listed");
+    assert!(older < newer, "{listing}");
+    assert!(listing.contains("2 pending decision(s)"), "{listing}");
+}
@@ -0,0 +1,203 @@
+//! Tests for `extends` in roles.yml: inherited path and tool lists, shared
+//! ancestors, and cycles.
+
+use hookwise::config::roles::RolesConfig;
+use hookwise::config::CompiledPathPolicy;
+use hookwise::error::HookwiseError;
+
+fn load(yaml: &str) -> hookwise::error::Result<RolesConfig> {
+    let tmp = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(tmp.path(), yaml).unwrap();
+    RolesConfig::load_from(tmp.path())
+}
+
+#[test]
+fn child_inherits_parent_globs_after_categories() {
+    let config = load(
+        r#"
+roles:
+  base:
+    name: base
+    description: "shared"
+    paths:
+      allow_write: ["{{source}}"]
+      deny_write: ["{{ci}}"]
+      allow_read: ["**"]
+  coder:
+    name: coder
+    description: "writes source, not generated code"
+    extends: [base]
+    paths:
+      allow_write: ["scripts/**"]
+      deny_write: ["src/generated/**"]
+"#,
+    )
+    .unwrap();
+
+    let coder = config.get_role("coder").unwrap();
+    assert_eq!(coder.paths.allow_write[0], "src/**");
+    assert!(coder.paths.allow_write.contains(&"scripts/**".to_string()));
+    assert!(coder.paths.deny_write.contains(&".github/**".to_string()));
+    assert_eq!(
+        coder.paths.deny_write.last().map(String::as_str),
+        Some("src/generated/**")
+    );
+    assert_eq!(coder.paths.allow_read, vec!["**".to_string()]);
+
+    // The parent itself is untouched
+    let base = config.get_role("base").unwrap();
+    assert!(!base.paths.allow_write.contains(&"scripts/**".to_string()));
+
+    // The child's deny still beats the allow it inherited
+    let compiled = CompiledPathPolicy::compile(&coder.paths, &[]).unwrap();
+    assert!(compiled.allow_write.is_match("src/generated/schema.rs"));
+    assert!(compiled.deny_write.is_match("src/generated/schema.rs"));
+}
+
+#[test]
+fn diamond_inheritance_includes_shared_ancestor_once() {
+    let config = load(
+        r#"
+roles:
+  reader:
+    name: reader
+    description: "root"
+    paths:
+      deny_write: [".env*"]
+      allow_read: ["**"]
+  writer:
+    name: writer
+    description: "left"
+    extends: [reader]
+    paths:
+      allow_write: ["src/**"]
+  tester:
+    name: tester
+    description: "right"
+    extends: [reader]
+    paths:
+      allow_write: ["tests/**"]
+  lead:
+    name: lead
+    description: "both"
+    extends: [writer, tester]
+"#,
+    )
+    .unwrap();
+
+    let lead = config.get_role("lead").unwrap();
+    assert_eq!(
+        lead.paths.allow_write,
+        vec!["src/**".to_string(), "tests/**".to_string()]
+    );
+    assert_eq!(lead.paths.deny_write, vec![".env*".to_string()]);
+    assert_eq!(lead.paths.allow_read, vec!["**".to_string()]);
+}
+
+#[test]
+fn inheritance_cycle_is_invalid_policy() {
+    let err = load(
+        r#"
+roles:
+  a:
+    name: a
+    description: "a"
+    extends: [b]
+  b:
+    name: b
+    description: "b"
+    extends: [c]
+  c:
+    name: c
+    description: "c"
+    extends: [a]
+"#,
+    )
+    .unwrap_err();
+
+    match err {
+        HookwiseError::InvalidPolicy { reason } => {
+            assert_eq!(
+                reason,
+                "roles extend each other in a cycle: a -> b -> c -> a"
+            );
+        }
+        other => panic!("expected InvalidPolicy, got {other}"),
+    }
+}
+
+#[test]
+fn extending_unknown_role_is_invalid_policy() {
+    let err = load(
+        r#"
+roles:
+  coder:
+    name: coder
+    description: "c"
+    extends: [nobody]
+"#,
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("role 'coder' extends unknown role 'nobody'"));
+}
+
+#[test]
+fn tool_lists_only_narrow_through_extends() {
+    let config = load(
+        r#"
+roles:
+  base:
+    name: base
+    description: "no browsing"
+    tools_allow: [Bash, Read, Edit, WebFetch]
+    tools_deny: [WebSearch]
+  reviewer:
+    name: reviewer
+    description: "reads, never fetches"
+    extends: [base]
+    tools_allow: [Read, Grep, WebFetch]
+    tools_deny: [WebFetch]
+  coder:
+    name: coder
+    description: "no list of its own"
+    extends: [base]
+"#,
+    )
+    .unwrap();
+
+    let reviewer = config.get_role("reviewer").unwrap();
+    assert_eq!(reviewer.tools_allow, vec!["Read", "WebFetch"]);
+    assert_eq!(reviewer.tools_deny, vec!["WebSearch", "WebFetch"]);
+    assert!(!reviewer.tool_blocked("Read"));
+    // Allowed by its own list, but not by the one it inherited
+    assert!(reviewer.tool_blocked("Grep"));
+    assert!(reviewer.tool_blocked("Bash"));
+    assert!(reviewer.tool_blocked("WebFetch"));
+
+    let coder = config.get_role("coder").unwrap();
+    assert_eq!(coder.tools_allow, vec!["Bash", "Read", "Edit", "WebFetch"]);
+    assert!(coder.tool_blocked("WebSearch"));
+    assert!(coder.tool_blocked("Grep"));
+}
+
+#[test]
+fn disjoint_tools_allow_is_invalid_policy() {
+    let err = load(
+        r#"
+roles:
+  base:
+    name: base
+    description: "b"
+    tools_allow: [Read]
+  coder:
+    name: coder
+    description: "c"
+    extends: [base]
+    tools_allow: [Bash]
+"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("role 'coder' allows no tools"));
+}
@@ -0,0 +1,58 @@
+//! Tests for the bounded in-memory session cache. These run in their own
+//! binary because they shrink the process-wide `SESSIONS` cache.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use tempfile::TempDir;
+
+use hookwise::session::{RegistrationEntry, SessionManager, SESSIONS};
+
+#[test]
+fn evicted_sessions_are_reloaded_from_disk() {
+    let capacity = 16;
+    SESSIONS.set_capacity(capacity);
+
+    let cwd = TempDir::new().unwrap();
+    let suffix = format!("test-lru-{}", std::process::id());
+    let mgr = SessionManager::new(Some(&suffix));
+    let reg_path = std::env::var("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| "/tmp".into())
+        .join(format!("hookwise-{suffix}-sessions.json"));
+
+    let ids: Vec<String> = (0..=capacity).map(|i| format!("lru-{i}")).collect();
+    let entries: HashMap<&str, RegistrationEntry> = ids
+        .iter()
+        .map(|id| {
+            let entry = RegistrationEntry {
+                role: "coder".into(),
+                task: Some(format!("task for {id}")),
+                prompt_hash: None,
+                prompt_path: None,
+                registered_at: Utc::now(),
+                registered_by: None,
+                tags: HashMap::new(),
+                last_seen: None,
+            };
+            (id.as_str(), entry)
+        })
+        .collect();
+    std::fs::write(&reg_path, serde_json::to_string(&entries).unwrap()).unwrap();
+
+    let cwd = cwd.path().to_string_lossy();
+    for id in &ids {
+        mgr.get_or_populate(id, &cwd).unwrap();
+    }
+    assert_eq!(SESSIONS.len(), capacity);
+    assert!(!SESSIONS.contains_key("lru-0"));
+
+    // The evicted session is still registered and loads transparently
+    for id in &ids {
+        let ctx = mgr.get_or_populate(id, &cwd).unwrap();
+        assert_eq!(ctx.task_description, Some(format!("task for {id}")));
+    }
+    assert_eq!(SESSIONS.len(), capacity);
+
+    let _ = std::fs::remove_file(&reg_path);
+}
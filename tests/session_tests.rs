@@ -1,8 +1,10 @@
 //! Unit tests for session registration file handling.
 
-use hookwise::session::registration;
-use hookwise::session::RegistrationEntry;
+use std::collections::HashMap;
+
 use chrono::Utc;
+use hookwise::session::registration;
+use hookwise::session::{RateLimiter, RegistrationEntry};
 use tempfile::TempDir;
 
 fn make_entry(role: &str) -> RegistrationEntry {
@@ -13,6 +15,8 @@ fn make_entry(role: &str) -> RegistrationEntry {
         prompt_path: None,
         registered_at: Utc::now(),
         registered_by: None,
+        tags: HashMap::new(),
+        last_seen: None,
     }
 }
 
@@ -24,6 +28,8 @@ fn make_entry_with_task(role: &str, task: &str) -> RegistrationEntry {
         prompt_path: None,
         registered_at: Utc::now(),
         registered_by: None,
+        tags: HashMap::new(),
+        last_seen: None,
     }
 }
 
@@ -162,6 +168,8 @@ fn entry_serialization_roundtrip() {
         prompt_path: Some("/tmp/prompt.md".into()),
         registered_at: Utc::now(),
         registered_by: Some("user@example.com".into()),
+        tags: HashMap::new(),
+        last_seen: None,
     };
 
     let json = serde_json::to_string(&entry).unwrap();
@@ -177,6 +185,46 @@ fn entry_serialization_roundtrip() {
     );
 }
 
+#[test]
+fn registration_tags_round_trip() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("sessions.json");
+
+    let mut entry = make_entry("coder");
+    entry.tags.insert("pr".into(), "1234".into());
+    entry.tags.insert("env".into(), "staging".into());
+    registration::write_registration_entry(&path, "s1", &entry).unwrap();
+
+    let entries = registration::read_registration_file(&path).unwrap();
+    assert_eq!(entries["s1"].tags.len(), 2);
+    assert_eq!(entries["s1"].tags["pr"], "1234");
+    assert_eq!(entries["s1"].tags["env"], "staging");
+}
+
+#[test]
+fn registration_without_tags_defaults_empty() {
+    let json = r#"{"role":"coder","task":null,"prompt_hash":null,"prompt_path":null,
+        "registered_at":"2026-01-01T00:00:00Z","registered_by":null}"#;
+    let entry: RegistrationEntry = serde_json::from_str(json).unwrap();
+    assert!(entry.tags.is_empty());
+}
+
+#[test]
+fn parse_tag_requires_key_and_equals() {
+    use hookwise::session::parse_tag;
+
+    assert_eq!(
+        parse_tag("pr=1234").unwrap(),
+        ("pr".to_string(), "1234".to_string())
+    );
+    assert_eq!(
+        parse_tag("note=").unwrap(),
+        ("note".to_string(), String::new())
+    );
+    assert!(parse_tag("pr").is_err());
+    assert!(parse_tag("=1234").is_err());
+}
+
 // ---------------------------------------------------------------------------
 // SessionManager: basic operations with tempfiles
 // ---------------------------------------------------------------------------
@@ -201,7 +249,8 @@ fn session_manager_register_and_check() {
     let _ = mgr.enable(&session_id);
 
     // Register the session
-    mgr.register(&session_id, "coder", None, None).unwrap();
+    mgr.register(&session_id, "coder", None, None, None)
+        .unwrap();
     assert!(mgr.is_registered(&session_id));
 
     // Clean up
@@ -220,7 +269,8 @@ fn session_manager_disable_and_enable() {
 
     let session_id = format!("test-dis-{}", Utc::now().timestamp_nanos_opt().unwrap_or(0));
 
-    mgr.register(&session_id, "coder", None, None).unwrap();
+    mgr.register(&session_id, "coder", None, None, None)
+        .unwrap();
     assert!(!mgr.is_disabled(&session_id));
 
     mgr.disable(&session_id).unwrap();
@@ -236,6 +286,180 @@ fn session_manager_disable_and_enable() {
     let _ = std::fs::remove_file(&exc_path);
 }
 
+#[test]
+fn prune_removes_only_expired_entries() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("sessions.json");
+    registration::write_registration_entry(&path, "old", &make_entry("coder")).unwrap();
+    registration::write_registration_entry(&path, "new", &make_entry("tester")).unwrap();
+
+    let removed = registration::prune_registration_entries(&path, |id, _| id == "old").unwrap();
+    assert_eq!(removed, 1);
+    let entries = registration::read_registration_file(&path).unwrap();
+    assert_eq!(entries.keys().collect::<Vec<_>>(), vec!["new"]);
+
+    assert_eq!(
+        registration::prune_registration_entries(&path, |_, _| false).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn switch_role_preserves_registered_by() {
+    use hookwise::session::SessionManager;
+
+    let suffix = format!("test-by-{}", std::process::id());
+    let mgr = SessionManager::new(Some(&suffix));
+    let reg_path = std::env::var("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| "/tmp".into())
+        .join(format!("hookwise-{suffix}-sessions.json"));
+    let registered_by = |id: &str| {
+        registration::read_registration_file(&reg_path).unwrap()[id]
+            .registered_by
+            .clone()
+    };
+
+    mgr.register("by-1", "coder", Some("fix login"), None, Some("alice"))
+        .unwrap();
+    mgr.switch_role("by-1", "tester", None).unwrap();
+    assert_eq!(registered_by("by-1").as_deref(), Some("alice"));
+    let entries = registration::read_registration_file(&reg_path).unwrap();
+    assert_eq!(entries["by-1"].role, "tester");
+    assert_eq!(entries["by-1"].task.as_deref(), Some("fix login"));
+
+    mgr.switch_role("by-1", "coder", Some("bob")).unwrap();
+    assert_eq!(registered_by("by-1").as_deref(), Some("bob"));
+
+    // Without an explicit registrant, the OS user is recorded
+    mgr.register("by-2", "coder", None, None, None).unwrap();
+    assert!(registered_by("by-2").is_some_and(|who| !who.is_empty()));
+
+    let _ = std::fs::remove_file(&reg_path);
+}
+
+#[test]
+fn session_manager_expires_registrations_past_ttl() {
+    use hookwise::session::{SessionContext, SessionManager, SESSIONS};
+
+    let suffix = format!("test-ttl-{}", std::process::id());
+    let mgr = SessionManager::new(Some(&suffix)).with_session_ttl(Some(3600));
+    let reg_path = std::env::var("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| "/tmp".into())
+        .join(format!("hookwise-{suffix}-sessions.json"));
+
+    let stale = RegistrationEntry {
+        registered_at: Utc::now() - chrono::Duration::hours(2),
+        ..make_entry("coder")
+    };
+    for id in ["ttl-stale", "ttl-cached", "ttl-lazy"] {
+        registration::write_registration_entry(&reg_path, id, &stale).unwrap();
+    }
+    mgr.register("ttl-fresh", "coder", None, None, None)
+        .unwrap();
+    // A session this process has loaded keeps working past the TTL
+    SESSIONS.insert(
+        "ttl-cached".into(),
+        SessionContext::new_minimal("user".into(), "org".into(), "project".into()),
+    );
+
+    // Looking a stale session up treats it as absent and prunes it
+    assert!(!mgr.is_registered("ttl-lazy"));
+    let entries = registration::read_registration_file(&reg_path).unwrap();
+    assert!(!entries.contains_key("ttl-lazy"));
+    assert!(!entries.contains_key("ttl-stale"));
+    assert!(entries.contains_key("ttl-cached"));
+    assert!(entries.contains_key("ttl-fresh"));
+
+    registration::write_registration_entry(&reg_path, "ttl-stale", &stale).unwrap();
+    assert_eq!(mgr.prune_expired().unwrap(), 1);
+    assert!(mgr.is_registered("ttl-fresh"));
+
+    // Without a TTL nothing expires
+    registration::write_registration_entry(&reg_path, "ttl-stale", &stale).unwrap();
+    assert_eq!(
+        SessionManager::new(Some(&suffix)).prune_expired().unwrap(),
+        0
+    );
+
+    SESSIONS.remove("ttl-cached");
+    let _ = std::fs::remove_file(&reg_path);
+}
+
+#[test]
+fn session_manager_ttl_runs_from_last_use() {
+    use hookwise::session::{SessionManager, SESSIONS};
+
+    let suffix = format!("test-ttl-seen-{}", std::process::id());
+    let mgr = SessionManager::new(Some(&suffix)).with_session_ttl(Some(3600));
+    let reg_path = std::env::var("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| "/tmp".into())
+        .join(format!("hookwise-{suffix}-sessions.json"));
+    let long_ago = Utc::now() - chrono::Duration::hours(2);
+
+    // Registered long ago, but used recently: still live
+    let active = RegistrationEntry {
+        registered_at: long_ago,
+        last_seen: Some(Utc::now() - chrono::Duration::minutes(5)),
+        ..make_entry("coder")
+    };
+    registration::write_registration_entry(&reg_path, "seen-active", &active).unwrap();
+    assert!(mgr.is_registered("seen-active"));
+
+    // Looking a session up refreshes its last use
+    let idle = RegistrationEntry {
+        registered_at: long_ago,
+        last_seen: Some(Utc::now() - chrono::Duration::minutes(59)),
+        ..make_entry("coder")
+    };
+    registration::write_registration_entry(&reg_path, "seen-idle", &idle).unwrap();
+    mgr.get_or_populate("seen-idle", env!("CARGO_MANIFEST_DIR"))
+        .unwrap();
+    let entries = registration::read_registration_file(&reg_path).unwrap();
+    assert!(entries["seen-idle"].last_seen.unwrap() > Utc::now() - chrono::Duration::minutes(1));
+
+    SESSIONS.remove("seen-idle");
+    let _ = std::fs::remove_file(&reg_path);
+}
+
+#[test]
+fn watch_picks_up_role_written_by_another_process() {
+    use hookwise::session::SessionManager;
+
+    let suffix = format!("test-watch-{}", std::process::id());
+    let mgr = SessionManager::new(Some(&suffix));
+    let reg_path = std::env::var("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| "/tmp".into())
+        .join(format!("hookwise-{suffix}-sessions.json"));
+    let cwd = env!("CARGO_MANIFEST_DIR");
+    let role_of = || {
+        mgr.get_or_populate("w-1", cwd)
+            .unwrap()
+            .role
+            .map(|r| r.name)
+    };
+
+    mgr.register("w-1", "coder", None, None, None).unwrap();
+    let handle = mgr.watch().unwrap();
+    assert!(handle.is_active());
+    assert_eq!(role_of().as_deref(), Some("coder"));
+
+    // Written straight to the file, as another process would; `switch_role`
+    // here would invalidate the cache itself
+    registration::write_registration_entry(&reg_path, "w-1", &make_entry("tester")).unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while role_of().as_deref() != Some("tester") && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    assert_eq!(role_of().as_deref(), Some("tester"));
+
+    drop(handle);
+    let _ = std::fs::remove_file(&reg_path);
+}
+
 // ---------------------------------------------------------------------------
 // Scope level parsing
 // ---------------------------------------------------------------------------
@@ -245,6 +469,7 @@ fn scope_level_from_str() {
     use hookwise::decision::ScopeLevel;
     use std::str::FromStr;
 
+    assert_eq!(ScopeLevel::from_str("global").unwrap(), ScopeLevel::Global);
     assert_eq!(ScopeLevel::from_str("org").unwrap(), ScopeLevel::Org);
     assert_eq!(
         ScopeLevel::from_str("project").unwrap(),
@@ -260,8 +485,84 @@ fn scope_level_from_str() {
 fn scope_level_display() {
     use hookwise::decision::ScopeLevel;
 
+    assert_eq!(format!("{}", ScopeLevel::Global), "global");
     assert_eq!(format!("{}", ScopeLevel::Org), "org");
     assert_eq!(format!("{}", ScopeLevel::Project), "project");
     assert_eq!(format!("{}", ScopeLevel::User), "user");
     assert_eq!(format!("{}", ScopeLevel::Role), "role");
 }
+
+#[test]
+fn scope_level_global_round_trips() {
+    use hookwise::decision::ScopeLevel;
+    use std::str::FromStr;
+
+    let json = serde_json::to_string(&ScopeLevel::Global).unwrap();
+    assert_eq!(json, "\"global\"");
+    assert_eq!(
+        serde_json::from_str::<ScopeLevel>(&json).unwrap(),
+        ScopeLevel::Global
+    );
+    assert_eq!(
+        ScopeLevel::from_str(&ScopeLevel::Global.to_string()).unwrap(),
+        ScopeLevel::Global
+    );
+    assert!(ScopeLevel::Global.precedence() > ScopeLevel::Org.precedence());
+}
+
+#[test]
+fn rate_limiter_escalates_the_call_past_the_limit() {
+    let limiter = RateLimiter::per_minute(3);
+    let start = 1_000.0;
+    for i in 0..3 {
+        assert!(limiter.try_acquire_at("session-a", start + i as f64));
+    }
+    assert!(!limiter.try_acquire_at("session-a", start + 3.0));
+    // Other sessions have their own window
+    assert!(limiter.try_acquire_at("session-b", start + 3.0));
+}
+
+#[test]
+fn rate_limiter_window_slides_over_time() {
+    let limiter = RateLimiter::per_minute(2);
+    let start = 1_000.0;
+    assert!(limiter.try_acquire_at("s", start));
+    assert!(limiter.try_acquire_at("s", start + 30.0));
+    assert!(!limiter.try_acquire_at("s", start + 59.0));
+    // The first call has left the window, the second has not
+    assert!(limiter.try_acquire_at("s", start + 60.0));
+    assert!(!limiter.try_acquire_at("s", start + 61.0));
+    // Refused calls don't count, so a full minute of quiet resets it
+    assert!(limiter.try_acquire_at("s", start + 121.0));
+    assert!(limiter.try_acquire_at("s", start + 122.0));
+}
+
+#[test]
+fn rate_limiter_state_is_shared_through_its_file() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("rate-limit.json");
+    let start = 1_000.0;
+    for i in 0..2 {
+        // A fresh limiter per call, as each `check` process builds one
+        let limiter = RateLimiter::persisted(2, path.clone());
+        assert!(limiter.try_acquire_at("s", start + i as f64));
+    }
+    let limiter = RateLimiter::persisted(2, path.clone());
+    assert!(!limiter.try_acquire_at("s", start + 2.0));
+    assert!(limiter.try_acquire_at("s", start + 61.0));
+}
+
+#[cfg(unix)]
+#[test]
+fn rate_limiter_state_file_is_owner_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("rate-limit.json");
+    let limiter = RateLimiter::persisted(2, path.clone());
+    assert!(limiter.try_acquire_at("s", 1_000.0));
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!path.with_extension("tmp").exists());
+}
@@ -1,5 +1,7 @@
 //! Unit tests for Tier 2a: token-level Jaccard similarity.
 
+use std::collections::HashMap;
+
 use hookwise::cascade::token_sim::TokenJaccard;
 use hookwise::decision::{
     CacheKey, Decision, DecisionMetadata, DecisionRecord, DecisionTier, ScopeLevel,
@@ -25,6 +27,11 @@ fn make_record(input: &str, tool: &str, role: &str, decision: Decision) -> Decis
         scope: ScopeLevel::Project,
         file_path: None,
         session_id: "test".into(),
+        tags: HashMap::new(),
+        immutable: false,
+        id: String::new(),
+        last_used: None,
+        hit_count: 0,
     }
 }
 
@@ -180,7 +187,7 @@ fn same_command_different_args() {
 
 #[test]
 fn insert_and_invalidate_by_role() {
-    let tj = TokenJaccard::new(0.7, 3);
+    let tj = TokenJaccard::new(0.7, 3, 1);
     tj.insert(&make_record(
         "cargo build --release",
         "Bash",
@@ -204,7 +211,7 @@ fn insert_and_invalidate_by_role() {
 
 #[test]
 fn load_from_records() {
-    let tj = TokenJaccard::new(0.7, 3);
+    let tj = TokenJaccard::new(0.7, 3, 1);
     let records = vec![
         make_record("cargo build --release", "Bash", "coder", Decision::Allow),
         make_record("npm test --coverage", "Bash", "tester", Decision::Deny),
@@ -213,11 +220,37 @@ fn load_from_records() {
     // No panic = success
 }
 
+#[tokio::test]
+async fn pattern_rules_are_not_indexed() {
+    use hookwise::cascade::CascadeTier;
+
+    let tj = TokenJaccard::new(0.5, 1, 1);
+    tj.load_from(&[make_record(
+        "pattern:git push *",
+        "Bash",
+        "*",
+        Decision::Allow,
+    )]);
+    tj.insert(&make_record(
+        "pattern:git status *",
+        "Bash",
+        "*",
+        Decision::Allow,
+    ));
+    assert_eq!(tj.len(), 0);
+    assert!(tj
+        .evaluate(&query("pattern git push origin"))
+        .await
+        .unwrap()
+        .is_none());
+}
+
 // ---------------------------------------------------------------------------
 // Similarity never auto-denies (verified at design level)
 // ---------------------------------------------------------------------------
 
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn design_similarity_never_auto_denies() {
     // This is a design invariant verified in the CascadeTier::evaluate implementation.
     // When a deny entry is the best match, TokenJaccard returns None (falls through).
@@ -228,8 +261,383 @@ fn design_similarity_never_auto_denies() {
     //   Decision::Deny => Ok(None), // Never auto-deny from similarity
     //
     // We verify the threshold and min_tokens configuration here.
-    let _tj = TokenJaccard::new(0.7, 3);
+    let _tj = TokenJaccard::new(0.7, 3, 1);
     // threshold=0.7 means 70% token overlap required
     // min_tokens=3 means queries with <3 tokens are skipped
     assert!(true, "design invariant documented");
 }
+
+// ---------------------------------------------------------------------------
+// Minimum token overlap
+// ---------------------------------------------------------------------------
+
+fn query(input: &str) -> hookwise::cascade::CascadeInput {
+    hookwise::cascade::CascadeInput {
+        session: hookwise::session::SessionContext::new_minimal(
+            "user".into(),
+            "org".into(),
+            "project".into(),
+        ),
+        tool_name: "Bash".into(),
+        tool_input: serde_json::json!({ "command": input }),
+        sanitized_input: input.into(),
+        file_path: None,
+        cwd: None,
+    }
+}
+
+#[tokio::test]
+async fn overlap_floor_blocks_single_shared_token() {
+    use hookwise::cascade::CascadeTier;
+
+    // A one-token command scores 1.0 against itself but shares only one token.
+    let tj = TokenJaccard::new(0.5, 1, 1);
+    tj.insert(&make_record("ls", "Bash", "*", Decision::Allow));
+    assert!(tj.evaluate(&query("ls")).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn two_token_commands_need_the_configured_overlap() {
+    use hookwise::cascade::CascadeTier;
+
+    // Two shared tokens meet the default floor of 2...
+    let tj = TokenJaccard::new(0.7, 2, 1);
+    tj.insert(&make_record("git status", "Bash", "*", Decision::Allow));
+    let hit = tj.evaluate(&query("git status")).await.unwrap().unwrap();
+    assert_eq!(hit.decision, Decision::Allow);
+
+    // ...but not a configured floor of 3.
+    let tj = TokenJaccard::new(0.7, 2, 1).with_min_overlap(3);
+    tj.insert(&make_record("git status", "Bash", "*", Decision::Allow));
+    assert!(tj.evaluate(&query("git status")).await.unwrap().is_none());
+}
+
+fn json_command(command: &str) -> String {
+    serde_json::json!({ "command": command }).to_string()
+}
+
+fn json_query(command: &str) -> hookwise::cascade::CascadeInput {
+    hookwise::cascade::CascadeInput {
+        sanitized_input: json_command(command),
+        ..query(command)
+    }
+}
+
+#[test]
+fn json_field_names_are_not_tokens() {
+    let tj = TokenJaccard::new(0.5, 2, 1);
+    assert_eq!(
+        tj.tokens(&json_command("git status")),
+        TokenJaccard::tokenize("git status")
+    );
+    let edit = serde_json::json!({ "file_path": "src/main.rs", "new_string": "fn main" });
+    assert_eq!(
+        tj.tokens(&edit.to_string()),
+        TokenJaccard::tokenize("src/main.rs fn main")
+    );
+}
+
+#[tokio::test]
+async fn json_inputs_sharing_one_value_token_do_not_match() {
+    use hookwise::cascade::CascadeTier;
+
+    // `command` is in both inputs, but only `git` is a shared value token
+    let tj = TokenJaccard::new(0.3, 2, 1);
+    tj.insert(&make_record(
+        &json_command("git status"),
+        "Bash",
+        "*",
+        Decision::Allow,
+    ));
+    assert!(tj
+        .evaluate(&json_query("git reset"))
+        .await
+        .unwrap()
+        .is_none());
+
+    // Two shared value tokens still meet the floor
+    let hit = tj
+        .evaluate(&json_query("git status --short"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.decision, Decision::Allow);
+}
+
+// ---------------------------------------------------------------------------
+// Per-role thresholds
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn role_override_raises_jaccard_threshold() {
+    use hookwise::cascade::CascadeTier;
+    use hookwise::config::{PolicyConfig, RoleDefinition};
+
+    let role: RoleDefinition = serde_yaml::from_str(
+        r#"
+name: maintainer
+description: "Full access, so similar is not close enough"
+paths:
+  allow_write: ["**"]
+  deny_write: []
+  allow_read: ["**"]
+similarity:
+  jaccard_threshold: 0.9
+"#,
+    )
+    .unwrap();
+    let policy = PolicyConfig::default();
+    let effective = policy.similarity.for_role(Some(&role));
+    assert_eq!(effective.jaccard_threshold, 0.9);
+    assert_eq!(
+        effective.embedding_threshold,
+        policy.similarity.embedding_threshold
+    );
+
+    // 4 of 5 tokens shared: 0.8, above the project default of 0.7
+    let tj = TokenJaccard::new(policy.similarity.jaccard_threshold, 2, 1);
+    tj.insert(&make_record(
+        "cargo test --workspace --release",
+        "Bash",
+        "*",
+        Decision::Allow,
+    ));
+    let mut input = query("cargo test --workspace --release --quiet");
+    let hit = tj.evaluate(&input).await.unwrap().unwrap();
+    assert_eq!(hit.decision, Decision::Allow);
+
+    input.session.role = Some(role);
+    assert!(tj.evaluate(&input).await.unwrap().is_none());
+}
+
+#[test]
+fn sanitized_env_assignments_stay_distinct() {
+    let pipeline = hookwise::sanitize::SanitizePipeline::default_pipeline();
+    let a = pipeline.sanitize("export DB_PASS=x7Kp2mN9qR4sW1tY6uV3bE8cF5gH0jA");
+    let b = pipeline.sanitize("export SESSION_SALT=Qm4vT8zL2pW6nR0yK5hB9cX3dF7gJ1s");
+    assert_eq!(a, "export DB_PASS=<REDACTED>");
+    assert_eq!(b, "export SESSION_SALT=<REDACTED>");
+
+    let similarity =
+        TokenJaccard::jaccard_coefficient(&TokenJaccard::tokenize(&a), &TokenJaccard::tokenize(&b));
+    assert!(similarity < 1.0, "got {}", similarity);
+}
+
+// ---------------------------------------------------------------------------
+// Top-K matches
+// ---------------------------------------------------------------------------
+
+fn top_k_corpus() -> TokenJaccard {
+    let tj = TokenJaccard::new(0.5, 3, 1);
+    tj.insert(&make_record(
+        "cargo build --release",
+        "Bash",
+        "coder",
+        Decision::Allow,
+    ));
+    tj.insert(&make_record(
+        "cargo build --release --locked",
+        "Bash",
+        "*",
+        Decision::Allow,
+    ));
+    tj.insert(&make_record("cargo build", "Bash", "coder", Decision::Ask));
+    // Other role, other tool, and no overlap: never returned
+    tj.insert(&make_record(
+        "cargo build --release",
+        "Bash",
+        "tester",
+        Decision::Allow,
+    ));
+    tj.insert(&make_record(
+        "cargo build --release",
+        "Write",
+        "coder",
+        Decision::Allow,
+    ));
+    tj.insert(&make_record(
+        "npm run lint --fix",
+        "Bash",
+        "coder",
+        Decision::Allow,
+    ));
+    tj
+}
+
+#[test]
+fn top_k_orders_matches_best_first() {
+    let tj = top_k_corpus();
+    let matches = tj.top_k("cargo build --release", "Bash", "coder", 2);
+    let inputs: Vec<&str> = matches
+        .iter()
+        .map(|(_, e)| e.cache_key.sanitized_input.as_str())
+        .collect();
+    assert_eq!(
+        inputs,
+        ["cargo build --release", "cargo build --release --locked"]
+    );
+    assert!(matches[0].0 > matches[1].0);
+}
+
+#[test]
+fn top_k_larger_than_corpus_returns_every_match() {
+    let tj = top_k_corpus();
+    let matches = tj.top_k("cargo build --release", "Bash", "coder", 100);
+    assert_eq!(matches.len(), 3);
+    assert!(matches.windows(2).all(|w| w[0].0 >= w[1].0));
+    assert!(matches
+        .iter()
+        .all(|(_, e)| e.cache_key.role != "tester" && e.cache_key.tool == "Bash"));
+}
+
+// ---------------------------------------------------------------------------
+// IDF weighting
+// ---------------------------------------------------------------------------
+
+#[test]
+fn weighted_jaccard_favors_rare_shared_tokens() {
+    let tj = TokenJaccard::new(0.5, 3, 1).with_idf_weighting(true);
+    let records: Vec<DecisionRecord> = ["alpha", "beta", "gamma", "delta", "epsilon"]
+        .iter()
+        .map(|pkg| {
+            make_record(
+                &format!("cargo build --release -p {pkg}"),
+                "Bash",
+                "coder",
+                Decision::Allow,
+            )
+        })
+        .chain(std::iter::once(make_record(
+            "frobnicate widget",
+            "Bash",
+            "coder",
+            Decision::Allow,
+        )))
+        .collect();
+    tj.load_from(&records);
+
+    let common_a = TokenJaccard::tokenize("cargo release one");
+    let common_b = TokenJaccard::tokenize("cargo release two");
+    let rare_a = TokenJaccard::tokenize("frobnicate widget one");
+    let rare_b = TokenJaccard::tokenize("frobnicate widget two");
+
+    // Unweighted, both pairs share two of four tokens
+    assert_eq!(
+        TokenJaccard::jaccard_coefficient(&common_a, &common_b),
+        TokenJaccard::jaccard_coefficient(&rare_a, &rare_b)
+    );
+    let common = tj.weighted_jaccard(&common_a, &common_b);
+    let rare = tj.weighted_jaccard(&rare_a, &rare_b);
+    assert!(rare > common, "rare {rare} <= common {common}");
+}
+
+// ---------------------------------------------------------------------------
+// N-gram tokens
+// ---------------------------------------------------------------------------
+
+#[test]
+fn unigrams_ignore_argument_order() {
+    let tj = TokenJaccard::new(0.7, 3, 1);
+    let a = tj.tokens("chmod 777 file");
+    let b = tj.tokens("chmod file 777");
+    assert_eq!(TokenJaccard::jaccard_coefficient(&a, &b), 1.0);
+}
+
+#[test]
+fn bigrams_score_reordered_arguments_lower() {
+    let tj = TokenJaccard::new(0.7, 3, 2);
+    let a = tj.tokens("chmod 777 file");
+    let b = tj.tokens("chmod file 777");
+    assert_eq!(a, ["777 file", "chmod 777"]);
+    assert!(TokenJaccard::jaccard_coefficient(&a, &b) < 1.0);
+    assert_eq!(
+        TokenJaccard::jaccard_coefficient(&a, &tj.tokens("CHMOD 777 file")),
+        1.0
+    );
+}
+
+#[test]
+fn ngrams_longer_than_input_keep_one_token() {
+    assert_eq!(
+        TokenJaccard::tokenize_ngrams("git status", 3),
+        ["git status"]
+    );
+    assert!(TokenJaccard::tokenize_ngrams("", 3).is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// Deny on similarity
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn deny_match_falls_through_by_default() {
+    use hookwise::cascade::CascadeTier;
+
+    let tj = TokenJaccard::new(0.7, 3, 1);
+    tj.insert(&make_record(
+        "rm -rf ./build ./dist",
+        "Bash",
+        "*",
+        Decision::Deny,
+    ));
+    assert!(tj
+        .evaluate(&query("rm -rf ./dist ./build"))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn deny_match_denies_when_enabled() {
+    use hookwise::cascade::CascadeTier;
+
+    let tj = TokenJaccard::new(0.7, 3, 1).with_deny_on_similarity(true);
+    tj.insert(&make_record(
+        "rm -rf ./build ./dist",
+        "Bash",
+        "*",
+        Decision::Deny,
+    ));
+    let hit = tj
+        .evaluate(&query("rm -rf ./dist ./build"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.decision, Decision::Deny);
+    assert!(hit.metadata.reason.contains("deny_on_match"));
+}
+
+// ---------------------------------------------------------------------------
+// Tier metrics
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn tier_metrics_count_matches_and_fall_throughs() {
+    use hookwise::cascade::CascadeTier;
+
+    let tj = TokenJaccard::new(0.7, 2, 1);
+    tj.insert(&make_record(
+        "git status --short",
+        "Bash",
+        "*",
+        Decision::Allow,
+    ));
+    assert_eq!(tj.tier_metrics().matches, 0);
+    assert_eq!(tj.tier_metrics().fall_throughs, 0);
+
+    let sequence = [
+        ("git status --short", true),
+        ("npm run build", false),
+        ("git status --short", true),
+        ("ls", false),
+        ("docker ps -a", false),
+    ];
+    for (input, hit) in sequence {
+        let decided = tj.evaluate(&query(input)).await.unwrap();
+        assert_eq!(decided.is_some(), hit, "{}", input);
+    }
+
+    let metrics = tj.tier_metrics();
+    assert_eq!(metrics.matches, 2);
+    assert_eq!(metrics.fall_throughs, 3);
+}